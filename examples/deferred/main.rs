@@ -602,5 +602,7 @@ impl<R: gfx::Resources> gfx_app::Application<R> for App<R> {
 pub fn main() {
     use gfx_app::Application;
     let wb = WindowBuilder::new().with_title("Deferred rendering example with gfx-rs");
-    App::launch_default(wb);
+    if let Some(config) = gfx_app::Config::from_args_or_usage() {
+        App::launch_default(wb, &config);
+    }
 }
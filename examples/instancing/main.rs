@@ -175,5 +175,7 @@ impl<R: gfx::Resources> gfx_app::Application<R> for App<R> {
 pub fn main() {
     use gfx_app::Application;
     let wb = winit::WindowBuilder::new().with_title("Instancing example");
-    App::launch_default(wb);
+    if let Some(config) = gfx_app::Config::from_args_or_usage() {
+        App::launch_default(wb, &config);
+    }
 }
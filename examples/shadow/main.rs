@@ -200,13 +200,14 @@ struct Scene<R: gfx::Resources, C: gfx::CommandBuffer<R>> {
 // Section-4: scene construction routines
 
 /// Create a full scene
-fn create_scene<R, F>(factory: &mut F,
+fn create_scene<R, C, F>(factory: &mut F,
                 out_color: gfx::handle::RenderTargetView<R, ColorFormat>,
                 out_depth: gfx::handle::DepthStencilView<R, DepthFormat>,
                 shadow_pso: gfx::PipelineState<R, shadow::Meta>)
-                -> Scene<R, F::CommandBuffer> where
+                -> Scene<R, C> where
     R: gfx::Resources,
-    F: gfx_app::Factory<R>,
+    C: gfx::CommandBuffer<R>,
+    F: gfx_app::Factory<R, C>,
 {
     use cgmath::{SquareMatrix, Matrix4, deg};
     use gfx::traits::FactoryExt;
@@ -430,8 +431,9 @@ impl<R, C> gfx_app::ApplicationBase<R, C> for App<R, C> where
     R: gfx::Resources + 'static,
     C: gfx::CommandBuffer<R> + Send + 'static,
 {
-    fn new<F>(factory: &mut F, backend: gfx_app::shade::Backend, window_targets: gfx_app::WindowTargets<R>) -> Self
-    where F: gfx_app::Factory<R, CommandBuffer=C>,
+    fn new<F>(factory: &mut F, backend: gfx_app::shade::Backend, window_targets: gfx_app::WindowTargets<R>,
+              _config: &gfx_app::Config) -> Self
+    where F: gfx_app::Factory<R, C>,
     {
         use std::env;
         use gfx::traits::FactoryExt;
@@ -625,7 +627,7 @@ impl<R, C> gfx_app::ApplicationBase<R, C> for App<R, C> where
     }
 
     fn on_resize<F>(&mut self, _factory: &mut F, window_targets: gfx_app::WindowTargets<R>)
-    where F: gfx_app::Factory<R, CommandBuffer=C>
+    where F: gfx_app::Factory<R, C>
     {
         for ent in self.scene.share.write().unwrap().entities.iter_mut() {
             ent.batch_forward.out_color = window_targets.color.clone();
@@ -641,5 +643,7 @@ impl<R, C> gfx_app::ApplicationBase<R, C> for App<R, C> where
 pub fn main() {
     let wb = winit::WindowBuilder::new().with_title(
         "Multi-threaded shadow rendering example with gfx-rs");
-    gfx_app::launch_gl3::<App<_, _>>(wb);
+    if let Some(config) = gfx_app::Config::from_args_or_usage() {
+        gfx_app::launch_gl3::<App<_, _>>(wb, &config);
+    }
 }
@@ -0,0 +1,104 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Spinning triangle, whose vertices are written every frame to a `RingBuffer`
+//! while the GPU may still be drawing the previous frames.
+
+#[macro_use]
+extern crate gfx;
+extern crate gfx_window_glutin;
+extern crate glutin;
+
+use gfx::traits::FactoryExt;
+use gfx::Device;
+use gfx::streaming::RingBuffer;
+
+pub type ColorFormat = gfx::format::Rgba8;
+pub type DepthFormat = gfx::format::DepthStencil;
+
+gfx_defines!{
+    vertex Vertex {
+        pos: [f32; 2] = "a_Pos",
+        color: [f32; 3] = "a_Color",
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<Vertex> = (),
+        out: gfx::RenderTarget<ColorFormat> = "Target0",
+    }
+}
+
+const CLEAR_COLOR: [f32; 4] = [0.1, 0.2, 0.3, 1.0];
+const FRAMES_IN_FLIGHT: usize = 3;
+
+fn triangle(angle: f32) -> [Vertex; 3] {
+    let corner = |i: usize, color: [f32; 3]| {
+        let a = angle + i as f32 * 2.0 * std::f32::consts::PI / 3.0;
+        Vertex { pos: [0.5 * a.sin(), 0.5 * a.cos()], color: color }
+    };
+    [corner(0, [1.0, 0.0, 0.0]), corner(1, [0.0, 1.0, 0.0]), corner(2, [0.0, 0.0, 1.0])]
+}
+
+pub fn main() {
+    let builder = glutin::WindowBuilder::new()
+        .with_title("Streaming example".to_string())
+        .with_dimensions(1024, 768)
+        .with_vsync();
+    let (window, mut device, mut factory, main_color, mut main_depth) =
+        gfx_window_glutin::init::<ColorFormat, DepthFormat>(builder);
+    let mut encoder: gfx::Encoder<_, _> = factory.create_command_buffer().into();
+    let pso = factory.create_pipeline_simple(
+        include_bytes!("../triangle/shader/triangle_150.glslv"),
+        include_bytes!("../triangle/shader/triangle_150.glslf"),
+        pipe::new()
+    ).unwrap();
+    let mut ring = RingBuffer::new(&mut factory, 3, FRAMES_IN_FLIGHT,
+                                   gfx::buffer::Role::Vertex, gfx::Bind::empty()).unwrap();
+    let mut data = pipe::Data {
+        vbuf: ring.buffer().clone(),
+        out: main_color
+    };
+    let mut angle = 0.0f32;
+
+    'main: loop {
+        // loop over events
+        for event in window.poll_events() {
+            match event {
+                glutin::Event::KeyboardInput(_, _, Some(glutin::VirtualKeyCode::Escape)) |
+                glutin::Event::Closed => break 'main,
+                glutin::Event::Resized(_width, _height) => {
+                    gfx_window_glutin::update_views(&window, &mut data.out, &mut main_depth);
+                },
+                _ => {},
+            }
+        }
+        // stream the vertices of this frame
+        angle += 0.01;
+        let (buffer, offset) = ring.write_frame(&mut factory, &mut device, &triangle(angle)).unwrap();
+        data.vbuf = buffer;
+        let slice = gfx::Slice {
+            start: 0,
+            end: 3,
+            base_vertex: offset as gfx::VertexCount,
+            instances: None,
+            buffer: gfx::IndexBuffer::Auto,
+        };
+        // draw a frame
+        encoder.clear(&data.out, CLEAR_COLOR);
+        encoder.draw(&slice, &pso, &data);
+        ring.end_frame(encoder.fenced_flush(&mut device).unwrap());
+        window.swap_buffers().unwrap();
+        device.cleanup();
+    }
+}
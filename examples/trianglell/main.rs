@@ -21,7 +21,7 @@ extern crate gfx_device_vulkanll as back;
 
 extern crate winit;
 
-use gfx_corell::{format, pso, shade, state,
+use gfx_corell::{format, image, pass, pso, shade, state,
     Primitive, Instance, Adapter, Surface, SwapChain, QueueFamily, Factory, SubPass};
 use gfx_corell::format::Formatted;
 
@@ -83,8 +83,27 @@ fn main() {    env_logger::init().unwrap();
         pixel_shader: Some("ps_main"),
     };
 
-    let pipeline_signature = device.create_pipeline_signature();
-    let render_pass = device.create_renderpass();
+    let pipeline_signature = device.create_pipeline_signature(&[], &[]);
+    let render_pass = {
+        let attachment = pass::Attachment {
+            format: ColorFormat::get_format(),
+            samples: 1,
+            load_op: pass::AttachmentLoadOp::Clear,
+            store_op: pass::AttachmentStoreOp::Store,
+            stencil_load_op: pass::AttachmentLoadOp::DontCare,
+            stencil_store_op: pass::AttachmentStoreOp::DontCare,
+            layouts: image::ImageLayout::Undefined .. image::ImageLayout::Present,
+        };
+
+        let subpass = pass::SubpassDesc {
+            color_attachments: &[(0, image::ImageLayout::ColorAttachmentOptimal)],
+            depth_stencil_attachment: None,
+            input_attachments: &[],
+            preserve_attachments: &[],
+        };
+
+        device.create_renderpass(&[attachment], &[subpass], &[])
+    };
 
     //
     let mut pipeline_desc = pso::GraphicsPipelineDesc::new(
@@ -110,10 +129,12 @@ fn main() {    env_logger::init().unwrap();
     //
     let pipelines = device.create_graphics_pipelines(&[
         (&shader_lib, &pipeline_signature, SubPass { index: 0, main_pass: &render_pass }, &pipeline_desc)
-    ]);
+    ], None);
 
     println!("{:?}", pipelines);
 
+    let acquire_semaphore = device.create_semaphore();
+
     //
     'main: loop {
         for event in window.poll_events() {
@@ -124,12 +145,15 @@ fn main() {    env_logger::init().unwrap();
             }
         }
 
-        let frame = swap_chain.acquire_frame();
+        let frame = match swap_chain.acquire_frame(Some(&acquire_semaphore)) {
+            Ok((frame, _)) => frame,
+            Err(err) => panic!("Unable to acquire a frame: {}", err),
+        };
 
         // rendering
 
         // present frame
-        swap_chain.present();
+        swap_chain.present(&[&acquire_semaphore]).unwrap();
     }
 }
 
@@ -523,5 +523,7 @@ impl<R: gfx::Resources> gfx_app::Application<R> for TileMap<R> {
 pub fn main() {
     use gfx_app::Application;
     let wb = winit::WindowBuilder::new().with_title("Tilemap example");
-    TileMap::launch_default(wb);
+    if let Some(config) = gfx_app::Config::from_args_or_usage() {
+        TileMap::launch_default(wb, &config);
+    }
 }
@@ -87,6 +87,10 @@ pub enum Command {
     DrawInstanced(UINT, UINT, UINT, UINT),
     DrawIndexed(UINT, UINT, INT),
     DrawIndexedInstanced(UINT, UINT, UINT, INT, UINT),
+    // debugging
+    BeginEvent(DataPointer),
+    EndEvent,
+    SetMarker(DataPointer),
 }
 
 unsafe impl Send for Command {}
@@ -124,6 +128,8 @@ pub trait Parser: Sized + Send {
     fn parse(&mut self, Command);
     fn update_buffer(&mut self, Buffer, &[u8], usize);
     fn update_texture(&mut self, Texture, tex::Kind, Option<tex::CubeFace>, &[u8], tex::RawImageInfo);
+    fn begin_event(&mut self, name: &str);
+    fn set_marker(&mut self, name: &str);
 }
 
 impl<P: Parser> From<P> for CommandBuffer<P> {
@@ -322,6 +328,12 @@ impl<P: Parser> command::Buffer<Resources> for CommandBuffer<P> {
                                               size_bytes as UINT));
     }
 
+    fn copy_texture_to_buffer(&mut self, _: Texture, _: tex::Kind, _: Option<tex::CubeFace>,
+                              _: tex::RawImageInfo, _: Buffer, _: usize) {
+        // D3D11 can only copy textures to staging textures
+        error!("Copying a texture to a buffer is not supported");
+    }
+
     fn update_buffer(&mut self, buf: Buffer, data: &[u8], offset: usize) {
         self.parser.update_buffer(buf, data, offset);
     }
@@ -376,4 +388,17 @@ impl<P: Parser> command::Buffer<Resources> for CommandBuffer<P> {
         });
     }
 
+
+    fn begin_debug_group(&mut self, name: &str) {
+        self.parser.begin_event(name);
+    }
+
+    fn end_debug_group(&mut self) {
+        self.parser.parse(Command::EndEvent);
+    }
+
+    fn insert_debug_marker(&mut self, name: &str) {
+        self.parser.set_marker(name);
+    }
+
 }
@@ -12,12 +12,85 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{mem, ptr};
+use std::{iter, mem, ptr};
 use winapi::{self, UINT};
 use core::{self, texture as tex};
 use command;
 use {Buffer, Texture};
 
+// `ID3DUserDefinedAnnotation` is not exposed by winapi, which lacks Direct3D 11.1
+#[allow(dead_code)]
+#[repr(C)]
+struct UserDefinedAnnotationVtbl {
+    parent: winapi::IUnknownVtbl,
+    begin_event: unsafe extern "system" fn(*mut UserDefinedAnnotation, winapi::LPCWSTR) -> winapi::INT,
+    end_event: unsafe extern "system" fn(*mut UserDefinedAnnotation) -> winapi::INT,
+    set_marker: unsafe extern "system" fn(*mut UserDefinedAnnotation, winapi::LPCWSTR),
+    get_status: unsafe extern "system" fn(*mut UserDefinedAnnotation) -> winapi::BOOL,
+}
+
+#[repr(C)]
+struct UserDefinedAnnotation {
+    vtbl: *const UserDefinedAnnotationVtbl,
+}
+
+const IID_USER_DEFINED_ANNOTATION: winapi::GUID = winapi::GUID {
+    Data1: 0xb2daad8b,
+    Data2: 0x03d4,
+    Data3: 0x4dbf,
+    Data4: [0x95, 0xeb, 0x32, 0xab, 0x4b, 0x63, 0xd0, 0xab],
+};
+
+fn to_wide(name: &[u8]) -> Vec<u16> {
+    String::from_utf8_lossy(name).encode_utf16().chain(iter::once(0)).collect()
+}
+
+/// Annotation interface of a context, queried once. Events and markers are
+/// ignored if the runtime doesn't support it.
+pub struct Annotation(*mut UserDefinedAnnotation);
+
+impl Annotation {
+    pub fn new(context: *mut winapi::ID3D11DeviceContext) -> Annotation {
+        let mut annotation: *mut UserDefinedAnnotation = ptr::null_mut();
+        let hr = unsafe {
+            (*context).QueryInterface(&IID_USER_DEFINED_ANNOTATION,
+                                      &mut annotation as *mut *mut _ as *mut *mut _)
+        };
+        Annotation(if winapi::SUCCEEDED(hr) { annotation } else { ptr::null_mut() })
+    }
+
+    pub fn begin_event(&self, name: &[u8]) {
+        if self.0.is_null() {
+            return;
+        }
+        let name = to_wide(name);
+        unsafe { ((*(*self.0).vtbl).begin_event)(self.0, name.as_ptr()); }
+    }
+
+    pub fn end_event(&self) {
+        if self.0.is_null() {
+            return;
+        }
+        unsafe { ((*(*self.0).vtbl).end_event)(self.0); }
+    }
+
+    pub fn set_marker(&self, name: &[u8]) {
+        if self.0.is_null() {
+            return;
+        }
+        let name = to_wide(name);
+        unsafe { ((*(*self.0).vtbl).set_marker)(self.0, name.as_ptr()); }
+    }
+}
+
+impl Drop for Annotation {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { (*(self.0 as *mut winapi::IUnknown)).Release() };
+        }
+    }
+}
+
 fn copy_buffer(context: *mut winapi::ID3D11DeviceContext,
                src: &Buffer, dst: &Buffer,
                src_offset: UINT, dst_offset: UINT,
@@ -98,7 +171,8 @@ pub fn update_texture(context: *mut winapi::ID3D11DeviceContext, texture: &Textu
 }
 
 
-pub fn process(ctx: *mut winapi::ID3D11DeviceContext, command: &command::Command, data_buf: &command::DataBuffer) {
+pub fn process(ctx: *mut winapi::ID3D11DeviceContext, annotation: &Annotation,
+               command: &command::Command, data_buf: &command::DataBuffer) {
     use winapi::UINT;
     use core::shade::Stage;
     use command::Command::*;
@@ -230,5 +304,14 @@ pub fn process(ctx: *mut winapi::ID3D11DeviceContext, command: &command::Command
         DrawIndexedInstanced(nind, ninst, sind, base, sinst) => unsafe {
             (*ctx).DrawIndexedInstanced(nind, ninst, sind, base, sinst);
         },
+        BeginEvent(ptr) => {
+            annotation.begin_event(data_buf.get(ptr));
+        },
+        EndEvent => {
+            annotation.end_event();
+        },
+        SetMarker(ptr) => {
+            annotation.set_marker(data_buf.get(ptr));
+        },
     }
 }
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{cmp, ptr, slice};
+use std::{cmp, mem, ptr, slice};
 use std::collections::BTreeMap as Map;
 use std::os::raw::c_void;
 use std::sync::Arc;
@@ -46,6 +46,10 @@ impl core::mapping::Gate<R> for MappingGate {
     unsafe fn mut_slice<'a, 'b, T>(&'a self, len: usize) -> &'b mut [T] {
         slice::from_raw_parts_mut(self.pointer as *mut T, len)
     }
+
+    unsafe fn copy_from<T: Copy>(&self, offset: usize, data: &[T]) {
+        ptr::copy_nonoverlapping(data.as_ptr(), (self.pointer as *mut T).offset(offset as isize), data.len());
+    }
 }
 
 #[derive(Debug)]
@@ -924,12 +928,68 @@ impl core::Factory<R> for Factory {
             })
         }
     }
+
+    unsafe fn write_mapping_unsynchronized<'a, 'b, T>(&'a mut self, buf: &'b h::Buffer<R, T>)
+                                                      -> Result<mapping::Unsynchronized<'b, R, T>,
+                                                                mapping::Error>
+        where T: Copy
+    {
+        // staging buffers are unmapped on every submission
+        mapping::unsynchronized(buf.raw(), |_| Err(mapping::Error::NotPersistent))
+    }
+
+    fn map_texture_readable<'a, 'b, T>(&'a mut self, tex: &'b h::RawTexture<R>,
+                                       face: Option<texture::CubeFace>, img: texture::RawImageInfo)
+                                       -> Result<mapping::TextureReader<'b, R, T>, mapping::Error>
+        where T: Copy
+    {
+        try!(mapping::check_texture_read(tex));
+        let info = tex.get_info();
+        let (layer, slice) = match (info.kind, face) {
+            (texture::Kind::D3(..), _) => (0, img.zoffset as usize),
+            (_, Some(f)) => (img.zoffset as u32 * 6 + f as u32, 0),
+            (kind, None) if kind.get_num_slices().is_some() => (img.zoffset as u32, 0),
+            _ => (0, 0),
+        };
+        let subresource = img.mipmap as winapi::UINT + layer * info.levels as winapi::UINT;
+        let resource = tex.resource().as_resource();
+        let mut ctx = ptr::null_mut();
+        let mut sres = winapi::d3d11::D3D11_MAPPED_SUBRESOURCE {
+            pData: ptr::null_mut(),
+            RowPitch: 0,
+            DepthPitch: 0,
+        };
+        let hr = unsafe {
+            (*self.device).GetImmediateContext(&mut ctx);
+            (*ctx).Map(resource, subresource, winapi::d3d11::D3D11_MAP_READ, 0, &mut sres)
+        };
+        if !winapi::SUCCEEDED(hr) {
+            error!("Unable to map a texture {:?}, error {:x}", tex, hr);
+            unsafe { (*ctx).Release(); }
+            return Err(mapping::Error::MapFailed);
+        }
+
+        let size = mem::size_of::<T>();
+        let row_pitch = sres.RowPitch as usize / size;
+        let offset = slice * sres.DepthPitch as usize / size +
+                     img.yoffset as usize * row_pitch + img.xoffset as usize;
+        let release = Box::new(move || unsafe {
+            (*ctx).Unmap(resource, subresource);
+            (*ctx).Release();
+        });
+        Ok(unsafe {
+            mapping::TextureReader::new((sres.pData as *const T).offset(offset as isize),
+                                        img.width as usize, img.height as usize,
+                                        row_pitch, release)
+        })
+    }
 }
 
 pub fn ensure_mapped(mapping: &mut MappingGate,
                      buffer: &h::RawBuffer<R>,
                      map_type: winapi::d3d11::D3D11_MAP,
-                     factory: &Factory) {
+                     factory: &Factory)
+                     -> Result<(), mapping::Error> {
     if mapping.pointer.is_null() {
         let raw_handle = *buffer.resource();                  
         let mut ctx = ptr::null_mut();
@@ -952,9 +1012,11 @@ pub fn ensure_mapped(mapping: &mut MappingGate,
         if winapi::SUCCEEDED(hr) {
             mapping.pointer = sres.pData;
         } else {
-            panic!("Unable to map a buffer {:?}, error {:x}", buffer, hr);
+            error!("Unable to map a buffer {:?}, error {:x}", buffer, hr);
+            return Err(mapping::Error::MapFailed);
         }
     }
+    Ok(())
 }
 
 pub fn ensure_unmapped(mapping: &mut MappingGate,
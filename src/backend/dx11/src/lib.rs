@@ -75,6 +75,7 @@ pub mod native {
 use std::cell::RefCell;
 use std::ptr;
 use std::sync::Arc;
+use std::thread;
 use core::{handle as h, texture as tex};
 use core::SubmissionResult;
 use core::command::{AccessInfo, AccessGuard};
@@ -168,6 +169,7 @@ pub struct Share {
 
 pub struct Device {
     context: *mut winapi::ID3D11DeviceContext,
+    annotation: execute::Annotation,
     feature_level: winapi::D3D_FEATURE_LEVEL,
     share: Arc<Share>,
     frame_handles: h::Manager<Resources>,
@@ -180,10 +182,18 @@ static FEATURE_LEVELS: [winapi::D3D_FEATURE_LEVEL; 3] = [
     winapi::D3D_FEATURE_LEVEL_10_0,
 ];
 
-pub fn create(driver_type: winapi::D3D_DRIVER_TYPE, desc: &winapi::DXGI_SWAP_CHAIN_DESC)
+/// Create a device, a factory and a swap chain. With `debug` set, the device
+/// is created with the debug layer, which requires the SDK layers to be installed.
+/// A non-null `adapter` requires the driver type to be `D3D_DRIVER_TYPE_UNKNOWN`.
+pub fn create(driver_type: winapi::D3D_DRIVER_TYPE, desc: &winapi::DXGI_SWAP_CHAIN_DESC, debug: bool,
+              adapter: *mut winapi::IDXGIAdapter)
               -> Result<(Device, Factory, *mut winapi::IDXGISwapChain), winapi::HRESULT> {
     let mut swap_chain = ptr::null_mut();
-    let create_flags = winapi::D3D11_CREATE_DEVICE_FLAG(0); //D3D11_CREATE_DEVICE_DEBUG;
+    let create_flags = if debug {
+        winapi::D3D11_CREATE_DEVICE_DEBUG
+    } else {
+        winapi::D3D11_CREATE_DEVICE_FLAG(0)
+    };
     let mut device = ptr::null_mut();
     let share = Share {
         capabilities: core::Capabilities {
@@ -207,7 +217,7 @@ pub fn create(driver_type: winapi::D3D_DRIVER_TYPE, desc: &winapi::DXGI_SWAP_CHA
     let mut context = ptr::null_mut();
     let mut feature_level = winapi::D3D_FEATURE_LEVEL_10_0;
     let hr = unsafe {
-        d3d11::D3D11CreateDeviceAndSwapChain(ptr::null_mut(), driver_type, ptr::null_mut(), create_flags.0,
+        d3d11::D3D11CreateDeviceAndSwapChain(adapter, driver_type, ptr::null_mut(), create_flags.0,
             &FEATURE_LEVELS[0], FEATURE_LEVELS.len() as winapi::UINT, winapi::D3D11_SDK_VERSION, desc,
             &mut swap_chain, &mut device, &mut feature_level, &mut context)
     };
@@ -217,6 +227,7 @@ pub fn create(driver_type: winapi::D3D_DRIVER_TYPE, desc: &winapi::DXGI_SWAP_CHA
 
     let dev = Device {
         context: context,
+        annotation: execute::Annotation::new(context),
         feature_level: feature_level,
         share: Arc::new(share),
         frame_handles: h::Manager::new(),
@@ -244,6 +255,28 @@ impl Device {
         }
     }
 
+    fn place_fence(&mut self) -> h::Fence<Resources> {
+        use core::handle::Producer;
+
+        let mut device = ptr::null_mut();
+        let mut query = ptr::null_mut();
+        let desc = winapi::D3D11_QUERY_DESC {
+            Query: winapi::D3D11_QUERY_EVENT,
+            MiscFlags: 0,
+        };
+        let hr = unsafe {
+            (*self.context).GetDevice(&mut device);
+            let hr = (*device).CreateQuery(&desc, &mut query);
+            (*device).Release();
+            hr
+        };
+        assert!(winapi::SUCCEEDED(hr));
+        unsafe {
+            (*self.context).End(query as *mut winapi::ID3D11Asynchronous);
+        }
+        self.share.handles.borrow_mut().make_fence(Fence(query))
+    }
+
     pub fn before_submit<'a>(&mut self, gpu_access: &'a AccessInfo<Resources>)
                              -> core::SubmissionResult<AccessGuard<'a, Resources>> {
         let mut gpu_access = try!(gpu_access.take_accesses());
@@ -282,13 +315,21 @@ impl command::Parser for CommandList {
         let ptr = self.1.add(data);
         self.0.push(command::Command::UpdateTexture(tex, kind, face, ptr, image));
     }
+    fn begin_event(&mut self, name: &str) {
+        let ptr = self.1.add(name.as_bytes());
+        self.0.push(command::Command::BeginEvent(ptr));
+    }
+    fn set_marker(&mut self, name: &str) {
+        let ptr = self.1.add(name.as_bytes());
+        self.0.push(command::Command::SetMarker(ptr));
+    }
 }
 
-pub struct DeferredContext(*mut winapi::ID3D11DeviceContext, Option<*mut winapi::ID3D11CommandList>);
+pub struct DeferredContext(*mut winapi::ID3D11DeviceContext, Option<*mut winapi::ID3D11CommandList>, execute::Annotation);
 unsafe impl Send for DeferredContext {}
 impl DeferredContext {
     pub fn new(dc: *mut winapi::ID3D11DeviceContext) -> DeferredContext {
-        DeferredContext(dc, None)
+        DeferredContext(dc, None, execute::Annotation::new(dc))
     }
 }
 impl Drop for DeferredContext {
@@ -308,7 +349,7 @@ impl command::Parser for DeferredContext {
     }
     fn parse(&mut self, com: command::Command) {
         let db = command::DataBuffer::new(); //not used
-        execute::process(self.0, &com, &db);
+        execute::process(self.0, &self.2, &com, &db);
     }
     fn update_buffer(&mut self, buf: Buffer, data: &[u8], offset: usize) {
         execute::update_buffer(self.0, &buf, data, offset);
@@ -316,6 +357,12 @@ impl command::Parser for DeferredContext {
     fn update_texture(&mut self, tex: Texture, kind: tex::Kind, face: Option<tex::CubeFace>, data: &[u8], image: tex::RawImageInfo) {
         execute::update_texture(self.0, &tex, kind, face, data, &image);
     }
+    fn begin_event(&mut self, name: &str) {
+        self.2.begin_event(name.as_bytes());
+    }
+    fn set_marker(&mut self, name: &str) {
+        self.2.set_marker(name.as_bytes());
+    }
 }
 
 
@@ -345,22 +392,38 @@ impl core::Device for Device {
         let _guard = try!(self.before_submit(access));
         unsafe { (*self.context).ClearState(); }
         for com in &cb.parser.0 {
-            execute::process(self.context, com, &cb.parser.1);
+            execute::process(self.context, &self.annotation, com, &cb.parser.1);
         }
         Ok(())
     }
 
     fn fenced_submit(&mut self,
-                     _: &mut Self::CommandBuffer,
-                     _: &AccessInfo<Resources>,
+                     cb: &mut Self::CommandBuffer,
+                     access: &AccessInfo<Resources>,
                      _after: Option<h::Fence<Resources>>)
                      -> SubmissionResult<h::Fence<Resources>>
     {
-        unimplemented!()
-    }
-
-    fn wait_fence(&mut self, _fence: &h::Fence<Self::Resources>) {
-        unimplemented!()
+        // the immediate context executes everything in submission order,
+        // so there is no need to wait for `_after`
+        try!(core::Device::submit(self, cb, access));
+        Ok(self.place_fence())
+    }
+
+    fn wait_fence(&mut self, fence: &h::Fence<Self::Resources>) {
+        let query = self.frame_handles.ref_fence(fence).0;
+        loop {
+            let hr = unsafe {
+                (*self.context).GetData(query as *mut winapi::ID3D11Asynchronous, ptr::null_mut(), 0, 0)
+            };
+            match hr {
+                winapi::S_OK => break,
+                winapi::S_FALSE => thread::yield_now(),
+                _ => {
+                    error!("Failed to wait for a fence: {:x}", hr);
+                    break
+                }
+            }
+        }
     }
 
     fn cleanup(&mut self) {
@@ -401,7 +464,7 @@ impl core::Device for Device {
             |_, v| unsafe { (*v.0).Release(); }, //RTV
             |_, v| unsafe { (*v.0).Release(); }, //DSV
             |_, v| unsafe { (*v.0).Release(); }, //sampler
-            |_, _fence| {},
+            |_, fence| unsafe { (*fence.0).Release(); },
         );
     }
 }
@@ -461,16 +524,18 @@ impl core::Device for Deferred {
     }
 
     fn fenced_submit(&mut self,
-                     _: &mut Self::CommandBuffer,
-                     _: &AccessInfo<Resources>,
+                     cb: &mut Self::CommandBuffer,
+                     access: &AccessInfo<Resources>,
                      _after: Option<h::Fence<Resources>>)
                      -> SubmissionResult<h::Fence<Resources>>
     {
-        unimplemented!()
+        // command lists are executed in order on the immediate context
+        try!(core::Device::submit(self, cb, access));
+        Ok(self.0.place_fence())
     }
 
-    fn wait_fence(&mut self, _fence: &h::Fence<Self::Resources>) {
-        unimplemented!()
+    fn wait_fence(&mut self, fence: &h::Fence<Self::Resources>) {
+        core::Device::wait_fence(&mut self.0, fence)
     }
 
     fn cleanup(&mut self) {
@@ -478,5 +543,8 @@ impl core::Device for Deferred {
     }
 }
 
+/// Event query, signaled once the GPU has processed everything before it.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct Fence(());
+pub struct Fence(*mut winapi::ID3D11Query);
+unsafe impl Send for Fence {}
+unsafe impl Sync for Fence {}
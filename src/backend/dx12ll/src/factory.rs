@@ -22,8 +22,9 @@ use std::ffi;
 use std::{mem, ptr};
 use std::os::raw::c_void;
 use std::collections::BTreeMap;
+use std::ops::Range;
 
-use core::{self, shade};
+use core::{self, buffer, descriptor, format, image, memory, pass, query, shade};
 use core::SubPass;
 use core::pso::{self, EntryPoint};
 use {data, state, mirror, native};
@@ -90,12 +91,170 @@ impl Device {
 }
 
 impl core::Factory<R> for Device {
-    fn create_renderpass(&mut self) -> () {
+    fn allocate_memory(&mut self, _memory_type: &memory::MemoryType, _size: u64) -> Result<(), memory::OutOfMemory> {
+        // TODO: ID3D12Heap
+        Ok(())
+    }
+
+    fn map_memory(&mut self, _heap: &(), _range: Range<u64>) -> *mut u8 {
+        // TODO: ID3D12Resource::Map, needs heaps backed by an upload or readback resource
+        unimplemented!("dx12: mapping memory is not supported yet")
+    }
+
+    fn unmap_memory(&mut self, _heap: &()) {
+        // unimplemented!()
+    }
+
+    fn create_buffer(&mut self, _size: u64, _usage: buffer::Usage) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn get_buffer_requirements(&mut self, _buffer: &()) -> memory::Requirements {
+        // unimplemented!()
+        memory::Requirements { size: 0, alignment: 1, type_mask: 0x7 }
+    }
+
+    fn bind_buffer_memory(&mut self, _heap: &(), _offset: u64, _buffer: ()) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_image(&mut self, _kind: image::Kind, _levels: image::Level, _format: format::Format,
+                    _usage: image::Usage) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn get_image_requirements(&mut self, _image: &()) -> memory::Requirements {
+        // unimplemented!()
+        memory::Requirements { size: 0, alignment: 1, type_mask: 0x7 }
+    }
+
+    fn bind_image_memory(&mut self, _heap: &(), _offset: u64, _image: ()) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_shader_resource_view(&mut self, _image: &(), _kind: image::ViewKind, _format: format::Format,
+                                   _range: image::SubresourceRange) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_buffer_view(&mut self, _buffer: &(), _format: format::Format, _range: Range<u64>) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_unordered_access_view(&mut self, _image: &(), _kind: image::ViewKind, _format: format::Format,
+                                    _range: image::SubresourceRange) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_render_target_view(&mut self, _image: &(), _format: format::Format,
+                                 _layers: image::SubresourceLayers) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_depth_stencil_view(&mut self, _image: &(), _format: format::Format,
+                                 _layers: image::SubresourceLayers) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_renderpass(&mut self, _attachments: &[pass::Attachment], _subpasses: &[pass::SubpassDesc],
+                         _dependencies: &[pass::SubpassDependency]) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_framebuffer(&mut self, _renderpass: &(),
+                          _color_attachments: &[()], _depth_stencil_attachments: &[()],
+                          _width: u32, _height: u32, _layers: u32) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_sampler(&mut self, _info: image::SamplerInfo) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_query_pool(&mut self, _ty: query::QueryType, _count: u32) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn get_query_results(&mut self, _pool: &(), _queries: Range<u32>) -> Option<Vec<u64>> {
+        // unimplemented!()
+        None
+    }
+
+    fn create_event(&mut self) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_semaphore(&mut self) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_fence(&mut self, _signaled: bool) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn reset_fences(&mut self, _fences: &[&()]) {
+        // unimplemented!()
+    }
+
+    fn wait_for_fences(&mut self, _fences: &[&()], _wait_all: bool, _timeout_ns: u64) -> bool {
+        // TODO: ID3D12Fence::SetEventOnCompletion
+        unimplemented!("dx12: waiting for fences is not supported yet")
+    }
+
+    fn get_fence_status(&mut self, _fence: &()) -> bool {
+        // TODO: ID3D12Fence::GetCompletedValue
+        unimplemented!("dx12: querying fence status is not supported yet")
+    }
+
+    fn wait_idle(&mut self) {
+        // TODO: signal a fence on each queue and wait for it
+        unimplemented!("dx12: waiting for the device to be idle is not supported yet")
+    }
+
+    fn create_descriptor_set_layout(&mut self, _bindings: &[descriptor::DescriptorSetLayoutBinding]) -> () {
+        // unimplemented!()
+        ()
+    }
+
+    fn create_descriptor_pool(&mut self, _max_sets: usize, _ranges: &[descriptor::DescriptorRangeDesc]) -> () {
         // unimplemented!()
         ()
     }
 
-    fn create_pipeline_signature(&mut self) -> native::PipelineSignature {
+    fn allocate_descriptor_sets(&mut self, _pool: &mut (), _layouts: &[&()]) -> Option<Vec<()>> {
+        // TODO: descriptor heaps
+        unimplemented!("dx12: allocating descriptor sets is not supported yet")
+    }
+
+    fn reset_descriptor_pool(&mut self, _pool: &mut ()) {
+        // unimplemented!()
+    }
+
+    fn update_descriptor_sets(&mut self, _writes: &[descriptor::DescriptorSetWrite<R>]) {
+        // unimplemented!()
+    }
+
+    fn create_pipeline_signature(&mut self, _sets: &[&()],
+                                 _push_constant_ranges: &[(shade::StageFlags, Range<u32>)])
+        -> native::PipelineSignature
+    {
+        // TODO: map descriptor sets to descriptor tables, and push constants to root constants
         let desc = winapi::D3D12_ROOT_SIGNATURE_DESC {
             NumParameters: 0,
             pParameters: ptr::null(),
@@ -127,7 +286,18 @@ impl core::Factory<R> for Device {
         native::PipelineSignature { inner: signature }
     }
 
-    fn create_graphics_pipelines<'a>(&mut self, descs: &[(&native::ShaderLib, &native::PipelineSignature, SubPass<'a, R>, &pso::GraphicsPipelineDesc)])
+    fn create_pipeline_cache(&mut self, _data: &[u8]) -> () {
+        // TODO: ID3D12PipelineLibrary, or cached blobs of the pipeline states
+        ()
+    }
+
+    fn get_pipeline_cache_data(&mut self, _cache: &()) -> Vec<u8> {
+        // unimplemented!()
+        Vec::new()
+    }
+
+    fn create_graphics_pipelines<'a>(&mut self, descs: &[(&native::ShaderLib, &native::PipelineSignature, SubPass<'a, R>, &pso::GraphicsPipelineDesc)],
+                                     _cache: Option<&()>)
         -> Vec<Result<native::Pipeline, pso::CreationError>>
     {
         descs.iter().map(|&(shader_lib, ref signature, _, ref desc)| {
@@ -226,8 +396,8 @@ impl core::Factory<R> for Device {
                 },
                 BlendState: winapi::D3D12_BLEND_DESC {
                     AlphaToCoverageEnable: winapi::FALSE, // TODO
-                    IndependentBlendEnable: winapi::FALSE, // TODO
-                    RenderTarget: state::map_render_targets(&desc.color_targets), // TODO
+                    IndependentBlendEnable: if desc.has_independent_blend() { winapi::TRUE } else { winapi::FALSE },
+                    RenderTarget: state::map_render_targets(&desc.color_targets, desc.logic_op), // TODO
                 },
                 SampleMask: winapi::UINT::max_value(),
                 RasterizerState: state::map_rasterizer(&desc.rasterizer),
@@ -279,7 +449,98 @@ impl core::Factory<R> for Device {
         }).collect()
     }
 
-    fn create_compute_pipelines(&mut self) -> Vec<Result<native::Pipeline, pso::CreationError>> {
-        unimplemented!()
+    fn create_compute_pipelines(&mut self, descs: &[(&native::ShaderLib, EntryPoint, &native::PipelineSignature)],
+                                _cache: Option<&()>)
+        -> Vec<Result<native::Pipeline, pso::CreationError>>
+    {
+        descs.iter().map(|&(shader_lib, entry, signature)| {
+            let cs = match shader_lib.shaders.get(entry) {
+                Some(shader) => winapi::D3D12_SHADER_BYTECODE {
+                    pShaderBytecode: unsafe { (&mut *shader.as_mut_ptr()).GetBufferPointer() as *const _ },
+                    BytecodeLength: unsafe { (&mut *shader.as_mut_ptr()).GetBufferSize() as u64 },
+                },
+                None => {
+                    error!("Couldn't find compute shader entry point {:?}", entry);
+                    return Err(pso::CreationError);
+                }
+            };
+
+            let pso_desc = winapi::D3D12_COMPUTE_PIPELINE_STATE_DESC {
+                pRootSignature: signature.inner.as_mut_ptr(),
+                CS: cs,
+                NodeMask: 0,
+                CachedPSO: winapi::D3D12_CACHED_PIPELINE_STATE {
+                    pCachedBlob: ptr::null(),
+                    CachedBlobSizeInBytes: 0,
+                },
+                Flags: winapi::D3D12_PIPELINE_STATE_FLAG_NONE,
+            };
+
+            let mut pipeline = ComPtr::<winapi::ID3D12PipelineState>::new(ptr::null_mut());
+            let hr = unsafe {
+                self.inner.CreateComputePipelineState(
+                    &pso_desc,
+                    &dxguid::IID_ID3D12PipelineState,
+                    pipeline.as_mut() as *mut *mut _ as *mut *mut c_void)
+            };
+
+            if winapi::SUCCEEDED(hr) {
+                Ok(native::Pipeline { inner: pipeline })
+            } else {
+                Err(pso::CreationError)
+            }
+        }).collect()
+    }
+
+    fn destroy_heap(&mut self, _heap: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_buffer(&mut self, _buffer: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_image(&mut self, _image: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_unbound_buffer(&mut self, _buffer: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_unbound_image(&mut self, _image: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_buffer_view(&mut self, _view: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_shader_resource_view(&mut self, _view: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_unordered_access_view(&mut self, _view: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_render_target_view(&mut self, _view: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_depth_stencil_view(&mut self, _view: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_framebuffer(&mut self, _framebuffer: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_sampler(&mut self, _sampler: ()) {
+        // unimplemented!()
+    }
+
+    fn destroy_fence(&mut self, _fence: ()) {
+        // unimplemented!()
     }
 }
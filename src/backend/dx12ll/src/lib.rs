@@ -49,6 +49,11 @@ impl core::QueueFamily for QueueFamily {
         true
     }
 
+    fn queue_type(&self) -> core::QueueType {
+        // direct command queues support all the operations
+        core::GRAPHICS | core::COMPUTE | core::TRANSFER
+    }
+
     fn num_queues(&self) -> u32 {
         // TODO: actually infinite, need to find a good way to handle this
         1
@@ -111,7 +116,55 @@ impl core::Adapter for Adapter {
             }).collect::<Vec<_>>()
         }).collect();
 
-        (Device { inner: device }, queues)
+        // Optional features of the device
+        let mut options: winapi::D3D12_FEATURE_DATA_D3D12_OPTIONS = unsafe { std::mem::zeroed() };
+        let hr = unsafe {
+            device.CheckFeatureSupport(
+                winapi::D3D12_FEATURE_D3D12_OPTIONS,
+                &mut options as *mut _ as *mut c_void,
+                std::mem::size_of::<winapi::D3D12_FEATURE_DATA_D3D12_OPTIONS>() as u32)
+        };
+        if !winapi::SUCCEEDED(hr) {
+            error!("error on feature support query: {:?}", hr);
+        }
+
+        // Default, upload and readback heap types
+        let memory_types = vec![
+            core::memory::MemoryType {
+                id: 0,
+                properties: core::memory::DEVICE_LOCAL,
+                heap_index: 0,
+            },
+            core::memory::MemoryType {
+                id: 1,
+                properties: core::memory::CPU_VISIBLE | core::memory::COHERENT,
+                heap_index: 1,
+            },
+            core::memory::MemoryType {
+                id: 2,
+                properties: core::memory::CPU_VISIBLE | core::memory::COHERENT | core::memory::CPU_CACHED,
+                heap_index: 1,
+            },
+        ];
+        let device = Device {
+            inner: device,
+            capabilities: core::Capabilities {
+                geometry_shader_supported: true,
+                layered_rendering_supported: true,
+                volume_slice_rendering_supported: true,
+                instance_rate_supported: true,
+                tessellation_supported: true,
+                anisotropy_supported: true,
+                independent_blend_supported: true,
+                dual_source_blending_supported: true,
+                logic_op_supported: options.OutputMergerLogicOp == winapi::TRUE,
+                .. core::Capabilities::default()
+            },
+            memory_types: memory_types,
+            memory_heaps: vec![0, 0], // TODO: query the adapter memory sizes
+        };
+
+        (device, queues)
     }
 
     fn get_info(&self) -> &core::AdapterInfo {
@@ -125,10 +178,115 @@ impl core::Adapter for Adapter {
 
 pub struct Device {
     inner: ComPtr<winapi::ID3D12Device>,
+    capabilities: core::Capabilities,
+    memory_types: Vec<core::memory::MemoryType>,
+    memory_heaps: Vec<u64>,
 }
 
 impl core::Device for Device {
+    fn get_capabilities(&self) -> &core::Capabilities {
+        &self.capabilities
+    }
+
+    fn get_limits(&self) -> core::Limits {
+        core::Limits {
+            min_constant_buffer_offset_alignment: winapi::D3D12_CONSTANT_BUFFER_DATA_PLACEMENT_ALIGNMENT as u64,
+            min_storage_buffer_offset_alignment: 16,
+            max_patch_size: 32, // D3D12_IA_PATCH_MAX_CONTROL_POINT_COUNT
+            max_texture_size: 16384, // D3D12_REQ_TEXTURE2D_U_OR_V_DIMENSION
+            max_color_attachments: core::MAX_COLOR_TARGETS,
+            max_compute_group_count: [65535; 3], // D3D12_CS_DISPATCH_MAX_THREAD_GROUPS_PER_DIMENSION
+            max_compute_group_size: [1024, 1024, 64],
+            max_sampler_anisotropy: 16, // D3D12_MAX_MAXANISOTROPY
+            buffer_image_granularity: 1, // placed resources are 64KB aligned already
+        }
+    }
+
+    fn get_memory_types(&self) -> &[core::memory::MemoryType] {
+        &self.memory_types
+    }
+
+    fn get_memory_heaps(&self) -> &[u64] {
+        &self.memory_heaps
+    }
+
+    fn get_format_usage(&self, format: core::format::Format) -> core::image::Usage {
+        use core::image;
+        let support = match self.get_format_support(format) {
+            Some(support) => support,
+            None => return image::Usage::empty(),
+        };
+        let pairs = [
+            (winapi::D3D12_FORMAT_SUPPORT1_SHADER_SAMPLE, image::SAMPLED),
+            (winapi::D3D12_FORMAT_SUPPORT1_TYPED_UNORDERED_ACCESS_VIEW, image::STORAGE),
+            (winapi::D3D12_FORMAT_SUPPORT1_RENDER_TARGET, image::COLOR_ATTACHMENT),
+            (winapi::D3D12_FORMAT_SUPPORT1_DEPTH_STENCIL, image::DEPTH_STENCIL_ATTACHMENT),
+        ];
+        // copies are supported by all the formats
+        pairs.iter().fold(image::TRANSFER_SRC | image::TRANSFER_DST, |usage, &(flag, bit)| {
+            if support.Support1.0 & flag.0 != 0 { usage | bit } else { usage }
+        })
+    }
+
+    fn get_supported_samples(&self, format: core::format::Format, _usage: core::image::Usage) -> Vec<core::image::NumSamples> {
+        let format = match data::map_format(format, true) {
+            Some(format) => format,
+            None => return Vec::new(),
+        };
+        [1, 2, 4, 8, 16, 32].iter().cloned().filter(|&samples| {
+            let mut levels = winapi::D3D12_FEATURE_DATA_MULTISAMPLE_QUALITY_LEVELS {
+                Format: format,
+                SampleCount: samples as u32,
+                Flags: winapi::D3D12_MULTISAMPLE_QUALITY_LEVELS_FLAG_NONE,
+                NumQualityLevels: 0,
+            };
+            let hr = unsafe {
+                (*self.inner.as_mut_ptr()).CheckFeatureSupport(
+                    winapi::D3D12_FEATURE_MULTISAMPLE_QUALITY_LEVELS,
+                    &mut levels as *mut _ as *mut c_void,
+                    std::mem::size_of::<winapi::D3D12_FEATURE_DATA_MULTISAMPLE_QUALITY_LEVELS>() as u32)
+            };
+            winapi::SUCCEEDED(hr) && levels.NumQualityLevels > 0
+        }).collect()
+    }
+
+    fn get_format_properties(&self, format: core::format::Format) -> core::format::FormatProperties {
+        use core::image;
+        let usage = self.get_format_usage(format);
+        let support1 = self.get_format_support(format).map_or(0, |support| support.Support1.0);
+        let attachment_usage = usage & (image::COLOR_ATTACHMENT | image::DEPTH_STENCIL_ATTACHMENT);
+        core::format::FormatProperties {
+            usage: usage,
+            blendable: support1 & winapi::D3D12_FORMAT_SUPPORT1_BLENDABLE.0 != 0,
+            linear_filtering: support1 & winapi::D3D12_FORMAT_SUPPORT1_SHADER_SAMPLE.0 != 0,
+            samples: if attachment_usage.is_empty() {
+                Vec::new()
+            } else {
+                self.get_supported_samples(format, attachment_usage)
+            },
+        }
+    }
+}
 
+impl Device {
+    // Support of the `format` by the device, `None` if it has no DXGI equivalent
+    fn get_format_support(&self, format: core::format::Format) -> Option<winapi::D3D12_FEATURE_DATA_FORMAT_SUPPORT> {
+        let mut support = winapi::D3D12_FEATURE_DATA_FORMAT_SUPPORT {
+            Format: match data::map_format(format, false) {
+                Some(format) => format,
+                None => return None,
+            },
+            Support1: winapi::D3D12_FORMAT_SUPPORT1_NONE,
+            Support2: winapi::D3D12_FORMAT_SUPPORT2_NONE,
+        };
+        let hr = unsafe {
+            (*self.inner.as_mut_ptr()).CheckFeatureSupport(
+                winapi::D3D12_FEATURE_FORMAT_SUPPORT,
+                &mut support as *mut _ as *mut c_void,
+                std::mem::size_of::<winapi::D3D12_FEATURE_DATA_FORMAT_SUPPORT>() as u32)
+        };
+        if winapi::SUCCEEDED(hr) { Some(support) } else { None }
+    }
 }
 
 pub struct CommandQueue {
@@ -137,8 +295,11 @@ pub struct CommandQueue {
 
 impl core::CommandQueue for CommandQueue {
     type CommandBuffer = ();
+    type Semaphore = ();
+    type Fence = ();
 
-    fn submit(&mut self, cmd_buffer: &()) {
+    fn submit(&mut self, cmd_buffers: &[&()], wait: &[(&(), core::pso::PipelineStage)],
+              signal: &[&()], fence: Option<&()>) {
         unimplemented!()
     }
 }
@@ -205,16 +366,32 @@ pub struct SwapChain {
 }
 
 impl<'a> core::SwapChain for SwapChain{
-    fn acquire_frame(&mut self) -> core::Frame {
+    type Semaphore = ();
+
+    fn acquire_frame(&mut self, _signal: Option<&()>)
+        -> Result<(core::Frame, core::SwapChainStatus), core::SwapChainError>
+    {
         // TODO: we need to block this at some point?
         let index = self.next_frame;
         self.frame_queue.push_back(index);
         self.next_frame = (self.next_frame + 1) % 2; // TODO: remove magic swap buffer count
-        core::Frame::new(index)
+        Ok((core::Frame::new(index), core::SwapChainStatus::Optimal))
     }
 
-    fn present(&mut self) {
-        unsafe { self.inner.Present(1, 0); }
+    fn present(&mut self, _wait: &[&()]) -> Result<core::SwapChainStatus, core::SwapChainError> {
+        // TODO: wait for the semaphores, once fences are implemented
+        self.frame_queue.pop_front();
+        let hr = unsafe { self.inner.Present(1, 0) };
+        match hr {
+            winapi::DXGI_ERROR_DEVICE_REMOVED | winapi::DXGI_ERROR_DEVICE_RESET =>
+                Err(core::SwapChainError::DeviceLost),
+            winapi::DXGI_STATUS_OCCLUDED => Ok(core::SwapChainStatus::Suboptimal),
+            _ if winapi::SUCCEEDED(hr) => Ok(core::SwapChainStatus::Optimal),
+            _ => {
+                error!("error on present {:x}", hr);
+                Err(core::SwapChainError::SurfaceLost)
+            }
+        }
     }
 }
 
@@ -350,11 +527,24 @@ impl core::Backend for Backend {
 pub enum Resources { }
 
 impl core::Resources for Resources {
+    type Heap = ();
+    type UnboundBuffer = ();
     type Buffer = ();
+    type BufferView = ();
+    type UnboundImage = ();
     type ShaderLib = native::ShaderLib;
     type RenderPass = ();
+    type FrameBuffer = ();
     type PipelineSignature = native::PipelineSignature;
     type PipelineStateObject = native::Pipeline;
+    type PipelineCache = ();
+    type DescriptorSetLayout = ();
+    type DescriptorPool = ();
+    type DescriptorSet = ();
+    type QueryPool = ();
+    type Event = ();
+    type Semaphore = ();
+    type Fence = ();
     type Image = ();
     type ShaderResourceView = ();
     type UnorderedAccessView = ();
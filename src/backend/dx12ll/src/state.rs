@@ -150,7 +150,21 @@ pub fn map_primitive_topology(primitive: Primitive) -> D3D12_PRIMITIVE_TOPOLOGY_
     }
 }
 
-fn map_blend_factor(factor: state::Factor, scalar: bool) -> D3D12_BLEND {
+fn map_blend_factor(factor: pso::BlendFactor, scalar: bool) -> D3D12_BLEND {
+    match factor {
+        pso::BlendFactor::Factor(factor) => map_factor(factor, scalar),
+        pso::BlendFactor::Src1Color if !scalar => D3D12_BLEND_SRC1_COLOR,
+        pso::BlendFactor::OneMinusSrc1Color if !scalar => D3D12_BLEND_INV_SRC1_COLOR,
+        pso::BlendFactor::Src1Alpha => D3D12_BLEND_SRC1_ALPHA,
+        pso::BlendFactor::OneMinusSrc1Alpha => D3D12_BLEND_INV_SRC1_ALPHA,
+        _ => {
+            error!("Invalid blend factor requested for alpha: {:?}", factor);
+            D3D12_BLEND_ZERO
+        }
+    }
+}
+
+fn map_factor(factor: state::Factor, scalar: bool) -> D3D12_BLEND {
     use core::state::BlendValue::*;
     use core::state::Factor::*;
     match factor {
@@ -188,7 +202,29 @@ fn map_blend_op(equation: state::Equation) -> D3D12_BLEND_OP {
     }
 }
 
-pub fn map_render_targets(color_targets: &[Option<pso::ColorTargetDesc>; MAX_COLOR_TARGETS]) -> [D3D12_RENDER_TARGET_BLEND_DESC; 8] {
+fn map_logic_op(op: pso::LogicOp) -> D3D12_LOGIC_OP {
+    use core::pso::LogicOp::*;
+    match op {
+        Clear => D3D12_LOGIC_OP_CLEAR,
+        And => D3D12_LOGIC_OP_AND,
+        AndReverse => D3D12_LOGIC_OP_AND_REVERSE,
+        Copy => D3D12_LOGIC_OP_COPY,
+        AndInverted => D3D12_LOGIC_OP_AND_INVERTED,
+        NoOp => D3D12_LOGIC_OP_NOOP,
+        Xor => D3D12_LOGIC_OP_XOR,
+        Or => D3D12_LOGIC_OP_OR,
+        Nor => D3D12_LOGIC_OP_NOR,
+        Equivalent => D3D12_LOGIC_OP_EQUIV,
+        Invert => D3D12_LOGIC_OP_INVERT,
+        OrReverse => D3D12_LOGIC_OP_OR_REVERSE,
+        CopyInverted => D3D12_LOGIC_OP_COPY_INVERTED,
+        OrInverted => D3D12_LOGIC_OP_OR_INVERTED,
+        Nand => D3D12_LOGIC_OP_NAND,
+        Set => D3D12_LOGIC_OP_SET,
+    }
+}
+
+pub fn map_render_targets(color_targets: &[Option<pso::ColorTargetDesc>; MAX_COLOR_TARGETS], logic_op: Option<pso::LogicOp>) -> [D3D12_RENDER_TARGET_BLEND_DESC; 8] {
     let dummy_target = D3D12_RENDER_TARGET_BLEND_DESC {
         BlendEnable: FALSE,
         LogicOpEnable: FALSE,
@@ -208,6 +244,13 @@ pub fn map_render_targets(color_targets: &[Option<pso::ColorTargetDesc>; MAX_COL
 
         target.RenderTargetWriteMask = info.mask.bits() as UINT8;
 
+        // Blending can't be enabled together with the logic operation
+        if let Some(op) = logic_op {
+            target.LogicOpEnable = TRUE;
+            target.LogicOp = map_logic_op(op);
+            continue;
+        }
+
         if let Some(ref b) = info.color {
             target.BlendEnable = TRUE;
             target.SrcBlend = map_blend_factor(b.source, false);
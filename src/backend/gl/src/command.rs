@@ -100,6 +100,8 @@ pub enum Command {
     CopyBuffer(Buffer, Buffer,
                gl::types::GLintptr, gl::types::GLintptr,
                gl::types::GLsizeiptr),
+    CopyTextureToBuffer(NewTexture, c::texture::Kind, Option<c::texture::CubeFace>,
+                        c::texture::RawImageInfo, Buffer, gl::types::GLintptr),
     // resource updates
     UpdateBuffer(Buffer, DataPointer, usize),
     UpdateTexture(Texture, c::texture::Kind, Option<c::texture::CubeFace>,
@@ -111,6 +113,9 @@ pub enum Command {
     DrawIndexed(gl::types::GLenum, gl::types::GLenum, RawOffset,
                 c::VertexCount, c::VertexCount, Option<command::InstanceParams>),
     _Blit(Rect, Rect, Mirror, usize),
+    PushDebugGroup(DataPointer),
+    PopDebugGroup,
+    InsertDebugMarker(DataPointer),
 }
 
 pub const COLOR_DEFAULT: s::Color = s::Color {
@@ -337,6 +342,14 @@ impl command::Buffer<Resources> for CommandBuffer {
                                           size_bytes as gl::types::GLsizeiptr));
     }
 
+    fn copy_texture_to_buffer(&mut self, src: NewTexture, kind: c::texture::Kind,
+                              face: Option<c::texture::CubeFace>, img: c::texture::RawImageInfo,
+                              dst: Buffer, dst_offset_bytes: usize) {
+        self.buf.push(Command::BindFrameBuffer(gl::READ_FRAMEBUFFER, self.fbo));
+        self.buf.push(Command::CopyTextureToBuffer(src, kind, face, img, dst,
+                                                   dst_offset_bytes as gl::types::GLintptr));
+    }
+
     fn update_buffer(&mut self, buf: Buffer, data: &[u8], offset_bytes: usize) {
         let ptr = self.data.add(data);
         self.buf.push(Command::UpdateBuffer(buf, ptr, offset_bytes));
@@ -393,4 +406,18 @@ impl command::Buffer<Resources> for CommandBuffer {
         self.buf.push(Command::DrawIndexed(self.cache.primitive,
             gl_index, RawOffset(offset as *const gl::types::GLvoid), count, base, instances));
     }
+
+    fn begin_debug_group(&mut self, name: &str) {
+        let ptr = self.data.add(name.as_bytes());
+        self.buf.push(Command::PushDebugGroup(ptr));
+    }
+
+    fn end_debug_group(&mut self) {
+        self.buf.push(Command::PopDebugGroup);
+    }
+
+    fn insert_debug_marker(&mut self, name: &str) {
+        let ptr = self.data.add(name.as_bytes());
+        self.buf.push(Command::InsertDebugMarker(ptr));
+    }
 }
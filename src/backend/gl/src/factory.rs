@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::ops::Range;
 use std::rc::Rc;
 use std::{slice, ptr};
 
@@ -23,6 +24,7 @@ use core::handle::{self, Producer};
 use core::target::{Layer, Level};
 
 use command::{CommandBuffer, COLOR_DEFAULT};
+use shade::ProgramBinary;
 use {Resources as R, Share, OutputMerger};
 use {Buffer, BufferElement, FatSampler, NewTexture,
      PipelineState, ResourceView, TargetView, Fence};
@@ -92,6 +94,23 @@ impl Factory {
         CommandBuffer::new(self.create_fbo_internal())
     }
 
+    /// Retrieve the binary of a linked program, to skip compiling and linking
+    /// its shaders the next time with `create_program_from_binary`.
+    /// Returns `None` if the driver doesn't support program binaries.
+    pub fn get_program_binary(&mut self, program: &handle::Program<R>) -> Option<ProgramBinary> {
+        let name = *self.frame_handles.ref_program(program);
+        ::shade::get_program_binary(&self.share.context, &self.share.private_caps, name)
+    }
+
+    /// Create a program from a binary retrieved with `get_program_binary`.
+    /// The `usage` is the one of the shader set the program was linked from.
+    pub fn create_program_from_binary(&mut self, binary: &ProgramBinary, usage: d::shade::Usage)
+                                      -> Result<handle::Program<R>, d::shade::CreateProgramError> {
+        ::shade::create_program_from_binary(&self.share.context, &self.share.capabilities,
+                                            &self.share.private_caps, binary, usage)
+            .map(|(name, info)| self.share.handles.borrow_mut().make_program(name, info))
+    }
+
     fn create_fbo_internal(&mut self) -> gl::types::GLuint {
         let gl = &self.share.context;
         let mut name = 0 as ::FrameBuffer;
@@ -275,20 +294,61 @@ impl mapping::Gate<R> for MappingGate {
     unsafe fn mut_slice<'a, 'b, T>(&'a self, len: usize) -> &'b mut [T] {
         slice::from_raw_parts_mut(self.pointer as *mut T, len)
     }
+
+    unsafe fn copy_from<T: Copy>(&self, offset: usize, data: &[T]) {
+        ptr::copy_nonoverlapping(data.as_ptr(), (self.pointer as *mut T).offset(offset as isize), data.len());
+    }
+
+    unsafe fn flush(&mut self, range: Range<usize>) {
+        // temporary mappings are flushed when unmapped
+        if let MappingKind::Persistent(ref mut status) = self.kind {
+            status.cpu_wrote(range);
+        }
+    }
+}
+
+pub fn persistent_ensure_mapped(pointer: &mut *mut ::std::os::raw::c_void,
+                                target: gl::types::GLenum,
+                                buffer: Buffer,
+                                size: usize,
+                                access: memory::Access,
+                                gl: &gl::Gl)
+                                -> Result<(), mapping::Error> {
+    if pointer.is_null() {
+        let gl_access = access_to_map_bits(access) |
+                        gl::MAP_PERSISTENT_BIT |
+                        gl::MAP_FLUSH_EXPLICIT_BIT;
+        unsafe {
+            gl.BindBuffer(target, buffer);
+            *pointer = gl.MapBufferRange(target, 0, size as gl::types::GLsizeiptr, gl_access)
+                as *mut ::std::os::raw::c_void;
+        }
+        if pointer.is_null() {
+            error!("Unable to map the buffer {}, error {:x}", buffer, unsafe { gl.GetError() });
+            return Err(mapping::Error::MapFailed);
+        }
+    }
+    Ok(())
 }
 
 pub fn temporary_ensure_mapped(pointer: &mut *mut ::std::os::raw::c_void,
                                target: gl::types::GLenum,
                                buffer: Buffer,
                                access: memory::Access,
-                               gl: &gl::Gl) {
+                               gl: &gl::Gl)
+                               -> Result<(), mapping::Error> {
     if pointer.is_null() {
         unsafe {
             gl.BindBuffer(target, buffer);
             *pointer = gl.MapBuffer(target, access_to_gl(access))
                 as *mut ::std::os::raw::c_void;
         }
+        if pointer.is_null() {
+            error!("Unable to map the buffer {}, error {:x}", buffer, unsafe { gl.GetError() });
+            return Err(mapping::Error::MapFailed);
+        }
     }
+    Ok(())
 }
 
 pub fn temporary_ensure_unmapped(pointer: &mut *mut ::std::os::raw::c_void,
@@ -516,15 +576,11 @@ impl f::Factory<R> for Factory {
         let gl = &self.share.context;
         let handles = &mut self.frame_handles;
         unsafe {
-            mapping::read(buf.raw(), |mapping| match mapping.kind {
-                MappingKind::Persistent(ref mut status) =>
-                    status.cpu_access(|fence| wait_fence(&handles.ref_fence(&fence), gl)),
-                MappingKind::Temporary =>
-                    temporary_ensure_mapped(&mut mapping.pointer,
-                                            role_to_target(buf.get_info().role),
-                                            *buf.raw().resource(),
-                                            memory::READ,
-                                            gl),
+            mapping::read(buf.raw(), |mapping| {
+                if let MappingKind::Persistent(ref mut status) = mapping.kind {
+                    status.cpu_access(|fence| wait_fence(&handles.ref_fence(&fence), gl));
+                }
+                ensure_mapped(mapping, buf.raw(), gl)
             })
         }
     }
@@ -537,18 +593,119 @@ impl f::Factory<R> for Factory {
         let gl = &self.share.context;
         let handles = &mut self.frame_handles;
         unsafe {
-            mapping::write(buf.raw(), |mapping| match mapping.kind {
-                MappingKind::Persistent(ref mut status) =>
-                    status.cpu_write_access(|fence| wait_fence(&handles.ref_fence(&fence), gl)),
-                MappingKind::Temporary =>
-                    temporary_ensure_mapped(&mut mapping.pointer,
-                                            role_to_target(buf.get_info().role),
-                                            *buf.raw().resource(),
-                                            memory::WRITE,
-                                            gl),
+            mapping::write(buf.raw(), |mapping| {
+                if let MappingKind::Persistent(ref mut status) = mapping.kind {
+                    status.cpu_write_access(|fence| wait_fence(&handles.ref_fence(&fence), gl));
+                }
+                ensure_mapped(mapping, buf.raw(), gl)
             })
         }
     }
+
+    unsafe fn write_mapping_unsynchronized<'a, 'b, T>(&'a mut self, buf: &'b handle::Buffer<R, T>)
+                                                      -> Result<mapping::Unsynchronized<'b, R, T>,
+                                                                mapping::Error>
+        where T: Copy
+    {
+        let gl = &self.share.context;
+        mapping::unsynchronized(buf.raw(), |mapping| match mapping.kind {
+            MappingKind::Persistent(_) => ensure_mapped(mapping, buf.raw(), gl),
+            MappingKind::Temporary => Err(mapping::Error::NotPersistent),
+        })
+    }
+
+    fn try_read_mapping<'a, 'b, T>(&'a mut self, buf: &'b handle::Buffer<R, T>)
+                                   -> Result<mapping::Reader<'b, R, T>,
+                                             mapping::Error>
+        where T: Copy
+    {
+        let gl = &self.share.context;
+        let handles = &mut self.frame_handles;
+        unsafe {
+            mapping::read(buf.raw(), |mapping| {
+                // temporary mappings wait for the GPU anyway
+                if let MappingKind::Persistent(ref mut status) = mapping.kind {
+                    if !status.try_cpu_access(|fence| is_fence_signaled(&handles.ref_fence(fence), gl)) {
+                        return Err(mapping::Error::WouldBlock);
+                    }
+                }
+                ensure_mapped(mapping, buf.raw(), gl)
+            })
+        }
+    }
+
+    fn try_write_mapping<'a, 'b, T>(&'a mut self, buf: &'b handle::Buffer<R, T>)
+                                    -> Result<mapping::Writer<'b, R, T>,
+                                              mapping::Error>
+        where T: Copy
+    {
+        let gl = &self.share.context;
+        let handles = &mut self.frame_handles;
+        unsafe {
+            mapping::write(buf.raw(), |mapping| {
+                if let MappingKind::Persistent(ref mut status) = mapping.kind {
+                    if !status.try_cpu_access(|fence| is_fence_signaled(&handles.ref_fence(fence), gl)) {
+                        return Err(mapping::Error::WouldBlock);
+                    }
+                }
+                ensure_mapped(mapping, buf.raw(), gl)
+            })
+        }
+    }
+
+    fn unmap(&mut self, buf: &handle::RawBuffer<R>) -> Result<(), mapping::Error> {
+        let gl = &self.share.context;
+        let target = role_to_target(buf.get_info().role);
+        unsafe {
+            mapping::with_access(buf, |mapping| {
+                if let MappingKind::Persistent(ref mut status) = mapping.kind {
+                    if !mapping.pointer.is_null() {
+                        // explicit flushes are only possible while mapped
+                        status.ensure_flushed(|range| {
+                            gl.BindBuffer(target, *buf.resource());
+                            gl.FlushMappedBufferRange(target,
+                                                      range.start as gl::types::GLintptr,
+                                                      (range.end - range.start) as gl::types::GLsizeiptr);
+                        });
+                    }
+                }
+                temporary_ensure_unmapped(&mut mapping.pointer, target, *buf.resource(), gl);
+                Ok(())
+            })
+        }
+    }
+
+    fn remap(&mut self, buf: &handle::RawBuffer<R>) -> Result<(), mapping::Error> {
+        let gl = &self.share.context;
+        unsafe {
+            mapping::with_access(buf, |mapping| ensure_mapped(mapping, buf, gl))
+        }
+    }
+}
+
+/// Map the buffer again if it has been unmapped, either on submission for
+/// temporary mappings, or explicitly with `Factory::unmap`.
+fn ensure_mapped(mapping: &mut MappingGate, buf: &handle::RawBuffer<R>, gl: &gl::Gl)
+                 -> Result<(), mapping::Error> {
+    let target = role_to_target(buf.get_info().role);
+    let access = match buf.get_info().usage {
+        memory::Usage::Download => memory::READ,
+        _ => memory::WRITE,
+    };
+    match mapping.kind {
+        MappingKind::Persistent(_) =>
+            persistent_ensure_mapped(&mut mapping.pointer, target, *buf.resource(),
+                                     buf.get_info().size, access, gl),
+        MappingKind::Temporary =>
+            temporary_ensure_mapped(&mut mapping.pointer, target, *buf.resource(), access, gl),
+    }
+}
+
+pub fn is_fence_signaled(fence: &Fence, gl: &gl::Gl) -> bool {
+    let status = unsafe {
+        gl.ClientWaitSync(fence.0, gl::SYNC_FLUSH_COMMANDS_BIT, 0)
+    };
+    status != gl::TIMEOUT_EXPIRED
 }
 
 pub fn wait_fence(fence: &Fence, gl: &gl::Gl) {
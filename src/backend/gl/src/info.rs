@@ -170,6 +170,8 @@ pub struct PrivateCaps {
     pub program_interface_supported: bool,
     pub buffer_storage_supported: bool,
     pub clear_buffer_supported: bool,
+    pub program_binary_supported: bool,
+    pub debug_output_supported: bool,
 }
 
 /// OpenGL implementation information
@@ -257,6 +259,9 @@ pub fn get(gl: &gl::Gl) -> (Info, Capabilities, PrivateCaps) {
         program_interface_supported:       info.is_version_or_extension_supported(4, 3, "GL_ARB_program_interface_query"),
         buffer_storage_supported:          info.is_version_or_extension_supported(4, 4, "GL_ARB_buffer_storage"),
         clear_buffer_supported:            info.is_version_supported(3, 0) | info.is_embedded_version_supported(3, 0),
+        program_binary_supported:          info.is_version_or_extension_supported(4, 1, "GL_ARB_get_program_binary") |
+                                           info.is_embedded_version_supported(3, 0),
+        debug_output_supported:            info.is_version_or_extension_supported(4, 3, "GL_KHR_debug"),
     };
     (info, caps, private)
 }
@@ -34,6 +34,7 @@ use factory::MappingKind;
 pub use self::command::CommandBuffer;
 pub use self::factory::Factory;
 pub use self::info::{Info, PlatformName, Version};
+pub use self::shade::ProgramBinary;
 
 mod command;
 mod factory;
@@ -589,6 +590,26 @@ impl Device {
                                          size);
                 }
             },
+            Command::CopyTextureToBuffer(src, kind, face, ref image, dst, offset) => {
+                let gl = &self.share.context;
+                let attachment = tex::format_to_attachment(image.format);
+                match src {
+                    NewTexture::Surface(s) => unsafe {
+                        gl.FramebufferRenderbuffer(gl::READ_FRAMEBUFFER, attachment, gl::RENDERBUFFER, s);
+                    },
+                    NewTexture::Texture(t) =>
+                        tex::attach_read_texture(gl, t, kind, face, image, attachment),
+                }
+                if let Err(e) = tex::read_pixels(gl, attachment, image, dst, offset) {
+                    error!("GL: copy of {:?} to Buffer({}) failed: {:?}", src, dst, e);
+                }
+                match src {
+                    NewTexture::Surface(_) => unsafe {
+                        gl.FramebufferRenderbuffer(gl::READ_FRAMEBUFFER, attachment, gl::RENDERBUFFER, 0);
+                    },
+                    NewTexture::Texture(_) => self.unbind_target(gl::READ_FRAMEBUFFER, attachment),
+                }
+            },
             Command::UpdateBuffer(buffer, pointer, offset) => {
                 let data = data_buf.get(pointer);
                 factory::update_sub_buffer(&self.share.context, buffer,
@@ -700,6 +721,30 @@ impl Device {
                     },
                 }
             },
+            Command::PushDebugGroup(pointer) => {
+                if self.share.private_caps.debug_output_supported {
+                    let name = data_buf.get(pointer);
+                    unsafe {
+                        self.share.context.PushDebugGroup(gl::DEBUG_SOURCE_APPLICATION, 0,
+                            name.len() as gl::types::GLsizei, name.as_ptr() as *const gl::types::GLchar);
+                    }
+                }
+            },
+            Command::PopDebugGroup => {
+                if self.share.private_caps.debug_output_supported {
+                    unsafe { self.share.context.PopDebugGroup() };
+                }
+            },
+            Command::InsertDebugMarker(pointer) => {
+                if self.share.private_caps.debug_output_supported {
+                    let name = data_buf.get(pointer);
+                    unsafe {
+                        self.share.context.DebugMessageInsert(gl::DEBUG_SOURCE_APPLICATION,
+                            gl::DEBUG_TYPE_MARKER, 0, gl::DEBUG_SEVERITY_NOTIFICATION,
+                            name.len() as gl::types::GLsizei, name.as_ptr() as *const gl::types::GLchar);
+                    }
+                }
+            },
             Command::_Blit(mut s_rect, d_rect, mirror, _) => {
                 type GLint = gl::types::GLint;
                 // mirror
@@ -783,10 +828,9 @@ impl Device {
                 _ => unreachable!(),
             };
 
-            status.ensure_flushed(|| unsafe {
+            status.ensure_flushed(|range| unsafe {
                 gl.BindBuffer(target, *buffer.resource());
-                let size = buffer.get_info().size as isize;
-                gl.FlushMappedBufferRange(target, 0, size);
+                gl.FlushMappedBufferRange(target, range.start as isize, (range.end - range.start) as isize);
             });
         }
     }
@@ -474,6 +474,18 @@ pub fn get_program_log(gl: &gl::Gl, name: super::Program) -> String {
     }
 }
 
+/// Linked program binary, as retrieved from the driver with `Factory::get_program_binary`.
+///
+/// Binaries are only valid for the driver that produced them: an updated driver
+/// may refuse them, in which case the program needs to be linked from its shaders again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgramBinary {
+    /// Driver-specific format of the binary.
+    pub format: gl::types::GLenum,
+    /// Binary data.
+    pub data: Vec<u8>,
+}
+
 pub fn create_program(gl: &gl::Gl, caps: &c::Capabilities, private: &PrivateCaps,
                       shaders: &[super::Shader], usage: s::Usage)
                       -> Result<(::Program, s::ProgramInfo), s::CreateProgramError> {
@@ -491,9 +503,59 @@ pub fn create_program(gl: &gl::Gl, caps: &c::Capabilities, private: &PrivateCaps
          }
     }
 
+    if private.program_binary_supported {
+        unsafe { gl.ProgramParameteri(name, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as gl::types::GLint) };
+    }
+
     unsafe { gl.LinkProgram(name) };
     info!("\tLinked program {}", name);
 
+    query_program(gl, caps, private, name, usage)
+}
+
+pub fn create_program_from_binary(gl: &gl::Gl, caps: &c::Capabilities, private: &PrivateCaps,
+                                  binary: &ProgramBinary, usage: s::Usage)
+                                  -> Result<(::Program, s::ProgramInfo), s::CreateProgramError> {
+    if !private.program_binary_supported {
+        return Err("Program binaries are not supported".into());
+    }
+    let name = unsafe { gl.CreateProgram() };
+    unsafe {
+        gl.ProgramBinary(name, binary.format, binary.data.as_ptr() as *const _,
+                         binary.data.len() as gl::types::GLsizei);
+    }
+    info!("\tLoaded program {} from a binary", name);
+
+    let result = query_program(gl, caps, private, name, usage);
+    if result.is_err() {
+        unsafe { gl.DeleteProgram(name) };
+    }
+    result
+}
+
+pub fn get_program_binary(gl: &gl::Gl, private: &PrivateCaps, name: super::Program) -> Option<ProgramBinary> {
+    if !private.program_binary_supported {
+        return None;
+    }
+    let mut length = get_program_iv(gl, name, gl::PROGRAM_BINARY_LENGTH);
+    if length <= 0 {
+        return None;
+    }
+    let mut format = 0;
+    let mut data = vec![0u8; length as usize];
+    unsafe {
+        gl.GetProgramBinary(name, length, &mut length, &mut format, data.as_mut_ptr() as *mut _);
+    }
+    data.truncate(length as usize);
+    Some(ProgramBinary {
+        format: format,
+        data: data,
+    })
+}
+
+fn query_program(gl: &gl::Gl, caps: &c::Capabilities, private: &PrivateCaps,
+                 name: super::Program, usage: s::Usage)
+                 -> Result<(::Program, s::ProgramInfo), s::CreateProgramError> {
     let status = get_program_iv(gl, name, gl::LINK_STATUS);
     let log = get_program_log(gl, name);
     if status != 0 {
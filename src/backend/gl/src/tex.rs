@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use {gl, Surface, Texture, Sampler};
+use {gl, Buffer, Surface, Texture, Sampler};
 use gl::types::{GLenum, GLuint, GLint, GLfloat, GLsizei, GLvoid};
 use state;
 use core::memory::SHADER_RESOURCE;
@@ -65,7 +65,7 @@ fn format_to_glpixel(format: NewFormat) -> GLenum {
         S::R16_G16_B16 | S::R32_G32_B32 | S::R5_G6_B5 | S::R11_G11_B10 => rgb,
         S::R8_G8_B8_A8 | S::R16_G16_B16_A16 | S::R32_G32_B32_A32 |
         S::R4_G4_B4_A4 | S::R5_G5_B5_A1 | S::R10_G10_B10_A2 => rgba,
-        S::D24_S8 => gl::DEPTH_STENCIL,
+        S::D24_S8 | S::D32_S8 => gl::DEPTH_STENCIL,
         S::D16 | S::D24 | S::D32 => gl::DEPTH,
         S::B8_G8_R8_A8 => unimplemented!(), // TODO
     }
@@ -97,6 +97,7 @@ fn format_to_gltype(format: NewFormat) -> Result<GLenum, ()> {
         S::D24 => gl::UNSIGNED_INT,
         S::D24_S8 => gl::UNSIGNED_INT_24_8,
         S::D32 => gl::FLOAT,
+        S::D32_S8 => gl::FLOAT_32_UNSIGNED_INT_24_8_REV,
     })
 }
 
@@ -214,6 +215,7 @@ fn format_to_glfull(format: NewFormat) -> Result<GLenum, ()> {
         S::D24 => gl::DEPTH_COMPONENT24,
         S::D24_S8 => gl::DEPTH24_STENCIL8,
         S::D32 => gl::DEPTH_COMPONENT32F,
+        S::D32_S8 => gl::DEPTH32F_STENCIL8,
     })
 }
 
@@ -654,6 +656,57 @@ pub fn update_texture(gl: &gl::Gl, name: Texture,
     update_texture_impl(gl, kind, target, pixel_format, data_type, img, data)
 }
 
+/// Framebuffer attachment to read the texels of the `format` from.
+pub fn format_to_attachment(format: NewFormat) -> GLenum {
+    use core::format::SurfaceType as S;
+    match format.0 {
+        S::D16 | S::D24 | S::D32 => gl::DEPTH_ATTACHMENT,
+        S::D24_S8 | S::D32_S8 => gl::DEPTH_STENCIL_ATTACHMENT,
+        _ => gl::COLOR_ATTACHMENT0,
+    }
+}
+
+/// Attach the image of a texture to the read framebuffer.
+pub fn attach_read_texture(gl: &gl::Gl, name: Texture,
+                           kind: t::Kind, face: Option<t::CubeFace>,
+                           img: &t::RawImageInfo, attachment: GLenum) {
+    let level = img.mipmap as GLint;
+    unsafe {
+        match (kind, face) {
+            (t::Kind::Cube(_), Some(f)) =>
+                gl.FramebufferTexture2D(gl::READ_FRAMEBUFFER, attachment,
+                                        cube_face_to_gl(f), name, level),
+            (_, _) if kind.get_num_slices().is_some() || kind.get_dimensions().2 > 1 =>
+                gl.FramebufferTextureLayer(gl::READ_FRAMEBUFFER, attachment,
+                                           name, level, img.zoffset as GLint),
+            (_, _) =>
+                gl.FramebufferTexture(gl::READ_FRAMEBUFFER, attachment, name, level),
+        }
+    }
+}
+
+/// Read a region of the read framebuffer into a buffer, at `offset` bytes.
+pub fn read_pixels(gl: &gl::Gl, attachment: GLenum, img: &t::RawImageInfo,
+                   buffer: Buffer, offset: isize) -> Result<(), t::CreationError> {
+    let pixel_format = format_to_glpixel(img.format);
+    let data_type = match format_to_gltype(img.format) {
+        Ok(t) => t,
+        Err(_) => return Err(t::CreationError::Format(img.format.0, Some(img.format.1))),
+    };
+    unsafe {
+        if attachment == gl::COLOR_ATTACHMENT0 {
+            gl.ReadBuffer(attachment);
+        }
+        gl.BindBuffer(gl::PIXEL_PACK_BUFFER, buffer);
+        gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl.ReadPixels(img.xoffset as GLint, img.yoffset as GLint,
+                      img.width as GLsizei, img.height as GLsizei,
+                      pixel_format, data_type, offset as *mut GLvoid);
+        gl.BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+    }
+    Ok(())
+}
+
 pub fn init_texture_data(gl: &gl::Gl, name: Texture, desc: t::Info, channel: ChannelType,
                          data: &[&[u8]]) -> Result<(), t::CreationError> {
     let opt_slices = desc.kind.get_num_slices();
@@ -324,6 +324,13 @@ impl command::Buffer<Resources> for CommandBuffer {
         unimplemented!()
     }
 
+    fn copy_texture_to_buffer(&mut self, _: Texture, _: texture::Kind,
+                              _: Option<texture::CubeFace>, _: texture::RawImageInfo,
+                              _: Buffer, _: usize) {
+        // TODO: encode with a blit command encoder
+        error!("Copying a texture to a buffer is not supported");
+    }
+
     fn update_buffer(&mut self, buf: Buffer, data: &[u8], offset: usize) {
         use map::{map_buffer_usage};
 
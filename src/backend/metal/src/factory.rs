@@ -14,7 +14,7 @@
 
 use std::os::raw::c_void;
 use std::sync::Arc;
-use std::{mem, slice, str};
+use std::{mem, ptr, slice, str};
 use std::path::Path;
 
 // use cocoa::base::{selector, class};
@@ -54,6 +54,10 @@ impl mapping::Gate<Resources> for RawMapping {
     unsafe fn mut_slice<'a, 'b, T>(&'a self, len: usize) -> &'b mut [T] {
         slice::from_raw_parts_mut(self.pointer as *mut T, len)
     }
+
+    unsafe fn copy_from<T: Copy>(&self, offset: usize, data: &[T]) {
+        ptr::copy_nonoverlapping(data.as_ptr(), (self.pointer as *mut T).offset(offset as isize), data.len());
+    }
 }
 
 
@@ -78,6 +82,16 @@ impl Factory {
         CommandBuffer::new(self.device, self.queue)
     }
 
+    /// Create a render target view of the window back buffer. The texture pointer
+    /// is updated by the window with every new drawable.
+    pub fn wrap_back_buffer(&mut self, back_buffer: *mut MTLTexture, info: core::texture::Info,
+                            desc: core::texture::RenderDesc) -> handle::RawRenderTargetView<Resources> {
+        use core::Factory;
+        let raw_tex = Texture(native::Texture(back_buffer), info.usage);
+        let color_tex = self.share.handles.borrow_mut().make_texture(raw_tex, info);
+        self.view_texture_as_render_target_raw(&color_tex, desc).unwrap()
+    }
+
     fn create_buffer_internal(&self,
                               info: buffer::Info,
                               raw_data: Option<*const c_void>)
@@ -97,13 +111,19 @@ impl Factory {
             self.device.new_buffer(info.size as u64, usage)
         };
 
-        let buf = Buffer(native::Buffer(Box::into_raw(Box::new(raw_buf))), info.usage, info.bind);
-
+        // staging buffers live in shared storage, so the mapping is coherent
         // TODO(fkaa): if we have a way to track buffers in use (added on
         //             scheduling of command buffers, removed on completion),
         //             we could block while in use on both sides. would need
         //             a state for each mode (`in-use` vs. `mapped`).
-        let mapping = None;
+        let mapping = match info.usage {
+            memory::Usage::Upload | memory::Usage::Download => Some(RawMapping {
+                pointer: raw_buf.contents() as *mut c_void,
+            }),
+            memory::Usage::Data | memory::Usage::Dynamic => None,
+        };
+
+        let buf = Buffer(native::Buffer(Box::into_raw(Box::new(raw_buf))), info.usage, info.bind);
 
         Ok(self.share.handles.borrow_mut().make_buffer(buf, info, mapping))
     }
@@ -812,7 +832,7 @@ impl core::Factory<Resources> for Factory {
                                          mapping::Error>
         where T: Copy
     {
-        unimplemented!()
+        unsafe { mapping::read(buf.raw(), |_| Ok(())) }
     }
 
     fn write_mapping<'a, 'b, T>(&'a mut self, buf: &'b handle::Buffer<Resources, T>)
@@ -820,6 +840,14 @@ impl core::Factory<Resources> for Factory {
                                           mapping::Error>
         where T: Copy
     {
-        unimplemented!()
+        unsafe { mapping::write(buf.raw(), |_| Ok(())) }
+    }
+
+    unsafe fn write_mapping_unsynchronized<'a, 'b, T>(&'a mut self, buf: &'b handle::Buffer<Resources, T>)
+                                                      -> Result<mapping::Unsynchronized<'b, Resources, T>,
+                                                                mapping::Error>
+        where T: Copy
+    {
+        mapping::unsynchronized(buf.raw(), |_| Ok(()))
     }
 }
@@ -335,6 +335,14 @@ pub fn map_format(format: Format, is_target: bool) -> Option<MTLPixelFormat> {
                 _ => return None,
             }
         }
+        D32_S8 => {
+            match (is_target, format.1) {
+                (true, _) => Depth32Float_Stencil8,
+                (false, Float) => Depth32Float_Stencil8,
+                (false, Uint) => return None,
+                _ => return None,
+            }
+        }
     })
 }
 
@@ -353,6 +361,7 @@ pub fn map_channel_hint(hint: SurfaceType) -> Option<ChannelType> {
         D24 => Unorm,
         D24_S8 => Unorm,
         D32 => Float,
+        D32_S8 => Float,
     })
 }
 
@@ -424,7 +433,7 @@ pub fn map_depth_surface(surface: SurfaceType) -> Option<(MTLPixelFormat, bool)>
         //D16 => (Depth16Unorm, false), TODO: add this depth format to metal-rs, and feature gate it
         D32 => (Depth32Float, false),
         D24_S8 => (Depth24Unorm_Stencil8, true),
-        // D32_S8 => (Depth32Float_Stencil8, true), TODO: add this depth format to gfx (DX11 supports as well)
+        D32_S8 => (Depth32Float_Stencil8, true),
         _ => return None,
     })
 }
@@ -547,8 +556,8 @@ pub fn map_buffer_usage(usage: Usage, bind: Bind) -> MTLResourceOptions {
             MTLResourceCPUCacheModeDefaultCache | MTLResourceStorageModeManaged
         },
         Usage::Dynamic => MTLResourceCPUCacheModeDefaultCache | MTLResourceStorageModeManaged,
-        Usage::Upload => map_access(memory::WRITE) | MTLResourceStorageModeManaged,
-        Usage::Download => map_access(memory::READ) | MTLResourceStorageModeManaged,
+        Usage::Upload => map_access(memory::WRITE) | MTLResourceStorageModeShared,
+        Usage::Download => map_access(memory::READ) | MTLResourceStorageModeShared,
     }
 }
 
@@ -12,16 +12,112 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{mem, ptr};
+use std::{cmp, fmt, iter, mem, ptr};
 use std::collections::hash_map::{HashMap, Entry};
+use std::error::Error;
+use std::ffi::CString;
+use std::os::raw::c_void;
 use vk;
 use core::{self, pso, shade, target, texture as tex, handle};
 use core::command::{self, AccessInfo, AccessGuard};
 use core::state::RefValues;
 use core::{IndexType, VertexCount, SubmissionResult};
-use native;
-use {Resources, Share, SharePointer};
+use {data, native};
+use {Fence, Resources, Share, SharePointer};
+
+/// An error in the set of vertex buffers bound to a command buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VertexBindingError {
+    /// The binding slot is not below the `maxVertexInputBindings` limit of the device.
+    OutOfLimits(usize),
+}
+
+impl fmt::Display for VertexBindingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VertexBindingError::OutOfLimits(slot) => write!(f, "{}: slot = {}", self.description(), slot),
+        }
+    }
+}
+
+impl Error for VertexBindingError {
+    fn description(&self) -> &str {
+        match *self {
+            VertexBindingError::OutOfLimits(_) => "The vertex buffer slot exceeds the device limits",
+        }
+    }
+}
+
+/// Largest update `vkCmdUpdateBuffer` accepts, bigger ones go through a staging buffer.
+const MAX_INLINE_UPDATE_SIZE: usize = 65536;
+/// Minimal size of the staging buffers a command buffer allocates.
+const STAGING_CHUNK_SIZE: u64 = 1 << 20;
+
+/// Persistently mapped host memory, the buffer updates are copied from.
+struct StagingChunk {
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    pointer: *mut c_void,
+    size: u64,
+}
+unsafe impl Send for StagingChunk {}
+
+impl StagingChunk {
+    fn new(share: &Share, memory_type: u32, size: u64) -> StagingChunk {
+        let (dev, vk) = share.get_device();
+        let info = vk::BufferCreateInfo {
+            sType: vk::STRUCTURE_TYPE_BUFFER_CREATE_INFO,
+            pNext: ptr::null(),
+            flags: 0,
+            size: size,
+            usage: vk::BUFFER_USAGE_TRANSFER_SRC_BIT,
+            sharingMode: vk::SHARING_MODE_EXCLUSIVE,
+            queueFamilyIndexCount: 0,
+            pQueueFamilyIndices: ptr::null(),
+        };
+        let mut buffer = 0;
+        assert_eq!(vk::SUCCESS, unsafe {
+            vk.CreateBuffer(dev, &info, ptr::null(), &mut buffer)
+        });
+        let reqs = unsafe {
+            let mut out = mem::zeroed();
+            vk.GetBufferMemoryRequirements(dev, buffer, &mut out);
+            out
+        };
+        let alloc_info = vk::MemoryAllocateInfo {
+            sType: vk::STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO,
+            pNext: ptr::null(),
+            allocationSize: reqs.size,
+            memoryTypeIndex: memory_type,
+        };
+        let mut memory = 0;
+        assert_eq!(vk::SUCCESS, unsafe {
+            vk.AllocateMemory(dev, &alloc_info, ptr::null(), &mut memory)
+        });
+        let mut pointer = ptr::null_mut();
+        assert_eq!(vk::SUCCESS, unsafe {
+            vk.BindBufferMemory(dev, buffer, memory, 0)
+        });
+        assert_eq!(vk::SUCCESS, unsafe {
+            vk.MapMemory(dev, memory, 0, vk::WHOLE_SIZE, 0, &mut pointer)
+        });
+        StagingChunk {
+            buffer: buffer,
+            memory: memory,
+            pointer: pointer,
+            size: size,
+        }
+    }
 
+    fn destroy(&self, share: &Share) {
+        let (dev, vk) = share.get_device();
+        unsafe {
+            vk.UnmapMemory(dev, self.memory);
+            vk.DestroyBuffer(dev, self.buffer, ptr::null());
+            vk.FreeMemory(dev, self.memory, ptr::null());
+        }
+    }
+}
 
 pub struct Buffer {
     inner: vk::CommandBuffer,
@@ -29,13 +125,18 @@ pub struct Buffer {
     family: u32,
     share: SharePointer,
     last_render_pass: vk::RenderPass,
+    in_render_pass: bool,
     fbo_cache: HashMap<pso::PixelTargetSet<Resources>, vk::Framebuffer>,
     temp_attachments: Vec<vk::ImageView>,
+    staging_memory_type: u32,
+    /// Staging buffers used by the recorded commands, the last one being filled.
+    staging: Vec<StagingChunk>,
+    staging_offset: u64,
 }
 
 impl Buffer {
     #[doc(hidden)]
-    pub fn new(pool: vk::CommandPool, family: u32, share: SharePointer) -> Buffer {
+    pub fn new(pool: vk::CommandPool, family: u32, share: SharePointer, staging_memory_type: u32) -> Buffer {
         let alloc_info = vk::CommandBufferAllocateInfo {
             sType: vk::STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
             pNext: ptr::null(),
@@ -65,8 +166,12 @@ impl Buffer {
             family: family,
             share: share,
             last_render_pass: 0,
+            in_render_pass: false,
             fbo_cache: HashMap::new(),
             temp_attachments: Vec::new(),
+            staging_memory_type: staging_memory_type,
+            staging: Vec::new(),
+            staging_offset: 0,
         }
     }
 }
@@ -82,6 +187,9 @@ impl Drop for Buffer {
                 vk.DestroyFramebuffer(dev, fbo, ptr::null());
             }
         }
+        for chunk in self.staging.drain(..) {
+            chunk.destroy(&self.share);
+        }
     }
 }
 
@@ -120,6 +228,76 @@ impl Buffer {
                 0, ptr::null(), 0, ptr::null(), 1, &barrier);
         }
     }
+
+    /// Copy `data` to the staging memory, returning the buffer and offset it is copied to.
+    /// The staging memory is only reused after the command buffer is reset.
+    fn stage(&mut self, data: &[u8]) -> (vk::Buffer, u64) {
+        let size = data.len() as u64;
+        let fits = match self.staging.last() {
+            Some(chunk) => self.staging_offset + size <= chunk.size,
+            None => false,
+        };
+        if !fits {
+            let chunk = StagingChunk::new(&self.share, self.staging_memory_type,
+                                          cmp::max(size, STAGING_CHUNK_SIZE));
+            self.staging.push(chunk);
+            self.staging_offset = 0;
+        }
+        let chunk = self.staging.last().unwrap();
+        let offset = self.staging_offset;
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), (chunk.pointer as *mut u8).offset(offset as isize),
+                                     data.len());
+        }
+        // keep the copies 4 bytes aligned
+        self.staging_offset = (offset + size + 3) & !3;
+        (chunk.buffer, offset)
+    }
+
+    /// End the current render pass, if any. The next `bind_pixel_targets` begins a new one.
+    fn end_render_pass(&mut self) {
+        if self.in_render_pass {
+            let (_, vk) = self.share.get_device();
+            unsafe {
+                vk.CmdEndRenderPass(self.inner);
+            }
+            self.in_render_pass = false;
+        }
+    }
+
+    /// Make a transfer to the `size` bytes of `buffer` at `offset` visible to the
+    /// vertex, index and constant buffer reads of the following draws.
+    fn transfer_barrier(&mut self, buffer: vk::Buffer, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        let barrier = vk::BufferMemoryBarrier {
+            sType: vk::STRUCTURE_TYPE_BUFFER_MEMORY_BARRIER,
+            pNext: ptr::null(),
+            srcAccessMask: vk::ACCESS_TRANSFER_WRITE_BIT,
+            dstAccessMask: vk::ACCESS_INDEX_READ_BIT | vk::ACCESS_VERTEX_ATTRIBUTE_READ_BIT |
+                           vk::ACCESS_UNIFORM_READ_BIT,
+            srcQueueFamilyIndex: self.family,
+            dstQueueFamilyIndex: self.family,
+            buffer: buffer,
+            offset: offset,
+            size: size,
+        };
+        let (_, vk) = self.share.get_device();
+        unsafe {
+            vk.CmdPipelineBarrier(self.inner,
+                vk::PIPELINE_STAGE_TRANSFER_BIT,
+                vk::PIPELINE_STAGE_VERTEX_INPUT_BIT | vk::PIPELINE_STAGE_VERTEX_SHADER_BIT |
+                vk::PIPELINE_STAGE_FRAGMENT_SHADER_BIT, 0,
+                0, ptr::null(), 1, &barrier, 0, ptr::null());
+        }
+    }
+
+    /// Check the vertex buffer set against the device limits.
+    fn check_vertex_buffers(&self, vbs: &pso::VertexBufferSet<Resources>) -> Result<(), VertexBindingError> {
+        let max_bindings = self.share.get_limits().maxVertexInputBindings as usize;
+        match vbs.0.iter().rposition(|vb| vb.is_some()) {
+            Some(last) if last >= max_bindings => Err(VertexBindingError::OutOfLimits(last)),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl command::Buffer<Resources> for Buffer {
@@ -128,6 +306,14 @@ impl command::Buffer<Resources> for Buffer {
         assert_eq!(vk::SUCCESS, unsafe {
             vk.ResetCommandBuffer(self.inner, 0)
         });
+        self.in_render_pass = false;
+        // only keep the latest staging buffer around, it is the largest
+        let last = self.staging.pop();
+        for chunk in self.staging.drain(..) {
+            chunk.destroy(&self.share);
+        }
+        self.staging.extend(last);
+        self.staging_offset = 0;
     }
 
     fn bind_pipeline_state(&mut self, pso: native::Pipeline) {
@@ -138,7 +324,38 @@ impl command::Buffer<Resources> for Buffer {
         }
     }
 
-    fn bind_vertex_buffers(&mut self, _: pso::VertexBufferSet<Resources>) {}
+    fn bind_vertex_buffers(&mut self, vbs: pso::VertexBufferSet<Resources>) {
+        if let Err(e) = self.check_vertex_buffers(&vbs) {
+            error!("Unable to bind vertex buffers: {}", e);
+            return;
+        }
+        let (_, vk) = self.share.get_device();
+        let mut buffers = Vec::with_capacity(vbs.0.len());
+        let mut offsets = Vec::with_capacity(vbs.0.len());
+        // bind each contiguous range of set slots with a single call,
+        // the unset slots in between are left alone
+        let mut first = 0;
+        for (i, vb) in vbs.0.iter().chain(iter::once(&None)).enumerate() {
+            match *vb {
+                Some((ref buffer, offset)) => {
+                    if buffers.is_empty() {
+                        first = i;
+                    }
+                    buffers.push(buffer.buffer);
+                    offsets.push(offset as vk::DeviceSize);
+                }
+                None if !buffers.is_empty() => {
+                    unsafe {
+                        vk.CmdBindVertexBuffers(self.inner, first as u32, buffers.len() as u32,
+                                                buffers.as_ptr(), offsets.as_ptr());
+                    }
+                    buffers.clear();
+                    offsets.clear();
+                }
+                None => (),
+            }
+        }
+    }
     fn bind_constant_buffers(&mut self, _: &[pso::ConstantBufferParam<Resources>]) {}
     fn bind_global_constant(&mut self, _: shade::Location, _: shade::UniformValue) {}
     fn bind_resource_views(&mut self, _: &[pso::ResourceViewParam<Resources>]) {}
@@ -146,6 +363,7 @@ impl command::Buffer<Resources> for Buffer {
     fn bind_samplers(&mut self, _: &[pso::SamplerParam<Resources>]) {}
 
     fn bind_pixel_targets(&mut self, pts: pso::PixelTargetSet<Resources>) {
+        self.end_render_pass();
         let (dev, vk) = self.share.get_device();
         let view = pts.get_view();
         let vp = vk::Viewport {
@@ -217,12 +435,19 @@ impl command::Buffer<Resources> for Buffer {
             vk.CmdSetViewport(self.inner, 0, 1, &vp);
             vk.CmdBeginRenderPass(self.inner, &rp_info, vk::SUBPASS_CONTENTS_INLINE);
         }
-        //TODO: EndRenderPass
+        self.in_render_pass = true;
     }
 
     fn bind_index(&mut self, _: native::Buffer, _: IndexType) {}
     fn set_scissor(&mut self, _: target::Rect) {}
-    fn set_ref_values(&mut self, _: RefValues) {}
+    fn set_ref_values(&mut self, rv: RefValues) {
+        let (_, vk) = self.share.get_device();
+        unsafe {
+            vk.CmdSetStencilReference(self.inner, vk::STENCIL_FACE_FRONT_BIT, rv.stencil.0 as u32);
+            vk.CmdSetStencilReference(self.inner, vk::STENCIL_FACE_BACK_BIT, rv.stencil.1 as u32);
+            vk.CmdSetBlendConstants(self.inner, &rv.blend);
+        }
+    }
 
     fn copy_buffer(&mut self, src: native::Buffer, dst: native::Buffer,
                    src_offset_bytes: usize, dst_offset_bytes: usize,
@@ -242,7 +467,76 @@ impl command::Buffer<Resources> for Buffer {
         unimplemented!(); // TODO: synchronisation
     }
 
-    fn update_buffer(&mut self, _: native::Buffer, _: &[u8], _: usize) {}
+    fn copy_texture_to_buffer(&mut self, src: native::Texture, kind: tex::Kind,
+                              face: Option<tex::CubeFace>, img: tex::RawImageInfo,
+                              dst: native::Buffer, dst_offset_bytes: usize) {
+        let (_, vk) = self.share.get_device();
+        let layer = match face {
+            Some(f) => img.zoffset as u32 * 6 + f as u32,
+            None if kind.get_num_slices().is_some() => img.zoffset as u32,
+            None => 0,
+        };
+        let is_3d = match kind {
+            tex::Kind::D3(..) => true,
+            _ => false,
+        };
+        let aspect = data::map_image_aspect(img.format.0, img.format.1, false);
+        let old_layout = src.layout.get();
+        self.image_barrier(src.image, aspect, old_layout, vk::IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL);
+        let region = vk::BufferImageCopy {
+            bufferOffset: dst_offset_bytes as vk::DeviceSize,
+            bufferRowLength: 0,
+            bufferImageHeight: 0,
+            imageSubresource: vk::ImageSubresourceLayers {
+                aspectMask: aspect,
+                mipLevel: img.mipmap as u32,
+                baseArrayLayer: if is_3d { 0 } else { layer },
+                layerCount: 1,
+            },
+            imageOffset: vk::Offset3D {
+                x: img.xoffset as i32,
+                y: img.yoffset as i32,
+                z: if is_3d { img.zoffset as i32 } else { 0 },
+            },
+            imageExtent: vk::Extent3D {
+                width: img.width as u32,
+                height: img.height as u32,
+                depth: if is_3d { img.depth as u32 } else { 1 },
+            },
+        };
+        unsafe {
+            vk.CmdCopyImageToBuffer(self.inner, src.image, vk::IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+                                    dst.buffer, 1, &region);
+        }
+        self.image_barrier(src.image, aspect, vk::IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL, old_layout);
+    }
+
+    fn update_buffer(&mut self, buffer: native::Buffer, data: &[u8], offset_bytes: usize) {
+        if data.is_empty() {
+            return;
+        }
+        // transfers are not allowed inside a render pass
+        self.end_render_pass();
+        if data.len() <= MAX_INLINE_UPDATE_SIZE && data.len() % 4 == 0 && offset_bytes % 4 == 0 {
+            let (_, vk) = self.share.get_device();
+            unsafe {
+                vk.CmdUpdateBuffer(self.inner, buffer.buffer, offset_bytes as vk::DeviceSize,
+                                   data.len() as vk::DeviceSize, data.as_ptr() as *const _);
+            }
+        } else {
+            let (staging, staging_offset) = self.stage(data);
+            let region = vk::BufferCopy {
+                srcOffset: staging_offset,
+                dstOffset: offset_bytes as vk::DeviceSize,
+                size: data.len() as vk::DeviceSize,
+            };
+            let (_, vk) = self.share.get_device();
+            unsafe {
+                vk.CmdCopyBuffer(self.inner, staging, buffer.buffer, 1, &region);
+            }
+        }
+        self.transfer_barrier(buffer.buffer, offset_bytes as vk::DeviceSize, data.len() as vk::DeviceSize);
+    }
     fn update_texture(&mut self, _: native::Texture, _: tex::Kind, _: Option<tex::CubeFace>,
                       _: &[u8], _: tex::RawImageInfo) {}
     fn generate_mipmap(&mut self, _: native::TextureView) {}
@@ -274,6 +568,50 @@ impl command::Buffer<Resources> for Buffer {
     fn call_draw(&mut self, _: VertexCount, _: VertexCount, _: Option<command::InstanceParams>) {}
     fn call_draw_indexed(&mut self, _: VertexCount, _: VertexCount,
                          _: VertexCount, _: Option<command::InstanceParams>) {}
+
+    fn begin_debug_group(&mut self, name: &str) {
+        if !self.share.has_debug_markers() {
+            return;
+        }
+        let (_, vk) = self.share.get_device();
+        let name = CString::new(name).unwrap_or_default();
+        let info = vk::DebugMarkerMarkerInfoEXT {
+            sType: vk::STRUCTURE_TYPE_DEBUG_MARKER_MARKER_INFO_EXT,
+            pNext: ptr::null(),
+            pMarkerName: name.as_ptr(),
+            color: [0.0; 4],
+        };
+        unsafe {
+            vk.CmdDebugMarkerBeginEXT(self.inner, &info);
+        }
+    }
+
+    fn end_debug_group(&mut self) {
+        if !self.share.has_debug_markers() {
+            return;
+        }
+        let (_, vk) = self.share.get_device();
+        unsafe {
+            vk.CmdDebugMarkerEndEXT(self.inner);
+        }
+    }
+
+    fn insert_debug_marker(&mut self, name: &str) {
+        if !self.share.has_debug_markers() {
+            return;
+        }
+        let (_, vk) = self.share.get_device();
+        let name = CString::new(name).unwrap_or_default();
+        let info = vk::DebugMarkerMarkerInfoEXT {
+            sType: vk::STRUCTURE_TYPE_DEBUG_MARKER_MARKER_INFO_EXT,
+            pNext: ptr::null(),
+            pMarkerName: name.as_ptr(),
+            color: [0.0; 4],
+        };
+        unsafe {
+            vk.CmdDebugMarkerInsertEXT(self.inner, &info);
+        }
+    }
 }
 
 
@@ -282,6 +620,7 @@ pub struct GraphicsQueue {
     family: u32,
     queue: vk::Queue,
     capabilities: core::Capabilities,
+    frame_handles: handle::Manager<Resources>,
 }
 
 impl GraphicsQueue {
@@ -307,6 +646,7 @@ impl GraphicsQueue {
             family: qf_id,
             queue: q,
             capabilities: caps,
+            frame_handles: handle::Manager::new(),
         }
     }
     #[doc(hidden)]
@@ -325,13 +665,13 @@ impl GraphicsQueue {
     fn ensure_mappings_flushed(&mut self, access: &mut AccessGuard<Resources>) {
         let (dev, vk) = self.share.get_device();
         for (buffer, mapping) in access.access_mapped_reads() {
-            mapping.status.ensure_flushed(|| {
+            mapping.status.ensure_flushed(|range| {
                 let memory_range = vk::MappedMemoryRange {
                     sType: vk::STRUCTURE_TYPE_MAPPED_MEMORY_RANGE,
                     pNext: ptr::null(),
                     memory: buffer.resource().memory,
-                    offset: 0,
-                    size: vk::WHOLE_SIZE,
+                    offset: range.start as vk::DeviceSize,
+                    size: (range.end - range.start) as vk::DeviceSize,
                 };
                 assert_eq!(vk::SUCCESS, unsafe {
                     vk.FlushMappedMemoryRanges(dev, 1, &memory_range)
@@ -363,25 +703,13 @@ impl GraphicsQueue {
             mapping.status.gpu_access(fence.clone());
         }
     }
-}
 
-impl core::Device for GraphicsQueue {
-    type Resources = Resources;
-    type CommandBuffer = Buffer;
-
-    fn get_capabilities(&self) -> &core::Capabilities {
-        &self.capabilities
-    }
-
-    fn pin_submitted_resources(&mut self, _: &core::handle::Manager<Resources>) {}
-
-    fn submit(&mut self,
-              com: &mut Buffer,
-              access: &AccessInfo<Resources>) -> SubmissionResult<()>
-    {
+    fn submit_impl(&mut self, com: &mut Buffer, access: &AccessInfo<Resources>,
+                   fence: Option<&handle::Fence<Resources>>) -> SubmissionResult<()> {
         assert_eq!(self.family, com.family);
         let share = self.share.clone();
         let (_, vk) = share.get_device();
+        com.end_render_pass();
         assert_eq!(vk::SUCCESS, unsafe {
             vk.EndCommandBuffer(com.inner)
         });
@@ -395,11 +723,18 @@ impl core::Device for GraphicsQueue {
             pCommandBuffers: &com.inner,
             .. unsafe { mem::zeroed() }
         };
+        let raw_fence = match fence {
+            Some(fence) => self.frame_handles.ref_fence(fence).0,
+            None => 0,
+        };
         assert_eq!(vk::SUCCESS, unsafe {
-            vk.QueueSubmit(self.queue, 1, &submit_info, 0)
+            vk.QueueSubmit(self.queue, 1, &submit_info, raw_fence)
         });
+        if let Some(fence) = fence {
+            self.track_mapped_gpu_access(&mut access, fence);
+        }
 
-        // TODO: memory barrier, invalidation and fence
+        // TODO: memory barrier and invalidation
 
         let begin_info = vk::CommandBufferBeginInfo {
             sType: vk::STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO,
@@ -412,25 +747,70 @@ impl core::Device for GraphicsQueue {
         });
         Ok(())
     }
+}
+
+impl core::Device for GraphicsQueue {
+    type Resources = Resources;
+    type CommandBuffer = Buffer;
+
+    fn get_capabilities(&self) -> &core::Capabilities {
+        &self.capabilities
+    }
+
+    fn pin_submitted_resources(&mut self, man: &core::handle::Manager<Resources>) {
+        self.frame_handles.extend(man);
+    }
+
+    fn submit(&mut self,
+              com: &mut Buffer,
+              access: &AccessInfo<Resources>) -> SubmissionResult<()>
+    {
+        self.submit_impl(com, access, None)
+    }
 
     fn fenced_submit(&mut self,
-                     _: &mut Buffer,
-                     _: &AccessInfo<Resources>,
-                     _after: Option<handle::Fence<Resources>>)
+                     com: &mut Buffer,
+                     access: &AccessInfo<Resources>,
+                     after: Option<handle::Fence<Resources>>)
                      -> SubmissionResult<handle::Fence<Resources>>
     {
-        unimplemented!()
+        use core::handle::Producer;
+
+        if let Some(fence) = after {
+            // there are no semaphores between submissions on the same queue yet
+            self.wait_fence(&fence);
+        }
+        let info = vk::FenceCreateInfo {
+            sType: vk::STRUCTURE_TYPE_FENCE_CREATE_INFO,
+            pNext: ptr::null(),
+            flags: 0,
+        };
+        let raw = {
+            let (dev, vk) = self.share.get_device();
+            let mut raw = 0;
+            assert_eq!(vk::SUCCESS, unsafe {
+                vk.CreateFence(dev, &info, ptr::null(), &mut raw)
+            });
+            raw
+        };
+        let fence = self.share.handles.lock().unwrap().make_fence(Fence(raw));
+        try!(self.submit_impl(com, access, Some(&fence)));
+        Ok(fence)
     }
 
     fn wait_fence(&mut self, fence: &handle::Fence<Self::Resources>) {
-        unimplemented!()
+        let raw = self.frame_handles.ref_fence(fence).0;
+        let (dev, vk) = self.share.get_device();
+        assert_eq!(vk::SUCCESS, unsafe {
+            vk.WaitForFences(dev, 1, &raw, vk::TRUE, !0)
+        });
     }
 
     //note: this should really live elsewhere (Factory?)
     fn cleanup(&mut self) {
         let (dev, mut functions) = self.share.get_device();
         use core::handle::Producer;
-        //self.frame_handles.clear();
+        self.frame_handles.clear();
         self.share.handles.lock().unwrap().clean_with(&mut functions,
             |vk, buffer| unsafe {
                 if buffer.is_mapped() {
@@ -48,7 +48,8 @@ pub fn map_image_view_type(kind: Kind, layer: Option<Layer>) -> Result<vk::Image
 
 pub fn map_image_aspect(surface: SurfaceType, channel: ChannelType, is_target: bool) -> vk::ImageAspectFlags {
     match surface {
-        SurfaceType::D16 | SurfaceType::D24 | SurfaceType::D24_S8 | SurfaceType::D32 => match (is_target, channel) {
+        SurfaceType::D16 | SurfaceType::D24 | SurfaceType::D24_S8 | SurfaceType::D32 |
+        SurfaceType::D32_S8 => match (is_target, channel) {
             (true, _) => vk::IMAGE_ASPECT_DEPTH_BIT | vk::IMAGE_ASPECT_STENCIL_BIT,
             (false, ChannelType::Float) | (false, ChannelType::Unorm) => vk::IMAGE_ASPECT_DEPTH_BIT,
             (false, ChannelType::Uint)  => vk::IMAGE_ASPECT_STENCIL_BIT,
@@ -254,6 +255,10 @@ pub fn map_format(surface: SurfaceType, chan: ChannelType) -> Option<vk::Format>
             Float => vk::FORMAT_D32_SFLOAT,
             _ => return None,
         },
+        D32_S8 => match chan {
+            Float => vk::FORMAT_D32_SFLOAT_S8_UINT,
+            _ => return None,
+        },
     })
 }
 
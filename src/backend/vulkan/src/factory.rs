@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::{cell, mem, ptr, slice};
+use std::ops::Range;
 use std::os::raw::c_void;
 use core::{self, handle as h, pso, state, texture, buffer, mapping};
 use core::memory::{self, Bind};
@@ -45,6 +46,14 @@ impl mapping::Gate<R> for MappingGate {
     unsafe fn mut_slice<'a, 'b, T>(&'a self, len: usize) -> &'b mut [T] {
         slice::from_raw_parts_mut(self.pointer as *mut T, len)
     }
+
+    unsafe fn copy_from<T: Copy>(&self, offset: usize, data: &[T]) {
+        ptr::copy_nonoverlapping(data.as_ptr(), (self.pointer as *mut T).offset(offset as isize), data.len());
+    }
+
+    unsafe fn flush(&mut self, range: Range<usize>) {
+        self.status.cpu_wrote(range);
+    }
 }
 
 pub struct Factory {
@@ -80,7 +89,8 @@ impl Factory {
     }
 
     pub fn create_command_buffer(&mut self) -> command::Buffer {
-        command::Buffer::new(self.command_pool, self.queue_family_index, self.share.clone())
+        command::Buffer::new(self.command_pool, self.queue_family_index, self.share.clone(),
+                             self.mem_system_id)
     }
 
     fn view_texture(&mut self, htex: &h::RawTexture<R>, desc: texture::ResourceDesc, is_target: bool)
@@ -609,6 +619,11 @@ impl core::Factory<R> for Factory {
                     });
                 }
             }
+            let dynamic_states = [
+                vk::DYNAMIC_STATE_VIEWPORT,
+                vk::DYNAMIC_STATE_BLEND_CONSTANTS,
+                vk::DYNAMIC_STATE_STENCIL_REFERENCE,
+            ];
             let mut attachments = Vec::new();
             for ocd in desc.color_targets.iter() {
                 if let &Some(ref cd) = ocd {
@@ -735,13 +750,8 @@ impl core::Factory<R> for Factory {
                     sType: vk::STRUCTURE_TYPE_PIPELINE_DYNAMIC_STATE_CREATE_INFO,
                     pNext: ptr::null(),
                     flags: 0,
-                    dynamicStateCount: 1,
-                    pDynamicStates: [
-                        vk::DYNAMIC_STATE_VIEWPORT,
-                        vk::DYNAMIC_STATE_SCISSOR,
-                        vk::DYNAMIC_STATE_BLEND_CONSTANTS,
-                        vk::DYNAMIC_STATE_STENCIL_REFERENCE,
-                        ].as_ptr(),
+                    dynamicStateCount: dynamic_states.len() as u32,
+                    pDynamicStates: dynamic_states.as_ptr(),
                 },
                 layout: pipe_layout,
                 renderPass: render_pass,
@@ -906,19 +916,99 @@ impl core::Factory<R> for Factory {
         self.share.handles.lock().unwrap().make_sampler(sampler, info)
     }
 
-    fn read_mapping<'a, 'b, T>(&'a mut self, _: &'b h::Buffer<R, T>)
+    fn read_mapping<'a, 'b, T>(&'a mut self, buf: &'b h::Buffer<R, T>)
                                -> Result<mapping::Reader<'b, R, T>,
                                          mapping::Error>
         where T: Copy
     {
-        unimplemented!()
+        let (dev, vk) = self.share.get_device();
+        let handles = &mut self.frame_handles;
+        unsafe {
+            mapping::read(buf.raw(), |mapping| {
+                mapping.status.cpu_access(|fence| wait_fence(handles.ref_fence(&fence), dev, vk));
+                Ok(())
+            })
+        }
     }
 
-    fn write_mapping<'a, 'b, T>(&'a mut self, _: &'b h::Buffer<R, T>)
+    fn write_mapping<'a, 'b, T>(&'a mut self, buf: &'b h::Buffer<R, T>)
                                 -> Result<mapping::Writer<'b, R, T>,
                                           mapping::Error>
         where T: Copy
     {
-        unimplemented!()
+        let (dev, vk) = self.share.get_device();
+        let handles = &mut self.frame_handles;
+        unsafe {
+            mapping::write(buf.raw(), |mapping| {
+                mapping.status.cpu_write_access(|fence| wait_fence(handles.ref_fence(&fence), dev, vk));
+                Ok(())
+            })
+        }
+    }
+
+    unsafe fn write_mapping_unsynchronized<'a, 'b, T>(&'a mut self, buf: &'b h::Buffer<R, T>)
+                                                      -> Result<mapping::Unsynchronized<'b, R, T>,
+                                                                mapping::Error>
+        where T: Copy
+    {
+        // staging buffers stay mapped for their whole life
+        mapping::unsynchronized(buf.raw(), |_| Ok(()))
     }
+
+    fn map_texture_readable<'a, 'b, T>(&'a mut self, tex: &'b h::RawTexture<R>,
+                                       face: Option<texture::CubeFace>, img: texture::RawImageInfo)
+                                       -> Result<mapping::TextureReader<'b, R, T>, mapping::Error>
+        where T: Copy
+    {
+        try!(mapping::check_texture_read(tex));
+        let info = tex.get_info();
+        let (layer, slice) = match (info.kind, face) {
+            (texture::Kind::D3(..), _) => (0, img.zoffset as vk::DeviceSize),
+            (_, Some(f)) => (img.zoffset as u32 * 6 + f as u32, 0),
+            (kind, None) if kind.get_num_slices().is_some() => (img.zoffset as u32, 0),
+            _ => (0, 0),
+        };
+        // `Download` textures are linearly tiled, so their layout can be queried
+        let subresource = vk::ImageSubresource {
+            aspectMask: data::map_image_aspect(img.format.0, img.format.1, false),
+            mipLevel: img.mipmap as u32,
+            arrayLayer: layer,
+        };
+        let native = tex.resource();
+        let size = mem::size_of::<T>() as vk::DeviceSize;
+        let (dev, vk) = self.share.get_device();
+        let mut layout = unsafe { mem::zeroed::<vk::SubresourceLayout>() };
+        unsafe {
+            vk.GetImageSubresourceLayout(dev, native.image, &subresource, &mut layout);
+        }
+        let offset = layout.offset + slice * layout.depthPitch +
+                     img.yoffset as vk::DeviceSize * layout.rowPitch +
+                     img.xoffset as vk::DeviceSize * size;
+        let mut pointer = ptr::null_mut();
+        let result = unsafe {
+            vk.MapMemory(dev, native.memory, offset, vk::WHOLE_SIZE, 0, &mut pointer)
+        };
+        if result != vk::SUCCESS {
+            error!("Unable to map a texture {:?}, error {:?}", tex, result);
+            return Err(mapping::Error::MapFailed);
+        }
+
+        let share = self.share.clone();
+        let memory = native.memory;
+        let release = Box::new(move || {
+            let (dev, vk) = share.get_device();
+            unsafe { vk.UnmapMemory(dev, memory) };
+        });
+        Ok(unsafe {
+            mapping::TextureReader::new(pointer as *const T,
+                                        img.width as usize, img.height as usize,
+                                        (layout.rowPitch / size) as usize, release)
+        })
+    }
+}
+
+fn wait_fence(fence: &::Fence, dev: vk::Device, vk: &vk::DevicePointers) {
+    assert_eq!(vk::SUCCESS, unsafe {
+        vk.WaitForFences(dev, 1, &fence.0, vk::TRUE, !0)
+    });
 }
@@ -24,7 +24,7 @@ use std::sync::{Arc, Mutex};
 use std::ffi::CStr;
 use shared_library::dynamic_library::DynamicLibrary;
 
-pub use self::command::{GraphicsQueue, Buffer as CommandBuffer};
+pub use self::command::{GraphicsQueue, Buffer as CommandBuffer, VertexBindingError};
 pub use self::factory::Factory;
 
 mod command;
@@ -35,7 +35,7 @@ mod mirror;
 
 struct PhysicalDeviceInfo {
     device: vk::PhysicalDevice,
-    _properties: vk::PhysicalDeviceProperties,
+    properties: vk::PhysicalDeviceProperties,
     queue_families: Vec<vk::QueueFamilyProperties>,
     memory: vk::PhysicalDeviceMemoryProperties,
     _features: vk::PhysicalDeviceFeatures,
@@ -45,7 +45,7 @@ impl PhysicalDeviceInfo {
     pub fn new(dev: vk::PhysicalDevice, vk: &vk::InstancePointers) -> PhysicalDeviceInfo {
         PhysicalDeviceInfo {
             device: dev,
-            _properties: unsafe {
+            properties: unsafe {
                 let mut out = mem::zeroed();
                 vk.GetPhysicalDeviceProperties(dev, &mut out);
                 out
@@ -70,6 +70,53 @@ impl PhysicalDeviceInfo {
             },
         }
     }
+
+    fn supports_graphics(&self) -> bool {
+        self.queue_families.iter().any(|qf| qf.queueFlags & vk::QUEUE_GRAPHICS_BIT != 0)
+    }
+
+    fn get_adapter_info(&self) -> AdapterInfo {
+        let heaps = &self.memory.memoryHeaps[.. self.memory.memoryHeapCount as usize];
+        AdapterInfo {
+            name: unsafe { CStr::from_ptr(self.properties.deviceName.as_ptr()) }
+                .to_string_lossy().into_owned(),
+            device_type: match self.properties.deviceType {
+                vk::PHYSICAL_DEVICE_TYPE_INTEGRATED_GPU => DeviceType::Integrated,
+                vk::PHYSICAL_DEVICE_TYPE_DISCRETE_GPU => DeviceType::Discrete,
+                vk::PHYSICAL_DEVICE_TYPE_VIRTUAL_GPU => DeviceType::Virtual,
+                vk::PHYSICAL_DEVICE_TYPE_CPU => DeviceType::Cpu,
+                _ => DeviceType::Other,
+            },
+            device_local_memory: heaps.iter()
+                .filter(|heap| heap.flags & vk::MEMORY_HEAP_DEVICE_LOCAL_BIT != 0)
+                .map(|heap| heap.size)
+                .sum(),
+            supports_graphics: self.supports_graphics(),
+        }
+    }
+}
+
+/// Type of a physical device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DeviceType {
+    Other,
+    Integrated,
+    Discrete,
+    Virtual,
+    Cpu,
+}
+
+/// Information about a physical device, available before creating the logical one.
+#[derive(Clone, Debug)]
+pub struct AdapterInfo {
+    /// Device name, as reported by the driver.
+    pub name: String,
+    /// Type of the device.
+    pub device_type: DeviceType,
+    /// Total size of the device local memory heaps, in bytes.
+    pub device_local_memory: u64,
+    /// The device has a queue family capable of graphics.
+    pub supports_graphics: bool,
 }
 
 
@@ -81,6 +128,8 @@ pub struct Share {
     device: vk::Device,
     dev_pointers: vk::DevicePointers,
     physical_device: vk::PhysicalDevice,
+    limits: vk::PhysicalDeviceLimits,
+    debug_markers: bool,
     handles: Mutex<core::handle::Manager<Resources>>,
 }
 
@@ -96,6 +145,13 @@ impl Share {
     pub fn get_physical_device(&self) -> vk::PhysicalDevice {
         self.physical_device
     }
+    pub fn get_limits(&self) -> &vk::PhysicalDeviceLimits {
+        &self.limits
+    }
+    /// Whether the `VK_EXT_debug_marker` device extension is enabled.
+    pub fn has_debug_markers(&self) -> bool {
+        self.debug_markers
+    }
 }
 
 const SURFACE_EXTENSIONS: &'static [&'static str] = &[
@@ -108,10 +164,10 @@ const SURFACE_EXTENSIONS: &'static [&'static str] = &[
     "VK_KHR_win32_surface",
 ];
 
+// Device extension enabled when available, for command buffer debug markers.
+const DEBUG_MARKER_EXTENSION: &'static str = "VK_EXT_debug_marker";
 
-pub fn create(app_name: &str, app_version: u32, layers: &[&str], extensions: &[&str],
-              dev_extensions: &[&str]) -> (command::GraphicsQueue, factory::Factory, SharePointer) {
-    use std::ffi::CString;
+fn load_library() -> (DynamicLibrary, vk::Static, vk::EntryPoints) {
     use std::path::Path;
 
     let dynamic_lib = DynamicLibrary::open(Some(
@@ -128,6 +184,90 @@ pub fn create(app_name: &str, app_version: u32, layers: &[&str], extensions: &[&
     let entry_points = vk::EntryPoints::load(|name| unsafe {
         mem::transmute(lib.GetInstanceProcAddr(0, name.as_ptr()))
     });
+    (dynamic_lib, lib, entry_points)
+}
+
+fn enumerate_physical_devices(instance: vk::Instance, inst_pointers: &vk::InstancePointers)
+                              -> Vec<PhysicalDeviceInfo> {
+    let physical_devices = {
+        let mut num = 0;
+        assert_eq!(vk::SUCCESS, unsafe {
+            inst_pointers.EnumeratePhysicalDevices(instance, &mut num, ptr::null_mut())
+        });
+        let mut devices = Vec::with_capacity(num as usize);
+        assert_eq!(vk::SUCCESS, unsafe {
+            inst_pointers.EnumeratePhysicalDevices(instance, &mut num, devices.as_mut_ptr())
+        });
+        unsafe { devices.set_len(num as usize); }
+        devices
+    };
+
+    physical_devices.iter()
+        .map(|dev| PhysicalDeviceInfo::new(*dev, inst_pointers))
+        .collect()
+}
+
+/// List the available physical devices, in the order expected by `create`.
+/// This uses a temporary instance, so it can be called before any device exists.
+pub fn enumerate_adapters() -> Vec<AdapterInfo> {
+    let (_dynamic_lib, lib, entry_points) = load_library();
+
+    let create_info = vk::InstanceCreateInfo {
+        sType: vk::STRUCTURE_TYPE_INSTANCE_CREATE_INFO,
+        pNext: ptr::null(),
+        flags: 0,
+        pApplicationInfo: ptr::null(),
+        enabledLayerCount: 0,
+        ppEnabledLayerNames: ptr::null(),
+        enabledExtensionCount: 0,
+        ppEnabledExtensionNames: ptr::null(),
+    };
+    let mut instance = 0;
+    assert_eq!(vk::SUCCESS, unsafe {
+        entry_points.CreateInstance(&create_info, ptr::null(), &mut instance)
+    });
+    let inst_pointers = vk::InstancePointers::load(|name| unsafe {
+        mem::transmute(lib.GetInstanceProcAddr(instance, name.as_ptr()))
+    });
+
+    let adapters = enumerate_physical_devices(instance, &inst_pointers)
+        .iter()
+        .map(|dev| dev.get_adapter_info())
+        .collect();
+
+    unsafe {
+        inst_pointers.DestroyInstance(instance, ptr::null());
+    }
+    adapters
+}
+
+/// List the names of the installed instance layers, to check optional layers before `create`.
+pub fn enumerate_layers() -> Vec<String> {
+    let (_dynamic_lib, _lib, entry_points) = load_library();
+
+    let mut num = 0;
+    assert_eq!(vk::SUCCESS, unsafe {
+        entry_points.EnumerateInstanceLayerProperties(&mut num, ptr::null_mut())
+    });
+    let mut layers = Vec::with_capacity(num as usize);
+    assert_eq!(vk::SUCCESS, unsafe {
+        entry_points.EnumerateInstanceLayerProperties(&mut num, layers.as_mut_ptr())
+    });
+    unsafe { layers.set_len(num as usize); }
+
+    layers.iter()
+          .map(|layer| unsafe { CStr::from_ptr(layer.layerName.as_ptr()) }.to_string_lossy().into_owned())
+          .collect()
+}
+
+/// Create the device. `adapter` is an index into the list returned by `enumerate_adapters`,
+/// if none is given the first one capable of graphics is picked.
+pub fn create(app_name: &str, app_version: u32, layers: &[&str], extensions: &[&str],
+              dev_extensions: &[&str], adapter: Option<usize>)
+              -> (command::GraphicsQueue, factory::Factory, SharePointer) {
+    use std::ffi::CString;
+
+    let (dynamic_lib, lib, entry_points) = load_library();
 
     let app_info = vk::ApplicationInfo {
         sType: vk::STRUCTURE_TYPE_APPLICATION_INFO,
@@ -189,28 +329,18 @@ pub fn create(app_name: &str, app_version: u32, layers: &[&str], extensions: &[&
         mem::transmute(lib.GetInstanceProcAddr(instance, name.as_ptr()))
     });
 
-    let physical_devices = {
-        let mut num = 0;
-        assert_eq!(vk::SUCCESS, unsafe {
-            inst_pointers.EnumeratePhysicalDevices(instance, &mut num, ptr::null_mut())
-        });
-        let mut devices = Vec::with_capacity(num as usize);
-        assert_eq!(vk::SUCCESS, unsafe {
-            inst_pointers.EnumeratePhysicalDevices(instance, &mut num, devices.as_mut_ptr())
-        });
-        unsafe { devices.set_len(num as usize); }
-        devices
+    let devices = enumerate_physical_devices(instance, &inst_pointers);
+    let candidates = match adapter {
+        Some(index) => &devices[index .. index + 1],
+        None => &devices[..],
     };
-    
-    let devices = physical_devices.iter()
-        .map(|dev| PhysicalDeviceInfo::new(*dev, &inst_pointers))
-        .collect::<Vec<_>>();
 
-    let (dev, (qf_id, _))  = devices.iter()
+    let (dev, (qf_id, _))  = candidates.iter()
         .flat_map(|d| iter::repeat(d).zip(d.queue_families.iter().enumerate()))
         .find(|&(_, (_, qf))| qf.queueFlags & vk::QUEUE_GRAPHICS_BIT != 0)
-        .unwrap();
-    info!("Chosen physical device {:?} with queue family {}", dev.device, qf_id);
+        .expect("No physical device with a graphics queue family");
+    info!("Chosen physical device {:?} ({}) with queue family {}",
+          dev.device, dev.get_adapter_info().name, qf_id);
 
     let mvid_id = dev.memory.memoryTypes.iter().take(dev.memory.memoryTypeCount as usize)
                             .position(|mt| (mt.propertyFlags & vk::MEMORY_PROPERTY_DEVICE_LOCAL_BIT != 0))
@@ -220,6 +350,27 @@ pub fn create(app_name: &str, app_version: u32, layers: &[&str], extensions: &[&
                                         && (mt.propertyFlags & vk::MEMORY_PROPERTY_HOST_VISIBLE_BIT != 0))
                             .unwrap() as u32;
 
+    let device_extensions = {
+        let mut num = 0;
+        assert_eq!(vk::SUCCESS, unsafe {
+            inst_pointers.EnumerateDeviceExtensionProperties(dev.device, ptr::null(), &mut num, ptr::null_mut())
+        });
+        let mut out = Vec::with_capacity(num as usize);
+        assert_eq!(vk::SUCCESS, unsafe {
+            inst_pointers.EnumerateDeviceExtensionProperties(dev.device, ptr::null(), &mut num, out.as_mut_ptr())
+        });
+        unsafe { out.set_len(num as usize); }
+        out
+    };
+
+    // Debug markers are usually only exposed while a graphics debugger is attached
+    let mut dev_extensions = dev_extensions.to_vec();
+    if !dev_extensions.contains(&DEBUG_MARKER_EXTENSION) && device_extensions.iter().any(|ext| unsafe {
+        CStr::from_ptr(ext.extensionName.as_ptr()).to_bytes() == DEBUG_MARKER_EXTENSION.as_bytes()
+    }) {
+        dev_extensions.push(DEBUG_MARKER_EXTENSION);
+    }
+
     let device = {
         let cstrings = dev_extensions.iter()
                                      .map(|&s| CString::new(s).unwrap())
@@ -273,6 +424,8 @@ pub fn create(app_name: &str, app_version: u32, layers: &[&str], extensions: &[&
         device: device,
         dev_pointers: dev_pointers,
         physical_device: dev.device,
+        limits: dev.properties.limits,
+        debug_markers: dev_extensions.contains(&DEBUG_MARKER_EXTENSION),
         handles: Mutex::new(core::handle::Manager::new()),
     });
     let gfx_device = command::GraphicsQueue::new(share.clone(), queue, qf_id as u32);
@@ -0,0 +1,1044 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ash::vk;
+use ash::version::DeviceV1_0;
+use core::{command, memory, pass, pso, query, shade, target, InstanceCount, VertexCount};
+use core::query::{QueryControl, QueryResultFlags};
+use core::image::{self, ImageLayout, SubresourceRange};
+use core::validate::Validator;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::{mem, ptr};
+use std::sync::Arc;
+use {Device, DeviceInner, QueueFamily, Resources as R};
+use {data, ext, native};
+
+/// Pool of command buffers, allocated for a queue family.
+///
+/// The command buffers of a pool can be recorded on any thread, but not concurrently,
+/// so multithreaded recording uses a pool per thread.
+///
+/// With several frames in flight, each frame typically records into its own pool,
+/// which is reset at once with `reset` after waiting for the previous submission
+/// of the frame to complete.
+pub struct CommandPool {
+    inner: vk::CommandPool,
+    device: Arc<DeviceInner>,
+    family_index: u32,
+}
+unsafe impl Send for CommandPool {}
+
+impl Device {
+    /// Create a pool allocating the command buffers submitted to the queues of `family`.
+    pub fn create_command_pool(&mut self, family: &QueueFamily) -> CommandPool {
+        let info = vk::CommandPoolCreateInfo {
+            s_type: vk::StructureType::CommandPoolCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::COMMAND_POOL_CREATE_RESET_COMMAND_BUFFER_BIT,
+            queue_family_index: family.family_index,
+        };
+        let pool = unsafe {
+            self.inner.0.create_command_pool(&info, None)
+                .expect("Error on command pool creation") // TODO: handle this better
+        };
+        CommandPool {
+            inner: pool,
+            device: self.inner.clone(),
+            family_index: family.family_index,
+        }
+    }
+}
+
+impl CommandPool {
+    fn allocate(&mut self, level: vk::CommandBufferLevel) -> CommandBuffer {
+        let info = vk::CommandBufferAllocateInfo {
+            s_type: vk::StructureType::CommandBufferAllocateInfo,
+            p_next: ptr::null(),
+            command_pool: self.inner,
+            level: level,
+            command_buffer_count: 1,
+        };
+        let buffers = unsafe {
+            self.device.0.allocate_command_buffers(&info)
+                .expect("Error on command buffer allocation") // TODO: handle this better
+        };
+        CommandBuffer {
+            inner: buffers[0],
+            device: self.device.clone(),
+            secondary: level == vk::CommandBufferLevel::Secondary,
+            family_index: self.family_index,
+            layouts: HashMap::new(),
+            validator: Validator::new(),
+        }
+    }
+
+    /// Allocate a command buffer, to be submitted to a queue.
+    pub fn allocate_primary(&mut self) -> CommandBuffer {
+        self.allocate(vk::CommandBufferLevel::Primary)
+    }
+
+    /// Allocate a command buffer, to be executed by primary ones with `execute_commands`.
+    pub fn allocate_secondary(&mut self) -> CommandBuffer {
+        self.allocate(vk::CommandBufferLevel::Secondary)
+    }
+
+    /// Free command buffers allocated from this pool.
+    /// They must not be pending execution anymore.
+    pub fn free(&mut self, buffers: Vec<CommandBuffer>) {
+        let buffers = buffers.iter().map(|buffer| buffer.inner).collect::<Vec<_>>();
+        unsafe {
+            self.device.0.free_command_buffers(self.inner, &buffers);
+        }
+    }
+
+    /// Reset all the command buffers of the pool to their initial state, ready to be recorded again.
+    /// None of them must be pending execution anymore.
+    /// If `release_resources` is set, the memory of the pool is given back to the system.
+    pub fn reset(&mut self, release_resources: bool) {
+        let flags = if release_resources {
+            vk::COMMAND_POOL_RESET_RELEASE_RESOURCES_BIT
+        } else {
+            vk::CommandPoolResetFlags::empty()
+        };
+        unsafe {
+            self.device.0.reset_command_pool(self.inner, flags)
+                .expect("Error on command pool reset") // TODO: handle this better
+        };
+    }
+}
+
+impl Drop for CommandPool {
+    fn drop(&mut self) {
+        // frees the command buffers of the pool as well
+        unsafe { self.device.0.destroy_command_pool(self.inner, None); }
+    }
+}
+
+// Layout used by a transfer command on an image in `layout`: images in `General`
+// stay in it, others are transitioned to the `optimal` transfer layout.
+fn transfer_layout(layout: vk::ImageLayout, optimal: vk::ImageLayout) -> vk::ImageLayout {
+    if layout == vk::ImageLayout::General { layout } else { optimal }
+}
+
+// Check that the `count` draw arguments of `command_size` bytes, `stride` bytes apart
+// from `offset`, are aligned and fit in the `buffer`. Invalid arguments are logged.
+fn check_indirect(buffer: &native::Buffer, offset: u64, count: u32, stride: u32, command_size: usize) -> bool {
+    let command_size = command_size as u64;
+    if count > 1 && (stride % 4 != 0 || (stride as u64) < command_size) {
+        error!("Invalid draw argument stride {}, arguments are {} bytes", stride, command_size);
+        return false;
+    }
+    let end = match count {
+        0 => offset,
+        n => offset + (n as u64 - 1) * stride as u64 + command_size,
+    };
+    if offset % 4 != 0 || end > buffer.size {
+        error!("Invalid draw argument offset {} for {} draws, buffer size {}", offset, count, buffer.size);
+        return false;
+    }
+    true
+}
+
+/// Split the `barriers` into their Vulkan memory, buffer and image barriers.
+fn map_barriers(barriers: &[memory::Barrier<R>])
+    -> (Vec<vk::MemoryBarrier>, Vec<vk::BufferMemoryBarrier>, Vec<vk::ImageMemoryBarrier>)
+{
+    let mut memory_barriers = Vec::new();
+    let mut buffer_barriers = Vec::new();
+    let mut image_barriers = Vec::new();
+
+    for barrier in barriers {
+        match *barrier {
+            memory::Barrier::AllMemory { src, dst } => {
+                memory_barriers.push(vk::MemoryBarrier {
+                    s_type: vk::StructureType::MemoryBarrier,
+                    p_next: ptr::null(),
+                    src_access_mask: data::map_access(src),
+                    dst_access_mask: data::map_access(dst),
+                });
+            }
+            memory::Barrier::Buffer { buffer, ref range, src, dst } => {
+                buffer_barriers.push(vk::BufferMemoryBarrier {
+                    s_type: vk::StructureType::BufferMemoryBarrier,
+                    p_next: ptr::null(),
+                    src_access_mask: data::map_access(src),
+                    dst_access_mask: data::map_access(dst),
+                    src_queue_family_index: vk::VK_QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::VK_QUEUE_FAMILY_IGNORED,
+                    buffer: buffer.inner,
+                    offset: range.start,
+                    size: range.end - range.start,
+                });
+            }
+            memory::Barrier::Image { image, ref subresource, src, dst, old, new } => {
+                image_barriers.push(vk::ImageMemoryBarrier {
+                    s_type: vk::StructureType::ImageMemoryBarrier,
+                    p_next: ptr::null(),
+                    src_access_mask: data::map_access(src),
+                    dst_access_mask: data::map_access(dst),
+                    old_layout: data::map_image_layout(old),
+                    new_layout: data::map_image_layout(new),
+                    src_queue_family_index: vk::VK_QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::VK_QUEUE_FAMILY_IGNORED,
+                    image: image.inner,
+                    subresource_range: data::map_subresource_range(subresource),
+                });
+            }
+        }
+    }
+
+    (memory_barriers, buffer_barriers, image_barriers)
+}
+
+pub struct CommandBuffer {
+    #[doc(hidden)]
+    pub inner: vk::CommandBuffer,
+    device: Arc<DeviceInner>,
+    secondary: bool,
+    #[doc(hidden)]
+    pub family_index: u32,
+    // Last layout the subresources of the images were transitioned to, by mipmap level and array
+    // layer, since the recording began
+    layouts: HashMap<(vk::Image, u32, u32), vk::ImageLayout>,
+    validator: Validator,
+}
+unsafe impl Send for CommandBuffer {}
+
+impl CommandBuffer {
+    fn begin_info(&mut self, flags: vk::CommandBufferUsageFlags,
+                  inheritance: *const vk::CommandBufferInheritanceInfo) {
+        let info = vk::CommandBufferBeginInfo {
+            s_type: vk::StructureType::CommandBufferBeginInfo,
+            p_next: ptr::null(),
+            flags: flags,
+            p_inheritance_info: inheritance,
+        };
+        self.layouts.clear();
+        unsafe {
+            self.device.0.begin_command_buffer(self.inner, &info)
+                .expect("Error on command buffer begin") // TODO: handle this better
+        };
+    }
+
+    /// Start recording a primary command buffer.
+    pub fn begin(&mut self) {
+        assert!(!self.secondary, "Secondary command buffers begin with `begin_secondary`");
+        self.validator.begin(None);
+        self.begin_info(vk::CommandBufferUsageFlags::empty(), ptr::null());
+    }
+
+    /// Start recording a secondary command buffer. Buffers executed inside a render pass
+    /// are given the render pass, the subpass index and, if known, the frame buffer.
+    pub fn begin_secondary(&mut self, renderpass: Option<(&native::RenderPass, usize, Option<&native::FrameBuffer>)>) {
+        assert!(self.secondary, "Primary command buffers begin with `begin`");
+        match renderpass {
+            Some((pass, subpass, _)) => {
+                let formats = pass.attachments.iter().map(|a| a.format).collect::<Vec<_>>();
+                self.validator.begin(Some((&formats, subpass, pass.subpass_count)));
+            }
+            None => self.validator.begin(None),
+        }
+        let (flags, render_pass, subpass, framebuffer) = match renderpass {
+            Some((pass, subpass, framebuffer)) =>
+                (vk::COMMAND_BUFFER_USAGE_RENDER_PASS_CONTINUE_BIT, pass.inner, subpass as u32,
+                 framebuffer.map_or(vk::Framebuffer::null(), |fb| fb.inner)),
+            None => (vk::CommandBufferUsageFlags::empty(), vk::RenderPass::null(), 0, vk::Framebuffer::null()),
+        };
+        let inheritance = vk::CommandBufferInheritanceInfo {
+            s_type: vk::StructureType::CommandBufferInheritanceInfo,
+            p_next: ptr::null(),
+            render_pass: render_pass,
+            subpass: subpass,
+            framebuffer: framebuffer,
+            occlusion_query_enable: vk::VK_FALSE,
+            query_flags: vk::QueryControlFlags::empty(),
+            pipeline_statistics: vk::QueryPipelineStatisticFlags::empty(),
+        };
+        self.begin_info(flags, &inheritance);
+    }
+
+    /// Finish recording the command buffer.
+    pub fn end(&mut self) {
+        let _ = self.validator.end(self.secondary);
+        unsafe {
+            self.device.0.end_command_buffer(self.inner)
+                .expect("Error on command buffer end") // TODO: handle this better
+        };
+    }
+
+    /// Reset the command buffer to its initial state, ready to be recorded again with `begin`.
+    /// It must not be pending execution anymore.
+    /// If `release_resources` is set, its memory is given back to the pool.
+    pub fn reset(&mut self, release_resources: bool) {
+        let flags = if release_resources {
+            vk::COMMAND_BUFFER_RESET_RELEASE_RESOURCES_BIT
+        } else {
+            vk::CommandBufferResetFlags::empty()
+        };
+        unsafe {
+            self.device.0.reset_command_buffer(self.inner, flags)
+                .expect("Error on command buffer reset") // TODO: handle this better
+        };
+    }
+
+    /// Transition the subresources of the image from the layout `old` to `new`, waiting for all
+    /// the previous commands, and blocking all the following ones.
+    fn transition_layout(&mut self, image: vk::Image, range: vk::ImageSubresourceRange,
+                         old: vk::ImageLayout, new: vk::ImageLayout) {
+        // images can't be transitioned back to `Undefined`, their content is just kept
+        if old == new || new == vk::ImageLayout::Undefined {
+            return;
+        }
+        self.track_layout(image, &range, new);
+        let barrier = vk::ImageMemoryBarrier {
+            s_type: vk::StructureType::ImageMemoryBarrier,
+            p_next: ptr::null(),
+            src_access_mask: vk::ACCESS_MEMORY_WRITE_BIT,
+            dst_access_mask: vk::ACCESS_MEMORY_READ_BIT | vk::ACCESS_MEMORY_WRITE_BIT,
+            old_layout: old,
+            new_layout: new,
+            src_queue_family_index: vk::VK_QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::VK_QUEUE_FAMILY_IGNORED,
+            image: image,
+            subresource_range: range,
+        };
+        unsafe {
+            self.device.0.cmd_pipeline_barrier(self.inner,
+                                               vk::PIPELINE_STAGE_ALL_COMMANDS_BIT,
+                                               vk::PIPELINE_STAGE_ALL_COMMANDS_BIT,
+                                               vk::DependencyFlags::empty(),
+                                               &[], &[], &[barrier]);
+        }
+    }
+
+    // Remember the `layout` of the subresources of the image.
+    fn track_layout(&mut self, image: vk::Image, range: &vk::ImageSubresourceRange, layout: vk::ImageLayout) {
+        for level in range.base_mip_level .. range.base_mip_level + range.level_count {
+            for layer in range.base_array_layer .. range.base_array_layer + range.layer_count {
+                self.layouts.insert((image, level, layer), layout);
+            }
+        }
+    }
+
+    // Remember the layouts the barriers transition the images to.
+    fn track_barriers(&mut self, barriers: &[vk::ImageMemoryBarrier]) {
+        for barrier in barriers {
+            if barrier.new_layout != vk::ImageLayout::Undefined {
+                self.track_layout(barrier.image, &barrier.subresource_range, barrier.new_layout);
+            }
+        }
+    }
+
+    /// Make sure the subresources of the image are in `layout` before a command using them,
+    /// transitioning the ones last left in another layout by this command buffer.
+    fn ensure_layout(&mut self, image: vk::Image, range: vk::ImageSubresourceRange, layout: vk::ImageLayout) {
+        let mut stale = Vec::new();
+        for level in range.base_mip_level .. range.base_mip_level + range.level_count {
+            for layer in range.base_array_layer .. range.base_array_layer + range.layer_count {
+                match self.layouts.get(&(image, level, layer)) {
+                    Some(&current) if current != layout => stale.push((level, layer, current)),
+                    _ => (),
+                }
+            }
+        }
+        for (level, layer, current) in stale {
+            warn!("Image subresource (level {}, layer {}) is in {:?} instead of {:?}, transitioning it",
+                  level, layer, current, layout);
+            let subresource = vk::ImageSubresourceRange {
+                aspect_mask: range.aspect_mask,
+                base_mip_level: level,
+                level_count: 1,
+                base_array_layer: layer,
+                layer_count: 1,
+            };
+            self.transition_layout(image, subresource, current, layout);
+        }
+    }
+
+    /// Record an indirect draw with `draw`, after checking that the count is within `count_buffer`.
+    fn indirect_count(&mut self, draw: ext::CmdDrawIndirectCount, buffer: native::Buffer, offset: u64,
+                      count_buffer: native::Buffer, count_offset: u64, max_draw_count: u32, stride: u32,
+                      command_size: usize) {
+        if !check_indirect(&buffer, offset, max_draw_count, stride, command_size) {
+            return;
+        }
+        if count_offset % 4 != 0 || count_offset + 4 > count_buffer.size {
+            error!("Invalid draw count offset {}, buffer size {}", count_offset, count_buffer.size);
+            return;
+        }
+        unsafe {
+            draw(self.inner, buffer.inner, offset, count_buffer.inner, count_offset, max_draw_count, stride);
+        }
+    }
+}
+
+impl command::CommandBuffer<R> for CommandBuffer {
+    fn draw(&mut self, first_vertex: VertexCount, vertex_count: VertexCount,
+            instances: Option<(InstanceCount, InstanceCount)>) {
+        if self.validator.draw().is_err() {
+            return;
+        }
+        let (instance_count, first_instance) = instances.unwrap_or((1, 0));
+        unsafe {
+            self.device.0.cmd_draw(self.inner, vertex_count, instance_count,
+                                   first_vertex, first_instance);
+        }
+    }
+
+    fn draw_indexed(&mut self, first_index: VertexCount, index_count: VertexCount, vertex_offset: i32,
+                    instances: Option<(InstanceCount, InstanceCount)>) {
+        if self.validator.draw().is_err() {
+            return;
+        }
+        let (instance_count, first_instance) = instances.unwrap_or((1, 0));
+        unsafe {
+            self.device.0.cmd_draw_indexed(self.inner, index_count, instance_count,
+                                           first_index, vertex_offset, first_instance);
+        }
+    }
+
+    fn draw_indirect(&mut self, buffer: native::Buffer, offset: u64, draw_count: u32, stride: u32) {
+        if self.validator.draw().is_err() {
+            return;
+        }
+        if !check_indirect(&buffer, offset, draw_count, stride, mem::size_of::<command::DrawIndirectCommand>()) {
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_draw_indirect(self.inner, buffer.inner, offset, draw_count, stride);
+        }
+    }
+
+    fn draw_indexed_indirect(&mut self, buffer: native::Buffer, offset: u64, draw_count: u32, stride: u32) {
+        if self.validator.draw().is_err() {
+            return;
+        }
+        if !check_indirect(&buffer, offset, draw_count, stride, mem::size_of::<command::DrawIndexedIndirectCommand>()) {
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_draw_indexed_indirect(self.inner, buffer.inner, offset, draw_count, stride);
+        }
+    }
+
+    fn draw_indirect_count(&mut self, buffer: native::Buffer, offset: u64, count_buffer: native::Buffer,
+                           count_offset: u64, max_draw_count: u32, stride: u32) {
+        if self.validator.draw().is_err() {
+            return;
+        }
+        let draw = match self.device.1.draw_indirect_count {
+            Some(ref functions) => functions.cmd_draw,
+            None => {
+                error!("Indirect draw count is not supported");
+                return;
+            }
+        };
+        self.indirect_count(draw, buffer, offset, count_buffer, count_offset, max_draw_count, stride,
+                            mem::size_of::<command::DrawIndirectCommand>());
+    }
+
+    fn draw_indexed_indirect_count(&mut self, buffer: native::Buffer, offset: u64, count_buffer: native::Buffer,
+                                   count_offset: u64, max_draw_count: u32, stride: u32) {
+        if self.validator.draw().is_err() {
+            return;
+        }
+        let draw = match self.device.1.draw_indirect_count {
+            Some(ref functions) => functions.cmd_draw_indexed,
+            None => {
+                error!("Indirect draw count is not supported");
+                return;
+            }
+        };
+        self.indirect_count(draw, buffer, offset, count_buffer, count_offset, max_draw_count, stride,
+                            mem::size_of::<command::DrawIndexedIndirectCommand>());
+    }
+
+    fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        if self.validator.outside_renderpass("dispatch").is_err() {
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_dispatch(self.inner, x, y, z);
+        }
+    }
+
+    fn dispatch_indirect(&mut self, buffer: native::Buffer, offset: u64) {
+        if self.validator.outside_renderpass("dispatch_indirect").is_err() {
+            return;
+        }
+        if offset % 4 != 0 || offset + mem::size_of::<command::DispatchIndirectCommand>() as u64 > buffer.size {
+            error!("Invalid dispatch argument offset {}, buffer size {}", offset, buffer.size);
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_dispatch_indirect(self.inner, buffer.inner, offset);
+        }
+    }
+
+    fn copy_buffer(&mut self, src: native::Buffer, dst: native::Buffer, regions: &[command::BufferCopy]) {
+        if self.validator.outside_renderpass("copy_buffer").is_err() {
+            return;
+        }
+        let regions = regions.iter().filter(|r| {
+            let valid = r.src + r.size <= src.size && r.dst + r.size <= dst.size;
+            if !valid {
+                error!("Buffer copy region {:?} is out of bounds, src size {}, dst size {}",
+                       r, src.size, dst.size);
+            }
+            valid
+        }).map(|r| vk::BufferCopy {
+            src_offset: r.src,
+            dst_offset: r.dst,
+            size: r.size,
+        }).collect::<Vec<_>>();
+
+        if !regions.is_empty() {
+            unsafe {
+                self.device.0.cmd_copy_buffer(self.inner, src.inner, dst.inner, &regions);
+            }
+        }
+    }
+
+    fn copy_image(&mut self, src: native::Image, src_layout: ImageLayout,
+                  dst: native::Image, dst_layout: ImageLayout, regions: &[command::ImageCopy]) {
+        if self.validator.outside_renderpass("copy_image").is_err() {
+            return;
+        }
+        let src_layout = data::map_image_layout(src_layout);
+        let src_transfer = transfer_layout(src_layout, vk::ImageLayout::TransferSrcOptimal);
+        let dst_layout = data::map_image_layout(dst_layout);
+        let dst_transfer = transfer_layout(dst_layout, vk::ImageLayout::TransferDstOptimal);
+        for r in regions {
+            self.ensure_layout(src.inner, data::map_layers_range(&r.src_subresource), src_layout);
+            self.ensure_layout(dst.inner, data::map_layers_range(&r.dst_subresource), dst_layout);
+            // copies need the transfer layouts, or `General`
+            self.transition_layout(src.inner, data::map_layers_range(&r.src_subresource), src_layout, src_transfer);
+            self.transition_layout(dst.inner, data::map_layers_range(&r.dst_subresource), dst_layout, dst_transfer);
+            let region = vk::ImageCopy {
+                src_subresource: data::map_subresource_layers(&r.src_subresource),
+                src_offset: data::map_offset(r.src_offset),
+                dst_subresource: data::map_subresource_layers(&r.dst_subresource),
+                dst_offset: data::map_offset(r.dst_offset),
+                extent: data::map_extent(r.extent),
+            };
+            unsafe {
+                self.device.0.cmd_copy_image(self.inner,
+                                             src.inner, src_transfer,
+                                             dst.inner, dst_transfer,
+                                             &[region]);
+            }
+            self.transition_layout(src.inner, data::map_layers_range(&r.src_subresource), src_transfer, src_layout);
+            self.transition_layout(dst.inner, data::map_layers_range(&r.dst_subresource), dst_transfer, dst_layout);
+        }
+    }
+
+    fn copy_buffer_to_image(&mut self, src: native::Buffer, dst: native::Image, dst_layout: ImageLayout,
+                            regions: &[command::BufferImageCopy]) {
+        if self.validator.outside_renderpass("copy_buffer_to_image").is_err() {
+            return;
+        }
+        let dst_layout = data::map_image_layout(dst_layout);
+        let dst_transfer = transfer_layout(dst_layout, vk::ImageLayout::TransferDstOptimal);
+        for r in regions {
+            self.ensure_layout(dst.inner, data::map_layers_range(&r.image_subresource), dst_layout);
+            self.transition_layout(dst.inner, data::map_layers_range(&r.image_subresource), dst_layout, dst_transfer);
+            unsafe {
+                self.device.0.cmd_copy_buffer_to_image(self.inner, src.inner,
+                                                       dst.inner, dst_transfer,
+                                                       &[data::map_buffer_image_copy(r)]);
+            }
+            self.transition_layout(dst.inner, data::map_layers_range(&r.image_subresource), dst_transfer, dst_layout);
+        }
+    }
+
+    fn copy_image_to_buffer(&mut self, src: native::Image, src_layout: ImageLayout, dst: native::Buffer,
+                            regions: &[command::BufferImageCopy]) {
+        if self.validator.outside_renderpass("copy_image_to_buffer").is_err() {
+            return;
+        }
+        let src_layout = data::map_image_layout(src_layout);
+        let src_transfer = transfer_layout(src_layout, vk::ImageLayout::TransferSrcOptimal);
+        for r in regions {
+            self.ensure_layout(src.inner, data::map_layers_range(&r.image_subresource), src_layout);
+            self.transition_layout(src.inner, data::map_layers_range(&r.image_subresource), src_layout, src_transfer);
+            unsafe {
+                self.device.0.cmd_copy_image_to_buffer(self.inner,
+                                                       src.inner, src_transfer,
+                                                       dst.inner, &[data::map_buffer_image_copy(r)]);
+            }
+            self.transition_layout(src.inner, data::map_layers_range(&r.image_subresource), src_transfer, src_layout);
+        }
+    }
+
+    fn blit_image(&mut self, src: native::Image, src_layout: ImageLayout,
+                  dst: native::Image, dst_layout: ImageLayout,
+                  filter: image::Filter, regions: &[command::ImageBlit]) {
+        if self.validator.outside_renderpass("blit_image").is_err() {
+            return;
+        }
+        let src_layout = data::map_image_layout(src_layout);
+        let src_transfer = transfer_layout(src_layout, vk::ImageLayout::TransferSrcOptimal);
+        let dst_layout = data::map_image_layout(dst_layout);
+        let dst_transfer = transfer_layout(dst_layout, vk::ImageLayout::TransferDstOptimal);
+        for r in regions {
+            self.ensure_layout(src.inner, data::map_layers_range(&r.src_subresource), src_layout);
+            self.ensure_layout(dst.inner, data::map_layers_range(&r.dst_subresource), dst_layout);
+            self.transition_layout(src.inner, data::map_layers_range(&r.src_subresource), src_layout, src_transfer);
+            self.transition_layout(dst.inner, data::map_layers_range(&r.dst_subresource), dst_layout, dst_transfer);
+            let region = vk::ImageBlit {
+                src_subresource: data::map_subresource_layers(&r.src_subresource),
+                src_offsets: [data::map_offset(r.src_bounds[0]), data::map_offset(r.src_bounds[1])],
+                dst_subresource: data::map_subresource_layers(&r.dst_subresource),
+                dst_offsets: [data::map_offset(r.dst_bounds[0]), data::map_offset(r.dst_bounds[1])],
+            };
+            unsafe {
+                self.device.0.cmd_blit_image(self.inner,
+                                             src.inner, src_transfer,
+                                             dst.inner, dst_transfer,
+                                             &[region], data::map_filter(filter));
+            }
+            self.transition_layout(src.inner, data::map_layers_range(&r.src_subresource), src_transfer, src_layout);
+            self.transition_layout(dst.inner, data::map_layers_range(&r.dst_subresource), dst_transfer, dst_layout);
+        }
+    }
+
+    fn resolve_image(&mut self, src: native::Image, src_layout: ImageLayout,
+                     dst: native::Image, dst_layout: ImageLayout, regions: &[command::ImageResolve]) {
+        if self.validator.outside_renderpass("resolve_image").is_err() {
+            return;
+        }
+        let src_layout = data::map_image_layout(src_layout);
+        let src_transfer = transfer_layout(src_layout, vk::ImageLayout::TransferSrcOptimal);
+        let dst_layout = data::map_image_layout(dst_layout);
+        let dst_transfer = transfer_layout(dst_layout, vk::ImageLayout::TransferDstOptimal);
+        for r in regions {
+            self.ensure_layout(src.inner, data::map_layers_range(&r.src_subresource), src_layout);
+            self.ensure_layout(dst.inner, data::map_layers_range(&r.dst_subresource), dst_layout);
+            self.transition_layout(src.inner, data::map_layers_range(&r.src_subresource), src_layout, src_transfer);
+            self.transition_layout(dst.inner, data::map_layers_range(&r.dst_subresource), dst_layout, dst_transfer);
+            let region = vk::ImageResolve {
+                src_subresource: data::map_subresource_layers(&r.src_subresource),
+                src_offset: data::map_offset(r.src_offset),
+                dst_subresource: data::map_subresource_layers(&r.dst_subresource),
+                dst_offset: data::map_offset(r.dst_offset),
+                extent: data::map_extent(r.extent),
+            };
+            unsafe {
+                self.device.0.cmd_resolve_image(self.inner,
+                                                src.inner, src_transfer,
+                                                dst.inner, dst_transfer,
+                                                &[region]);
+            }
+            self.transition_layout(src.inner, data::map_layers_range(&r.src_subresource), src_transfer, src_layout);
+            self.transition_layout(dst.inner, data::map_layers_range(&r.dst_subresource), dst_transfer, dst_layout);
+        }
+    }
+
+    fn pipeline_barrier(&mut self, src_stages: pso::PipelineStage, dst_stages: pso::PipelineStage,
+                        barriers: &[memory::Barrier<R>]) {
+        let (memory_barriers, buffer_barriers, image_barriers) = map_barriers(barriers);
+        self.track_barriers(&image_barriers);
+
+        unsafe {
+            self.device.0.cmd_pipeline_barrier(self.inner,
+                                               data::map_pipeline_stage(src_stages),
+                                               data::map_pipeline_stage(dst_stages),
+                                               vk::DependencyFlags::empty(),
+                                               &memory_barriers, &buffer_barriers, &image_barriers);
+        }
+    }
+
+    fn transition_image(&mut self, image: native::Image, range: SubresourceRange,
+                        old: ImageLayout, new: ImageLayout) {
+        if self.validator.outside_renderpass("transition_image").is_err() {
+            return;
+        }
+        let range = data::map_subresource_range(&range);
+        let (old, new) = (data::map_image_layout(old), data::map_image_layout(new));
+        self.ensure_layout(image.inner, range.clone(), old);
+        self.transition_layout(image.inner, range, old, new);
+    }
+
+    fn set_event(&mut self, event: &native::Event, stages: pso::PipelineStage) {
+        unsafe {
+            self.device.0.cmd_set_event(self.inner, event.inner, data::map_pipeline_stage(stages));
+        }
+    }
+
+    fn reset_event(&mut self, event: &native::Event, stages: pso::PipelineStage) {
+        unsafe {
+            self.device.0.cmd_reset_event(self.inner, event.inner, data::map_pipeline_stage(stages));
+        }
+    }
+
+    fn wait_events(&mut self, events: &[&native::Event], src_stages: pso::PipelineStage,
+                   dst_stages: pso::PipelineStage, barriers: &[memory::Barrier<R>]) {
+        let events = events.iter().map(|event| event.inner).collect::<Vec<_>>();
+        let (memory_barriers, buffer_barriers, image_barriers) = map_barriers(barriers);
+        self.track_barriers(&image_barriers);
+
+        unsafe {
+            self.device.0.cmd_wait_events(self.inner, &events,
+                                          data::map_pipeline_stage(src_stages),
+                                          data::map_pipeline_stage(dst_stages),
+                                          &memory_barriers, &buffer_barriers, &image_barriers);
+        }
+    }
+
+    fn begin_renderpass(&mut self, renderpass: &native::RenderPass, framebuffer: &native::FrameBuffer,
+                        render_area: target::Rect, clear_values: &[command::ClearValue],
+                        contents: command::SubpassContents) {
+        if render_area.x as u32 + render_area.w as u32 > framebuffer.width ||
+           render_area.y as u32 + render_area.h as u32 > framebuffer.height {
+            error!("Invalid render area {:?}, frame buffer size {}x{}", render_area, framebuffer.width, framebuffer.height);
+            return;
+        }
+        // clear values are indexed by attachment, up to the last one cleared
+        let clears = renderpass.attachments.iter()
+            .rposition(|a| a.load_op == pass::AttachmentLoadOp::Clear || a.stencil_load_op == pass::AttachmentLoadOp::Clear)
+            .map_or(0, |last| last + 1);
+        if clear_values.len() < clears {
+            error!("Missing clear values: {} given, render pass clears {} attachments", clear_values.len(), clears);
+            return;
+        }
+        let formats = renderpass.attachments.iter().map(|a| a.format).collect::<Vec<_>>();
+        if self.validator.begin_renderpass(&formats, renderpass.subpass_count).is_err() {
+            return;
+        }
+        let clear_values = clear_values.iter().map(|&value| data::map_clear_value(value)).collect::<Vec<_>>();
+        let info = vk::RenderPassBeginInfo {
+            s_type: vk::StructureType::RenderPassBeginInfo,
+            p_next: ptr::null(),
+            render_pass: renderpass.inner,
+            framebuffer: framebuffer.inner,
+            render_area: data::map_rect(&render_area),
+            clear_value_count: clear_values.len() as u32,
+            p_clear_values: clear_values.as_ptr(),
+        };
+        unsafe {
+            self.device.0.cmd_begin_render_pass(self.inner, &info, data::map_subpass_contents(contents));
+        }
+    }
+
+    fn next_subpass(&mut self, contents: command::SubpassContents) {
+        if self.validator.next_subpass().is_err() {
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_next_subpass(self.inner, data::map_subpass_contents(contents));
+        }
+    }
+
+    fn end_renderpass(&mut self) {
+        if self.validator.end_renderpass().is_err() {
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_end_render_pass(self.inner);
+        }
+    }
+
+    fn clear_attachments(&mut self, clears: &[command::AttachmentClear], rects: &[target::Rect]) {
+        if self.validator.inside_renderpass("clear_attachments").is_err() {
+            return;
+        }
+        let attachments = clears.iter().map(|clear| match *clear {
+            command::AttachmentClear::Color(index, value) => vk::ClearAttachment {
+                aspect_mask: vk::IMAGE_ASPECT_COLOR_BIT,
+                color_attachment: index as u32,
+                clear_value: data::map_clear_value(command::ClearValue::Color(value)),
+            },
+            command::AttachmentClear::DepthStencil(depth, stencil) => {
+                let mut aspect_mask = vk::ImageAspectFlags::empty();
+                if depth.is_some() {
+                    aspect_mask = aspect_mask | vk::IMAGE_ASPECT_DEPTH_BIT;
+                }
+                if stencil.is_some() {
+                    aspect_mask = aspect_mask | vk::IMAGE_ASPECT_STENCIL_BIT;
+                }
+                vk::ClearAttachment {
+                    aspect_mask: aspect_mask,
+                    color_attachment: 0,
+                    clear_value: data::map_clear_value(command::ClearValue::DepthStencil(
+                        depth.unwrap_or(0.0), stencil.unwrap_or(0))),
+                }
+            }
+        }).collect::<Vec<_>>();
+
+        let rects = rects.iter().map(|rect| vk::ClearRect {
+            rect: data::map_rect(rect),
+            base_array_layer: 0,
+            layer_count: 1,
+        }).collect::<Vec<_>>();
+
+        unsafe {
+            self.device.0.cmd_clear_attachments(self.inner, &attachments, &rects);
+        }
+    }
+
+    fn clear_color(&mut self, image: native::Image, layout: ImageLayout,
+                   range: SubresourceRange, value: command::ClearColor) {
+        if self.validator.outside_renderpass("clear_color").is_err() {
+            return;
+        }
+        let layout = data::map_image_layout(layout);
+        let transfer = transfer_layout(layout, vk::ImageLayout::TransferDstOptimal);
+        let range = data::map_subresource_range(&range);
+        let value = match value {
+            command::ClearColor::Float(v) => vk::ClearColorValue::new_float32(v),
+            command::ClearColor::Int(v) => vk::ClearColorValue::new_int32(v),
+            command::ClearColor::Uint(v) => vk::ClearColorValue::new_uint32(v),
+        };
+        self.ensure_layout(image.inner, range.clone(), layout);
+        self.transition_layout(image.inner, range.clone(), layout, transfer);
+        unsafe {
+            self.device.0.cmd_clear_color_image(self.inner, image.inner,
+                                                transfer,
+                                                &value, &[range.clone()]);
+        }
+        self.transition_layout(image.inner, range, transfer, layout);
+    }
+
+    fn clear_depth_stencil(&mut self, image: native::Image, layout: ImageLayout,
+                           range: SubresourceRange, depth: Option<f32>, stencil: Option<u32>) {
+        if self.validator.outside_renderpass("clear_depth_stencil").is_err() {
+            return;
+        }
+        let layout = data::map_image_layout(layout);
+        let transfer = transfer_layout(layout, vk::ImageLayout::TransferDstOptimal);
+        let mut range = data::map_subresource_range(&range);
+        // only clear the aspects that are given a value
+        range.aspect_mask = vk::ImageAspectFlags::empty();
+        if depth.is_some() {
+            range.aspect_mask = range.aspect_mask | vk::IMAGE_ASPECT_DEPTH_BIT;
+        }
+        if stencil.is_some() {
+            range.aspect_mask = range.aspect_mask | vk::IMAGE_ASPECT_STENCIL_BIT;
+        }
+        let value = vk::ClearDepthStencilValue {
+            depth: depth.unwrap_or(0.0),
+            stencil: stencil.unwrap_or(0),
+        };
+        self.ensure_layout(image.inner, range.clone(), layout);
+        self.transition_layout(image.inner, range.clone(), layout, transfer);
+        unsafe {
+            self.device.0.cmd_clear_depth_stencil_image(self.inner, image.inner,
+                                                        transfer,
+                                                        &value, &[range.clone()]);
+        }
+        self.transition_layout(image.inner, range, transfer, layout);
+    }
+
+    fn fill_buffer(&mut self, buffer: native::Buffer, offset: u64, size: u64, value: u32) {
+        if self.validator.outside_renderpass("fill_buffer").is_err() {
+            return;
+        }
+        if offset % 4 != 0 || size % 4 != 0 || offset + size > buffer.size {
+            error!("Invalid buffer fill range {}..{}, buffer size {}", offset, offset + size, buffer.size);
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_fill_buffer(self.inner, buffer.inner, offset, size, value);
+        }
+    }
+
+    fn set_depth_bias(&mut self, constant_factor: f32, clamp: f32, slope_factor: f32) {
+        unsafe {
+            self.device.0.cmd_set_depth_bias(self.inner, constant_factor, clamp, slope_factor);
+        }
+    }
+
+    fn set_depth_bounds(&mut self, bounds: Range<f32>) {
+        unsafe {
+            self.device.0.cmd_set_depth_bounds(self.inner, bounds.start, bounds.end);
+        }
+    }
+
+    fn set_line_width(&mut self, width: f32) {
+        unsafe {
+            self.device.0.cmd_set_line_width(self.inner, width);
+        }
+    }
+
+    fn set_stencil_reference(&mut self, front: u8, back: u8) {
+        unsafe {
+            if front == back {
+                self.device.0.cmd_set_stencil_reference(self.inner, vk::STENCIL_FRONT_AND_BACK, front as u32);
+            } else {
+                self.device.0.cmd_set_stencil_reference(self.inner, vk::STENCIL_FACE_FRONT_BIT, front as u32);
+                self.device.0.cmd_set_stencil_reference(self.inner, vk::STENCIL_FACE_BACK_BIT, back as u32);
+            }
+        }
+    }
+
+    fn bind_pipeline(&mut self, pipeline: &native::Pipeline) {
+        if pipeline.bind_point == vk::PipelineBindPoint::Graphics {
+            self.validator.bind_graphics_pipeline(pipeline.renderpass.as_ref().map(|&(ref formats, subpass)| (&formats[..], subpass)));
+        }
+        unsafe {
+            self.device.0.cmd_bind_pipeline(self.inner, pipeline.bind_point, pipeline.pipeline);
+        }
+    }
+
+    fn bind_vertex_buffers(&mut self, first_binding: u32, vbs: pso::VertexBufferSet<Resources>) {
+        if let Some(&(buffer, offset)) = vbs.0.iter().find(|&&(buffer, offset)| offset >= buffer.size) {
+            error!("Invalid vertex buffer offset {}, buffer size {}", offset, buffer.size);
+            return;
+        }
+        let buffers = vbs.0.iter().map(|&(buffer, _)| buffer.inner).collect::<Vec<_>>();
+        let offsets = vbs.0.iter().map(|&(_, offset)| offset).collect::<Vec<_>>();
+        unsafe {
+            self.device.0.cmd_bind_vertex_buffers(self.inner, first_binding, &buffers, &offsets);
+        }
+    }
+
+    fn bind_descriptor_sets(&mut self, bind_point: pso::BindPoint, layout: &native::PipelineSignature,
+                            first_set: usize, sets: &[&native::DescriptorSet], dynamic_offsets: &[u32]) {
+        if first_set + sets.len() > layout.set_count {
+            error!("Descriptor sets {}..{} are not within the {} sets of the layout",
+                   first_set, first_set + sets.len(), layout.set_count);
+            return;
+        }
+        let sets = sets.iter().map(|set| set.inner).collect::<Vec<_>>();
+        unsafe {
+            self.device.0.cmd_bind_descriptor_sets(self.inner, data::map_bind_point(bind_point),
+                                                   layout.layout, first_set as u32,
+                                                   &sets, dynamic_offsets);
+        }
+    }
+
+    fn push_constants(&mut self, layout: &native::PipelineSignature, stages: shade::StageFlags,
+                      offset: u32, data: &[u8]) {
+        let end = offset + data.len() as u32;
+        let covered = layout.push_constant_ranges.iter().any(|&(range_stages, ref range)| {
+            range_stages.contains(stages) && range.start <= offset && end <= range.end
+        });
+        if !covered {
+            error!("Push constants {}..{} for the stages {:?} are not within the ranges of the layout {:?}",
+                   offset, end, stages, layout.push_constant_ranges);
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_push_constants(self.inner, layout.layout, data::map_stage_flags(stages),
+                                             offset, data);
+        }
+    }
+
+    fn reset_query_pool(&mut self, pool: &native::QueryPool, queries: Range<u32>) {
+        if self.validator.outside_renderpass("reset_query_pool").is_err() {
+            return;
+        }
+        if queries.start > queries.end || queries.end > pool.count {
+            error!("Invalid query range {:?}, pool size {}", queries, pool.count);
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_reset_query_pool(self.inner, pool.inner, queries.start,
+                                               queries.end - queries.start);
+        }
+    }
+
+    fn begin_query(&mut self, pool: &native::QueryPool, query: u32, control: QueryControl) {
+        if query >= pool.count {
+            error!("Invalid query {}, pool size {}", query, pool.count);
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_begin_query(self.inner, pool.inner, query,
+                                          data::map_query_control(control));
+        }
+    }
+
+    fn end_query(&mut self, pool: &native::QueryPool, query: u32) {
+        if query >= pool.count {
+            error!("Invalid query {}, pool size {}", query, pool.count);
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_end_query(self.inner, pool.inner, query);
+        }
+    }
+
+    fn write_timestamp(&mut self, pool: &native::QueryPool, query: u32) {
+        if query >= pool.count {
+            error!("Invalid query {}, pool size {}", query, pool.count);
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_write_timestamp(self.inner, vk::PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT,
+                                              pool.inner, query);
+        }
+    }
+
+    fn copy_query_results(&mut self, pool: &native::QueryPool, queries: Range<u32>, buffer: native::Buffer,
+                          offset: u64, stride: u64, flags: QueryResultFlags) {
+        if self.validator.outside_renderpass("copy_query_results").is_err() {
+            return;
+        }
+        if queries.start > queries.end || queries.end > pool.count {
+            error!("Invalid query range {:?}, pool size {}", queries, pool.count);
+            return;
+        }
+        let count = queries.end - queries.start;
+        if count == 0 {
+            return;
+        }
+        // each result is a 64 bit integer, followed by its availability if requested
+        let values = pool.values as u64 + if flags.contains(query::WITH_AVAILABILITY) { 1 } else { 0 };
+        let result_size = 8 * values;
+        let end = offset + (count as u64 - 1) * stride + result_size;
+        if offset % 8 != 0 || stride % 8 != 0 || end > buffer.size {
+            error!("Invalid query results range {}..{} with stride {}, buffer size {}",
+                offset, end, stride, buffer.size);
+            return;
+        }
+        unsafe {
+            self.device.0.cmd_copy_query_pool_results(self.inner, pool.inner, queries.start, count,
+                                                      buffer.inner, offset, stride,
+                                                      data::map_query_result_flags(flags));
+        }
+    }
+
+    fn begin_conditional_rendering(&mut self, buffer: native::Buffer, offset: u64, inverted: bool) {
+        if offset % 4 != 0 || offset + 4 > buffer.size {
+            error!("Invalid conditional rendering offset {}, buffer size {}", offset, buffer.size);
+            return;
+        }
+        let flags = if inverted { ext::CONDITIONAL_RENDERING_INVERTED_BIT } else { 0 };
+        let info = ext::ConditionalRenderingBeginInfo::new(buffer.inner, offset, flags);
+        match self.device.1.conditional_rendering {
+            Some(ref functions) => unsafe { (functions.cmd_begin)(self.inner, &info) },
+            None => error!("Conditional rendering is not supported"),
+        }
+    }
+
+    fn end_conditional_rendering(&mut self) {
+        match self.device.1.conditional_rendering {
+            Some(ref functions) => unsafe { (functions.cmd_end)(self.inner) },
+            None => error!("Conditional rendering is not supported"),
+        }
+    }
+
+    fn execute_commands(&mut self, buffers: &[&CommandBuffer]) {
+        if self.secondary || buffers.iter().any(|buffer| !buffer.secondary) {
+            error!("Only secondary command buffers can be executed, by primary ones");
+            return;
+        }
+        let buffers = buffers.iter().map(|buffer| buffer.inner).collect::<Vec<_>>();
+        unsafe {
+            self.device.0.cmd_execute_commands(self.inner, &buffers);
+        }
+    }
+}
@@ -14,6 +14,8 @@
 
 use ash::vk;
 use core::format::{SurfaceType, ChannelType};
+use core::{buffer, command, descriptor, memory, pass, pso, query, shade, target};
+use core::image::{self, ImageLayout};
 
 pub fn map_format(surface: SurfaceType, chan: ChannelType) -> Option<vk::Format> {
     use core::format::SurfaceType::*;
@@ -149,3 +151,426 @@ pub fn map_format(surface: SurfaceType, chan: ChannelType) -> Option<vk::Format>
     })
 }
 
+pub fn map_image_aspect(aspect: image::Aspect) -> vk::ImageAspectFlags {
+    use core::image::Aspect::*;
+    match aspect {
+        Color => vk::IMAGE_ASPECT_COLOR_BIT,
+        Depth => vk::IMAGE_ASPECT_DEPTH_BIT,
+        Stencil => vk::IMAGE_ASPECT_STENCIL_BIT,
+        DepthStencil => vk::IMAGE_ASPECT_DEPTH_BIT | vk::IMAGE_ASPECT_STENCIL_BIT,
+    }
+}
+
+pub fn map_image_layout(layout: ImageLayout) -> vk::ImageLayout {
+    match layout {
+        ImageLayout::General => vk::ImageLayout::General,
+        ImageLayout::ColorAttachmentOptimal => vk::ImageLayout::ColorAttachmentOptimal,
+        ImageLayout::DepthStencilAttachmentOptimal => vk::ImageLayout::DepthStencilAttachmentOptimal,
+        ImageLayout::DepthStencilReadOnlyOptimal => vk::ImageLayout::DepthStencilReadOnlyOptimal,
+        ImageLayout::ShaderReadOnlyOptimal => vk::ImageLayout::ShaderReadOnlyOptimal,
+        ImageLayout::TransferSrcOptimal => vk::ImageLayout::TransferSrcOptimal,
+        ImageLayout::TransferDstOptimal => vk::ImageLayout::TransferDstOptimal,
+        ImageLayout::Undefined => vk::ImageLayout::Undefined,
+        ImageLayout::Preinitialized => vk::ImageLayout::Preinitialized,
+        ImageLayout::Present => vk::ImageLayout::PresentSrcKhr,
+    }
+}
+
+pub fn map_subresource_layers(sub: &image::SubresourceLayers) -> vk::ImageSubresourceLayers {
+    vk::ImageSubresourceLayers {
+        aspect_mask: map_image_aspect(sub.aspect),
+        mip_level: sub.level as u32,
+        base_array_layer: sub.base_layer as u32,
+        layer_count: sub.layer_count as u32,
+    }
+}
+
+/// Range of the subresources covered by the layers.
+pub fn map_layers_range(sub: &image::SubresourceLayers) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: map_image_aspect(sub.aspect),
+        base_mip_level: sub.level as u32,
+        level_count: 1,
+        base_array_layer: sub.base_layer as u32,
+        layer_count: sub.layer_count as u32,
+    }
+}
+
+pub fn map_offset(offset: image::Offset) -> vk::Offset3D {
+    vk::Offset3D {
+        x: offset.x,
+        y: offset.y,
+        z: offset.z,
+    }
+}
+
+pub fn map_extent(extent: image::Extent) -> vk::Extent3D {
+    vk::Extent3D {
+        width: extent.width,
+        height: extent.height,
+        depth: extent.depth,
+    }
+}
+
+pub fn map_buffer_image_copy(region: &command::BufferImageCopy) -> vk::BufferImageCopy {
+    vk::BufferImageCopy {
+        buffer_offset: region.buffer_offset,
+        buffer_row_length: region.buffer_row_length,
+        buffer_image_height: region.buffer_image_height,
+        image_subresource: map_subresource_layers(&region.image_subresource),
+        image_offset: map_offset(region.image_offset),
+        image_extent: map_extent(region.image_extent),
+    }
+}
+
+pub fn map_filter(filter: image::Filter) -> vk::Filter {
+    match filter {
+        image::Filter::Nearest => vk::Filter::Nearest,
+        image::Filter::Linear => vk::Filter::Linear,
+    }
+}
+
+pub fn map_mipmap_mode(filter: image::Filter) -> vk::SamplerMipmapMode {
+    match filter {
+        image::Filter::Nearest => vk::SamplerMipmapMode::Nearest,
+        image::Filter::Linear => vk::SamplerMipmapMode::Linear,
+    }
+}
+
+pub fn map_wrap(wrap: image::WrapMode) -> vk::SamplerAddressMode {
+    match wrap {
+        image::WrapMode::Tile => vk::SamplerAddressMode::Repeat,
+        image::WrapMode::Mirror => vk::SamplerAddressMode::MirroredRepeat,
+        image::WrapMode::Clamp => vk::SamplerAddressMode::ClampToEdge,
+        image::WrapMode::Border => vk::SamplerAddressMode::ClampToBorder,
+    }
+}
+
+pub fn map_border_color(color: image::BorderColor) -> vk::BorderColor {
+    match color {
+        image::BorderColor::TransparentBlack => vk::BorderColor::FloatTransparentBlack,
+        image::BorderColor::OpaqueBlack => vk::BorderColor::FloatOpaqueBlack,
+        image::BorderColor::OpaqueWhite => vk::BorderColor::FloatOpaqueWhite,
+    }
+}
+
+pub fn map_subresource_range(sub: &image::SubresourceRange) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: map_image_aspect(sub.aspect),
+        base_mip_level: sub.base_level as u32,
+        level_count: sub.level_count as u32,
+        base_array_layer: sub.base_layer as u32,
+        layer_count: sub.layer_count as u32,
+    }
+}
+
+pub fn map_pipeline_stage(stages: pso::PipelineStage) -> vk::PipelineStageFlags {
+    let pairs = [
+        (pso::TOP_OF_PIPE, vk::PIPELINE_STAGE_TOP_OF_PIPE_BIT),
+        (pso::DRAW_INDIRECT, vk::PIPELINE_STAGE_DRAW_INDIRECT_BIT),
+        (pso::VERTEX_INPUT, vk::PIPELINE_STAGE_VERTEX_INPUT_BIT),
+        (pso::VERTEX_SHADER, vk::PIPELINE_STAGE_VERTEX_SHADER_BIT),
+        (pso::HULL_SHADER, vk::PIPELINE_STAGE_TESSELLATION_CONTROL_SHADER_BIT),
+        (pso::DOMAIN_SHADER, vk::PIPELINE_STAGE_TESSELLATION_EVALUATION_SHADER_BIT),
+        (pso::GEOMETRY_SHADER, vk::PIPELINE_STAGE_GEOMETRY_SHADER_BIT),
+        (pso::FRAGMENT_SHADER, vk::PIPELINE_STAGE_FRAGMENT_SHADER_BIT),
+        (pso::EARLY_FRAGMENT_TESTS, vk::PIPELINE_STAGE_EARLY_FRAGMENT_TESTS_BIT),
+        (pso::LATE_FRAGMENT_TESTS, vk::PIPELINE_STAGE_LATE_FRAGMENT_TESTS_BIT),
+        (pso::COLOR_ATTACHMENT_OUTPUT, vk::PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT),
+        (pso::COMPUTE_SHADER, vk::PIPELINE_STAGE_COMPUTE_SHADER_BIT),
+        (pso::TRANSFER, vk::PIPELINE_STAGE_TRANSFER_BIT),
+        (pso::BOTTOM_OF_PIPE, vk::PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT),
+        (pso::HOST, vk::PIPELINE_STAGE_HOST_BIT),
+    ];
+    pairs.iter().fold(vk::PipelineStageFlags::empty(), |flags, &(stage, vk_stage)| {
+        if stages.contains(stage) { flags | vk_stage } else { flags }
+    })
+}
+
+pub fn map_access(access: memory::Access) -> vk::AccessFlags {
+    let pairs = [
+        (memory::INDIRECT_COMMAND_READ, vk::ACCESS_INDIRECT_COMMAND_READ_BIT),
+        (memory::INDEX_BUFFER_READ, vk::ACCESS_INDEX_READ_BIT),
+        (memory::VERTEX_ATTRIBUTE_READ, vk::ACCESS_VERTEX_ATTRIBUTE_READ_BIT),
+        (memory::CONSTANT_BUFFER_READ, vk::ACCESS_UNIFORM_READ_BIT),
+        (memory::INPUT_ATTACHMENT_READ, vk::ACCESS_INPUT_ATTACHMENT_READ_BIT),
+        (memory::SHADER_READ, vk::ACCESS_SHADER_READ_BIT),
+        (memory::SHADER_WRITE, vk::ACCESS_SHADER_WRITE_BIT),
+        (memory::COLOR_ATTACHMENT_READ, vk::ACCESS_COLOR_ATTACHMENT_READ_BIT),
+        (memory::COLOR_ATTACHMENT_WRITE, vk::ACCESS_COLOR_ATTACHMENT_WRITE_BIT),
+        (memory::DEPTH_STENCIL_ATTACHMENT_READ, vk::ACCESS_DEPTH_STENCIL_ATTACHMENT_READ_BIT),
+        (memory::DEPTH_STENCIL_ATTACHMENT_WRITE, vk::ACCESS_DEPTH_STENCIL_ATTACHMENT_WRITE_BIT),
+        (memory::TRANSFER_READ, vk::ACCESS_TRANSFER_READ_BIT),
+        (memory::TRANSFER_WRITE, vk::ACCESS_TRANSFER_WRITE_BIT),
+        (memory::HOST_READ, vk::ACCESS_HOST_READ_BIT),
+        (memory::HOST_WRITE, vk::ACCESS_HOST_WRITE_BIT),
+        (memory::MEMORY_READ, vk::ACCESS_MEMORY_READ_BIT),
+        (memory::MEMORY_WRITE, vk::ACCESS_MEMORY_WRITE_BIT),
+    ];
+    pairs.iter().fold(vk::AccessFlags::empty(), |flags, &(a, vk_a)| {
+        if access.contains(a) { flags | vk_a } else { flags }
+    })
+}
+
+pub fn map_attachment_load_op(op: pass::AttachmentLoadOp) -> vk::AttachmentLoadOp {
+    match op {
+        pass::AttachmentLoadOp::Load => vk::AttachmentLoadOp::Load,
+        pass::AttachmentLoadOp::Clear => vk::AttachmentLoadOp::Clear,
+        pass::AttachmentLoadOp::DontCare => vk::AttachmentLoadOp::DontCare,
+    }
+}
+
+pub fn map_attachment_store_op(op: pass::AttachmentStoreOp) -> vk::AttachmentStoreOp {
+    match op {
+        pass::AttachmentStoreOp::Store => vk::AttachmentStoreOp::Store,
+        pass::AttachmentStoreOp::DontCare => vk::AttachmentStoreOp::DontCare,
+    }
+}
+
+pub fn map_clear_value(value: command::ClearValue) -> vk::ClearValue {
+    match value {
+        command::ClearValue::Color(command::ClearColor::Float(v)) =>
+            vk::ClearValue::new_color(vk::ClearColorValue::new_float32(v)),
+        command::ClearValue::Color(command::ClearColor::Int(v)) =>
+            vk::ClearValue::new_color(vk::ClearColorValue::new_int32(v)),
+        command::ClearValue::Color(command::ClearColor::Uint(v)) =>
+            vk::ClearValue::new_color(vk::ClearColorValue::new_uint32(v)),
+        command::ClearValue::DepthStencil(depth, stencil) =>
+            vk::ClearValue::new_depth_stencil(vk::ClearDepthStencilValue {
+                depth: depth,
+                stencil: stencil,
+            }),
+    }
+}
+
+pub fn map_subpass_contents(contents: command::SubpassContents) -> vk::SubpassContents {
+    match contents {
+        command::SubpassContents::Inline => vk::SubpassContents::Inline,
+        command::SubpassContents::SecondaryBuffers => vk::SubpassContents::SecondaryCommandBuffers,
+    }
+}
+
+pub fn map_rect(rect: &target::Rect) -> vk::Rect2D {
+    vk::Rect2D {
+        offset: vk::Offset2D {
+            x: rect.x as i32,
+            y: rect.y as i32,
+        },
+        extent: vk::Extent2D {
+            width: rect.w as u32,
+            height: rect.h as u32,
+        },
+    }
+}
+
+pub fn map_bind_point(bind_point: pso::BindPoint) -> vk::PipelineBindPoint {
+    match bind_point {
+        pso::BindPoint::Graphics => vk::PipelineBindPoint::Graphics,
+        pso::BindPoint::Compute => vk::PipelineBindPoint::Compute,
+    }
+}
+
+pub fn map_stage_flags(stages: shade::StageFlags) -> vk::ShaderStageFlags {
+    let pairs = [
+        (shade::VERTEX, vk::SHADER_STAGE_VERTEX_BIT),
+        (shade::HULL, vk::SHADER_STAGE_TESSELLATION_CONTROL_BIT),
+        (shade::DOMAIN, vk::SHADER_STAGE_TESSELLATION_EVALUATION_BIT),
+        (shade::GEOMETRY, vk::SHADER_STAGE_GEOMETRY_BIT),
+        (shade::PIXEL, vk::SHADER_STAGE_FRAGMENT_BIT),
+        (shade::COMPUTE, vk::SHADER_STAGE_COMPUTE_BIT),
+    ];
+    pairs.iter().fold(vk::ShaderStageFlags::empty(), |flags, &(stage, vk_stage)| {
+        if stages.contains(stage) { flags | vk_stage } else { flags }
+    })
+}
+
+pub fn map_descriptor_type(ty: descriptor::DescriptorType) -> vk::DescriptorType {
+    use core::descriptor::DescriptorType::*;
+    match ty {
+        Sampler => vk::DescriptorType::Sampler,
+        CombinedImageSampler => vk::DescriptorType::CombinedImageSampler,
+        SampledImage => vk::DescriptorType::SampledImage,
+        StorageImage => vk::DescriptorType::StorageImage,
+        ConstantBuffer => vk::DescriptorType::UniformBuffer,
+        StorageBuffer => vk::DescriptorType::StorageBuffer,
+        InputAttachment => vk::DescriptorType::InputAttachment,
+        UniformTexelBuffer => vk::DescriptorType::UniformTexelBuffer,
+        StorageTexelBuffer => vk::DescriptorType::StorageTexelBuffer,
+    }
+}
+
+pub fn map_query_type(ty: query::QueryType) -> vk::QueryType {
+    match ty {
+        query::QueryType::Occlusion => vk::QueryType::Occlusion,
+        query::QueryType::Timestamp => vk::QueryType::Timestamp,
+        query::QueryType::PipelineStatistics(_) => vk::QueryType::PipelineStatistics,
+    }
+}
+
+pub fn map_pipeline_statistics(statistics: query::PipelineStatistic) -> vk::QueryPipelineStatisticFlags {
+    let pairs = [
+        (query::INPUT_ASSEMBLY_VERTICES, vk::QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_VERTICES_BIT),
+        (query::INPUT_ASSEMBLY_PRIMITIVES, vk::QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_PRIMITIVES_BIT),
+        (query::VERTEX_SHADER_INVOCATIONS, vk::QUERY_PIPELINE_STATISTIC_VERTEX_SHADER_INVOCATIONS_BIT),
+        (query::GEOMETRY_SHADER_INVOCATIONS, vk::QUERY_PIPELINE_STATISTIC_GEOMETRY_SHADER_INVOCATIONS_BIT),
+        (query::GEOMETRY_SHADER_PRIMITIVES, vk::QUERY_PIPELINE_STATISTIC_GEOMETRY_SHADER_PRIMITIVES_BIT),
+        (query::CLIPPING_INVOCATIONS, vk::QUERY_PIPELINE_STATISTIC_CLIPPING_INVOCATIONS_BIT),
+        (query::CLIPPING_PRIMITIVES, vk::QUERY_PIPELINE_STATISTIC_CLIPPING_PRIMITIVES_BIT),
+        (query::PIXEL_SHADER_INVOCATIONS, vk::QUERY_PIPELINE_STATISTIC_FRAGMENT_SHADER_INVOCATIONS_BIT),
+        (query::HULL_SHADER_PATCHES, vk::QUERY_PIPELINE_STATISTIC_TESSELLATION_CONTROL_SHADER_PATCHES_BIT),
+        (query::DOMAIN_SHADER_INVOCATIONS, vk::QUERY_PIPELINE_STATISTIC_TESSELLATION_EVALUATION_SHADER_INVOCATIONS_BIT),
+        (query::COMPUTE_SHADER_INVOCATIONS, vk::QUERY_PIPELINE_STATISTIC_COMPUTE_SHADER_INVOCATIONS_BIT),
+    ];
+    pairs.iter().fold(vk::QueryPipelineStatisticFlags::empty(), |flags, &(stat, vk_stat)| {
+        if statistics.contains(stat) { flags | vk_stat } else { flags }
+    })
+}
+
+pub fn map_query_control(control: query::QueryControl) -> vk::QueryControlFlags {
+    if control.contains(query::PRECISE) {
+        vk::QUERY_CONTROL_PRECISE_BIT
+    } else {
+        vk::QueryControlFlags::empty()
+    }
+}
+
+pub fn map_query_result_flags(flags: query::QueryResultFlags) -> vk::QueryResultFlags {
+    let pairs = [
+        (query::WAIT, vk::QUERY_RESULT_WAIT_BIT),
+        (query::WITH_AVAILABILITY, vk::QUERY_RESULT_WITH_AVAILABILITY_BIT),
+        (query::PARTIAL, vk::QUERY_RESULT_PARTIAL_BIT),
+    ];
+    // results are always 64 bit wide
+    pairs.iter().fold(vk::QUERY_RESULT_64_BIT, |vk_flags, &(flag, vk_flag)| {
+        if flags.contains(flag) { vk_flags | vk_flag } else { vk_flags }
+    })
+}
+
+pub fn map_dynamic_states(states: pso::DynamicStates) -> Vec<vk::DynamicState> {
+    let pairs = [
+        (pso::DEPTH_BIAS, vk::DynamicState::DepthBias),
+        (pso::DEPTH_BOUNDS, vk::DynamicState::DepthBounds),
+        (pso::LINE_WIDTH, vk::DynamicState::LineWidth),
+        (pso::STENCIL_REFERENCE, vk::DynamicState::StencilReference),
+    ];
+    pairs.iter()
+         .filter(|&&(state, _)| states.contains(state))
+         .map(|&(_, vk_state)| vk_state)
+         .collect()
+}
+
+pub fn map_buffer_usage(usage: buffer::Usage) -> vk::BufferUsageFlags {
+    let pairs = [
+        (buffer::TRANSFER_SRC, vk::BUFFER_USAGE_TRANSFER_SRC_BIT),
+        (buffer::TRANSFER_DST, vk::BUFFER_USAGE_TRANSFER_DST_BIT),
+        (buffer::CONSTANT, vk::BUFFER_USAGE_UNIFORM_BUFFER_BIT),
+        (buffer::STORAGE, vk::BUFFER_USAGE_STORAGE_BUFFER_BIT),
+        (buffer::INDEX, vk::BUFFER_USAGE_INDEX_BUFFER_BIT),
+        (buffer::VERTEX, vk::BUFFER_USAGE_VERTEX_BUFFER_BIT),
+        (buffer::INDIRECT, vk::BUFFER_USAGE_INDIRECT_BUFFER_BIT),
+        (buffer::UNIFORM_TEXEL, vk::BUFFER_USAGE_UNIFORM_TEXEL_BUFFER_BIT),
+        (buffer::STORAGE_TEXEL, vk::BUFFER_USAGE_STORAGE_TEXEL_BUFFER_BIT),
+    ];
+    pairs.iter().fold(vk::BufferUsageFlags::empty(), |flags, &(bit, vk_bit)| {
+        if usage.contains(bit) { flags | vk_bit } else { flags }
+    })
+}
+
+pub fn map_image_usage(usage: image::Usage) -> vk::ImageUsageFlags {
+    let pairs = [
+        (image::TRANSFER_SRC, vk::IMAGE_USAGE_TRANSFER_SRC_BIT),
+        (image::TRANSFER_DST, vk::IMAGE_USAGE_TRANSFER_DST_BIT),
+        (image::COLOR_ATTACHMENT, vk::IMAGE_USAGE_COLOR_ATTACHMENT_BIT),
+        (image::DEPTH_STENCIL_ATTACHMENT, vk::IMAGE_USAGE_DEPTH_STENCIL_ATTACHMENT_BIT),
+        (image::SAMPLED, vk::IMAGE_USAGE_SAMPLED_BIT),
+        (image::STORAGE, vk::IMAGE_USAGE_STORAGE_BIT),
+        (image::TRANSIENT_ATTACHMENT, vk::IMAGE_USAGE_TRANSIENT_ATTACHMENT_BIT),
+    ];
+    pairs.iter().fold(vk::ImageUsageFlags::empty(), |flags, &(bit, vk_bit)| {
+        if usage.contains(bit) { flags | vk_bit } else { flags }
+    })
+}
+
+pub fn map_sample_count(samples: image::NumSamples) -> vk::SampleCountFlags {
+    match samples {
+        1 => vk::SAMPLE_COUNT_1_BIT,
+        2 => vk::SAMPLE_COUNT_2_BIT,
+        4 => vk::SAMPLE_COUNT_4_BIT,
+        8 => vk::SAMPLE_COUNT_8_BIT,
+        16 => vk::SAMPLE_COUNT_16_BIT,
+        32 => vk::SAMPLE_COUNT_32_BIT,
+        64 => vk::SAMPLE_COUNT_64_BIT,
+        _ => panic!("Unsupported number of samples: {}", samples),
+    }
+}
+
+pub fn map_format_features(features: vk::FormatFeatureFlags) -> image::Usage {
+    let pairs = [
+        (vk::FORMAT_FEATURE_SAMPLED_IMAGE_BIT, image::SAMPLED),
+        (vk::FORMAT_FEATURE_STORAGE_IMAGE_BIT, image::STORAGE),
+        (vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BIT, image::COLOR_ATTACHMENT),
+        (vk::FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT, image::DEPTH_STENCIL_ATTACHMENT),
+        (vk::FORMAT_FEATURE_BLIT_SRC_BIT, image::TRANSFER_SRC),
+        (vk::FORMAT_FEATURE_BLIT_DST_BIT, image::TRANSFER_DST),
+    ];
+    pairs.iter().fold(image::Usage::empty(), |usage, &(vk_bit, bit)| {
+        if features.intersects(vk_bit) { usage | bit } else { usage }
+    })
+}
+
+/// Map a set of sample counts to the supported numbers of samples, in increasing order.
+pub fn map_sample_counts(flags: vk::SampleCountFlags) -> Vec<image::NumSamples> {
+    [1, 2, 4, 8, 16, 32, 64].iter()
+        .cloned()
+        .filter(|&samples| flags.intersects(map_sample_count(samples)))
+        .collect()
+}
+
+pub fn map_view_kind(kind: image::ViewKind) -> vk::ImageViewType {
+    match kind {
+        image::ViewKind::D1 => vk::ImageViewType::Type1d,
+        image::ViewKind::D1Array => vk::ImageViewType::Type1dArray,
+        image::ViewKind::D2 => vk::ImageViewType::Type2d,
+        image::ViewKind::D2Array => vk::ImageViewType::Type2dArray,
+        image::ViewKind::D3 => vk::ImageViewType::Type3d,
+        image::ViewKind::Cube => vk::ImageViewType::Cube,
+        image::ViewKind::CubeArray => vk::ImageViewType::CubeArray,
+    }
+}
+
+/// Map the dimensions of an image to its type, extent and number of array layers.
+pub fn map_image_kind(kind: image::Kind) -> (vk::ImageType, vk::Extent3D, u32) {
+    match kind {
+        image::Kind::D1(width, layers) =>
+            (vk::ImageType::Type1d, vk::Extent3D { width: width, height: 1, depth: 1 }, layers as u32),
+        image::Kind::D2(width, height, layers, _) =>
+            (vk::ImageType::Type2d, vk::Extent3D { width: width, height: height, depth: 1 }, layers as u32),
+        image::Kind::D3(width, height, depth) =>
+            (vk::ImageType::Type3d, vk::Extent3D { width: width, height: height, depth: depth }, 1),
+        image::Kind::Cube(size) =>
+            (vk::ImageType::Type2d, vk::Extent3D { width: size, height: size, depth: 1 }, 6),
+        image::Kind::CubeArray(size, cubes) =>
+            (vk::ImageType::Type2d, vk::Extent3D { width: size, height: size, depth: 1 }, 6 * cubes as u32),
+    }
+}
+
+pub fn map_memory_properties(flags: vk::MemoryPropertyFlags) -> memory::MemoryProperties {
+    let pairs = [
+        (vk::MEMORY_PROPERTY_DEVICE_LOCAL_BIT, memory::DEVICE_LOCAL),
+        (vk::MEMORY_PROPERTY_HOST_VISIBLE_BIT, memory::CPU_VISIBLE),
+        (vk::MEMORY_PROPERTY_HOST_COHERENT_BIT, memory::COHERENT),
+        (vk::MEMORY_PROPERTY_HOST_CACHED_BIT, memory::CPU_CACHED),
+        (vk::MEMORY_PROPERTY_LAZILY_ALLOCATED_BIT, memory::LAZILY_ALLOCATED),
+    ];
+    pairs.iter().fold(memory::MemoryProperties::empty(), |properties, &(vk_bit, bit)| {
+        if flags.intersects(vk_bit) { properties | bit } else { properties }
+    })
+}
+
+pub fn map_memory_requirements(requirements: vk::MemoryRequirements) -> memory::Requirements {
+    memory::Requirements {
+        size: requirements.size,
+        alignment: requirements.alignment,
+        type_mask: requirements.memory_type_bits as u64,
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Optional device extensions, which are not exposed by `ash`.
+
+use ash::vk;
+use std::ffi::CString;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+
+pub const CONDITIONAL_RENDERING_NAME: &'static str = "VK_EXT_conditional_rendering";
+pub const DRAW_INDIRECT_COUNT_NAME: &'static str = "VK_KHR_draw_indirect_count";
+pub const MAINTENANCE1_NAME: &'static str = "VK_KHR_maintenance1";
+
+/// Lets 3D images be viewed as 2D arrays of their slices, from `VK_KHR_maintenance1`.
+pub const IMAGE_CREATE_2D_ARRAY_COMPATIBLE_BIT: u32 = 0x20;
+
+const STRUCTURE_TYPE_CONDITIONAL_RENDERING_BEGIN_INFO: u32 = 1000081002;
+pub const CONDITIONAL_RENDERING_INVERTED_BIT: u32 = 0x1;
+
+#[repr(C)]
+pub struct ConditionalRenderingBeginInfo {
+    pub s_type: u32,
+    pub p_next: *const c_void,
+    pub buffer: vk::Buffer,
+    pub offset: vk::DeviceSize,
+    pub flags: u32,
+}
+
+impl ConditionalRenderingBeginInfo {
+    pub fn new(buffer: vk::Buffer, offset: vk::DeviceSize, flags: u32) -> Self {
+        ConditionalRenderingBeginInfo {
+            s_type: STRUCTURE_TYPE_CONDITIONAL_RENDERING_BEGIN_INFO,
+            p_next: ::std::ptr::null(),
+            buffer: buffer,
+            offset: offset,
+            flags: flags,
+        }
+    }
+}
+
+pub struct ConditionalRendering {
+    pub cmd_begin: unsafe extern "system" fn(vk::CommandBuffer, *const ConditionalRenderingBeginInfo),
+    pub cmd_end: unsafe extern "system" fn(vk::CommandBuffer),
+}
+
+pub type CmdDrawIndirectCount = unsafe extern "system" fn(vk::CommandBuffer, vk::Buffer, vk::DeviceSize,
+                                                         vk::Buffer, vk::DeviceSize, u32, u32);
+
+pub struct DrawIndirectCount {
+    pub cmd_draw: CmdDrawIndirectCount,
+    pub cmd_draw_indexed: CmdDrawIndirectCount,
+}
+
+/// Entry points of the enabled extensions.
+#[derive(Default)]
+pub struct DeviceExtensions {
+    pub conditional_rendering: Option<ConditionalRendering>,
+    pub draw_indirect_count: Option<DrawIndirectCount>,
+    pub maintenance1: bool,
+}
+
+impl DeviceExtensions {
+    /// Load the entry points of the `enabled` extensions, with `get_proc_addr` returning
+    /// the address of a device function, or null.
+    pub fn load<F>(enabled: &[&str], mut get_proc_addr: F) -> DeviceExtensions
+        where F: FnMut(*const c_char) -> *const c_void
+    {
+        let mut load = |name: &str| {
+            let name = CString::new(name).unwrap();
+            let address = get_proc_addr(name.as_ptr());
+            if address.is_null() {
+                error!("Unable to load {:?}", name);
+                None
+            } else {
+                Some(address)
+            }
+        };
+
+        let conditional_rendering = if enabled.contains(&CONDITIONAL_RENDERING_NAME) {
+            match (load("vkCmdBeginConditionalRenderingEXT"), load("vkCmdEndConditionalRenderingEXT")) {
+                (Some(begin), Some(end)) => unsafe {
+                    Some(ConditionalRendering {
+                        cmd_begin: mem::transmute(begin),
+                        cmd_end: mem::transmute(end),
+                    })
+                },
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        let draw_indirect_count = if enabled.contains(&DRAW_INDIRECT_COUNT_NAME) {
+            match (load("vkCmdDrawIndirectCountKHR"), load("vkCmdDrawIndexedIndirectCountKHR")) {
+                (Some(draw), Some(draw_indexed)) => unsafe {
+                    Some(DrawIndirectCount {
+                        cmd_draw: mem::transmute(draw),
+                        cmd_draw_indexed: mem::transmute(draw_indexed),
+                    })
+                },
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        DeviceExtensions {
+            conditional_rendering: conditional_rendering,
+            draw_indirect_count: draw_indirect_count,
+            maintenance1: enabled.contains(&MAINTENANCE1_NAME),
+        }
+    }
+}
@@ -15,15 +15,37 @@
 use ash::vk;
 use ash::version::DeviceV1_0;
 use std::{mem, ptr};
+use std::ffi::CString;
 use std::sync::Arc;
 use std::collections::BTreeMap;
+use std::ops::Range;
 
-use core::{self, shade, state as s};
-use core::SubPass;
+use core::{self, buffer, descriptor, format, image, memory, pass, query, shade, state as s};
+use core::{Primitive, SubPass};
 use core::pso::{self, EntryPoint};
-use {native, state};
+use {data, ext, native, state};
 use {Device, Resources as R};
 
+// States of a graphics pipeline, with the storage their create infos point to. The heap
+// allocations of the vectors and names don't move with the struct, but the states need to
+// stay in place once referenced by a `vk::GraphicsPipelineCreateInfo`.
+struct GraphicsPipelineStates {
+    _names: Vec<CString>,
+    stages: Vec<vk::PipelineShaderStageCreateInfo>,
+    _bindings: Vec<vk::VertexInputBindingDescription>,
+    _attributes: Vec<vk::VertexInputAttributeDescription>,
+    _blend_targets: Vec<vk::PipelineColorBlendAttachmentState>,
+    _dynamic_states: Vec<vk::DynamicState>,
+    vertex_input: vk::PipelineVertexInputStateCreateInfo,
+    input_assembly: vk::PipelineInputAssemblyStateCreateInfo,
+    tessellation: Option<vk::PipelineTessellationStateCreateInfo>,
+    rasterization: vk::PipelineRasterizationStateCreateInfo,
+    multisample: vk::PipelineMultisampleStateCreateInfo,
+    depth_stencil: vk::PipelineDepthStencilStateCreateInfo,
+    color_blend: vk::PipelineColorBlendStateCreateInfo,
+    dynamic_state: vk::PipelineDynamicStateCreateInfo,
+}
+
 impl Device {
     pub fn create_shader_library(&mut self, shaders: &[(EntryPoint, &[u8])]) -> Result<native::ShaderLib, shade::CreateShaderError> {
         let mut shader_map = BTreeMap::new();
@@ -34,35 +56,544 @@ impl Device {
         }
         Ok(native::ShaderLib { shaders: shader_map })
     }
+
+    fn create_image_view(&mut self, image: &native::Image, view_type: vk::ImageViewType,
+                         format: format::Format, range: vk::ImageSubresourceRange) -> vk::ImageView {
+        let info = vk::ImageViewCreateInfo {
+            s_type: vk::StructureType::ImageViewCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::ImageViewCreateFlags::empty(),
+            image: image.inner,
+            view_type: view_type,
+            format: data::map_format(format.0, format.1).expect("Unsupported view format"),
+            components: vk::ComponentMapping {
+                r: vk::ComponentSwizzle::Identity,
+                g: vk::ComponentSwizzle::Identity,
+                b: vk::ComponentSwizzle::Identity,
+                a: vk::ComponentSwizzle::Identity,
+            },
+            subresource_range: range,
+        };
+
+        unsafe {
+            self.inner.0.create_image_view(&info, None)
+                .expect("Error on image view creation") // TODO: handle this better
+        }
+    }
+
+    // Map the states of a graphics pipeline, and check them against the device features.
+    fn map_graphics_pipeline_states(&self, shader_lib: &native::ShaderLib, desc: &pso::GraphicsPipelineDesc)
+        -> Result<GraphicsPipelineStates, pso::CreationError>
+    {
+        let (bindings, attributes) = match map_vertex_input(desc) {
+            Some(input) => input,
+            None => return Err(pso::CreationError),
+        };
+        if desc.has_independent_blend() && !self.capabilities.independent_blend_supported {
+            error!("Independent blend states of color targets are not supported by the device");
+            return Err(pso::CreationError);
+        }
+        for (i, target) in desc.color_targets.iter().enumerate() {
+            let dual_source = target.map_or(false, |(_, info)| info.is_dual_source());
+            if dual_source && (i > 0 || !self.capabilities.dual_source_blending_supported) {
+                error!("Dual-source blending of color target {} is not supported", i);
+                return Err(pso::CreationError);
+            }
+        }
+        if desc.logic_op.is_some() && !self.capabilities.logic_op_supported {
+            error!("Logic operations are not supported by the device");
+            return Err(pso::CreationError);
+        }
+        if desc.shader_entries.geometry_shader.is_some() && !self.capabilities.geometry_shader_supported {
+            error!("Geometry shaders are not supported by the device");
+            return Err(pso::CreationError);
+        }
+        let patch_size = match (desc.shader_entries.hull_shader, desc.shader_entries.domain_shader, desc.primitive) {
+            (Some(_), Some(_), Primitive::PatchList(size)) => {
+                if size == 0 || size as u32 > self.limits.max_tessellation_patch_size {
+                    error!("Invalid patch size {}, the maximum is {}", size, self.limits.max_tessellation_patch_size);
+                    return Err(pso::CreationError);
+                }
+                Some(size)
+            }
+            (None, None, Primitive::PatchList(_)) |
+            (Some(_), Some(_), _) => {
+                error!("Patch lists are only supported with both hull and domain shaders");
+                return Err(pso::CreationError);
+            }
+            (None, None, _) => None,
+            _ => {
+                error!("Hull and domain shaders must be used together");
+                return Err(pso::CreationError);
+            }
+        };
+
+        let entries = [
+            (vk::SHADER_STAGE_VERTEX_BIT, Some(desc.shader_entries.vertex_shader)),
+            (vk::SHADER_STAGE_FRAGMENT_BIT, desc.shader_entries.pixel_shader),
+            (vk::SHADER_STAGE_GEOMETRY_BIT, desc.shader_entries.geometry_shader),
+            (vk::SHADER_STAGE_TESSELLATION_EVALUATION_BIT, desc.shader_entries.domain_shader),
+            (vk::SHADER_STAGE_TESSELLATION_CONTROL_BIT, desc.shader_entries.hull_shader),
+        ];
+        let mut names = Vec::new();
+        let mut stages = Vec::new();
+        for &(stage, entry) in entries.iter() {
+            let entry = if let Some(entry) = entry { entry } else { continue };
+            let module = if let Some(module) = shader_lib.shaders.get(&entry)
+                { module } else { return Err(pso::CreationError) };
+            // Entry point names need to be null-terminated for vulkan
+            let name = CString::new(entry).unwrap();
+            stages.push(vk::PipelineShaderStageCreateInfo {
+                s_type: vk::StructureType::PipelineShaderStageCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineShaderStageCreateFlags::empty(),
+                stage: stage,
+                module: *module,
+                p_name: name.as_ptr(),
+                p_specialization_info: ptr::null(),
+            });
+            names.push(name);
+        }
+
+        let (polygon_mode, line_width) = state::map_polygon_mode(desc.rasterizer.method);
+        let blend_targets = state::map_blend_targets(&desc.color_targets);
+        let dynamic_states = data::map_dynamic_states(desc.dynamic_states);
+
+        Ok(GraphicsPipelineStates {
+            vertex_input: vk::PipelineVertexInputStateCreateInfo {
+                s_type: vk::StructureType::PipelineVertexInputStateCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineVertexInputStateCreateFlags::empty(),
+                vertex_binding_description_count: bindings.len() as u32,
+                p_vertex_binding_descriptions: bindings.as_ptr(),
+                vertex_attribute_description_count: attributes.len() as u32,
+                p_vertex_attribute_descriptions: attributes.as_ptr(),
+            },
+            input_assembly: vk::PipelineInputAssemblyStateCreateInfo {
+                s_type: vk::StructureType::PipelineInputAssemblyStateCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineInputAssemblyStateCreateFlags::empty(),
+                topology: state::map_topology(desc.primitive),
+                primitive_restart_enable: vk::VK_FALSE,
+            },
+            tessellation: patch_size.map(|patch_size| vk::PipelineTessellationStateCreateInfo {
+                s_type: vk::StructureType::PipelineTessellationStateCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineTessellationStateCreateFlags::empty(),
+                patch_control_points: patch_size as u32,
+            }),
+            rasterization: vk::PipelineRasterizationStateCreateInfo {
+                s_type: vk::StructureType::PipelineRasterizationStateCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineRasterizationStateCreateFlags::empty(),
+                depth_clamp_enable: vk::VK_TRUE, // TODO
+                rasterizer_discard_enable: vk::VK_FALSE, // TODO
+                polygon_mode: polygon_mode,
+                cull_mode: state::map_cull_mode(desc.rasterizer.cull_face),
+                front_face: state::map_front_face(desc.rasterizer.front_face),
+                depth_bias_enable: if desc.rasterizer.offset.is_some() || desc.dynamic_states.contains(pso::DEPTH_BIAS) {
+                    vk::VK_TRUE
+                } else {
+                    vk::VK_FALSE
+                },
+                depth_bias_constant_factor: desc.rasterizer.offset.map_or(0.0, |off| off.1 as f32),
+                depth_bias_clamp: 16.0, // TODO: magic value?
+                depth_bias_slope_factor: desc.rasterizer.offset.map_or(0.0, |off| off.0 as f32),
+                line_width: line_width,
+            },
+            multisample: vk::PipelineMultisampleStateCreateInfo {
+                s_type: vk::StructureType::PipelineMultisampleStateCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineMultisampleStateCreateFlags::empty(),
+                rasterization_samples: vk::SAMPLE_COUNT_1_BIT, // TODO
+                sample_shading_enable: vk::VK_FALSE, // TODO
+                min_sample_shading: 0.0,  // TODO
+                p_sample_mask: ptr::null(), // TODO
+                alpha_to_coverage_enable: vk::VK_FALSE, // TODO
+                alpha_to_one_enable: vk::VK_FALSE, // TODO
+            },
+            depth_stencil: vk::PipelineDepthStencilStateCreateInfo {
+                s_type: vk::StructureType::PipelineDepthStencilStateCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineDepthStencilStateCreateFlags::empty(),
+                depth_test_enable: if let Some((_, pso::DepthStencilInfo { depth: Some(_), .. })) =
+                    desc.depth_stencil { vk::VK_TRUE } else { vk::VK_FALSE },
+                depth_write_enable: if let Some((_, pso::DepthStencilInfo { depth: Some(s::Depth { write: true, .. }), .. })) =
+                    desc.depth_stencil { vk::VK_TRUE } else { vk::VK_FALSE },
+                depth_compare_op: if let Some((_, pso::DepthStencilInfo { depth: Some(s::Depth { fun, .. }), ..})) =
+                    desc.depth_stencil { state::map_comparison(fun) } else { vk::CompareOp::Never },
+                depth_bounds_test_enable: if desc.dynamic_states.contains(pso::DEPTH_BOUNDS) { vk::VK_TRUE } else { vk::VK_FALSE },
+                stencil_test_enable: match desc.depth_stencil {
+                    Some((_, pso::DepthStencilInfo { front: Some(_), .. })) |
+                    Some((_, pso::DepthStencilInfo { back: Some(_), .. })) => vk::VK_TRUE,
+                    _ => vk::VK_FALSE,
+                },
+                // a face without stencil test passes and keeps the values
+                front: state::map_stencil_side(&match desc.depth_stencil {
+                    Some((_, pso::DepthStencilInfo { front: Some(side), .. })) => side,
+                    _ => s::StencilSide::default(),
+                }),
+                back: state::map_stencil_side(&match desc.depth_stencil {
+                    Some((_, pso::DepthStencilInfo { back: Some(side), .. })) => side,
+                    _ => s::StencilSide::default(),
+                }),
+                min_depth_bounds: 0.0,
+                max_depth_bounds: 1.0,
+            },
+            color_blend: vk::PipelineColorBlendStateCreateInfo {
+                s_type: vk::StructureType::PipelineColorBlendStateCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineColorBlendStateCreateFlags::empty(),
+                logic_op_enable: if desc.logic_op.is_some() { vk::VK_TRUE } else { vk::VK_FALSE },
+                logic_op: desc.logic_op.map_or(vk::LogicOp::Clear, state::map_logic_op),
+                attachment_count: blend_targets.len() as u32,
+                p_attachments: blend_targets.as_ptr(),
+                blend_constants: [0.0; 4], // TODO:
+            },
+            dynamic_state: vk::PipelineDynamicStateCreateInfo {
+                s_type: vk::StructureType::PipelineDynamicStateCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineDynamicStateCreateFlags::empty(),
+                dynamic_state_count: dynamic_states.len() as u32,
+                p_dynamic_states: dynamic_states.as_ptr(),
+            },
+            _names: names,
+            stages: stages,
+            _bindings: bindings,
+            _attributes: attributes,
+            _blend_targets: blend_targets,
+            _dynamic_states: dynamic_states,
+        })
+    }
+}
+
+// Attachments spanning several layers are rendered to with layered rendering.
+fn map_attachment_view_type(layers: &image::SubresourceLayers) -> vk::ImageViewType {
+    if layers.layer_count > 1 {
+        vk::ImageViewType::Type2dArray
+    } else {
+        vk::ImageViewType::Type2d
+    }
+}
+
+// Attributes are bound to the locations matching their index in the description.
+fn map_vertex_input(desc: &pso::GraphicsPipelineDesc)
+    -> Option<(Vec<vk::VertexInputBindingDescription>, Vec<vk::VertexInputAttributeDescription>)>
+{
+    let mut bindings = Vec::with_capacity(desc.vertex_buffers.len());
+    for (i, vb) in desc.vertex_buffers.iter().enumerate() {
+        if vb.rate > 1 {
+            error!("Unsupported instance rate {} of vertex buffer {}", vb.rate, i);
+            return None;
+        }
+        bindings.push(vk::VertexInputBindingDescription {
+            binding: i as u32,
+            stride: vb.stride as u32,
+            input_rate: if vb.is_per_instance() {
+                vk::VertexInputRate::Instance
+            } else {
+                vk::VertexInputRate::Vertex
+            },
+        });
+    }
+
+    let mut attributes = Vec::with_capacity(desc.attributes.len());
+    for (location, &(binding, element)) in desc.attributes.iter().enumerate() {
+        if binding as usize >= desc.vertex_buffers.len() {
+            error!("Couldn't find associated vertex buffer description {:?}", binding);
+            return None;
+        }
+        let format = match data::map_format(element.format.0, element.format.1) {
+            Some(format) => format,
+            None => {
+                error!("Unable to find vulkan format for {:?}", element.format);
+                return None;
+            }
+        };
+        attributes.push(vk::VertexInputAttributeDescription {
+            location: location as u32,
+            binding: binding as u32,
+            format: format,
+            offset: element.offset,
+        });
+    }
+
+    Some((bindings, attributes))
 }
 
 impl core::Factory<R> for Device {
-    fn create_renderpass(&mut self) -> native::RenderPass {
-        // TODO:
-        // Dummy renderpass only
-        let subpass_desc = vk::SubpassDescription {
-            flags: vk::SubpassDescriptionFlags::empty(),
-            pipeline_bind_point: vk::PipelineBindPoint::Graphics,
-            input_attachment_count: 0, // TODO
-            p_input_attachments: ptr::null(), // TODO
-            color_attachment_count: 0, // TODO
-            p_color_attachments: ptr::null(), // TODO
-            p_resolve_attachments: ptr::null(), // TODO
-            p_depth_stencil_attachment: ptr::null(), // TODO
-            preserve_attachment_count: 0, // TODO
-            p_preserve_attachments: ptr::null(), // TODO
+    fn allocate_memory(&mut self, memory_type: &memory::MemoryType, size: u64)
+        -> Result<native::Heap, memory::OutOfMemory>
+    {
+        let info = vk::MemoryAllocateInfo {
+            s_type: vk::StructureType::MemoryAllocateInfo,
+            p_next: ptr::null(),
+            allocation_size: size,
+            memory_type_index: memory_type.id as u32,
+        };
+
+        match unsafe { self.inner.0.allocate_memory(&info, None) } {
+            Ok(memory) => Ok(native::Heap { inner: memory, size: size }),
+            Err(vk::Result::ErrorOutOfDeviceMemory) |
+            Err(vk::Result::ErrorOutOfHostMemory) |
+            Err(vk::Result::ErrorTooManyObjects) => Err(memory::OutOfMemory),
+            Err(err) => panic!("Error on memory allocation {:?}", err), // TODO: handle this better
+        }
+    }
+
+    fn map_memory(&mut self, heap: &native::Heap, range: Range<u64>) -> *mut u8 {
+        let mut data = ptr::null_mut();
+        let result = unsafe {
+            self.inner.0.fp_v1_0().map_memory(self.inner.0.handle(), heap.inner, range.start,
+                                              range.end - range.start, vk::MemoryMapFlags::empty(),
+                                              &mut data)
+        };
+        assert_eq!(vk::Result::Success, result); // TODO: handle this better
+        data as *mut u8
+    }
+
+    fn unmap_memory(&mut self, heap: &native::Heap) {
+        unsafe { self.inner.0.unmap_memory(heap.inner) };
+    }
+
+    fn create_buffer(&mut self, size: u64, usage: buffer::Usage) -> native::UnboundBuffer {
+        let info = vk::BufferCreateInfo {
+            s_type: vk::StructureType::BufferCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::BufferCreateFlags::empty(),
+            size: size,
+            usage: data::map_buffer_usage(usage),
+            sharing_mode: vk::SharingMode::Exclusive, // TODO: concurrent
+            queue_family_index_count: 0,
+            p_queue_family_indices: ptr::null(),
         };
 
+        let buffer = unsafe {
+            self.inner.0.create_buffer(&info, None)
+                .expect("Error on buffer creation") // TODO: handle this better
+        };
+
+        native::UnboundBuffer { inner: buffer, size: size }
+    }
+
+    fn get_buffer_requirements(&mut self, buffer: &native::UnboundBuffer) -> memory::Requirements {
+        let requirements = self.inner.0.get_buffer_memory_requirements(buffer.inner);
+        data::map_memory_requirements(requirements)
+    }
+
+    fn bind_buffer_memory(&mut self, heap: &native::Heap, offset: u64, buffer: native::UnboundBuffer) -> native::Buffer {
+        unsafe {
+            self.inner.0.bind_buffer_memory(buffer.inner, heap.inner, offset)
+                .expect("Error on buffer memory binding") // TODO: handle this better
+        };
+
+        native::Buffer { inner: buffer.inner, size: buffer.size }
+    }
+
+    fn create_image(&mut self, kind: image::Kind, levels: image::Level, format: format::Format,
+                    usage: image::Usage) -> native::UnboundImage {
+        let (image_type, extent, layers) = data::map_image_kind(kind);
+        let flags = match kind {
+            image::Kind::Cube(_) | image::Kind::CubeArray(_, _) => vk::IMAGE_CREATE_CUBE_COMPATIBLE_BIT,
+            // slices of volumes are rendered to through 2D views
+            image::Kind::D3(_, _, _) if self.inner.1.maintenance1 &&
+                usage.intersects(image::COLOR_ATTACHMENT | image::DEPTH_STENCIL_ATTACHMENT) => unsafe {
+                // the flag is unknown to `ash`
+                mem::transmute(ext::IMAGE_CREATE_2D_ARRAY_COMPATIBLE_BIT)
+            },
+            _ => vk::ImageCreateFlags::empty(),
+        };
+        let flags = if usage.contains(image::MUTABLE_FORMAT) {
+            flags | vk::IMAGE_CREATE_MUTABLE_FORMAT_BIT
+        } else {
+            flags
+        };
+        let info = vk::ImageCreateInfo {
+            s_type: vk::StructureType::ImageCreateInfo,
+            p_next: ptr::null(),
+            flags: flags,
+            image_type: image_type,
+            format: data::map_format(format.0, format.1).expect("Unsupported image format"),
+            extent: extent,
+            mip_levels: levels as u32,
+            array_layers: layers,
+            samples: data::map_sample_count(kind.num_samples()),
+            tiling: vk::ImageTiling::Optimal,
+            usage: data::map_image_usage(usage),
+            sharing_mode: vk::SharingMode::Exclusive, // TODO: concurrent
+            queue_family_index_count: 0,
+            p_queue_family_indices: ptr::null(),
+            initial_layout: vk::ImageLayout::Undefined,
+        };
+
+        let image = unsafe {
+            self.inner.0.create_image(&info, None)
+                .expect("Error on image creation") // TODO: handle this better
+        };
+
+        native::UnboundImage { inner: image }
+    }
+
+    fn get_image_requirements(&mut self, image: &native::UnboundImage) -> memory::Requirements {
+        let requirements = self.inner.0.get_image_memory_requirements(image.inner);
+        data::map_memory_requirements(requirements)
+    }
+
+    fn bind_image_memory(&mut self, heap: &native::Heap, offset: u64, image: native::UnboundImage) -> native::Image {
+        unsafe {
+            self.inner.0.bind_image_memory(image.inner, heap.inner, offset)
+                .expect("Error on image memory binding") // TODO: handle this better
+        };
+
+        native::Image { inner: image.inner }
+    }
+
+    fn create_shader_resource_view(&mut self, image: &native::Image, kind: image::ViewKind, format: format::Format,
+                                   range: image::SubresourceRange) -> native::ShaderResourceView {
+        let view = self.create_image_view(image, data::map_view_kind(kind), format,
+                                          data::map_subresource_range(&range));
+        native::ShaderResourceView { view: view }
+    }
+
+    fn create_buffer_view(&mut self, buffer: &native::Buffer, format: format::Format, range: Range<u64>) -> native::BufferView {
+        if range.start > range.end || range.end > buffer.size {
+            error!("Invalid buffer view range {:?}, buffer size {}", range, buffer.size);
+        }
+        let info = vk::BufferViewCreateInfo {
+            s_type: vk::StructureType::BufferViewCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::BufferViewCreateFlags::empty(),
+            buffer: buffer.inner,
+            format: data::map_format(format.0, format.1).expect("Unsupported buffer view format"),
+            offset: range.start,
+            range: range.end - range.start,
+        };
+
+        let view = unsafe {
+            self.inner.0.create_buffer_view(&info, None)
+                .expect("Error on buffer view creation") // TODO: handle this better
+        };
+
+        native::BufferView { inner: view }
+    }
+
+    fn create_unordered_access_view(&mut self, image: &native::Image, kind: image::ViewKind, format: format::Format,
+                                    range: image::SubresourceRange) -> native::UnorderedAccessView {
+        let view = self.create_image_view(image, data::map_view_kind(kind), format,
+                                          data::map_subresource_range(&range));
+        native::UnorderedAccessView { view: view }
+    }
+
+    fn create_render_target_view(&mut self, image: &native::Image, format: format::Format,
+                                 layers: image::SubresourceLayers) -> native::RenderTargetView {
+        let view = self.create_image_view(image, map_attachment_view_type(&layers), format,
+                                          data::map_layers_range(&layers));
+        native::RenderTargetView { view: view }
+    }
+
+    fn create_depth_stencil_view(&mut self, image: &native::Image, format: format::Format,
+                                 layers: image::SubresourceLayers) -> native::DepthStencilView {
+        let view = self.create_image_view(image, map_attachment_view_type(&layers), format,
+                                          data::map_layers_range(&layers));
+        native::DepthStencilView { view: view }
+    }
+
+    fn create_renderpass(&mut self, attachments: &[pass::Attachment], subpasses: &[pass::SubpassDesc],
+                         dependencies: &[pass::SubpassDependency]) -> native::RenderPass {
+        let map_subpass_ref = |pass: pass::SubpassRef| match pass {
+            pass::SubpassRef::External => vk::VK_SUBPASS_EXTERNAL,
+            pass::SubpassRef::Pass(id) => id as u32,
+        };
+
+        for (i, subpass) in subpasses.iter().enumerate() {
+            let refs = subpass.color_attachments.iter()
+                .chain(subpass.depth_stencil_attachment.iter())
+                .chain(subpass.input_attachments.iter())
+                .map(|&(id, _)| id)
+                .chain(subpass.preserve_attachments.iter().cloned());
+            for id in refs {
+                if id >= attachments.len() {
+                    error!("Invalid attachment {} in subpass {}, only {} attachments", id, i, attachments.len());
+                }
+            }
+        }
+        for dependency in dependencies {
+            for &pass in &[dependency.src_pass, dependency.dst_pass] {
+                match pass {
+                    pass::SubpassRef::Pass(id) if id >= subpasses.len() =>
+                        error!("Invalid subpass {} in dependency, only {} subpasses", id, subpasses.len()),
+                    _ => (),
+                }
+            }
+        }
+        let attachment_descs = attachments.to_vec();
+
+        let attachments = attachments.iter().map(|attachment| {
+            vk::AttachmentDescription {
+                flags: vk::AttachmentDescriptionFlags::empty(),
+                format: data::map_format(attachment.format.0, attachment.format.1)
+                    .expect("Unsupported attachment format"),
+                samples: data::map_sample_count(attachment.samples),
+                load_op: data::map_attachment_load_op(attachment.load_op),
+                store_op: data::map_attachment_store_op(attachment.store_op),
+                stencil_load_op: data::map_attachment_load_op(attachment.stencil_load_op),
+                stencil_store_op: data::map_attachment_store_op(attachment.stencil_store_op),
+                initial_layout: data::map_image_layout(attachment.layouts.start),
+                final_layout: data::map_image_layout(attachment.layouts.end),
+            }
+        }).collect::<Vec<_>>();
+
+        let map_refs = |refs: &[pass::AttachmentRef]| {
+            refs.iter().map(|&(id, layout)| vk::AttachmentReference {
+                attachment: id as u32,
+                layout: data::map_image_layout(layout),
+            }).collect::<Vec<_>>()
+        };
+        // the references need to outlive the subpass descriptions pointing to them
+        let references = subpasses.iter().map(|subpass| {
+            (map_refs(subpass.color_attachments),
+             map_refs(subpass.input_attachments),
+             subpass.depth_stencil_attachment.map(|r| map_refs(&[r])).unwrap_or(Vec::new()),
+             subpass.preserve_attachments.iter().map(|&id| id as u32).collect::<Vec<_>>())
+        }).collect::<Vec<_>>();
+
+        let subpasses = references.iter().map(|&(ref colors, ref inputs, ref depth_stencil, ref preserves)| {
+            vk::SubpassDescription {
+                flags: vk::SubpassDescriptionFlags::empty(),
+                pipeline_bind_point: vk::PipelineBindPoint::Graphics,
+                input_attachment_count: inputs.len() as u32,
+                p_input_attachments: inputs.as_ptr(),
+                color_attachment_count: colors.len() as u32,
+                p_color_attachments: colors.as_ptr(),
+                p_resolve_attachments: ptr::null(),
+                p_depth_stencil_attachment: depth_stencil.first().map_or(ptr::null(), |r| r as *const _),
+                preserve_attachment_count: preserves.len() as u32,
+                p_preserve_attachments: preserves.as_ptr(),
+            }
+        }).collect::<Vec<_>>();
+
+        let dependencies = dependencies.iter().map(|dependency| {
+            vk::SubpassDependency {
+                src_subpass: map_subpass_ref(dependency.src_pass),
+                dst_subpass: map_subpass_ref(dependency.dst_pass),
+                src_stage_mask: data::map_pipeline_stage(dependency.src_stage),
+                dst_stage_mask: data::map_pipeline_stage(dependency.dst_stage),
+                src_access_mask: data::map_access(dependency.src_access),
+                dst_access_mask: data::map_access(dependency.dst_access),
+                dependency_flags: vk::DependencyFlags::empty(),
+            }
+        }).collect::<Vec<_>>();
+
         let info = vk::RenderPassCreateInfo {
             s_type: vk::StructureType::RenderPassCreateInfo,
             p_next: ptr::null(),
             flags: vk::RenderPassCreateFlags::empty(),
-            attachment_count: 0, // TODO
-            p_attachments: ptr::null(), // TODO
-            subpass_count: 1, // TODO
-            p_subpasses: &subpass_desc, // TODO
-            dependency_count: 0, // TODO
-            p_dependencies: ptr::null(), // TODO
+            attachment_count: attachments.len() as u32,
+            p_attachments: attachments.as_ptr(),
+            subpass_count: subpasses.len() as u32,
+            p_subpasses: subpasses.as_ptr(),
+            dependency_count: dependencies.len() as u32,
+            p_dependencies: dependencies.as_ptr(),
         };
 
         let renderpass = unsafe {
@@ -70,20 +601,405 @@ impl core::Factory<R> for Device {
                 .expect("Error on render pass creation") // TODO: handle this better
         };
 
-        native::RenderPass { inner: renderpass }
+        native::RenderPass {
+            inner: renderpass,
+            attachments: attachment_descs,
+            subpass_count: info.subpass_count as usize,
+        }
+    }
+
+    fn create_framebuffer(&mut self, renderpass: &native::RenderPass,
+                          color_attachments: &[native::RenderTargetView], depth_stencil_attachments: &[native::DepthStencilView],
+                          width: u32, height: u32, layers: u32) -> native::FrameBuffer {
+        if color_attachments.len() + depth_stencil_attachments.len() != renderpass.attachments.len() {
+            error!("Invalid frame buffer attachments: {} color and {} depth stencil views given, render pass has {} attachments",
+                   color_attachments.len(), depth_stencil_attachments.len(), renderpass.attachments.len());
+        }
+        let attachments = color_attachments.iter().map(|rtv| rtv.view)
+            .chain(depth_stencil_attachments.iter().map(|dsv| dsv.view))
+            .collect::<Vec<_>>();
+
+        let info = vk::FramebufferCreateInfo {
+            s_type: vk::StructureType::FramebufferCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::FramebufferCreateFlags::empty(),
+            render_pass: renderpass.inner,
+            attachment_count: attachments.len() as u32,
+            p_attachments: attachments.as_ptr(),
+            width: width,
+            height: height,
+            layers: layers,
+        };
+
+        let framebuffer = unsafe {
+            self.inner.0.create_framebuffer(&info, None)
+                .expect("Error on framebuffer creation") // TODO: handle this better
+        };
+
+        native::FrameBuffer {
+            inner: framebuffer,
+            width: width,
+            height: height,
+            layers: layers,
+        }
+    }
+
+    fn create_sampler(&mut self, info: image::SamplerInfo) -> native::Sampler {
+        let anisotropy = match info.anisotropy {
+            Some(_) if !self.capabilities.anisotropy_supported => {
+                error!("Anisotropic filtering is not supported by the device, disabling it");
+                None
+            }
+            anisotropy => anisotropy,
+        };
+        let info = vk::SamplerCreateInfo {
+            s_type: vk::StructureType::SamplerCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::SamplerCreateFlags::empty(),
+            mag_filter: data::map_filter(info.mag_filter),
+            min_filter: data::map_filter(info.min_filter),
+            mipmap_mode: data::map_mipmap_mode(info.mip_filter),
+            address_mode_u: data::map_wrap(info.wrap_mode.0),
+            address_mode_v: data::map_wrap(info.wrap_mode.1),
+            address_mode_w: data::map_wrap(info.wrap_mode.2),
+            mip_lod_bias: info.lod_bias.into(),
+            anisotropy_enable: if anisotropy.is_some() { vk::VK_TRUE } else { vk::VK_FALSE },
+            max_anisotropy: anisotropy.map_or(1.0, |max| (max as f32).min(self.limits.max_sampler_anisotropy)),
+            compare_enable: if info.comparison.is_some() { vk::VK_TRUE } else { vk::VK_FALSE },
+            compare_op: info.comparison.map_or(vk::CompareOp::Never, state::map_comparison),
+            min_lod: info.lod_range.0.into(),
+            max_lod: info.lod_range.1.into(),
+            border_color: data::map_border_color(info.border),
+            unnormalized_coordinates: vk::VK_FALSE,
+        };
+
+        let sampler = unsafe {
+            self.inner.0.create_sampler(&info, None)
+                .expect("Error on sampler creation") // TODO: handle this better
+        };
+
+        native::Sampler { inner: sampler }
     }
 
-    fn create_pipeline_signature(&mut self) -> native::PipelineSignature {
-        // TODO:
-        // Dummy signature only
+    fn create_query_pool(&mut self, ty: query::QueryType, count: u32) -> native::QueryPool {
+        let query_type = data::map_query_type(ty);
+        let (statistics, values) = match ty {
+            query::QueryType::PipelineStatistics(stats) => (data::map_pipeline_statistics(stats), stats.count() as u32),
+            _ => (vk::QueryPipelineStatisticFlags::empty(), 1),
+        };
+        let info = vk::QueryPoolCreateInfo {
+            s_type: vk::StructureType::QueryPoolCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::QueryPoolCreateFlags::empty(),
+            query_type: query_type,
+            query_count: count,
+            pipeline_statistics: statistics,
+        };
+
+        let pool = unsafe {
+            self.inner.0.create_query_pool(&info, None)
+                .expect("Error on query pool creation") // TODO: handle this better
+        };
+
+        native::QueryPool {
+            inner: pool,
+            ty: query_type,
+            count: count,
+            values: values,
+        }
+    }
+
+    fn get_query_results(&mut self, pool: &native::QueryPool, queries: Range<u32>) -> Option<Vec<u64>> {
+        if queries.start > queries.end || queries.end > pool.count {
+            error!("Invalid query range {:?}, pool size {}", queries, pool.count);
+            return None;
+        }
+        let mut results = vec![0u64; ((queries.end - queries.start) * pool.values) as usize];
+        let result = unsafe {
+            self.inner.0.get_query_pool_results(pool.inner, queries.start, queries.end - queries.start,
+                                                &mut results, vk::QUERY_RESULT_64_BIT)
+        };
+        match result {
+            Ok(()) => (),
+            Err(vk::Result::NotReady) => return None,
+            Err(err) => panic!("Error on query results: {:?}", err), // TODO: handle this better
+        }
+
+        if pool.ty == vk::QueryType::Timestamp {
+            // timestamps are counted in ticks of `timestamp_period` nanoseconds
+            let period = self.limits.timestamp_period as f64;
+            for time in &mut results {
+                *time = (*time as f64 * period) as u64;
+            }
+        }
+        Some(results)
+    }
+
+    fn create_event(&mut self) -> native::Event {
+        let info = vk::EventCreateInfo {
+            s_type: vk::StructureType::EventCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::EventCreateFlags::empty(),
+        };
+
+        let event = unsafe {
+            self.inner.0.create_event(&info, None)
+                .expect("Error on event creation") // TODO: handle this better
+        };
+
+        native::Event { inner: event }
+    }
+
+    fn create_semaphore(&mut self) -> native::Semaphore {
+        let info = vk::SemaphoreCreateInfo {
+            s_type: vk::StructureType::SemaphoreCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::SemaphoreCreateFlags::empty(),
+        };
+
+        let semaphore = unsafe {
+            self.inner.0.create_semaphore(&info, None)
+                .expect("Error on semaphore creation") // TODO: handle this better
+        };
+
+        native::Semaphore { inner: semaphore }
+    }
+
+    fn create_fence(&mut self, signaled: bool) -> native::Fence {
+        let info = vk::FenceCreateInfo {
+            s_type: vk::StructureType::FenceCreateInfo,
+            p_next: ptr::null(),
+            flags: if signaled { vk::FENCE_CREATE_SIGNALED_BIT } else { vk::FenceCreateFlags::empty() },
+        };
+
+        let fence = unsafe {
+            self.inner.0.create_fence(&info, None)
+                .expect("Error on fence creation") // TODO: handle this better
+        };
+
+        native::Fence { inner: fence }
+    }
+
+    fn reset_fences(&mut self, fences: &[&native::Fence]) {
+        let fences = fences.iter().map(|fence| fence.inner).collect::<Vec<_>>();
+        unsafe {
+            self.inner.0.reset_fences(&fences)
+                .expect("Error on fence reset") // TODO: handle this better
+        };
+    }
+
+    fn wait_for_fences(&mut self, fences: &[&native::Fence], wait_all: bool, timeout_ns: u64) -> bool {
+        let fences = fences.iter().map(|fence| fence.inner).collect::<Vec<_>>();
+        match unsafe { self.inner.0.wait_for_fences(&fences, wait_all, timeout_ns) } {
+            Ok(()) => true,
+            Err(vk::Result::Timeout) => false,
+            Err(err) => panic!("Error on fence wait {:?}", err), // TODO: handle this better
+        }
+    }
+
+    fn get_fence_status(&mut self, fence: &native::Fence) -> bool {
+        let result = unsafe {
+            self.inner.0.fp_v1_0().get_fence_status(self.inner.0.handle(), fence.inner)
+        };
+        match result {
+            vk::Result::Success => true,
+            vk::Result::NotReady => false,
+            err => panic!("Error on fence status {:?}", err), // TODO: handle this better
+        }
+    }
+
+    fn wait_idle(&mut self) {
+        unsafe {
+            self.inner.0.device_wait_idle()
+                .expect("Error on device wait idle") // TODO: handle this better
+        };
+    }
+
+    fn create_descriptor_set_layout(&mut self, bindings: &[descriptor::DescriptorSetLayoutBinding])
+        -> native::DescriptorSetLayout
+    {
+        let bindings = bindings.iter().map(|binding| {
+            vk::DescriptorSetLayoutBinding {
+                binding: binding.binding as u32,
+                descriptor_type: data::map_descriptor_type(binding.ty),
+                descriptor_count: binding.count as u32,
+                stage_flags: data::map_stage_flags(binding.stages),
+                p_immutable_samplers: ptr::null(),
+            }
+        }).collect::<Vec<_>>();
+
+        let info = vk::DescriptorSetLayoutCreateInfo {
+            s_type: vk::StructureType::DescriptorSetLayoutCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::DescriptorSetLayoutCreateFlags::empty(),
+            binding_count: bindings.len() as u32,
+            p_bindings: bindings.as_ptr(),
+        };
+
+        let layout = unsafe {
+            self.inner.0.create_descriptor_set_layout(&info, None)
+                .expect("Error on descriptor set layout creation") // TODO: handle this better
+        };
+
+        native::DescriptorSetLayout { inner: layout }
+    }
+
+    fn create_descriptor_pool(&mut self, max_sets: usize, ranges: &[descriptor::DescriptorRangeDesc])
+        -> native::DescriptorPool
+    {
+        let pool_sizes = ranges.iter().map(|range| {
+            vk::DescriptorPoolSize {
+                typ: data::map_descriptor_type(range.ty),
+                descriptor_count: range.count as u32,
+            }
+        }).collect::<Vec<_>>();
+
+        let info = vk::DescriptorPoolCreateInfo {
+            s_type: vk::StructureType::DescriptorPoolCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::DescriptorPoolCreateFlags::empty(),
+            max_sets: max_sets as u32,
+            pool_size_count: pool_sizes.len() as u32,
+            p_pool_sizes: pool_sizes.as_ptr(),
+        };
+
+        let pool = unsafe {
+            self.inner.0.create_descriptor_pool(&info, None)
+                .expect("Error on descriptor pool creation") // TODO: handle this better
+        };
+
+        native::DescriptorPool { inner: pool }
+    }
+
+    fn allocate_descriptor_sets(&mut self, pool: &mut native::DescriptorPool,
+                                layouts: &[&native::DescriptorSetLayout]) -> Option<Vec<native::DescriptorSet>>
+    {
+        let layouts = layouts.iter().map(|layout| layout.inner).collect::<Vec<_>>();
+        let info = vk::DescriptorSetAllocateInfo {
+            s_type: vk::StructureType::DescriptorSetAllocateInfo,
+            p_next: ptr::null(),
+            descriptor_pool: pool.inner,
+            descriptor_set_count: layouts.len() as u32,
+            p_set_layouts: layouts.as_ptr(),
+        };
+
+        match unsafe { self.inner.0.allocate_descriptor_sets(&info) } {
+            Ok(sets) => Some(sets.into_iter().map(|set| native::DescriptorSet { inner: set }).collect()),
+            Err(vk::Result::ErrorOutOfPoolMemory) |
+            Err(vk::Result::ErrorFragmentedPool) => None,
+            Err(err) => panic!("Error on descriptor set allocation {:?}", err), // TODO: handle this better
+        }
+    }
+
+    fn reset_descriptor_pool(&mut self, pool: &mut native::DescriptorPool) {
+        let result = unsafe {
+            self.inner.0.fp_v1_0().reset_descriptor_pool(self.inner.0.handle(), pool.inner,
+                                                         vk::DescriptorPoolResetFlags::empty())
+        };
+        assert_eq!(vk::Result::Success, result);
+    }
+
+    fn update_descriptor_sets(&mut self, writes: &[descriptor::DescriptorSetWrite<R>]) {
+        use core::descriptor::DescriptorWrite::*;
+
+        for write in writes {
+            if let StorageImage(images) = write.write {
+                if images.iter().any(|&(_, layout)| layout != image::ImageLayout::General) {
+                    error!("Storage images need to be in the General layout");
+                    return;
+                }
+            }
+            let (buffers, alignment) = match write.write {
+                ConstantBuffer(buffers) => (buffers, self.limits.min_uniform_buffer_offset_alignment),
+                StorageBuffer(buffers) => (buffers, self.limits.min_storage_buffer_offset_alignment),
+                _ => continue,
+            };
+            for &(buffer, ref range) in buffers {
+                if range.start % alignment != 0 || range.start > range.end || range.end > buffer.size {
+                    error!("Invalid descriptor buffer range {:?}, buffer size {}, offset alignment {}",
+                           range, buffer.size, alignment);
+                    return;
+                }
+            }
+        }
+
+        // The descriptor infos need to outlive the writes pointing to them
+        let infos = writes.iter().map(|write| {
+            match write.write {
+                Sampler(samplers) => (samplers.iter().map(|sampler| vk::DescriptorImageInfo {
+                    sampler: sampler.inner,
+                    image_view: vk::ImageView::null(),
+                    image_layout: vk::ImageLayout::Undefined,
+                }).collect(), Vec::new(), Vec::new()),
+                CombinedImageSampler(images) =>
+                    (images.iter().map(|&(sampler, srv, layout)| vk::DescriptorImageInfo {
+                        sampler: sampler.inner,
+                        image_view: srv.view,
+                        image_layout: data::map_image_layout(layout),
+                    }).collect(), Vec::new(), Vec::new()),
+                SampledImage(images) | InputAttachment(images) =>
+                    (images.iter().map(|&(srv, layout)| vk::DescriptorImageInfo {
+                        sampler: vk::Sampler::null(),
+                        image_view: srv.view,
+                        image_layout: data::map_image_layout(layout),
+                    }).collect(), Vec::new(), Vec::new()),
+                StorageImage(images) => (images.iter().map(|&(uav, layout)| vk::DescriptorImageInfo {
+                    sampler: vk::Sampler::null(),
+                    image_view: uav.view,
+                    image_layout: data::map_image_layout(layout),
+                }).collect(), Vec::new(), Vec::new()),
+                ConstantBuffer(buffers) | StorageBuffer(buffers) =>
+                    (Vec::new(), buffers.iter().map(|&(buffer, ref range)| vk::DescriptorBufferInfo {
+                        buffer: buffer.inner,
+                        offset: range.start,
+                        range: range.end - range.start,
+                    }).collect(), Vec::new()),
+                UniformTexelBuffer(views) | StorageTexelBuffer(views) =>
+                    (Vec::new(), Vec::new(), views.iter().map(|view| view.inner).collect()),
+            }
+        }).collect::<Vec<(Vec<_>, Vec<_>, Vec<_>)>>();
+
+        let writes = writes.iter().zip(infos.iter()).map(|(write, &(ref images, ref buffers, ref texel_views))| {
+            vk::WriteDescriptorSet {
+                s_type: vk::StructureType::WriteDescriptorSet,
+                p_next: ptr::null(),
+                dst_set: write.set.inner,
+                dst_binding: write.binding as u32,
+                dst_array_element: write.array_offset as u32,
+                descriptor_count: write.write.count() as u32,
+                descriptor_type: data::map_descriptor_type(write.write.ty()),
+                p_image_info: images.as_ptr(),
+                p_buffer_info: buffers.as_ptr(),
+                p_texel_buffer_view: texel_views.as_ptr(),
+            }
+        }).collect::<Vec<_>>();
+
+        unsafe {
+            self.inner.0.update_descriptor_sets(&writes, &[]);
+        }
+    }
+
+    fn create_pipeline_signature(&mut self, sets: &[&native::DescriptorSetLayout],
+                                 push_constant_ranges: &[(shade::StageFlags, Range<u32>)])
+        -> native::PipelineSignature
+    {
+        let set_layouts = sets.iter().map(|set| set.inner).collect::<Vec<_>>();
+        let push_constants = push_constant_ranges.iter().map(|&(stages, ref range)| {
+            vk::PushConstantRange {
+                stage_flags: data::map_stage_flags(stages),
+                offset: range.start,
+                size: range.end - range.start,
+            }
+        }).collect::<Vec<_>>();
+
         let info = vk::PipelineLayoutCreateInfo {
             s_type: vk::StructureType::PipelineLayoutCreateInfo,
             p_next: ptr::null(),
             flags: vk::PipelineLayoutCreateFlags::empty(),
-            set_layout_count: 0, // TODO
-            p_set_layouts: ptr::null(), // TODO
-            push_constant_range_count: 0, // TODO
-            p_push_constant_ranges: ptr::null(), // TODO
+            set_layout_count: set_layouts.len() as u32,
+            p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: push_constants.len() as u32,
+            p_push_constant_ranges: push_constants.as_ptr(),
         };
 
         let layout = unsafe {
@@ -91,201 +1007,73 @@ impl core::Factory<R> for Device {
                 .expect("Error on pipeline signature creation") // TODO: handle this better
         };
 
-        native::PipelineSignature { layout: layout }
+        native::PipelineSignature {
+            layout: layout,
+            set_count: sets.len(),
+            push_constant_ranges: push_constant_ranges.to_vec(),
+        }
     }
 
-    fn create_graphics_pipelines<'a>(&mut self, descs: &[(&native::ShaderLib, &native::PipelineSignature, SubPass<'a, R>, &pso::GraphicsPipelineDesc)])
-        -> Vec<Result<(), pso::CreationError>>
-    {
-        let infos = descs.iter().map(|&(shader_lib, signature, ref subpass, desc)| {
-            let stages = {
-                let mut stages = Vec::new();
-
-                // Vertex stage
-                let vs_module = if let Some(module) = shader_lib.shaders.get(&desc.shader_entries.vertex_shader)
-                    { module } else { return Err(pso::CreationError) };
-                stages.push(vk::PipelineShaderStageCreateInfo {
-                    s_type: vk::StructureType::PipelineShaderStageCreateInfo,
-                    p_next: ptr::null(),
-                    flags: vk::PipelineShaderStageCreateFlags::empty(),
-                    stage: vk::SHADER_STAGE_VERTEX_BIT,
-                    module: *vs_module,
-                    p_name: desc.shader_entries.vertex_shader.as_bytes().as_ptr() as *const i8,
-                    p_specialization_info: ptr::null(),
-                });
-
-                // Pixel stage
-                if let Some(pixel_shader) = desc.shader_entries.pixel_shader {
-                    let ps_module = if let Some(module) = shader_lib.shaders.get(&pixel_shader)
-                        { module } else { return Err(pso::CreationError) };
-                    stages.push(vk::PipelineShaderStageCreateInfo {
-                        s_type: vk::StructureType::PipelineShaderStageCreateInfo,
-                        p_next: ptr::null(),
-                        flags: vk::PipelineShaderStageCreateFlags::empty(),
-                        stage: vk::SHADER_STAGE_FRAGMENT_BIT,
-                        module: *ps_module,
-                        p_name: pixel_shader.as_bytes().as_ptr() as *const i8,
-                        p_specialization_info: ptr::null(),
-                    });
-                }
+    fn create_pipeline_cache(&mut self, data: &[u8]) -> native::PipelineCache {
+        let info = vk::PipelineCacheCreateInfo {
+            s_type: vk::StructureType::PipelineCacheCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::PipelineCacheCreateFlags::empty(),
+            initial_data_size: data.len(),
+            p_initial_data: data.as_ptr() as *const _,
+        };
 
-                // Geometry stage
-                if let Some(geometry_shader) = desc.shader_entries.geometry_shader {
-                    let gs_module = if let Some(module) = shader_lib.shaders.get(&geometry_shader)
-                        { module } else { return Err(pso::CreationError) };
-                    stages.push(vk::PipelineShaderStageCreateInfo {
-                        s_type: vk::StructureType::PipelineShaderStageCreateInfo,
-                        p_next: ptr::null(),
-                        flags: vk::PipelineShaderStageCreateFlags::empty(),
-                        stage: vk::SHADER_STAGE_GEOMETRY_BIT,
-                        module: *gs_module,
-                        p_name: geometry_shader.as_bytes().as_ptr() as *const i8,
-                        p_specialization_info: ptr::null(),
-                    });
-                }
+        let cache = unsafe {
+            self.inner.0.create_pipeline_cache(&info, None)
+                .expect("Error on pipeline cache creation") // TODO: handle this better
+        };
 
-                // Domain stage
-                if let Some(domain_shader) = desc.shader_entries.domain_shader {
-                    let ds_module = if let Some(module) = shader_lib.shaders.get(&domain_shader)
-                        { module } else { return Err(pso::CreationError) };
-                    stages.push(vk::PipelineShaderStageCreateInfo {
-                        s_type: vk::StructureType::PipelineShaderStageCreateInfo,
-                        p_next: ptr::null(),
-                        flags: vk::PipelineShaderStageCreateFlags::empty(),
-                        stage: vk::SHADER_STAGE_TESSELLATION_EVALUATION_BIT,
-                        module: *ds_module,
-                        p_name: domain_shader.as_bytes().as_ptr() as *const i8,
-                        p_specialization_info: ptr::null(),
-                    });
-                }
+        native::PipelineCache { inner: cache }
+    }
 
-                // Hull stage
-                if let Some(hull_shader) = desc.shader_entries.hull_shader {
-                    let hs_module = if let Some(module) = shader_lib.shaders.get(&hull_shader)
-                        { module } else { return Err(pso::CreationError) };
-                    stages.push(vk::PipelineShaderStageCreateInfo {
-                        s_type: vk::StructureType::PipelineShaderStageCreateInfo,
-                        p_next: ptr::null(),
-                        flags: vk::PipelineShaderStageCreateFlags::empty(),
-                        stage: vk::SHADER_STAGE_TESSELLATION_CONTROL_BIT,
-                        module: *hs_module,
-                        p_name: hull_shader.as_bytes().as_ptr() as *const i8,
-                        p_specialization_info: ptr::null(),
-                    });
-                }
+    fn get_pipeline_cache_data(&mut self, cache: &native::PipelineCache) -> Vec<u8> {
+        let fp = self.inner.0.fp_v1_0();
+        let device = self.inner.0.handle();
+        unsafe {
+            let mut size = 0;
+            let result = fp.get_pipeline_cache_data(device, cache.inner, &mut size, ptr::null_mut());
+            assert_eq!(vk::Result::Success, result);
 
-                stages
-            };
+            let mut data = Vec::<u8>::with_capacity(size);
+            let result = fp.get_pipeline_cache_data(device, cache.inner, &mut size, data.as_mut_ptr() as *mut _);
+            assert_eq!(vk::Result::Success, result);
+            data.set_len(size);
+            data
+        }
+    }
 
-            let (polygon_mode, line_width) = state::map_polygon_mode(desc.rasterizer.method);
-            let dynamic_states = [];
+    fn create_graphics_pipelines<'a>(&mut self, descs: &[(&native::ShaderLib, &native::PipelineSignature, SubPass<'a, R>, &pso::GraphicsPipelineDesc)],
+                                     cache: Option<&native::PipelineCache>)
+        -> Vec<Result<native::Pipeline, pso::CreationError>>
+    {
+        // Kept alive and in place until the pipelines are created
+        let states = descs.iter()
+                          .map(|&(shader_lib, _, _, desc)| self.map_graphics_pipeline_states(shader_lib, desc))
+                          .collect::<Vec<_>>();
 
+        let infos = descs.iter().zip(states.iter()).map(|(&(_, signature, ref subpass, _), states)| {
+            let states = try!(states.as_ref().map_err(|err| err.clone()));
             Ok(vk::GraphicsPipelineCreateInfo {
                 s_type: vk::StructureType::GraphicsPipelineCreateInfo,
                 p_next: ptr::null(),
                 flags: vk::PipelineCreateFlags::empty(),
-                stage_count: stages.len() as u32,
-                p_stages: stages.as_ptr(),
-                p_vertex_input_state: &vk::PipelineVertexInputStateCreateInfo {
-                    s_type: vk::StructureType::PipelineVertexInputStateCreateInfo,
-                    p_next: ptr::null(),
-                    flags: vk::PipelineVertexInputStateCreateFlags::empty(),
-                    vertex_binding_description_count: 0, // TODO
-                    p_vertex_binding_descriptions: ptr::null(), // TODO
-                    vertex_attribute_description_count: 0, // TODO
-                    p_vertex_attribute_descriptions: ptr::null(), // TODO
-                },
-                p_input_assembly_state: &vk::PipelineInputAssemblyStateCreateInfo {
-                    s_type: vk::StructureType::PipelineInputAssemblyStateCreateInfo,
-                    p_next: ptr::null(),
-                    flags: vk::PipelineInputAssemblyStateCreateFlags::empty(),
-                    topology: state::map_topology(desc.primitive),
-                    primitive_restart_enable: vk::VK_FALSE,
-                },
-                p_tessellation_state: if desc.shader_entries.hull_shader.is_some() &&
-                                         desc.shader_entries.domain_shader.is_some() {
-                    &vk::PipelineTessellationStateCreateInfo {
-                        s_type: vk::StructureType::PipelineTessellationStateCreateInfo,
-                        p_next: ptr::null(),
-                        flags: vk::PipelineTessellationStateCreateFlags::empty(),
-                        patch_control_points: 1 // TODO: 0 < control_points <= VkPhysicalDeviceLimits::maxTessellationPatchSize
-                    }
-                }  else {
-                    // tessellation stage not enabled
-                    ptr::null()
-                },
+                stage_count: states.stages.len() as u32,
+                p_stages: states.stages.as_ptr(),
+                p_vertex_input_state: &states.vertex_input,
+                p_input_assembly_state: &states.input_assembly,
+                // tessellation stage not enabled without patch size
+                p_tessellation_state: states.tessellation.as_ref().map_or(ptr::null(), |info| info),
                 p_viewport_state: ptr::null(), // TODO
-                p_rasterization_state: &vk::PipelineRasterizationStateCreateInfo {
-                    s_type: vk::StructureType::PipelineRasterizationStateCreateInfo,
-                    p_next: ptr::null(),
-                    flags: vk::PipelineRasterizationStateCreateFlags::empty(),
-                    depth_clamp_enable: vk::VK_TRUE, // TODO
-                    rasterizer_discard_enable: vk::VK_FALSE, // TODO
-                    polygon_mode: polygon_mode,
-                    cull_mode: state::map_cull_mode(desc.rasterizer.cull_face),
-                    front_face: state::map_front_face(desc.rasterizer.front_face),
-                    depth_bias_enable: if desc.rasterizer.offset.is_some() { vk::VK_TRUE } else { vk::VK_FALSE },
-                    depth_bias_constant_factor: desc.rasterizer.offset.map_or(0.0, |off| off.1 as f32),
-                    depth_bias_clamp: 16.0, // TODO: magic value?
-                    depth_bias_slope_factor: desc.rasterizer.offset.map_or(0.0, |off| off.0 as f32),
-                    line_width: line_width,
-                },
-                p_multisample_state: &vk::PipelineMultisampleStateCreateInfo {
-                    s_type: vk::StructureType::PipelineMultisampleStateCreateInfo,
-                    p_next: ptr::null(),
-                    flags: vk::PipelineMultisampleStateCreateFlags::empty(),
-                    rasterization_samples: vk::SAMPLE_COUNT_1_BIT, // TODO
-                    sample_shading_enable: vk::VK_FALSE, // TODO
-                    min_sample_shading: 0.0,  // TODO
-                    p_sample_mask: ptr::null(), // TODO
-                    alpha_to_coverage_enable: vk::VK_FALSE, // TODO
-                    alpha_to_one_enable: vk::VK_FALSE, // TODO
-                },
-                p_depth_stencil_state: &vk::PipelineDepthStencilStateCreateInfo {
-                    s_type: vk::StructureType::PipelineDepthStencilStateCreateInfo,
-                    p_next: ptr::null(),
-                    flags: vk::PipelineDepthStencilStateCreateFlags::empty(),
-                    depth_test_enable: if let Some((_, pso::DepthStencilInfo { depth: Some(_), .. })) =
-                        desc.depth_stencil { vk::VK_TRUE } else { vk::VK_FALSE },
-                    depth_write_enable: if let Some((_, pso::DepthStencilInfo { depth: Some(s::Depth { write: true, .. }), .. })) =
-                        desc.depth_stencil { vk::VK_TRUE } else { vk::VK_FALSE },
-                    depth_compare_op: if let Some((_, pso::DepthStencilInfo { depth: Some(s::Depth { fun, .. }), ..})) =
-                        desc.depth_stencil { state::map_comparison(fun) } else { vk::CompareOp::Never },
-                    depth_bounds_test_enable: vk::VK_FALSE, // TODO
-                    stencil_test_enable: match desc.depth_stencil {
-                        Some((_, pso::DepthStencilInfo { front: Some(_), .. })) |
-                        Some((_, pso::DepthStencilInfo { back: Some(_), .. })) => vk::VK_TRUE,
-                        _ => vk::VK_FALSE,
-                    },
-                    front: match desc.depth_stencil {
-                        Some((_, pso::DepthStencilInfo { front: Some(ref s), .. })) => state::map_stencil_side(s),
-                        _ => unsafe { mem::zeroed() }, // TODO
-                    },
-                    back: match desc.depth_stencil {
-                        Some((_, pso::DepthStencilInfo { back: Some(ref s), .. })) => state::map_stencil_side(s),
-                        _ => unsafe { mem::zeroed() }, // TODO
-                    },
-                    min_depth_bounds: 0.0,
-                    max_depth_bounds: 1.0,
-                }, // TODO
-                p_color_blend_state: &vk::PipelineColorBlendStateCreateInfo {
-                    s_type: vk::StructureType::PipelineColorBlendStateCreateInfo,
-                    p_next: ptr::null(),
-                    flags: vk::PipelineColorBlendStateCreateFlags::empty(),
-                    logic_op_enable: vk::VK_FALSE, // TODO
-                    logic_op: vk::LogicOp::Clear,
-                    attachment_count: 0, // TODO:
-                    p_attachments: ptr::null(), // TODO:
-                    blend_constants: [0.0; 4], // TODO:
-                }, // TODO
-                p_dynamic_state: &vk::PipelineDynamicStateCreateInfo {
-                    s_type: vk::StructureType::PipelineDynamicStateCreateInfo,
-                    p_next: ptr::null(),
-                    flags: vk::PipelineDynamicStateCreateFlags::empty(),
-                    dynamic_state_count: dynamic_states.len() as u32,
-                    p_dynamic_states: dynamic_states.as_ptr(),
-                }, // TODO
-                // TODO:
+                p_rasterization_state: &states.rasterization,
+                p_multisample_state: &states.multisample,
+                p_depth_stencil_state: &states.depth_stencil,
+                p_color_blend_state: &states.color_blend,
+                p_dynamic_state: &states.dynamic_state,
                 layout: signature.layout,
                 render_pass: subpass.main_pass.inner,
                 subpass: subpass.index as u32,
@@ -293,13 +1081,144 @@ impl core::Factory<R> for Device {
                 base_pipeline_index: -1,
             })
         }).collect::<Vec<_>>();
-        
-        // TODO: create the pipelines!
 
-        Vec::new()
+        // Only create the pipelines with valid descriptions, but keep the results in order.
+        let valid_infos = infos.iter().filter_map(|info| info.as_ref().ok().cloned()).collect::<Vec<_>>();
+        let mut pipelines = match unsafe {
+            self.inner.0.create_graphics_pipelines(cache.map_or(vk::PipelineCache::null(), |cache| cache.inner),
+                                                   &valid_infos, None)
+        } {
+            Ok(pipelines) => pipelines.into_iter().map(Some).collect::<Vec<_>>(),
+            Err((pipelines, err)) => {
+                error!("Error on graphics pipeline creation: {:?}", err);
+                // Pipelines failing creation are returned as null handles.
+                pipelines.into_iter()
+                         .map(|pipeline| if pipeline == vk::Pipeline::null() { None } else { Some(pipeline) })
+                         .collect::<Vec<_>>()
+            }
+        }.into_iter();
+
+        infos.into_iter().zip(descs.iter()).map(|(info, &(_, _, ref subpass, _))| {
+            try!(info);
+            match pipelines.next() {
+                Some(Some(pipeline)) => Ok(native::Pipeline {
+                    pipeline: pipeline,
+                    bind_point: vk::PipelineBindPoint::Graphics,
+                    renderpass: Some((subpass.main_pass.attachments.iter().map(|a| a.format).collect(), subpass.index)),
+                }),
+                _ => Err(pso::CreationError),
+            }
+        }).collect()
+    }
+
+    fn create_compute_pipelines(&mut self, descs: &[(&native::ShaderLib, EntryPoint, &native::PipelineSignature)],
+                                cache: Option<&native::PipelineCache>)
+        -> Vec<Result<native::Pipeline, pso::CreationError>>
+    {
+        // Entry point names need to be null-terminated for vulkan
+        let names = descs.iter().map(|&(_, entry, _)| CString::new(entry).unwrap()).collect::<Vec<_>>();
+
+        let infos = descs.iter().zip(names.iter()).map(|(&(shader_lib, entry, signature), name)| {
+            let module = if let Some(module) = shader_lib.shaders.get(&entry)
+                { module } else { return Err(pso::CreationError) };
+
+            Ok(vk::ComputePipelineCreateInfo {
+                s_type: vk::StructureType::ComputePipelineCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::PipelineCreateFlags::empty(),
+                stage: vk::PipelineShaderStageCreateInfo {
+                    s_type: vk::StructureType::PipelineShaderStageCreateInfo,
+                    p_next: ptr::null(),
+                    flags: vk::PipelineShaderStageCreateFlags::empty(),
+                    stage: vk::SHADER_STAGE_COMPUTE_BIT,
+                    module: *module,
+                    p_name: name.as_ptr(),
+                    p_specialization_info: ptr::null(),
+                },
+                layout: signature.layout,
+                base_pipeline_handle: vk::Pipeline::null(),
+                base_pipeline_index: -1,
+            })
+        }).collect::<Vec<_>>();
+
+        // Only create the pipelines with valid descriptions, but keep the results in order.
+        let valid_infos = infos.iter().filter_map(|info| info.as_ref().ok().cloned()).collect::<Vec<_>>();
+        let mut pipelines = match unsafe {
+            self.inner.0.create_compute_pipelines(cache.map_or(vk::PipelineCache::null(), |cache| cache.inner),
+                                                  &valid_infos, None)
+        } {
+            Ok(pipelines) => pipelines.into_iter().map(Some).collect::<Vec<_>>(),
+            Err((pipelines, err)) => {
+                error!("Error on compute pipeline creation: {:?}", err);
+                // Pipelines failing creation are returned as null handles.
+                pipelines.into_iter()
+                         .map(|pipeline| if pipeline == vk::Pipeline::null() { None } else { Some(pipeline) })
+                         .collect::<Vec<_>>()
+            }
+        }.into_iter();
+
+        infos.into_iter().map(|info| {
+            try!(info);
+            match pipelines.next() {
+                Some(Some(pipeline)) => Ok(native::Pipeline {
+                    pipeline: pipeline,
+                    bind_point: vk::PipelineBindPoint::Compute,
+                    renderpass: None,
+                }),
+                _ => Err(pso::CreationError),
+            }
+        }).collect()
+    }
+
+    fn destroy_heap(&mut self, heap: native::Heap) {
+        unsafe { self.inner.0.free_memory(heap.inner, None); }
+    }
+
+    fn destroy_buffer(&mut self, buffer: native::Buffer) {
+        unsafe { self.inner.0.destroy_buffer(buffer.inner, None); }
+    }
+
+    fn destroy_image(&mut self, image: native::Image) {
+        unsafe { self.inner.0.destroy_image(image.inner, None); }
+    }
+
+    fn destroy_unbound_buffer(&mut self, buffer: native::UnboundBuffer) {
+        unsafe { self.inner.0.destroy_buffer(buffer.inner, None); }
+    }
+
+    fn destroy_unbound_image(&mut self, image: native::UnboundImage) {
+        unsafe { self.inner.0.destroy_image(image.inner, None); }
+    }
+
+    fn destroy_buffer_view(&mut self, view: native::BufferView) {
+        unsafe { self.inner.0.destroy_buffer_view(view.inner, None); }
+    }
+
+    fn destroy_shader_resource_view(&mut self, view: native::ShaderResourceView) {
+        unsafe { self.inner.0.destroy_image_view(view.view, None); }
+    }
+
+    fn destroy_unordered_access_view(&mut self, view: native::UnorderedAccessView) {
+        unsafe { self.inner.0.destroy_image_view(view.view, None); }
+    }
+
+    fn destroy_render_target_view(&mut self, view: native::RenderTargetView) {
+        unsafe { self.inner.0.destroy_image_view(view.view, None); }
+    }
+
+    fn destroy_depth_stencil_view(&mut self, view: native::DepthStencilView) {
+        unsafe { self.inner.0.destroy_image_view(view.view, None); }
+    }
+
+    fn destroy_framebuffer(&mut self, framebuffer: native::FrameBuffer) {
+        unsafe { self.inner.0.destroy_framebuffer(framebuffer.inner, None); }
+    }
+
+    fn destroy_sampler(&mut self, sampler: native::Sampler) {
+        unsafe { self.inner.0.destroy_sampler(sampler.inner, None); }
     }
 
-    fn create_compute_pipelines(&mut self) -> Vec<Result<(), pso::CreationError>> {
-        unimplemented!()
+    fn destroy_fence(&mut self, fence: native::Fence) {
+        unsafe { self.inner.0.destroy_fence(fence.inner, None); }
     }
 }
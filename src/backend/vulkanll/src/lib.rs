@@ -27,20 +27,28 @@ extern crate kernel32;
 use ash::version::{DeviceV1_0, EntryV1_0, InstanceV1_0, V1_0};
 use ash::vk;
 use ash::{Entry, LoadingError};
-use core::format;
+use ash::extensions::DebugReport;
+use core::{format, pso};
 use std::ffi::{CStr, CString};
-use std::iter;
+use std::{cmp, iter};
 use std::mem;
 use std::ptr;
+use std::os::raw;
 use std::sync::Arc;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::VecDeque;
 
+mod command;
 mod data;
+mod ext;
 mod factory;
 mod native;
 mod state;
+mod upload;
+
+pub use command::{CommandBuffer, CommandPool};
+pub use upload::Uploader;
 
 lazy_static! {
     static ref VK_ENTRY: Result<Entry<V1_0>, LoadingError> = Entry::new();
@@ -69,6 +77,20 @@ impl core::QueueFamily for QueueFamily {
         }
     }
 
+    fn queue_type(&self) -> core::QueueType {
+        let mut queue_type = core::QueueType::empty();
+        if self.queue_type.intersects(vk::QUEUE_GRAPHICS_BIT) {
+            queue_type |= core::GRAPHICS | core::TRANSFER;
+        }
+        if self.queue_type.intersects(vk::QUEUE_COMPUTE_BIT) {
+            queue_type |= core::COMPUTE | core::TRANSFER;
+        }
+        if self.queue_type.intersects(vk::QUEUE_TRANSFER_BIT) {
+            queue_type |= core::TRANSFER;
+        }
+        queue_type
+    }
+
     fn num_queues(&self) -> u32 {
         self.queue_count
     }
@@ -89,20 +111,32 @@ impl core::Adapter for Adapter {
     fn open<'a, I>(&self, queue_descs: I) -> (Device, Vec<CommandQueue>)
         where I: Iterator<Item=(&'a QueueFamily, u32)>
     {
-        let queue_infos = queue_descs.map(|(family, queue_count)| {
+        let queue_descs = queue_descs.collect::<Vec<_>>();
+        let max_queue_count = queue_descs.iter().map(|&(_, count)| count).max().unwrap_or(0);
+        let priorities = vec![0.0f32; max_queue_count as usize];
+        let queue_infos = queue_descs.iter().map(|&(family, queue_count)| {
                 vk::DeviceQueueCreateInfo {
                     s_type: vk::StructureType::DeviceQueueCreateInfo,
                     p_next: ptr::null(),
                     flags: vk::DeviceQueueCreateFlags::empty(),
                     queue_family_index: family.family_index,
                     queue_count: queue_count,
-                    p_queue_priorities: vec![0.0f32; queue_count as usize].as_ptr(),
+                    p_queue_priorities: priorities.as_ptr(),
                 }
             }).collect::<Vec<_>>();
 
-        // Create device
-        let device_extensions = &[vk::VK_KHR_SWAPCHAIN_EXTENSION_NAME,];
-
+        // Create device, enabling the optional extensions supported by the adapter
+        let available_extensions = self.instance.0.enumerate_device_extension_properties(self.handle)
+                                       .expect("Unable to enumerate device extensions");
+        let device_extensions = iter::once(vk::VK_KHR_SWAPCHAIN_EXTENSION_NAME)
+            .chain(OPTIONAL_DEVICE_EXTENSIONS.iter().cloned().filter(|&extension| {
+                available_extensions.iter().any(|properties| unsafe {
+                    CStr::from_ptr(properties.extension_name.as_ptr()).to_str() == Ok(extension)
+                })
+            }))
+            .collect::<Vec<_>>();
+
+        let available_features = self.instance.0.get_physical_device_features(self.handle);
         let device_raw = {
             let cstrings = device_extensions.iter()
                                     .map(|&s| CString::new(s).unwrap())
@@ -112,7 +146,14 @@ impl core::Adapter for Adapter {
                                     .map(|s| s.as_ptr())
                                     .collect::<Vec<_>>();
 
-            let features = unsafe { mem::zeroed() };
+            // geometry shaders are also needed by layered rendering
+            let mut features: vk::PhysicalDeviceFeatures = unsafe { mem::zeroed() };
+            features.geometry_shader = available_features.geometry_shader;
+            features.tessellation_shader = available_features.tessellation_shader;
+            features.sampler_anisotropy = available_features.sampler_anisotropy;
+            features.independent_blend = available_features.independent_blend;
+            features.dual_src_blend = available_features.dual_src_blend;
+            features.logic_op = available_features.logic_op;
             let info = vk::DeviceCreateInfo {
                 s_type: vk::StructureType::DeviceCreateInfo,
                 p_next: ptr::null(),
@@ -132,8 +173,46 @@ impl core::Adapter for Adapter {
             }
         };
 
+        let extensions = ext::DeviceExtensions::load(&device_extensions, |name| unsafe {
+            mem::transmute(self.instance.0.fp_v1_0().get_device_proc_addr(device_raw.handle(), name))
+        });
+        let capabilities = core::Capabilities {
+            conditional_rendering_supported: extensions.conditional_rendering.is_some(),
+            draw_indirect_count_supported: extensions.draw_indirect_count.is_some(),
+            geometry_shader_supported: available_features.geometry_shader == vk::VK_TRUE,
+            layered_rendering_supported: available_features.geometry_shader == vk::VK_TRUE,
+            volume_slice_rendering_supported: extensions.maintenance1,
+            // divisors need VK_EXT_vertex_attribute_divisor
+            instance_rate_supported: false,
+            tessellation_supported: available_features.tessellation_shader == vk::VK_TRUE,
+            anisotropy_supported: available_features.sampler_anisotropy == vk::VK_TRUE,
+            independent_blend_supported: available_features.independent_blend == vk::VK_TRUE,
+            dual_source_blending_supported: available_features.dual_src_blend == vk::VK_TRUE,
+            logic_op_supported: available_features.logic_op == vk::VK_TRUE,
+            separate_stencil_masks_supported: true,
+        };
+
+        let properties = self.instance.0.get_physical_device_properties(self.handle);
+        let memory_properties = self.instance.0.get_physical_device_memory_properties(self.handle);
+        let memory_types = memory_properties.memory_types[..memory_properties.memory_type_count as usize]
+            .iter().enumerate().map(|(i, memory_type)| {
+                core::memory::MemoryType {
+                    id: i,
+                    properties: data::map_memory_properties(memory_type.property_flags),
+                    heap_index: memory_type.heap_index as usize,
+                }
+            }).collect();
+        let memory_heaps = memory_properties.memory_heaps[..memory_properties.memory_heap_count as usize]
+            .iter().map(|heap| heap.size).collect();
+
         let device = Device {
-            inner: Arc::new(DeviceInner(device_raw)),
+            inner: Arc::new(DeviceInner(device_raw, extensions)),
+            physical_device: self.handle,
+            instance: self.instance.clone(),
+            limits: properties.limits,
+            capabilities: capabilities,
+            memory_types: memory_types,
+            memory_heaps: memory_heaps,
         };
 
         // Create associated command queues for each queue type
@@ -145,6 +224,7 @@ impl core::Adapter for Adapter {
                 CommandQueue {
                     inner: CommandQueueInner(Rc::new(RefCell::new(queue))),
                     device: device.inner.clone(),
+                    family_index: info.queue_family_index,
                 }
             }).collect::<Vec<_>>()
         }).collect();
@@ -161,7 +241,7 @@ impl core::Adapter for Adapter {
     }
 }
 
-struct DeviceInner(ash::Device<V1_0>);
+struct DeviceInner(ash::Device<V1_0>, ext::DeviceExtensions);
 impl Drop for DeviceInner {
     fn drop(&mut self) {
         unsafe { self.0.destroy_device(None); }
@@ -170,9 +250,89 @@ impl Drop for DeviceInner {
 
 pub struct Device {
     inner: Arc<DeviceInner>,
+    physical_device: vk::PhysicalDevice,
+    instance: Arc<InstanceInner>,
+    limits: vk::PhysicalDeviceLimits,
+    capabilities: core::Capabilities,
+    memory_types: Vec<core::memory::MemoryType>,
+    memory_heaps: Vec<u64>,
 }
 
 impl core::Device for Device {
+    fn get_capabilities(&self) -> &core::Capabilities {
+        &self.capabilities
+    }
+
+    fn get_limits(&self) -> core::Limits {
+        core::Limits {
+            min_constant_buffer_offset_alignment: self.limits.min_uniform_buffer_offset_alignment,
+            min_storage_buffer_offset_alignment: self.limits.min_storage_buffer_offset_alignment,
+            max_patch_size: cmp::min(self.limits.max_tessellation_patch_size, core::PatchSize::max_value() as u32) as core::PatchSize,
+            max_texture_size: self.limits.max_image_dimension2d as usize,
+            max_color_attachments: self.limits.max_color_attachments as usize,
+            max_compute_group_count: self.limits.max_compute_work_group_count,
+            max_compute_group_size: self.limits.max_compute_work_group_size,
+            max_sampler_anisotropy: self.limits.max_sampler_anisotropy as u8,
+            buffer_image_granularity: self.limits.buffer_image_granularity,
+        }
+    }
+
+    fn get_memory_types(&self) -> &[core::memory::MemoryType] {
+        &self.memory_types
+    }
+
+    fn get_memory_heaps(&self) -> &[u64] {
+        &self.memory_heaps
+    }
+
+    fn get_format_usage(&self, format: core::format::Format) -> core::image::Usage {
+        data::map_format_features(self.get_format_features(format))
+    }
+
+    fn get_supported_samples(&self, format: core::format::Format, usage: core::image::Usage) -> Vec<core::image::NumSamples> {
+        let format = match data::map_format(format.0, format.1) {
+            Some(format) => format,
+            None => return Vec::new(),
+        };
+        let mut properties = unsafe { mem::zeroed::<vk::ImageFormatProperties>() };
+        let result = unsafe {
+            self.instance.0.fp_v1_0().get_physical_device_image_format_properties(
+                self.physical_device, format, vk::ImageType::Type2d, vk::ImageTiling::Optimal,
+                data::map_image_usage(usage), vk::ImageCreateFlags::empty(), &mut properties)
+        };
+        if result != vk::Result::Success {
+            return Vec::new();
+        }
+        data::map_sample_counts(properties.sample_counts)
+    }
+
+    fn get_format_properties(&self, format: core::format::Format) -> core::format::FormatProperties {
+        use core::image;
+        let features = self.get_format_features(format);
+        let usage = data::map_format_features(features);
+        let attachment_usage = usage & (image::COLOR_ATTACHMENT | image::DEPTH_STENCIL_ATTACHMENT);
+        core::format::FormatProperties {
+            usage: usage,
+            blendable: features.intersects(vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BLEND_BIT),
+            linear_filtering: features.intersects(vk::FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT),
+            samples: if attachment_usage.is_empty() {
+                Vec::new()
+            } else {
+                self.get_supported_samples(format, attachment_usage)
+            },
+        }
+    }
+}
+
+impl Device {
+    // Features of optimally tiled images of the `format`
+    fn get_format_features(&self, format: core::format::Format) -> vk::FormatFeatureFlags {
+        match data::map_format(format.0, format.1) {
+            Some(format) => self.instance.0.get_physical_device_format_properties(self.physical_device, format)
+                                           .optimal_tiling_features,
+            None => vk::FormatFeatureFlags::empty(),
+        }
+    }
 }
 
 // # Synchronization
@@ -186,13 +346,44 @@ struct CommandQueueInner(Rc<RefCell<vk::Queue>>);
 pub struct CommandQueue {
     inner: CommandQueueInner,
     device: Arc<DeviceInner>,
+    family_index: u32,
 }
 
 impl core::CommandQueue for CommandQueue {
-    type CommandBuffer = ();
+    type CommandBuffer = command::CommandBuffer;
+    type Semaphore = native::Semaphore;
+    type Fence = native::Fence;
+
+    fn submit(&mut self, cmd_buffers: &[&command::CommandBuffer],
+              wait: &[(&native::Semaphore, pso::PipelineStage)], signal: &[&native::Semaphore],
+              fence: Option<&native::Fence>)
+    {
+        if cmd_buffers.iter().any(|cmd_buffer| cmd_buffer.family_index != self.family_index) {
+            error!("Command buffers must be allocated from a pool of the queue family they are submitted to");
+            return;
+        }
+        let buffers = cmd_buffers.iter().map(|cmd_buffer| cmd_buffer.inner).collect::<Vec<_>>();
+        let wait_semaphores = wait.iter().map(|&(semaphore, _)| semaphore.inner).collect::<Vec<_>>();
+        let wait_stages = wait.iter().map(|&(_, stages)| data::map_pipeline_stage(stages)).collect::<Vec<_>>();
+        let signal_semaphores = signal.iter().map(|semaphore| semaphore.inner).collect::<Vec<_>>();
 
-    fn submit(&mut self, cmd_buffer: &()) {
-        unimplemented!()
+        let info = vk::SubmitInfo {
+            s_type: vk::StructureType::SubmitInfo,
+            p_next: ptr::null(),
+            wait_semaphore_count: wait_semaphores.len() as u32,
+            p_wait_semaphores: wait_semaphores.as_ptr(),
+            p_wait_dst_stage_mask: wait_stages.as_ptr(),
+            command_buffer_count: buffers.len() as u32,
+            p_command_buffers: buffers.as_ptr(),
+            signal_semaphore_count: signal_semaphores.len() as u32,
+            p_signal_semaphores: signal_semaphores.as_ptr(),
+        };
+
+        unsafe {
+            self.device.0.queue_submit(*self.inner.0.borrow(), &[info],
+                                       fence.map_or(vk::Fence::null(), |fence| fence.inner))
+                .expect("Error on queue submission") // TODO: handle this better
+        };
     }
 }
 
@@ -342,44 +533,60 @@ pub struct SwapChain {
     frame_queue: VecDeque<usize>,
 }
 
+fn map_swapchain_result(result: vk::Result) -> Result<core::SwapChainStatus, core::SwapChainError> {
+    match result {
+        vk::Result::Success => Ok(core::SwapChainStatus::Optimal),
+        vk::Result::SuboptimalKhr => Ok(core::SwapChainStatus::Suboptimal),
+        vk::Result::ErrorOutOfDateKhr => Err(core::SwapChainError::OutOfDate),
+        vk::Result::ErrorSurfaceLostKhr => Err(core::SwapChainError::SurfaceLost),
+        vk::Result::ErrorDeviceLost => Err(core::SwapChainError::DeviceLost),
+        other => panic!("Unexpected swapchain result {:?}", other),
+    }
+}
+
 impl core::SwapChain for SwapChain {
-    fn acquire_frame(&mut self) -> core::Frame {
-        // TODO: handle synchronization, requires a fence or semaphore
-        // TODO: error handling
-        let index = unsafe {
-            let mut index = mem::uninitialized();
+    type Semaphore = native::Semaphore;
+
+    fn acquire_frame(&mut self, signal: Option<&native::Semaphore>)
+        -> Result<(core::Frame, core::SwapChainStatus), core::SwapChainError>
+    {
+        let mut index = 0;
+        let result = unsafe {
             self.swapchain_fn.acquire_next_image_khr(
                     self.device.0.handle(),
                     self.inner,
                     std::u64::MAX, // will block if no image is available
-                    vk::Semaphore::null(),
+                    signal.map_or(vk::Semaphore::null(), |semaphore| semaphore.inner),
                     vk::Fence::null(),
-                    &mut index);
-            index
+                    &mut index)
         };
+        let status = try!(map_swapchain_result(result));
 
         self.frame_queue.push_back(index as usize);
-        core::Frame::new(index as usize)
+        Ok((core::Frame::new(index as usize), status))
     }
 
-    fn present(&mut self) {
+    fn present(&mut self, wait: &[&native::Semaphore])
+        -> Result<core::SwapChainStatus, core::SwapChainError>
+    {
         let frame = self.frame_queue.pop_front().expect("No frame currently queued up. Need to acquire a frame first.");
+        let semaphores = wait.iter().map(|semaphore| semaphore.inner).collect::<Vec<_>>();
 
         // TODO: ensure correct image layout (present)
         let info = vk::PresentInfoKHR {
             s_type: vk::StructureType::PresentInfoKhr,
             p_next: ptr::null(),
-            wait_semaphore_count: 0,
-            p_wait_semaphores: ptr::null(),
+            wait_semaphore_count: semaphores.len() as u32,
+            p_wait_semaphores: semaphores.as_ptr(),
             swapchain_count: 1,
             p_swapchains: &self.inner,
             p_image_indices: &(frame as u32),
             p_results: ptr::null_mut(),
         };
-        unsafe {
-            self.swapchain_fn.queue_present_khr(*self.present_queue.0.borrow(), &info);
-        }
-        // TODO: handle result and return code
+        let result = unsafe {
+            self.swapchain_fn.queue_present_khr(*self.present_queue.0.borrow(), &info)
+        };
+        map_swapchain_result(result)
     }
 }
 
@@ -394,13 +601,51 @@ impl Drop for SwapChain {
     }
 }
 
-struct InstanceInner(pub ash::Instance<V1_0>);
+struct InstanceInner(pub ash::Instance<V1_0>, Option<(DebugReport, vk::DebugReportCallbackEXT)>);
 impl Drop for InstanceInner {
     fn drop(&mut self) {
-        unsafe { self.0.destroy_instance(None); }
+        unsafe {
+            if let Some((ref loader, callback)) = self.1 {
+                loader.destroy_debug_report_callback_ext(callback, None);
+            }
+            self.0.destroy_instance(None);
+        }
     }
 }
 
+const VALIDATION_LAYERS: &'static [&'static str] = &[
+    "VK_LAYER_LUNARG_standard_validation",
+];
+const DEBUG_REPORT_EXTENSION: &'static str = "VK_EXT_debug_report";
+
+unsafe extern "system" fn debug_report_callback(flags: vk::DebugReportFlagsEXT,
+                                                _ob_type: vk::DebugReportObjectTypeEXT, _object: u64,
+                                                _location: usize, _msg_code: i32,
+                                                layer_prefix: *const raw::c_char,
+                                                description: *const raw::c_char,
+                                                _user_data: *mut raw::c_void) -> u32
+{
+    let layer_prefix = CStr::from_ptr(layer_prefix).to_string_lossy();
+    let description = CStr::from_ptr(description).to_string_lossy();
+    if flags.intersects(vk::DEBUG_REPORT_ERROR_BIT_EXT) {
+        error!("[{}] {}", layer_prefix, description);
+    } else if flags.intersects(vk::DEBUG_REPORT_WARNING_BIT_EXT | vk::DEBUG_REPORT_PERFORMANCE_WARNING_BIT_EXT) {
+        warn!("[{}] {}", layer_prefix, description);
+    } else if flags.intersects(vk::DEBUG_REPORT_INFORMATION_BIT_EXT) {
+        info!("[{}] {}", layer_prefix, description);
+    } else {
+        debug!("[{}] {}", layer_prefix, description);
+    }
+    vk::VK_FALSE
+}
+
+/// Device extensions enabled when the adapter supports them.
+const OPTIONAL_DEVICE_EXTENSIONS: &'static [&'static str] = &[
+    ext::CONDITIONAL_RENDERING_NAME,
+    ext::DRAW_INDIRECT_COUNT_NAME,
+    ext::MAINTENANCE1_NAME,
+];
+
 const SURFACE_EXTENSIONS: &'static [&'static str] = &[
     vk::VK_KHR_SURFACE_EXTENSION_NAME,
 
@@ -423,12 +668,10 @@ pub struct Instance {
     surface_extensions: Vec<&'static str>,
 }
 
-impl core::Instance for Instance {
-    type Adapter = Adapter;
-    type Surface = Surface;
-    type Window = winit::Window;
-
-    fn create() -> Instance {
+impl Instance {
+    /// Create a new instance, optionally enabling the standard validation layers.
+    /// Validation messages are forwarded to the `log` crate.
+    pub fn create_with_validation(validation: bool) -> Instance {
         // TODO: return errors instead of panic
         let entry = VK_ENTRY.as_ref().expect("Unable to load vulkan entry points");
 
@@ -452,10 +695,29 @@ impl core::Instance for Instance {
             }).and_then(|_| Some(*ext))
         }).collect::<Vec<&str>>();
 
+        // Only enable validation if the layers are actually installed
+        let validation = validation && {
+            let instance_layers = entry.enumerate_instance_layer_properties()
+                                       .expect("Unable to enumerate instance layers");
+            let available = VALIDATION_LAYERS.iter().all(|layer| {
+                instance_layers.iter().any(|inst_layer| unsafe {
+                    CStr::from_ptr(inst_layer.layer_name.as_ptr()).to_bytes() == layer.as_bytes()
+                })
+            });
+            if !available {
+                warn!("Validation layers requested but not available");
+            }
+            available
+        };
+        let layers = if validation { VALIDATION_LAYERS } else { &[] };
+        let debug_extensions = if validation { &[DEBUG_REPORT_EXTENSION][..] } else { &[] };
+
         let instance = {
-            let cstrings = surface_extensions.iter()
-                                    .map(|&s| CString::new(s).unwrap())
-                                    .collect::<Vec<_>>();
+            let cstrings = layers.iter()
+                                 .chain(surface_extensions.iter())
+                                 .chain(debug_extensions.iter())
+                                 .map(|&s| CString::new(s).unwrap())
+                                 .collect::<Vec<_>>();
 
             let str_pointers = cstrings.iter()
                                     .map(|s| s.as_ptr())
@@ -466,20 +728,51 @@ impl core::Instance for Instance {
                 p_next: ptr::null(),
                 flags: vk::InstanceCreateFlags::empty(),
                 p_application_info: &app_info,
-                enabled_layer_count: 0,
-                pp_enabled_layer_names: ptr::null(),
-                enabled_extension_count: str_pointers.len() as u32,
-                pp_enabled_extension_names: str_pointers.as_ptr(),
+                enabled_layer_count: layers.len() as u32,
+                pp_enabled_layer_names: str_pointers.as_ptr(),
+                enabled_extension_count: (str_pointers.len() - layers.len()) as u32,
+                pp_enabled_extension_names: str_pointers[layers.len()..].as_ptr(),
             };
 
             entry.create_instance(&create_info, None).expect("Unable to create vulkan instance")
         };
 
+        let debug_report = if validation {
+            let loader = DebugReport::new(entry, &instance)
+                                     .expect("Unable to load debug report functions");
+            let info = vk::DebugReportCallbackCreateInfoEXT {
+                s_type: vk::StructureType::DebugReportCallbackCreateInfoExt,
+                p_next: ptr::null(),
+                flags: vk::DEBUG_REPORT_ERROR_BIT_EXT | vk::DEBUG_REPORT_WARNING_BIT_EXT |
+                       vk::DEBUG_REPORT_PERFORMANCE_WARNING_BIT_EXT | vk::DEBUG_REPORT_INFORMATION_BIT_EXT |
+                       vk::DEBUG_REPORT_DEBUG_BIT_EXT,
+                pfn_callback: debug_report_callback,
+                p_user_data: ptr::null_mut(),
+            };
+            let callback = unsafe {
+                loader.create_debug_report_callback_ext(&info, None)
+                      .expect("Unable to create debug report callback")
+            };
+            Some((loader, callback))
+        } else {
+            None
+        };
+
         Instance {
-            inner: Arc::new(InstanceInner(instance)),
+            inner: Arc::new(InstanceInner(instance, debug_report)),
             surface_extensions: surface_extensions,
         }
     }
+}
+
+impl core::Instance for Instance {
+    type Adapter = Adapter;
+    type Surface = Surface;
+    type Window = winit::Window;
+
+    fn create() -> Instance {
+        Instance::create_with_validation(cfg!(debug_assertions))
+    }
 
     fn enumerate_adapters(&self) -> Vec<Adapter> {
         self.inner.0.enumerate_physical_devices()
@@ -587,7 +880,7 @@ impl core::Instance for Instance {
 pub enum Backend { }
 
 impl core::Backend for Backend {
-    type CommandBuffer = ();
+    type CommandBuffer = command::CommandBuffer;
     type CommandQueue = CommandQueue;
     type Device = Device;
     type Instance = Instance;
@@ -601,15 +894,28 @@ impl core::Backend for Backend {
 pub enum Resources { }
 
 impl core::Resources for Resources {
-    type Buffer = ();
+    type Heap = native::Heap;
+    type UnboundBuffer = native::UnboundBuffer;
+    type Buffer = native::Buffer;
+    type BufferView = native::BufferView;
+    type UnboundImage = native::UnboundImage;
     type ShaderLib = native::ShaderLib;
     type RenderPass = native::RenderPass;
+    type FrameBuffer = native::FrameBuffer;
     type PipelineSignature = native::PipelineSignature;
-    type PipelineStateObject = ();
-    type Image = ();
-    type ShaderResourceView = ();
-    type UnorderedAccessView = ();
-    type RenderTargetView = ();
-    type DepthStencilView = ();
-    type Sampler = ();
+    type PipelineStateObject = native::Pipeline;
+    type PipelineCache = native::PipelineCache;
+    type DescriptorSetLayout = native::DescriptorSetLayout;
+    type DescriptorPool = native::DescriptorPool;
+    type DescriptorSet = native::DescriptorSet;
+    type QueryPool = native::QueryPool;
+    type Event = native::Event;
+    type Semaphore = native::Semaphore;
+    type Fence = native::Fence;
+    type Image = native::Image;
+    type ShaderResourceView = native::ShaderResourceView;
+    type UnorderedAccessView = native::UnorderedAccessView;
+    type RenderTargetView = native::RenderTargetView;
+    type DepthStencilView = native::DepthStencilView;
+    type Sampler = native::Sampler;
 }
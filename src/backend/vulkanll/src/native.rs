@@ -12,9 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use core::pso;
+use core::{format, pass, pso, shade};
 use vk;
 use std::collections::BTreeMap;
+use std::ops::Range;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct ShaderLib {
@@ -29,14 +30,177 @@ unsafe impl Sync for ShaderLib {}
 #[derive(Clone, Debug, Hash)]
 pub struct PipelineSignature {
     pub layout: vk::PipelineLayout,
+    pub set_count: usize,
+    pub push_constant_ranges: Vec<(shade::StageFlags, Range<u32>)>,
 }
 unsafe impl Send for PipelineSignature {}
 unsafe impl Sync for PipelineSignature {}
 
+#[derive(Clone, Debug, Hash)]
+pub struct PipelineCache {
+    pub inner: vk::PipelineCache,
+}
+unsafe impl Send for PipelineCache {}
+unsafe impl Sync for PipelineCache {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct Pipeline {
+    pub pipeline: vk::Pipeline,
+    pub bind_point: vk::PipelineBindPoint,
+    /// Attachment formats of the render pass and index of the subpass of graphics pipelines.
+    pub renderpass: Option<(Vec<format::Format>, usize)>,
+}
+unsafe impl Send for Pipeline {}
+unsafe impl Sync for Pipeline {}
+
 #[derive(Clone, Debug, Hash)]
 pub struct RenderPass {
     pub inner: vk::RenderPass,
+    pub attachments: Vec<pass::Attachment>,
+    pub subpass_count: usize,
 }
 unsafe impl Send for RenderPass {}
 unsafe impl Sync for RenderPass {}
 
+#[derive(Clone, Debug, Hash)]
+pub struct Heap {
+    pub inner: vk::DeviceMemory,
+    pub size: u64,
+}
+unsafe impl Send for Heap {}
+unsafe impl Sync for Heap {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct UnboundBuffer {
+    pub inner: vk::Buffer,
+    pub size: u64,
+}
+unsafe impl Send for UnboundBuffer {}
+unsafe impl Sync for UnboundBuffer {}
+
+#[derive(Clone, Copy, Debug, Hash)]
+pub struct Buffer {
+    pub inner: vk::Buffer,
+    pub size: u64,
+}
+unsafe impl Send for Buffer {}
+unsafe impl Sync for Buffer {}
+
+#[derive(Clone, Copy, Debug, Hash)]
+pub struct BufferView {
+    pub inner: vk::BufferView,
+}
+unsafe impl Send for BufferView {}
+unsafe impl Sync for BufferView {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct UnboundImage {
+    pub inner: vk::Image,
+}
+unsafe impl Send for UnboundImage {}
+unsafe impl Sync for UnboundImage {}
+
+#[derive(Clone, Copy, Debug, Hash)]
+pub struct Image {
+    pub inner: vk::Image,
+}
+unsafe impl Send for Image {}
+unsafe impl Sync for Image {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct FrameBuffer {
+    pub inner: vk::Framebuffer,
+    pub width: u32,
+    pub height: u32,
+    pub layers: u32,
+}
+unsafe impl Send for FrameBuffer {}
+unsafe impl Sync for FrameBuffer {}
+
+#[derive(Clone, Copy, Debug, Hash)]
+pub struct RenderTargetView {
+    pub view: vk::ImageView,
+}
+unsafe impl Send for RenderTargetView {}
+unsafe impl Sync for RenderTargetView {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct DepthStencilView {
+    pub view: vk::ImageView,
+}
+unsafe impl Send for DepthStencilView {}
+unsafe impl Sync for DepthStencilView {}
+
+#[derive(Clone, Copy, Debug, Hash)]
+pub struct ShaderResourceView {
+    pub view: vk::ImageView,
+}
+unsafe impl Send for ShaderResourceView {}
+unsafe impl Sync for ShaderResourceView {}
+
+#[derive(Clone, Copy, Debug, Hash)]
+pub struct UnorderedAccessView {
+    pub view: vk::ImageView,
+}
+unsafe impl Send for UnorderedAccessView {}
+unsafe impl Sync for UnorderedAccessView {}
+
+#[derive(Clone, Copy, Debug, Hash)]
+pub struct Sampler {
+    pub inner: vk::Sampler,
+}
+unsafe impl Send for Sampler {}
+unsafe impl Sync for Sampler {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct DescriptorSetLayout {
+    pub inner: vk::DescriptorSetLayout,
+}
+unsafe impl Send for DescriptorSetLayout {}
+unsafe impl Sync for DescriptorSetLayout {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct DescriptorPool {
+    pub inner: vk::DescriptorPool,
+}
+unsafe impl Send for DescriptorPool {}
+unsafe impl Sync for DescriptorPool {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct DescriptorSet {
+    pub inner: vk::DescriptorSet,
+}
+unsafe impl Send for DescriptorSet {}
+unsafe impl Sync for DescriptorSet {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct QueryPool {
+    pub inner: vk::QueryPool,
+    pub ty: vk::QueryType,
+    pub count: u32,
+    /// Number of results of each query.
+    pub values: u32,
+}
+unsafe impl Send for QueryPool {}
+unsafe impl Sync for QueryPool {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct Event {
+    pub inner: vk::Event,
+}
+unsafe impl Send for Event {}
+unsafe impl Sync for Event {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct Semaphore {
+    pub inner: vk::Semaphore,
+}
+unsafe impl Send for Semaphore {}
+unsafe impl Sync for Semaphore {}
+
+#[derive(Clone, Debug, Hash)]
+pub struct Fence {
+    pub inner: vk::Fence,
+}
+unsafe impl Send for Fence {}
+unsafe impl Sync for Fence {}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use core::Primitive;
+use core::{pso, Primitive};
 use core::state;
 use vk;
 
@@ -88,4 +88,107 @@ pub fn map_stencil_side(side: &state::StencilSide) -> vk::StencilOpState {
         write_mask: side.mask_write as u32,
         reference: 0,
     }
-}
\ No newline at end of file
+}
+pub fn map_blend_factor(factor: pso::BlendFactor) -> vk::BlendFactor {
+    match factor {
+        pso::BlendFactor::Factor(factor) => map_factor(factor),
+        pso::BlendFactor::Src1Color         => vk::BlendFactor::Src1Color,
+        pso::BlendFactor::OneMinusSrc1Color => vk::BlendFactor::OneMinusSrc1Color,
+        pso::BlendFactor::Src1Alpha         => vk::BlendFactor::Src1Alpha,
+        pso::BlendFactor::OneMinusSrc1Alpha => vk::BlendFactor::OneMinusSrc1Alpha,
+    }
+}
+
+fn map_factor(factor: state::Factor) -> vk::BlendFactor {
+    use core::state::BlendValue::*;
+    use core::state::Factor::*;
+    match factor {
+        Zero                  => vk::BlendFactor::Zero,
+        One                   => vk::BlendFactor::One,
+        SourceAlphaSaturated  => vk::BlendFactor::SrcAlphaSaturate,
+        ZeroPlus(SourceColor) => vk::BlendFactor::SrcColor,
+        ZeroPlus(SourceAlpha) => vk::BlendFactor::SrcAlpha,
+        ZeroPlus(DestColor)   => vk::BlendFactor::DstColor,
+        ZeroPlus(DestAlpha)   => vk::BlendFactor::DstAlpha,
+        ZeroPlus(ConstColor)  => vk::BlendFactor::ConstantColor,
+        ZeroPlus(ConstAlpha)  => vk::BlendFactor::ConstantAlpha,
+        OneMinus(SourceColor) => vk::BlendFactor::OneMinusSrcColor,
+        OneMinus(SourceAlpha) => vk::BlendFactor::OneMinusSrcAlpha,
+        OneMinus(DestColor)   => vk::BlendFactor::OneMinusDstColor,
+        OneMinus(DestAlpha)   => vk::BlendFactor::OneMinusDstAlpha,
+        OneMinus(ConstColor)  => vk::BlendFactor::OneMinusConstantColor,
+        OneMinus(ConstAlpha)  => vk::BlendFactor::OneMinusConstantAlpha,
+    }
+}
+
+pub fn map_blend_op(equation: state::Equation) -> vk::BlendOp {
+    use core::state::Equation::*;
+    match equation {
+        Add    => vk::BlendOp::Add,
+        Sub    => vk::BlendOp::Subtract,
+        RevSub => vk::BlendOp::ReverseSubtract,
+        Min    => vk::BlendOp::Min,
+        Max    => vk::BlendOp::Max,
+    }
+}
+
+pub fn map_color_mask(mask: state::ColorMask) -> vk::ColorComponentFlags {
+    let pairs = [
+        (state::RED, vk::COLOR_COMPONENT_R_BIT),
+        (state::GREEN, vk::COLOR_COMPONENT_G_BIT),
+        (state::BLUE, vk::COLOR_COMPONENT_B_BIT),
+        (state::ALPHA, vk::COLOR_COMPONENT_A_BIT),
+    ];
+    pairs.iter().fold(vk::ColorComponentFlags::empty(), |flags, &(bit, vk_bit)| {
+        if mask.contains(bit) { flags | vk_bit } else { flags }
+    })
+}
+
+pub fn map_logic_op(op: pso::LogicOp) -> vk::LogicOp {
+    use core::pso::LogicOp::*;
+    match op {
+        Clear        => vk::LogicOp::Clear,
+        And          => vk::LogicOp::And,
+        AndReverse   => vk::LogicOp::AndReverse,
+        Copy         => vk::LogicOp::Copy,
+        AndInverted  => vk::LogicOp::AndInverted,
+        NoOp         => vk::LogicOp::NoOp,
+        Xor          => vk::LogicOp::Xor,
+        Or           => vk::LogicOp::Or,
+        Nor          => vk::LogicOp::Nor,
+        Equivalent   => vk::LogicOp::Equivalent,
+        Invert       => vk::LogicOp::Invert,
+        OrReverse    => vk::LogicOp::OrReverse,
+        CopyInverted => vk::LogicOp::CopyInverted,
+        OrInverted   => vk::LogicOp::OrInverted,
+        Nand         => vk::LogicOp::Nand,
+        Set          => vk::LogicOp::Set,
+    }
+}
+
+// Channels without blending keep the source value
+fn map_blend_channel(channel: Option<pso::BlendChannel>) -> (vk::BlendFactor, vk::BlendFactor, vk::BlendOp) {
+    match channel {
+        Some(b) => (map_blend_factor(b.source), map_blend_factor(b.destination), map_blend_op(b.equation)),
+        None => (vk::BlendFactor::One, vk::BlendFactor::Zero, vk::BlendOp::Add),
+    }
+}
+
+/// Map the blend state of each color target, up to the first unused one.
+pub fn map_blend_targets(color_targets: &[Option<pso::ColorTargetDesc>]) -> Vec<vk::PipelineColorBlendAttachmentState> {
+    color_targets.iter().take_while(|desc| desc.is_some()).map(|desc| {
+        let info = desc.unwrap().1;
+        let (src_color, dst_color, color_op) = map_blend_channel(info.color);
+        let (src_alpha, dst_alpha, alpha_op) = map_blend_channel(info.alpha);
+        vk::PipelineColorBlendAttachmentState {
+            blend_enable: if info.color.is_some() || info.alpha.is_some() { vk::VK_TRUE } else { vk::VK_FALSE },
+            src_color_blend_factor: src_color,
+            dst_color_blend_factor: dst_color,
+            color_blend_op: color_op,
+            src_alpha_blend_factor: src_alpha,
+            dst_alpha_blend_factor: dst_alpha,
+            alpha_blend_op: alpha_op,
+            color_write_mask: map_color_mask(info.mask),
+        }
+    }).collect()
+}
@@ -0,0 +1,152 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::{command, CommandQueue as CoreQueue, Factory};
+use core::command::CommandBuffer as CoreBuffer;
+use core::image::ImageLayout;
+use std::u64;
+use {native, CommandBuffer, CommandPool, CommandQueue, Device, QueueFamily};
+
+/// Records buffer and image uploads on a dedicated transfer queue, so that large copies
+/// run alongside the rendering instead of stalling the graphics queue.
+///
+/// The copies recorded since the last `flush` are submitted together, signaling a semaphore
+/// that the graphics submissions using the uploaded resources wait upon at the `TRANSFER` stage.
+/// The source buffers must be kept alive until the submission completes, see `is_complete`.
+///
+/// When the transfer queue belongs to another family than the graphics queue, the uploaded
+/// resources need to be shared concurrently between both families.
+///
+/// The uploader has to be released with `destroy`, which waits for the pending copies.
+pub struct Uploader {
+    pool: CommandPool,
+    queue: CommandQueue,
+    // Command buffer currently recorded, if any copy has been recorded since the last flush
+    current: Option<CommandBuffer>,
+    // Submitted command buffers, with their submission index and the fence signaled on completion
+    pending: Vec<(u64, CommandBuffer, native::Fence)>,
+    free_buffers: Vec<CommandBuffer>,
+    free_fences: Vec<native::Fence>,
+    next_submission: u64,
+}
+
+impl Uploader {
+    /// Create an uploader submitting to `queue`, which must belong to `family`.
+    pub fn new(device: &mut Device, family: &QueueFamily, queue: CommandQueue) -> Uploader {
+        assert_eq!(family.family_index, queue.family_index);
+        Uploader {
+            pool: device.create_command_pool(family),
+            queue: queue,
+            current: None,
+            pending: Vec::new(),
+            free_buffers: Vec::new(),
+            free_fences: Vec::new(),
+            next_submission: 0,
+        }
+    }
+
+    fn command_buffer(&mut self, device: &mut Device) -> &mut CommandBuffer {
+        if self.current.is_none() {
+            self.reclaim(device);
+            let mut cmd_buffer = match self.free_buffers.pop() {
+                Some(cmd_buffer) => cmd_buffer,
+                None => self.pool.allocate_primary(),
+            };
+            cmd_buffer.begin();
+            self.current = Some(cmd_buffer);
+        }
+        self.current.as_mut().unwrap()
+    }
+
+    /// Record copies from the `src` staging buffer to `dst`.
+    pub fn upload_buffer(&mut self, device: &mut Device, src: native::Buffer, dst: native::Buffer,
+                         regions: &[command::BufferCopy]) {
+        self.command_buffer(device).copy_buffer(src, dst, regions);
+    }
+
+    /// Record copies from the `src` staging buffer to `dst`, which is in the `dst_layout`.
+    pub fn upload_image(&mut self, device: &mut Device, src: native::Buffer, dst: native::Image,
+                        dst_layout: ImageLayout, regions: &[command::BufferImageCopy]) {
+        self.command_buffer(device).copy_buffer_to_image(src, dst, dst_layout, regions);
+    }
+
+    /// Submit the copies recorded since the last flush, signaling `signal` once they complete.
+    /// Return the index of the submission, or `None` if there was nothing to submit.
+    pub fn flush(&mut self, device: &mut Device, signal: &native::Semaphore) -> Option<u64> {
+        let mut cmd_buffer = match self.current.take() {
+            Some(cmd_buffer) => cmd_buffer,
+            None => return None,
+        };
+        cmd_buffer.end();
+
+        let fence = match self.free_fences.pop() {
+            Some(fence) => {
+                device.reset_fences(&[&fence]);
+                fence
+            }
+            None => device.create_fence(false),
+        };
+        self.queue.submit(&[&cmd_buffer], &[], &[signal], Some(&fence));
+
+        let submission = self.next_submission;
+        self.next_submission += 1;
+        self.pending.push((submission, cmd_buffer, fence));
+        Some(submission)
+    }
+
+    /// Return whether the `submission` has completed, and its source buffers can be reused.
+    pub fn is_complete(&mut self, device: &mut Device, submission: u64) -> bool {
+        self.reclaim(device);
+        self.pending.iter().all(|&(index, _, _)| index != submission)
+    }
+
+    /// Block until all the submitted copies have completed.
+    pub fn wait_idle(&mut self, device: &mut Device) {
+        if !self.pending.is_empty() {
+            let fences = self.pending.iter().map(|&(_, _, ref fence)| fence).collect::<Vec<_>>();
+            device.wait_for_fences(&fences, true, u64::MAX);
+        }
+        self.reclaim(device);
+    }
+
+    /// Wait for the pending copies, and release the command buffers and fences.
+    /// Copies recorded since the last flush are discarded.
+    pub fn destroy(mut self, device: &mut Device) {
+        self.wait_idle(device);
+        for fence in self.free_fences.drain(..) {
+            device.destroy_fence(fence);
+        }
+        let mut buffers = self.free_buffers.drain(..).collect::<Vec<_>>();
+        buffers.extend(self.current.take());
+        if !buffers.is_empty() {
+            self.pool.free(buffers);
+        }
+        // the pool itself is destroyed when dropped
+    }
+
+    // Recycle the command buffers and fences of the completed submissions.
+    fn reclaim(&mut self, device: &mut Device) {
+        let mut i = 0;
+        while i < self.pending.len() {
+            if device.get_fence_status(&self.pending[i].2) {
+                let (_, mut cmd_buffer, fence) = self.pending.swap_remove(i);
+                cmd_buffer.reset(false);
+                self.free_buffers.push(cmd_buffer);
+                self.free_fences.push(fence);
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
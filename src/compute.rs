@@ -0,0 +1,91 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compute-only launch mode, running a workload on the low level API without
+//! creating a window, a surface or a swapchain.
+//!
+//! The device is opened with a single queue supporting compute operations. The
+//! application records its dispatches once, the harness submits them and waits
+//! for their completion before letting the application read the results back.
+
+use std::iter;
+
+use gfx_corell::{Adapter, CommandQueue, Factory, Instance, QueueFamily, COMPUTE};
+use gfx_device_vulkanll as back;
+
+use {init_logger, AdapterKind, Backend, Config};
+
+/// Application dispatching a compute workload, for GPGPU experiments and compute tests.
+pub trait ComputeApplication: Sized {
+    /// Create the pipelines and resources of the workload, and upload its inputs.
+    fn new(&mut back::Device, &Config) -> Self;
+    /// Record the dispatches of the workload. The results read back from mapped memory
+    /// need a barrier to the `HOST` stage with `memory::HOST_READ` accesses.
+    fn dispatch(&mut self, &mut back::CommandBuffer);
+    /// Read the results back, once the dispatches have completed.
+    fn read_back(&mut self, &mut back::Device);
+
+    /// Run the workload on the adapter picked by `config.device`, and hand the application
+    /// back once its results are read. `name` only identifies the workload in the logs.
+    fn launch_compute(name: &str, config: &Config) -> Option<Self> {
+        init_logger(config);
+        config.check_backend(Backend::Vulkan);
+
+        let instance = back::Instance::create_with_validation(config.validation);
+        let adapters = instance.enumerate_adapters();
+        let descriptions = adapters.iter().map(|a| (a.get_info().name.as_str(),
+            if a.get_info().software_rendering { AdapterKind::Software } else { AdapterKind::Other }));
+        let adapter = match config.device.select(descriptions) {
+            Some(index) => &adapters[index],
+            None => {
+                error!("No Vulkan adapter matches {:?}", config.device);
+                return None;
+            }
+        };
+        let family = match adapter.get_queue_families().find(|family| family.queue_type().contains(COMPUTE)) {
+            Some(family) => family,
+            None => {
+                error!("The adapter {} has no compute queue", adapter.get_info().name);
+                return None;
+            }
+        };
+        info!("Running {} on {}", name, adapter.get_info().name);
+
+        let (mut device, mut queues) = adapter.open(iter::once((family, 1)));
+        let mut app = Self::new(&mut device, config);
+
+        let mut pool = device.create_command_pool(family);
+        let mut cmd_buffer = pool.allocate_primary();
+        cmd_buffer.begin();
+        app.dispatch(&mut cmd_buffer);
+        cmd_buffer.end();
+
+        let fence = device.create_fence(false);
+        queues[0].submit(&[&cmd_buffer], &[], &[], Some(&fence));
+        let completed = device.wait_for_fences(&[&fence], true, !0);
+        if !completed {
+            error!("Timeout while waiting for {} to complete", name);
+            // the command buffer is still pending, it can't be freed with its pool before
+            device.wait_idle();
+        }
+        device.destroy_fence(fence);
+        pool.free(vec![cmd_buffer]);
+        if !completed {
+            return None;
+        }
+
+        app.read_back(&mut device);
+        Some(app)
+    }
+}
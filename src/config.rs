@@ -0,0 +1,300 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Filling a `Config` from the environment and from configuration files.
+
+use std::error::Error;
+use std::{env, fmt, io};
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use toml;
+
+use {Backend, Config};
+
+/// Keys understood by `Config::set`, also used as the names of the
+/// environment variables (upper-cased, with a `GFX_APP_` prefix) and of
+/// the configuration file entries.
+pub const KEYS: &'static [&'static str] = &["size", "backend", "vsync", "msaa", "fullscreen", "headless", "max_frames"];
+
+/// Command line options understood by `Config::from_args`.
+pub const USAGE: &'static str = "Options:
+    --backend NAME    gl, d3d11, d3d11-pix, metal or vulkan
+    --size WxH        inner size of the window
+    --frames N        exit after rendering N frames
+    --vsync on|off    synchronize with the vertical blank
+    --msaa N          number of samples of the main color target
+    --fullscreen      go fullscreen on the primary monitor
+    --headless        keep the window hidden";
+
+/// Error filling a `Config`.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file could not be read.
+    Io(io::Error),
+    /// The configuration file is not valid TOML.
+    #[cfg(feature = "serde")]
+    Toml(toml::de::Error),
+    /// The key is not one of `KEYS`.
+    UnknownKey(String),
+    /// A command line option is missing its value.
+    MissingValue(String),
+    /// The value can't be parsed for the given key.
+    InvalidValue { key: String, value: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io(ref e) => write!(f, "Unable to read the configuration: {}", e),
+            #[cfg(feature = "serde")]
+            ConfigError::Toml(ref e) => write!(f, "Invalid configuration file: {}", e),
+            ConfigError::UnknownKey(ref key) => write!(f, "Unknown configuration key `{}`", key),
+            ConfigError::MissingValue(ref key) => write!(f, "Missing value for the option `--{}`", key),
+            ConfigError::InvalidValue { ref key, ref value } =>
+                write!(f, "Invalid value `{}` for the configuration key `{}`", value, key),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn description(&self) -> &str {
+        match *self {
+            ConfigError::Io(_) => "Unable to read the configuration",
+            #[cfg(feature = "serde")]
+            ConfigError::Toml(_) => "Invalid configuration file",
+            ConfigError::UnknownKey(_) => "Unknown configuration key",
+            ConfigError::MissingValue(_) => "Missing command line option value",
+            ConfigError::InvalidValue { .. } => "Invalid configuration value",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            ConfigError::Io(ref e) => Some(e),
+            #[cfg(feature = "serde")]
+            ConfigError::Toml(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> ConfigError {
+        ConfigError::Io(e)
+    }
+}
+
+impl FromStr for Backend {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Backend, ()> {
+        match &*s.to_lowercase() {
+            "gl" | "opengl" => Ok(Backend::OpenGL2),
+            "d3d11" | "dx11" => Ok(Backend::Direct3D11 { pix_mode: false }),
+            "d3d11-pix" | "dx11-pix" => Ok(Backend::Direct3D11 { pix_mode: true }),
+            "metal" => Ok(Backend::Metal),
+            "vulkan" | "vk" => Ok(Backend::Vulkan),
+            _ => Err(()),
+        }
+    }
+}
+
+fn parse_size(value: &str) -> Option<(u32, u32)> {
+    let mut parts = value.splitn(2, 'x');
+    match (parts.next().map(str::parse), parts.next().map(str::parse)) {
+        (Some(Ok(w)), Some(Ok(h))) if w != 0 && h != 0 => Some((w, h)),
+        _ => None,
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match &*value.to_lowercase() {
+        "1" | "true" | "on" | "yes" => Some(true),
+        "0" | "false" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+impl Config {
+    /// Parse `value` and assign it to the field named by `key`, which is one of `KEYS`.
+    /// Sizes are written as `WIDTHxHEIGHT`, switches as `on`/`off`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        let invalid = || ConfigError::InvalidValue {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        match key {
+            "size" => self.size = Some(try!(parse_size(value).ok_or_else(&invalid))),
+            "backend" => self.backend = Some(try!(value.parse().map_err(|_| invalid()))),
+            "vsync" => self.vsync = try!(parse_bool(value).ok_or_else(&invalid)),
+            "msaa" => self.msaa = try!(value.parse().map_err(|_| invalid())),
+            "fullscreen" => self.fullscreen = try!(parse_bool(value).ok_or_else(&invalid)),
+            "headless" => self.headless = try!(parse_bool(value).ok_or_else(&invalid)),
+            // the harness checks the count after each frame, so 0 would never match
+            "max_frames" => self.max_frames = match value.parse() {
+                Ok(0) | Err(_) => return Err(invalid()),
+                Ok(frames) => Some(frames),
+            },
+            _ => return Err(ConfigError::UnknownKey(key.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Default configuration, overridden by the `GFX_APP_SIZE`, `GFX_APP_BACKEND`,
+    /// `GFX_APP_VSYNC`, `GFX_APP_MSAA`, `GFX_APP_FULLSCREEN`, `GFX_APP_HEADLESS` and
+    /// `GFX_APP_MAX_FRAMES` environment variables that are set.
+    pub fn from_env() -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        for key in KEYS {
+            if let Ok(value) = env::var(format!("GFX_APP_{}", key.to_uppercase())) {
+                try!(config.set(key, &value));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Default configuration, overridden by the command line options of the
+    /// process, as listed in `USAGE`.
+    pub fn from_args() -> Result<Config, ConfigError> {
+        Config::parse_args(env::args().skip(1))
+    }
+
+    /// Same as `from_args`, but on failure the error is printed to stderr,
+    /// followed by `USAGE`, and `None` is returned.
+    pub fn from_args_or_usage() -> Option<Config> {
+        use std::io::Write;
+
+        match Config::from_args() {
+            Ok(config) => Some(config),
+            Err(e) => {
+                let _ = writeln!(io::stderr(), "{}\n{}", e, USAGE);
+                None
+            }
+        }
+    }
+
+    /// Default configuration, overridden by the given command line options.
+    /// Both `--option value` and `--option=value` are accepted.
+    pub fn parse_args<I, S>(args: I) -> Result<Config, ConfigError> where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut config = Config::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let arg = arg.as_ref();
+            if !arg.starts_with("--") {
+                return Err(ConfigError::UnknownKey(arg.to_string()));
+            }
+            let option = &arg[2..];
+            let (name, inline_value) = match option.find('=') {
+                Some(pos) => (&option[..pos], Some(&option[pos + 1..])),
+                None => (option, None),
+            };
+            let key = match name {
+                "frames" => "max_frames",
+                other => other,
+            };
+            let value = match (inline_value, key) {
+                (Some(value), _) => value.to_string(),
+                // switches don't need a value
+                (None, "fullscreen") | (None, "headless") => "on".to_string(),
+                (None, _) => match args.next() {
+                    Some(value) => value.as_ref().to_string(),
+                    None => return Err(ConfigError::MissingValue(name.to_string())),
+                },
+            };
+            try!(config.set(key, &value));
+        }
+        Ok(config)
+    }
+
+    /// Default configuration, overridden by the entries of a TOML file, e.g.
+    ///
+    /// ```toml
+    /// size = "1280x720"
+    /// backend = "vulkan"
+    /// vsync = false
+    /// msaa = 4
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        use std::fs::File;
+        use std::io::Read;
+
+        let mut text = String::new();
+        try!(try!(File::open(path)).read_to_string(&mut text));
+        let root: toml::Value = try!(text.parse().map_err(ConfigError::Toml));
+
+        let mut config = Config::default();
+        for (key, value) in root.as_table().into_iter().flat_map(|table| table.iter()) {
+            let value = match *value {
+                toml::Value::String(ref s) => s.clone(),
+                toml::Value::Integer(i) => i.to_string(),
+                toml::Value::Boolean(b) => if b { "on" } else { "off" }.to_string(),
+                ref other => return Err(ConfigError::InvalidValue {
+                    key: key.clone(),
+                    value: other.to_string(),
+                }),
+            };
+            try!(config.set(key, &value));
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Backend, Config};
+
+    #[test]
+    fn test_set() {
+        let mut config = Config::default();
+        config.set("size", "1280x720").unwrap();
+        config.set("backend", "Vulkan").unwrap();
+        config.set("vsync", "off").unwrap();
+        config.set("msaa", "4").unwrap();
+        config.set("max_frames", "100").unwrap();
+        assert_eq!(config.size, Some((1280, 720)));
+        assert_eq!(config.backend, Some(Backend::Vulkan));
+        assert!(!config.vsync);
+        assert_eq!(config.msaa, 4);
+        assert_eq!(config.max_frames, Some(100));
+
+        assert!(config.set("size", "1280").is_err());
+        assert!(config.set("size", "0x720").is_err());
+        assert!(config.set("vsync", "maybe").is_err());
+        assert!(config.set("backend", "glide").is_err());
+        assert!(config.set("max_frames", "0").is_err());
+        assert!(config.set("color", "red").is_err());
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let config = Config::parse_args(&["--backend", "gl", "--size=640x480",
+            "--frames", "10", "--headless", "--vsync", "off"]).unwrap();
+        assert_eq!(config.backend, Some(Backend::OpenGL2));
+        assert_eq!(config.size, Some((640, 480)));
+        assert_eq!(config.max_frames, Some(10));
+        assert!(config.headless);
+        assert!(!config.vsync);
+
+        assert!(Config::parse_args(&["--frames"]).is_err());
+        assert!(Config::parse_args(&["--frames", "0"]).is_err());
+        assert!(Config::parse_args(&["frames", "10"]).is_err());
+        assert!(Config::parse_args(&["--color", "red"]).is_err());
+    }
+}
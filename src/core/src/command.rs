@@ -68,6 +68,9 @@ pub trait Buffer<R: Resources>: Send {
     fn copy_buffer(&mut self, src: R::Buffer, dst: R::Buffer,
                    src_offset_bytes: usize, dst_offset_bytes: usize,
                    size_bytes: usize);
+    /// Copy part of a texture to a buffer, the texels being tightly packed
+    fn copy_texture_to_buffer(&mut self, src: R::Texture, texture::Kind, Option<texture::CubeFace>,
+                              texture::RawImageInfo, dst: R::Buffer, dst_offset_bytes: usize);
     /// Update a vertex/index/uniform buffer
     fn update_buffer(&mut self, R::Buffer, data: &[u8], offset: usize);
     /// Update a texture
@@ -82,6 +85,12 @@ pub trait Buffer<R: Resources>: Send {
     fn call_draw(&mut self, VertexCount, VertexCount, Option<InstanceParams>);
     /// Draw a primitive with index buffer
     fn call_draw_indexed(&mut self, VertexCount, VertexCount, VertexCount, Option<InstanceParams>);
+    /// Begin a group of commands labeled `name`, shown by graphics debuggers
+    fn begin_debug_group(&mut self, _name: &str) {}
+    /// End the last group of commands begun
+    fn end_debug_group(&mut self) {}
+    /// Insert a marker labeled `name` between the commands, shown by graphics debuggers
+    fn insert_debug_marker(&mut self, _name: &str) {}
 }
 
 macro_rules! impl_clear {
@@ -142,14 +151,16 @@ impl<R: Resources> AccessInfo<R> {
 
     /// Clear access informations
     pub fn clear(&mut self) {
-        self.mapped_reads.clear();
+        for buffer in self.mapped_reads.drain() {
+            buffer.mapping().unwrap().release_read();
+        }
         self.mapped_writes.clear();
     }
 
     /// Register a buffer read access
     pub fn buffer_read(&mut self, buffer: &handle::RawBuffer<R>) {
-        if buffer.is_mapped() {
-            self.mapped_reads.insert(buffer.clone());
+        if buffer.is_mapped() && self.mapped_reads.insert(buffer.clone()) {
+            buffer.mapping().unwrap().record_read();
         }
     }
 
@@ -182,10 +193,14 @@ impl<R: Resources> AccessInfo<R> {
 
     /// Takes all the accesses necessary for submission
     pub fn take_accesses(&self) -> SubmissionResult<AccessGuard<R>> {
-        for buffer in self.mapped_reads().chain(self.mapped_writes()) {
+        for (i, buffer) in self.mapped_reads().chain(self.mapped_writes()).enumerate() {
             unsafe {
                 if !buffer.mapping().unwrap().take_access() {
-                    return Err(SubmissionError::AccessOverlap);
+                    // give back the accesses taken so far, or they would stay locked
+                    for taken in self.mapped_reads().chain(self.mapped_writes()).take(i) {
+                        taken.mapping().unwrap().release_access();
+                    }
+                    return Err(SubmissionError::MappingBusy);
                 }
             }
         }
@@ -193,6 +208,12 @@ impl<R: Resources> AccessInfo<R> {
     }
 }
 
+impl<R: Resources> Drop for AccessInfo<R> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
 #[allow(missing_docs)]
 pub type AccessInfoBuffers<'a, R> = hash_set::Iter<'a, handle::RawBuffer<R>>;
 
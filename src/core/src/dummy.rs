@@ -56,6 +56,7 @@ impl mapping::Gate<DummyResources> for DummyMapping {
     unsafe fn set<T>(&self, _index: usize, _val: T) { unimplemented!() }
     unsafe fn slice<'a, 'b, T>(&'a self, _len: usize) -> &'b [T] { unimplemented!() }
     unsafe fn mut_slice<'a, 'b, T>(&'a self, _len: usize) -> &'b mut [T] { unimplemented!() }
+    unsafe fn copy_from<T: Copy>(&self, _offset: usize, _data: &[T]) { unimplemented!() }
 }
 
 impl DummyDevice {
@@ -100,6 +101,8 @@ impl command::Buffer<DummyResources> for DummyCommandBuffer {
     fn copy_buffer(&mut self, _: (), _: (),
                    _: usize, _: usize,
                    _: usize) {}
+    fn copy_texture_to_buffer(&mut self, _: (), _: texture::Kind, _: Option<texture::CubeFace>,
+                              _: texture::RawImageInfo, _: (), _: usize) {}
     fn update_buffer(&mut self, _: (), _: &[u8], _: usize) {}
     fn update_texture(&mut self, _: (), _: texture::Kind, _: Option<texture::CubeFace>,
                       _: &[u8], _: texture::RawImageInfo) {}
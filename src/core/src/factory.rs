@@ -274,6 +274,80 @@ pub trait Factory<R: Resources> {
                                           mapping::Error>
         where T: Copy;
 
+    /// Same as `read_mapping`, but fails with `mapping::Error::WouldBlock` instead
+    /// of stalling the CPU when the GPU is still using the buffer, so that the caller
+    /// can do something else and try again later.
+    ///
+    /// Backends that can't tell whether the GPU is done with the buffer block.
+    fn try_read_mapping<'a, 'b, T>(&'a mut self, buf: &'b handle::Buffer<R, T>)
+                                   -> Result<mapping::Reader<'b, R, T>,
+                                             mapping::Error>
+        where T: Copy
+    {
+        self.read_mapping(buf)
+    }
+
+    /// Same as `write_mapping`, but fails with `mapping::Error::WouldBlock` instead
+    /// of stalling the CPU when the GPU is still using the buffer.
+    ///
+    /// Backends that can't tell whether the GPU is done with the buffer block.
+    fn try_write_mapping<'a, 'b, T>(&'a mut self, buf: &'b handle::Buffer<R, T>)
+                                    -> Result<mapping::Writer<'b, R, T>,
+                                              mapping::Error>
+        where T: Copy
+    {
+        self.write_mapping(buf)
+    }
+
+    /// Same as `write_mapping`, but the writer keeps a handle to the buffer instead
+    /// of borrowing it, so that it can be sent to another thread.
+    fn write_mapping_owned<T>(&mut self, buf: &handle::Buffer<R, T>)
+                              -> Result<mapping::OwnedWriter<R, T>, mapping::Error>
+        where T: Copy
+    {
+        let writer = try!(self.write_mapping(buf));
+        Ok(unsafe { writer.into_owned(buf.raw().clone()) })
+    }
+
+    /// Release the backend mapping of the buffer, without destroying it, e.g. to give
+    /// the address space back on GL, where persistent mappings stay until the buffer
+    /// is deleted. Pending writes are flushed first. The buffer is mapped again by
+    /// the next `read_mapping`/`write_mapping`, or explicitly with `remap`.
+    ///
+    /// Fails with `mapping::Error::AccessOverlap` if the buffer is currently mapped.
+    fn unmap(&mut self, _buf: &handle::RawBuffer<R>) -> Result<(), mapping::Error> {
+        Ok(())
+    }
+
+    /// Restore the backend mapping of a buffer released with `unmap`.
+    fn remap(&mut self, _buf: &handle::RawBuffer<R>) -> Result<(), mapping::Error> {
+        Ok(())
+    }
+
+    /// Map an upload buffer for writing without waiting for the GPU nor tracking the access,
+    /// which is only possible for persistently mapped buffers.
+    ///
+    /// Unsafe because the caller must make sure that the GPU is done with the written
+    /// parts of the buffer, and that no other mapping of it is alive.
+    unsafe fn write_mapping_unsynchronized<'a, 'b, T>(&'a mut self, buf: &'b handle::Buffer<R, T>)
+                                                      -> Result<mapping::Unsynchronized<'b, R, T>,
+                                                                mapping::Error>
+        where T: Copy;
+
+    /// Map the image `img` of a `Download` texture for reading on the CPU. Such textures
+    /// are staging or linearly tiled ones, depending on the backend. Only the layer, or
+    /// depth slice, at `img.zoffset` is mapped, and `T` is the type of a texel.
+    ///
+    /// The GPU must be done writing to the texture, e.g. by waiting for the fence of the
+    /// submission that wrote it. Backends that can't map textures return `mapping::Error::MapFailed`.
+    fn map_texture_readable<'a, 'b, T>(&'a mut self, _tex: &'b handle::RawTexture<R>,
+                                       _face: Option<texture::CubeFace>, _img: texture::RawImageInfo)
+                                       -> Result<mapping::TextureReader<'b, R, T>, mapping::Error>
+        where T: Copy
+    {
+        Err(mapping::Error::MapFailed)
+    }
+
     /// Create a new empty raw texture with no data. The channel type parameter is a hint,
     /// required to assist backends that have no concept of typeless formats (OpenGL).
     /// The initial data, if given, has to be provided for all mip levels and slices:
@@ -147,7 +147,7 @@ impl_formats! {
     D24             : Vec1<Unorm> = f32 {8} [TextureSurface, DepthSurface], //hacky stencil bits
     D24_S8          : Vec1<Unorm, Uint> = u32 {8} [TextureSurface, DepthSurface, StencilSurface],
     D32             : Vec1<Float> = f32 {0} [TextureSurface, DepthSurface],
-    //D32_S8          : Vec1<Unorm, Float, Uint> = (f32, u32) {32} [TextureSurface, DepthSurface, StencilSurface],
+    D32_S8          : Vec1<Float, Uint> = [u32; 2] {8} [TextureSurface, DepthSurface, StencilSurface],
 }
 
 
@@ -354,6 +354,8 @@ pub type Depth = (D24, Unorm);
 pub type DepthStencil = (D24_S8, Unorm);
 /// Standard 32-bit floating-point depth format.
 pub type Depth32F = (D32, Float);
+/// Standard 32-bit floating-point depth format with 8-bit stencil.
+pub type Depth32FStencil8 = (D32_S8, Float);
 
 
 macro_rules! impl_simple_formats {
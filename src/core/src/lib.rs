@@ -216,13 +216,16 @@ pub trait Resources:          Clone + Hash + Debug + Eq + PartialEq + Any {
 #[allow(missing_docs)]
 pub enum SubmissionError {
     AccessOverlap,
+    /// A mapped buffer used by the commands is currently accessed by the CPU,
+    /// through a `Reader` or `Writer` that is still alive.
+    MappingBusy,
 }
 
 impl fmt::Display for SubmissionError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use self::SubmissionError::*;
         match *self {
-            AccessOverlap => write!(f, "{}", self.description()),
+            AccessOverlap | MappingBusy => write!(f, "{}", self.description()),
         }
     }
 }
@@ -231,7 +234,8 @@ impl Error for SubmissionError {
     fn description(&self) -> &str {
         use self::SubmissionError::*;
         match *self {
-            AccessOverlap => "A resource access overlaps with another"
+            AccessOverlap => "A resource access overlaps with another",
+            MappingBusy => "A mapped buffer is currently accessed by the CPU",
         }
     }
 }
@@ -19,8 +19,11 @@
 use std::error::Error as StdError;
 use std::fmt;
 use std::cell::UnsafeCell;
-use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{self, AtomicBool};
+use std::cmp;
+use std::marker::PhantomData;
+use std::{mem, slice};
+use std::ops::{Deref, DerefMut, Range};
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
 use Resources;
 use {memory, buffer, handle};
 
@@ -33,15 +36,45 @@ pub trait Gate<R: Resources> {
     unsafe fn slice<'a, 'b, T>(&'a self, len: usize) -> &'b [T];
     /// Returns a mutable slice of the specified length.
     unsafe fn mut_slice<'a, 'b, T>(&'a self, len: usize) -> &'b mut [T];
+    /// Copy `data` to the elements starting at `offset`. Not bounds-checked.
+    unsafe fn copy_from<T: Copy>(&self, offset: usize, data: &[T]);
+    /// Make the CPU writes to the `range` of bytes visible to the device.
+    /// Only non-coherent mappings need to do something here.
+    unsafe fn flush(&mut self, _range: Range<usize>) {}
+    /// Make the device writes to the `range` of bytes visible to the CPU.
+    /// Only non-coherent mappings need to do something here.
+    unsafe fn invalidate(&mut self, _range: Range<usize>) {}
 }
 
 /// Error accessing a mapping.
+///
+/// Access to a mapping is never waited for: if the mapping is used by the CPU
+/// or by a submission at the same time, `AccessOverlap` is returned right away.
+/// The other way around, submitting while a `Reader` or `Writer` is alive fails
+/// with `SubmissionError::MappingBusy`.
+/// The access is released when the `Reader` or `Writer` is dropped, including
+/// while unwinding from a panic.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Error {
     /// The requested mapping access did not match the expected usage.
     InvalidAccess(memory::Access, memory::Usage),
     /// The requested mapping access overlaps with another.
     AccessOverlap,
+    /// The GPU is still using the buffer, so the mapping would block.
+    WouldBlock,
+    /// The requested range of elements is not within the mapping.
+    OutOfBounds {
+        /// Start of the range.
+        start: usize,
+        /// End of the range.
+        end: usize,
+        /// Number of elements in the mapping.
+        len: usize,
+    },
+    /// The backend failed to map the buffer.
+    MapFailed,
+    /// The buffer is not persistently mapped, so it can't be accessed without synchronization.
+    NotPersistent,
 }
 
 impl fmt::Display for Error {
@@ -51,7 +84,10 @@ impl fmt::Display for Error {
             InvalidAccess(ref access, ref usage) => {
                 write!(f, "{}: access = {:?}, usage = {:?}", self.description(), access, usage)
             }
-            AccessOverlap => write!(f, "{}", self.description())
+            OutOfBounds { start, end, len } => {
+                write!(f, "{}: range = {}..{}, len = {}", self.description(), start, end, len)
+            }
+            AccessOverlap | WouldBlock | MapFailed | NotPersistent => write!(f, "{}", self.description())
         }
     }
 }
@@ -61,7 +97,11 @@ impl StdError for Error {
         use self::Error::*;
         match *self {
             InvalidAccess(..) => "The requested mapping access did not match the expected usage",
-            AccessOverlap => "The requested mapping access overlaps with another"
+            AccessOverlap => "The requested mapping access overlaps with another",
+            WouldBlock => "The buffer is still in use by the GPU",
+            OutOfBounds { .. } => "The requested range is not within the mapping",
+            MapFailed => "The backend failed to map the buffer",
+            NotPersistent => "The buffer is not persistently mapped",
         }
     }
 }
@@ -71,6 +111,8 @@ impl StdError for Error {
 pub struct Raw<R: Resources> {
     resource: UnsafeCell<R::Mapping>,
     accessible: AtomicBool,
+    /// Number of command buffers recording GPU reads of the buffer, not submitted yet.
+    pending_reads: AtomicUsize,
 }
 
 #[doc(hidden)]
@@ -79,6 +121,7 @@ impl<R: Resources> Raw<R> {
         Raw {
             resource: UnsafeCell::new(resource),
             accessible: AtomicBool::new(true),
+            pending_reads: AtomicUsize::new(0),
         }
     }
 
@@ -87,7 +130,7 @@ impl<R: Resources> Raw<R> {
     }
 
     pub unsafe fn release_access(&self) {
-        if cfg!(debug) {
+        if cfg!(debug_assertions) {
             assert!(self.accessible.swap(true, atomic::Ordering::Relaxed) == false);
         } else {
             self.accessible.store(true, atomic::Ordering::Relaxed)
@@ -97,6 +140,34 @@ impl<R: Resources> Raw<R> {
     pub unsafe fn use_access(&self) -> &mut R::Mapping {
         &mut *self.resource.get()
     }
+
+    /// Record that a command buffer reads from the buffer, until `release_read`.
+    /// Only tracked with the `mapping-validation` feature.
+    pub fn record_read(&self) {
+        if cfg!(feature = "mapping-validation") {
+            self.pending_reads.fetch_add(1, atomic::Ordering::Relaxed);
+        }
+    }
+
+    pub fn release_read(&self) {
+        if cfg!(feature = "mapping-validation") {
+            self.pending_reads.fetch_sub(1, atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Report a CPU write while recorded GPU reads are pending: the GPU would
+    /// see the new contents instead of the ones at recording time.
+    fn validate_write(&self) {
+        let pending = self.pending_reads.load(atomic::Ordering::Relaxed);
+        if pending != 0 {
+            let msg = "Writing to a mapped buffer read by commands that are not submitted yet";
+            if cfg!(debug_assertions) {
+                panic!("{} ({} command buffers)", msg, pending);
+            } else {
+                error!("{} ({} command buffers)", msg, pending);
+            }
+        }
+    }
 }
 
 unsafe impl<R: Resources> Sync for Raw<R> {}
@@ -148,16 +219,23 @@ fn take_access_checked<R>(access: memory::Access, buffer: &buffer::Raw<R>)
         _ => return Err(Error::InvalidAccess(access, usage)),
     }
 
-    Guard::new(buffer.mapping().unwrap())
+    match buffer.mapping() {
+        Some(raw) => Guard::new(raw),
+        // the backend didn't map the buffer on creation
+        None => Err(Error::MapFailed),
+    }
 }
 
+/// Take the mapping of `buffer` for reading. `sync` makes the mapping accessible
+/// to the CPU, or fails if it can't, e.g. with `Error::WouldBlock`.
 #[doc(hidden)]
 pub unsafe fn read<R, T, S>(buffer: &buffer::Raw<R>, sync: S)
                             -> Result<Reader<R, T>, Error>
-    where R: Resources, T: Copy, S: FnOnce(&mut R::Mapping)
+    where R: Resources, T: Copy, S: FnOnce(&mut R::Mapping) -> Result<(), Error>
 {
     let mut mapping = try!(take_access_checked(memory::READ, buffer));
-    sync(&mut mapping);
+    try!(sync(&mut mapping));
+    mapping.invalidate(0 .. buffer.get_info().size);
 
     Ok(Reader {
         slice: mapping.slice(buffer.len::<T>()),
@@ -165,20 +243,63 @@ pub unsafe fn read<R, T, S>(buffer: &buffer::Raw<R>, sync: S)
     })
 }
 
+/// Take the mapping of `buffer` for writing, see `read`.
 #[doc(hidden)]
 pub unsafe fn write<R, T, S>(buffer: &buffer::Raw<R>, sync: S)
                              -> Result<Writer<R, T>, Error>
-    where R: Resources, T: Copy, S: FnOnce(&mut R::Mapping)
+    where R: Resources, T: Copy, S: FnOnce(&mut R::Mapping) -> Result<(), Error>
 {
     let mut mapping = try!(take_access_checked(memory::WRITE, buffer));
-    sync(&mut mapping);
+    try!(sync(&mut mapping));
+    mapping.raw.validate_write();
 
     Ok(Writer {
         slice: mapping.mut_slice(buffer.len::<T>()),
         mapping: mapping,
+        flushed: false,
+    })
+}
+
+/// Take the mapping of `buffer` for writing, without any access tracking.
+/// `sync` only checks that the buffer stays mapped.
+#[doc(hidden)]
+pub unsafe fn unsynchronized<R, T, S>(buffer: &buffer::Raw<R>, sync: S)
+                                      -> Result<Unsynchronized<R, T>, Error>
+    where R: Resources, T: Copy, S: FnOnce(&mut R::Mapping) -> Result<(), Error>
+{
+    let usage = buffer.get_info().usage;
+    if usage != memory::Usage::Upload {
+        return Err(Error::InvalidAccess(memory::WRITE, usage));
+    }
+    let raw = match buffer.mapping() {
+        Some(raw) => raw,
+        None => return Err(Error::MapFailed),
+    };
+    let mapping = raw.use_access();
+    try!(sync(mapping));
+    raw.validate_write();
+
+    Ok(Unsynchronized {
+        slice: mapping.mut_slice(buffer.len::<T>()),
+        raw: raw,
+        flushed: false,
+        _not_send: PhantomData,
     })
 }
 
+/// Take the mapping of `buffer` for the duration of `f`, regardless of its usage,
+/// so that the backend can change how it is mapped.
+#[doc(hidden)]
+pub unsafe fn with_access<R, F>(buffer: &buffer::Raw<R>, f: F) -> Result<(), Error>
+    where R: Resources, F: FnOnce(&mut R::Mapping) -> Result<(), Error>
+{
+    let mut mapping = match buffer.mapping() {
+        Some(raw) => try!(Guard::new(raw)),
+        None => return Err(Error::MapFailed),
+    };
+    f(&mut mapping)
+}
+
 /// Mapping reader
 pub struct Reader<'a, R: Resources, T: 'a + Copy> {
     slice: &'a [T],
@@ -191,12 +312,168 @@ impl<'a, R: Resources, T: 'a + Copy> Deref for Reader<'a, R, T> {
     fn deref(&self) -> &[T] { self.slice }
 }
 
+/// Reader of a mapped texture image, whose rows are `row_pitch` elements apart
+/// in memory. The rows can be padded by the backend, so they are best accessed
+/// with `row` and `rows` rather than as a contiguous slice.
+pub struct TextureReader<'a, R: Resources, T: 'a + Copy> {
+    slice: &'a [T],
+    width: usize,
+    height: usize,
+    row_pitch: usize,
+    release: Box<FnMut() + 'a>,
+    _texture: PhantomData<&'a handle::RawTexture<R>>,
+}
+
+impl<'a, R: Resources, T: 'a + Copy> TextureReader<'a, R, T> {
+    /// Wrap the mapped memory of an image, `release` unmapping it once the reader is dropped.
+    #[doc(hidden)]
+    pub unsafe fn new(pointer: *const T, width: usize, height: usize, row_pitch: usize,
+                      release: Box<FnMut() + 'a>) -> Self {
+        assert!(row_pitch >= width, "The rows of the image can not overlap");
+        let len = if height == 0 { 0 } else { row_pitch * (height - 1) + width };
+        TextureReader {
+            slice: slice::from_raw_parts(pointer, len),
+            width: width,
+            height: height,
+            row_pitch: row_pitch,
+            release: release,
+            _texture: PhantomData,
+        }
+    }
+
+    /// Get the number of elements of each row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get the distance in elements between the starts of two consecutive rows.
+    pub fn row_pitch(&self) -> usize {
+        self.row_pitch
+    }
+
+    /// Get the elements of the `index`-th row, without the padding.
+    pub fn row(&self, index: usize) -> &[T] {
+        let start = index * self.row_pitch;
+        &self.slice[start .. start + self.width]
+    }
+
+    /// Iterate over the rows of the image, without the padding.
+    pub fn rows(&self) -> Rows<T> {
+        Rows {
+            chunks: self.slice.chunks(cmp::max(1, self.row_pitch)),
+            width: self.width,
+        }
+    }
+}
+
+impl<'a, R: Resources, T: 'a + Copy> Drop for TextureReader<'a, R, T> {
+    fn drop(&mut self) {
+        (self.release)();
+    }
+}
+
+/// Iterator over the rows of a `TextureReader`.
+pub struct Rows<'r, T: 'r> {
+    chunks: slice::Chunks<'r, T>,
+    width: usize,
+}
+
+impl<'r, T: 'r> Iterator for Rows<'r, T> {
+    type Item = &'r [T];
+
+    fn next(&mut self) -> Option<&'r [T]> {
+        let width = self.width;
+        self.chunks.next().map(|row| &row[.. width])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+/// Check that `texture` can be mapped for reading, i.e. that it has `Download` usage.
+#[doc(hidden)]
+pub fn check_texture_read<R: Resources>(texture: &handle::RawTexture<R>) -> Result<(), Error> {
+    let usage = texture.get_info().usage;
+    if usage == memory::Usage::Download {
+        Ok(())
+    } else {
+        Err(Error::InvalidAccess(memory::READ, usage))
+    }
+}
+
 /// Mapping writer.
 /// Currently is not possible to make write-only slice so while it is technically possible
 /// to read from Writer, it will lead to an undefined behavior. Please do not read from it.
+///
+/// Unless `flush_range` is called, the whole buffer is considered written when the
+/// writer is dropped.
 pub struct Writer<'a, R: Resources, T: 'a + Copy> {
     slice: &'a mut [T],
-    #[allow(dead_code)] mapping: Guard<'a, R>,
+    mapping: Guard<'a, R>,
+    flushed: bool,
+}
+
+impl<'a, R: Resources, T: 'a + Copy> Writer<'a, R, T> {
+    /// Declare that only the elements from `start` to `end` have been written,
+    /// so that non-coherent mappings don't have to flush the whole buffer.
+    /// Can be called several times for disjoint ranges.
+    pub fn flush_range(&mut self, start: usize, end: usize) -> Result<(), Error> {
+        try!(self.check_range(start, end));
+        let size = mem::size_of::<T>();
+        unsafe { self.mapping.flush(start * size .. end * size) };
+        self.flushed = true;
+        Ok(())
+    }
+
+    /// Copy `data` to the elements starting at `offset`, with a single bounds check.
+    /// This is the fast path for uploading vertex data through a mapping.
+    pub fn copy_from_slice(&mut self, offset: usize, data: &[T]) -> Result<(), Error> {
+        try!(self.check_range(offset, offset.saturating_add(data.len())));
+        unsafe { self.mapping.copy_from(offset, data) };
+        Ok(())
+    }
+
+    /// Turn into an `OwnedWriter`, which keeps the access to the mapping.
+    /// `buffer` has to be the mapped buffer.
+    #[doc(hidden)]
+    pub unsafe fn into_owned(mut self, buffer: handle::RawBuffer<R>) -> OwnedWriter<R, T> {
+        let owned = OwnedWriter {
+            buffer: buffer,
+            pointer: self.slice.as_mut_ptr(),
+            len: self.slice.len(),
+            flushed: self.flushed,
+        };
+        // the access is released by the owned writer
+        mem::forget(self);
+        owned
+    }
+
+    fn check_range(&self, start: usize, end: usize) -> Result<(), Error> {
+        if start <= end && end <= self.slice.len() {
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds {
+                start: start,
+                end: end,
+                len: self.slice.len(),
+            })
+        }
+    }
+}
+
+impl<'a, R: Resources, T: 'a + Copy> Drop for Writer<'a, R, T> {
+    fn drop(&mut self) {
+        if !self.flushed {
+            let size = self.slice.len() * mem::size_of::<T>();
+            unsafe { self.mapping.flush(0 .. size) };
+        }
+    }
 }
 
 impl<'a, R: Resources, T: 'a + Copy> Deref for Writer<'a, R, T> {
@@ -209,11 +486,139 @@ impl<'a, R: Resources, T: 'a + Copy> DerefMut for Writer<'a, R, T> {
     fn deref_mut(&mut self) -> &mut [T] { self.slice }
 }
 
+/// Mapping writer that owns a handle to its buffer instead of borrowing it,
+/// so that it can be sent to another thread, e.g. to fill an upload buffer
+/// from an asset loading thread, see `Factory::write_mapping_owned`.
+///
+/// The buffer is not accessible to the GPU nor to other mappings until the
+/// writer is dropped.
+pub struct OwnedWriter<R: Resources, T: Copy> {
+    buffer: handle::RawBuffer<R>,
+    pointer: *mut T,
+    len: usize,
+    flushed: bool,
+}
+
+unsafe impl<R: Resources, T: Copy + Send> Send for OwnedWriter<R, T> {}
+
+impl<R: Resources, T: Copy> OwnedWriter<R, T> {
+    /// Declare that only the elements from `start` to `end` have been written,
+    /// see `Writer::flush_range`.
+    pub fn flush_range(&mut self, start: usize, end: usize) -> Result<(), Error> {
+        if start > end || end > self.len {
+            return Err(Error::OutOfBounds {
+                start: start,
+                end: end,
+                len: self.len,
+            });
+        }
+        let size = mem::size_of::<T>();
+        unsafe { self.buffer.mapping().unwrap().use_access().flush(start * size .. end * size) };
+        self.flushed = true;
+        Ok(())
+    }
+
+    /// Get the handle of the mapped buffer.
+    pub fn buffer(&self) -> &handle::RawBuffer<R> {
+        &self.buffer
+    }
+}
+
+impl<R: Resources, T: Copy> Drop for OwnedWriter<R, T> {
+    fn drop(&mut self) {
+        let raw = self.buffer.mapping().unwrap();
+        unsafe {
+            if !self.flushed {
+                raw.use_access().flush(0 .. self.len * mem::size_of::<T>());
+            }
+            raw.release_access();
+        }
+    }
+}
+
+impl<R: Resources, T: Copy> Deref for OwnedWriter<R, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.pointer, self.len) }
+    }
+}
+
+impl<R: Resources, T: Copy> DerefMut for OwnedWriter<R, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.pointer, self.len) }
+    }
+}
+
+/// Mapping writer of a persistently mapped buffer, that neither waits for
+/// the GPU nor prevents overlapping accesses, see `Factory::write_mapping_unsynchronized`.
+///
+/// It is meant for streaming data every frame from a single thread, the user being
+/// responsible for not writing to the parts of the buffer that the GPU is still using,
+/// e.g. by waiting on the fences of the previous frames.
+pub struct Unsynchronized<'a, R: Resources, T: 'a + Copy> {
+    slice: &'a mut [T],
+    raw: &'a Raw<R>,
+    flushed: bool,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a, R: Resources, T: 'a + Copy> Unsynchronized<'a, R, T> {
+    /// Declare that only the elements from `start` to `end` have been written,
+    /// see `Writer::flush_range`.
+    pub fn flush_range(&mut self, start: usize, end: usize) -> Result<(), Error> {
+        try!(self.check_range(start, end));
+        let size = mem::size_of::<T>();
+        unsafe { self.raw.use_access().flush(start * size .. end * size) };
+        self.flushed = true;
+        Ok(())
+    }
+
+    /// Copy `data` to the elements starting at `offset`, see `Writer::copy_from_slice`.
+    pub fn copy_from_slice(&mut self, offset: usize, data: &[T]) -> Result<(), Error> {
+        let end = offset.saturating_add(data.len());
+        try!(self.check_range(offset, end));
+        self.slice[offset .. end].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn check_range(&self, start: usize, end: usize) -> Result<(), Error> {
+        if start <= end && end <= self.slice.len() {
+            Ok(())
+        } else {
+            Err(Error::OutOfBounds {
+                start: start,
+                end: end,
+                len: self.slice.len(),
+            })
+        }
+    }
+}
+
+impl<'a, R: Resources, T: 'a + Copy> Drop for Unsynchronized<'a, R, T> {
+    fn drop(&mut self) {
+        if !self.flushed {
+            let size = self.slice.len() * mem::size_of::<T>();
+            unsafe { self.raw.use_access().flush(0 .. size) };
+        }
+    }
+}
+
+impl<'a, R: Resources, T: 'a + Copy> Deref for Unsynchronized<'a, R, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] { &*self.slice }
+}
+
+impl<'a, R: Resources, T: 'a + Copy> DerefMut for Unsynchronized<'a, R, T> {
+    fn deref_mut(&mut self) -> &mut [T] { self.slice }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash)]
 #[doc(hidden)]
 /// A service struct that can be used by backends to track the mapping status
 pub struct Status<R: Resources> {
-    cpu_wrote: bool,
+    cpu_wrote: Option<Range<usize>>,
     gpu_access: Option<handle::Fence<R>>,
 }
 
@@ -221,7 +626,7 @@ pub struct Status<R: Resources> {
 impl<R: Resources> Status<R> {
     pub fn clean() -> Self {
         Status {
-            cpu_wrote: false,
+            cpu_wrote: None,
             gpu_access: None,
         }
     }
@@ -232,11 +637,34 @@ impl<R: Resources> Status<R> {
         self.gpu_access.take().map(wait_fence);
     }
 
+    /// Same as `cpu_access`, but returns `false` instead of waiting
+    /// if the fence of the last GPU access is not signaled yet.
+    pub fn try_cpu_access<F>(&mut self, is_signaled: F) -> bool
+        where F: FnOnce(&handle::Fence<R>) -> bool
+    {
+        let signaled = match self.gpu_access {
+            Some(ref fence) => is_signaled(fence),
+            None => true,
+        };
+        if signaled {
+            self.gpu_access = None;
+        }
+        signaled
+    }
+
     pub fn cpu_write_access<F>(&mut self, wait_fence: F)
         where F: FnOnce(handle::Fence<R>)
     {
         self.cpu_access(wait_fence);
-        self.cpu_wrote = true;
+    }
+
+    /// Record that the CPU wrote to the `range` of bytes, to be flushed before
+    /// the next GPU access.
+    pub fn cpu_wrote(&mut self, range: Range<usize>) {
+        self.cpu_wrote = Some(match self.cpu_wrote.take() {
+            Some(dirty) => cmp::min(dirty.start, range.start) .. cmp::max(dirty.end, range.end),
+            None => range,
+        });
     }
 
     pub fn gpu_access(&mut self, fence: handle::Fence<R>) {
@@ -244,11 +672,93 @@ impl<R: Resources> Status<R> {
     }
 
     pub fn ensure_flushed<F>(&mut self, flush: F)
-        where F: FnOnce()
+        where F: FnOnce(Range<usize>)
     {
-        if self.cpu_wrote {
-            flush();
-            self.cpu_wrote = false;
+        if let Some(range) = self.cpu_wrote.take() {
+            flush(range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Guard, Raw, Status, TextureReader, Unsynchronized, Writer};
+    use std::cell::Cell;
+    use std::marker::PhantomData;
+    use dummy::{DummyMapping, DummyResources};
+
+    #[test]
+    fn test_status_cpu_wrote() {
+        let mut status = Status::<DummyResources>::clean();
+        status.ensure_flushed(|_| panic!("Nothing was written"));
+        status.cpu_wrote(16 .. 32);
+        status.cpu_wrote(0 .. 8);
+        status.cpu_wrote(20 .. 24);
+        let mut flushed = None;
+        status.ensure_flushed(|range| flushed = Some(range));
+        assert_eq!(flushed, Some(0 .. 32));
+        status.ensure_flushed(|_| panic!("Already flushed"));
+    }
+
+    #[test]
+    fn test_writer_out_of_bounds() {
+        let raw = Raw::<DummyResources>::new(DummyMapping);
+        let mut data = [0u32; 4];
+        let mut writer = Writer {
+            slice: &mut data[..],
+            mapping: Guard::new(&raw).unwrap(),
+            flushed: false,
+        };
+        assert_eq!(writer.check_range(0, 4), Ok(()));
+        assert_eq!(writer.check_range(4, 4), Ok(()));
+        assert_eq!(writer.flush_range(2, 5), Err(Error::OutOfBounds { start: 2, end: 5, len: 4 }));
+        assert_eq!(writer.flush_range(3, 1), Err(Error::OutOfBounds { start: 3, end: 1, len: 4 }));
+        assert_eq!(writer.copy_from_slice(3, &[1, 2]), Err(Error::OutOfBounds { start: 3, end: 5, len: 4 }));
+        assert_eq!(writer.copy_from_slice(!0, &[1]), Err(Error::OutOfBounds { start: !0, end: !0, len: 4 }));
+        assert!(!writer.flushed);
+    }
+
+    #[test]
+    fn test_unsynchronized_copy_from_slice() {
+        let raw = Raw::<DummyResources>::new(DummyMapping);
+        let mut data = [0u32; 4];
+        {
+            let mut writer = Unsynchronized {
+                slice: &mut data[..],
+                raw: &raw,
+                flushed: false,
+                _not_send: PhantomData,
+            };
+            assert_eq!(writer.copy_from_slice(1, &[1, 2]), Ok(()));
+            assert_eq!(writer.copy_from_slice(3, &[3, 4]), Err(Error::OutOfBounds { start: 3, end: 5, len: 4 }));
+            assert_eq!(writer.flush_range(1, 3), Ok(()));
         }
+        assert_eq!(data, [0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn test_texture_reader_rows() {
+        let data = [1u8, 2, 0, 0, 3, 4, 0, 0, 5, 6];
+        let released = Cell::new(false);
+        {
+            let reader = unsafe {
+                TextureReader::<DummyResources, _>::new(data.as_ptr(), 2, 3, 4, Box::new(|| released.set(true)))
+            };
+            assert_eq!(reader.row(0), &[1, 2]);
+            assert_eq!(reader.row(2), &[5, 6]);
+            assert_eq!(reader.rows().collect::<Vec<_>>(), vec![&[1, 2], &[3, 4], &[5, 6]]);
+            assert!(!released.get());
+        }
+        assert!(released.get());
+    }
+
+    #[test]
+    fn test_texture_reader_empty() {
+        let data: [u8; 0] = [];
+        let reader = unsafe {
+            TextureReader::<DummyResources, _>::new(data.as_ptr(), 4, 0, 8, Box::new(|| ()))
+        };
+        assert_eq!(reader.height(), 0);
+        assert_eq!(reader.rows().count(), 0);
     }
 }
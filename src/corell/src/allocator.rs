@@ -0,0 +1,569 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Memory allocators, placing several resources in each heap.
+//!
+//! Devices only support a limited number of heap allocations, so resources are sub-allocated
+//! from larger heaps: long-lived resources with a `GeneralAllocator`, and transient resources,
+//! released all at once, with a `LinearAllocator`.
+
+use std::ops::Range;
+use memory::{MemoryProperties, MemoryType, OutOfMemory, Requirements};
+use {buffer, format, image, Factory, Limits, Resources};
+
+/// Default size in bytes of the heaps allocated by a `GeneralAllocator`.
+pub const DEFAULT_BLOCK_SIZE: u64 = 64 << 20;
+
+fn align(offset: u64, alignment: u64) -> u64 {
+    if alignment <= 1 { offset } else { (offset + alignment - 1) / alignment * alignment }
+}
+
+/// Pad the `requirements` to whole pages of `granularity` bytes, so that a buffer
+/// and an image placed next to each other never share a page.
+fn pad_to_granularity(requirements: &Requirements, granularity: u64) -> Requirements {
+    Requirements {
+        size: align(requirements.size, granularity),
+        alignment: if requirements.alignment > granularity { requirements.alignment } else { granularity },
+        type_mask: requirements.type_mask,
+    }
+}
+
+/// Return the first memory type supported by the `requirements`,
+/// having at least the `properties`.
+pub fn find_memory_type<'a>(types: &'a [MemoryType], requirements: &Requirements,
+                            properties: MemoryProperties) -> Option<&'a MemoryType> {
+    types.iter().find(|ty| requirements.supports(ty) && ty.properties.contains(properties))
+}
+
+/// Ranges of a heap which are not allocated.
+#[derive(Clone, Debug)]
+pub struct FreeList {
+    // Sorted ranges, never adjacent to each other
+    free: Vec<Range<u64>>,
+}
+
+impl FreeList {
+    /// Create a free list covering `size` bytes.
+    pub fn new(size: u64) -> FreeList {
+        FreeList {
+            free: if size > 0 { vec![0 .. size] } else { Vec::new() },
+        }
+    }
+
+    /// Allocate `size` bytes aligned to `alignment`, in the first free range large enough.
+    pub fn allocate(&mut self, size: u64, alignment: u64) -> Option<Range<u64>> {
+        let position = self.free.iter().position(|range| align(range.start, alignment) + size <= range.end);
+        position.map(|i| {
+            let range = self.free.remove(i);
+            let start = align(range.start, alignment);
+            let end = start + size;
+            if end < range.end {
+                self.free.insert(i, end .. range.end);
+            }
+            if range.start < start {
+                self.free.insert(i, range.start .. start);
+            }
+            start .. end
+        })
+    }
+
+    /// Give a range returned by `allocate` back to the list.
+    pub fn free(&mut self, range: Range<u64>) {
+        if range.start == range.end {
+            return;
+        }
+        let i = self.free.iter().position(|free| free.start > range.start).unwrap_or(self.free.len());
+        let merge_prev = i > 0 && self.free[i - 1].end == range.start;
+        let merge_next = i < self.free.len() && self.free[i].start == range.end;
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                let next = self.free.remove(i);
+                self.free[i - 1].end = next.end;
+            }
+            (true, false) => self.free[i - 1].end = range.end,
+            (false, true) => self.free[i].start = range.start,
+            (false, false) => self.free.insert(i, range),
+        }
+    }
+
+    /// Return whether nothing is allocated from a list of `size` bytes.
+    pub fn is_empty(&self, size: u64) -> bool {
+        self.free.len() == 1 && self.free[0] == (0 .. size)
+    }
+}
+
+/// Memory of a resource allocated by a `GeneralAllocator`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Allocation {
+    memory_type: usize,
+    block: usize,
+    range: Range<u64>,
+}
+
+impl Allocation {
+    /// Return the range of bytes allocated in the heap.
+    pub fn range(&self) -> Range<u64> {
+        self.range.clone()
+    }
+}
+
+struct Block<R: Resources> {
+    heap: R::Heap,
+    size: u64,
+    free: FreeList,
+}
+
+/// Allocator for long-lived resources, which are freed individually.
+///
+/// Heaps of `block_size` bytes are allocated for each memory type as needed, and resources
+/// are placed in their free ranges. Resources larger than a block get a dedicated heap.
+/// Buffers and images share the blocks, so allocations are padded to the
+/// `Limits::buffer_image_granularity` of the device.
+pub struct GeneralAllocator<R: Resources> {
+    memory_types: Vec<MemoryType>,
+    block_size: u64,
+    granularity: u64,
+    // Blocks of each memory type, `None` once freed
+    blocks: Vec<Vec<Option<Block<R>>>>,
+}
+
+impl<R: Resources> GeneralAllocator<R> {
+    /// Create an allocator for the `memory_types` and `limits` of a device,
+    /// see `Device::get_memory_types` and `Device::get_limits`.
+    pub fn new(memory_types: &[MemoryType], limits: &Limits, block_size: u64) -> GeneralAllocator<R> {
+        GeneralAllocator {
+            memory_types: memory_types.to_vec(),
+            block_size: block_size,
+            granularity: limits.buffer_image_granularity,
+            blocks: memory_types.iter().map(|_| Vec::new()).collect(),
+        }
+    }
+
+    /// Allocate memory satisfying the `requirements`, of a memory type with the `properties`.
+    pub fn allocate<F>(&mut self, factory: &mut F, requirements: &Requirements, properties: MemoryProperties)
+                       -> Result<Allocation, OutOfMemory>
+        where F: Factory<R>
+    {
+        let requirements = &pad_to_granularity(requirements, self.granularity);
+        let memory_type = match find_memory_type(&self.memory_types, requirements, properties) {
+            Some(ty) => *ty,
+            None => return Err(OutOfMemory),
+        };
+        let blocks = &mut self.blocks[memory_type.id];
+
+        for (i, block) in blocks.iter_mut().enumerate() {
+            if let Some(ref mut block) = *block {
+                if let Some(range) = block.free.allocate(requirements.size, requirements.alignment) {
+                    return Ok(Allocation { memory_type: memory_type.id, block: i, range: range });
+                }
+            }
+        }
+
+        let size = if requirements.size > self.block_size { requirements.size } else { self.block_size };
+        let mut block = Block {
+            heap: try!(factory.allocate_memory(&memory_type, size)),
+            size: size,
+            free: FreeList::new(size),
+        };
+        let range = block.free.allocate(requirements.size, requirements.alignment).unwrap();
+        let index = match blocks.iter().position(|block| block.is_none()) {
+            Some(i) => { blocks[i] = Some(block); i }
+            None => { blocks.push(Some(block)); blocks.len() - 1 }
+        };
+        Ok(Allocation { memory_type: memory_type.id, block: index, range: range })
+    }
+
+    /// Free an `allocation`. Its resource must not be used anymore.
+    /// Heaps left empty are kept for later allocations, see `release_empty_heaps`.
+    pub fn free(&mut self, allocation: Allocation) {
+        let block = self.blocks[allocation.memory_type][allocation.block].as_mut()
+                                                                         .expect("Allocation already freed");
+        block.free.free(allocation.range);
+    }
+
+    /// Return the heap of an `allocation`, to map it for example.
+    pub fn heap(&self, allocation: &Allocation) -> &R::Heap {
+        &self.blocks[allocation.memory_type][allocation.block].as_ref().unwrap().heap
+    }
+
+    /// Stop tracking the heaps without any allocation, returning them.
+    pub fn release_empty_heaps(&mut self) -> Vec<R::Heap> {
+        let mut heaps = Vec::new();
+        for blocks in &mut self.blocks {
+            for slot in blocks.iter_mut() {
+                let empty = match *slot {
+                    Some(ref block) => block.free.is_empty(block.size),
+                    None => false,
+                };
+                if empty {
+                    heaps.push(slot.take().unwrap().heap);
+                }
+            }
+        }
+        heaps
+    }
+
+    /// Create a buffer and bind it to newly allocated memory.
+    pub fn create_buffer<F>(&mut self, factory: &mut F, size: u64, usage: buffer::Usage,
+                            properties: MemoryProperties) -> Result<(R::Buffer, Allocation), OutOfMemory>
+        where F: Factory<R>
+    {
+        let buffer = factory.create_buffer(size, usage);
+        let requirements = factory.get_buffer_requirements(&buffer);
+        let allocation = match self.allocate(factory, &requirements, properties) {
+            Ok(allocation) => allocation,
+            Err(e) => {
+                factory.destroy_unbound_buffer(buffer);
+                return Err(e);
+            }
+        };
+        let buffer = factory.bind_buffer_memory(self.heap(&allocation), allocation.range.start, buffer);
+        Ok((buffer, allocation))
+    }
+
+    /// Create an image and bind it to newly allocated memory.
+    pub fn create_image<F>(&mut self, factory: &mut F, kind: image::Kind, levels: image::Level,
+                           format: format::Format, usage: image::Usage, properties: MemoryProperties)
+                           -> Result<(R::Image, Allocation), OutOfMemory>
+        where F: Factory<R>
+    {
+        let image = factory.create_image(kind, levels, format, usage);
+        let requirements = factory.get_image_requirements(&image);
+        let allocation = match self.allocate(factory, &requirements, properties) {
+            Ok(allocation) => allocation,
+            Err(e) => {
+                factory.destroy_unbound_image(image);
+                return Err(e);
+            }
+        };
+        let image = factory.bind_image_memory(self.heap(&allocation), allocation.range.start, image);
+        Ok((image, allocation))
+    }
+}
+
+/// Allocator for transient resources, placed one after the other in a single heap,
+/// and all released at once with `reset`. Like with a `GeneralAllocator`, allocations
+/// are padded to the `Limits::buffer_image_granularity` of the device.
+pub struct LinearAllocator<R: Resources> {
+    heap: R::Heap,
+    memory_type: MemoryType,
+    size: u64,
+    offset: u64,
+    granularity: u64,
+}
+
+impl<R: Resources> LinearAllocator<R> {
+    /// Create an allocator over a new heap of `size` bytes of the `memory_type`.
+    pub fn new<F>(factory: &mut F, memory_type: &MemoryType, limits: &Limits, size: u64)
+                  -> Result<LinearAllocator<R>, OutOfMemory>
+        where F: Factory<R>
+    {
+        Ok(LinearAllocator {
+            heap: try!(factory.allocate_memory(memory_type, size)),
+            memory_type: *memory_type,
+            size: size,
+            offset: 0,
+            granularity: limits.buffer_image_granularity,
+        })
+    }
+
+    /// Return the heap of the allocator.
+    pub fn heap(&self) -> &R::Heap {
+        &self.heap
+    }
+
+    /// Allocate memory satisfying the `requirements` after the previous allocations,
+    /// returning its offset in the heap.
+    pub fn allocate(&mut self, requirements: &Requirements) -> Result<u64, OutOfMemory> {
+        let requirements = pad_to_granularity(requirements, self.granularity);
+        if !requirements.supports(&self.memory_type) {
+            return Err(OutOfMemory);
+        }
+        let start = align(self.offset, requirements.alignment);
+        if start + requirements.size > self.size {
+            return Err(OutOfMemory);
+        }
+        self.offset = start + requirements.size;
+        Ok(start)
+    }
+
+    /// Release all the allocations. Their resources must not be used anymore.
+    pub fn reset(&mut self) {
+        self.offset = 0;
+    }
+
+    /// Create a buffer and bind it to the next memory of the heap.
+    pub fn create_buffer<F>(&mut self, factory: &mut F, size: u64, usage: buffer::Usage)
+                            -> Result<R::Buffer, OutOfMemory>
+        where F: Factory<R>
+    {
+        let buffer = factory.create_buffer(size, usage);
+        let requirements = factory.get_buffer_requirements(&buffer);
+        let offset = match self.allocate(&requirements) {
+            Ok(offset) => offset,
+            Err(e) => {
+                factory.destroy_unbound_buffer(buffer);
+                return Err(e);
+            }
+        };
+        Ok(factory.bind_buffer_memory(&self.heap, offset, buffer))
+    }
+
+    /// Create an image and bind it to the next memory of the heap.
+    pub fn create_image<F>(&mut self, factory: &mut F, kind: image::Kind, levels: image::Level,
+                           format: format::Format, usage: image::Usage) -> Result<R::Image, OutOfMemory>
+        where F: Factory<R>
+    {
+        let image = factory.create_image(kind, levels, format, usage);
+        let requirements = factory.get_image_requirements(&image);
+        let offset = match self.allocate(&requirements) {
+            Ok(offset) => offset,
+            Err(e) => {
+                factory.destroy_unbound_image(image);
+                return Err(e);
+            }
+        };
+        Ok(factory.bind_image_memory(&self.heap, offset, image))
+    }
+}
+
+/// Identifier of a resource placed in a `TransientHeap`.
+pub type TransientId = usize;
+
+struct Transient {
+    range: Range<u64>,
+    lifetime: Range<usize>,
+}
+
+fn overlaps<T: PartialOrd>(a: &Range<T>, b: &Range<T>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Heap shared by transient attachments, like G-buffers or bloom chains, which are only
+/// used during a range of the passes of a frame.
+///
+/// Each resource is given the `lifetime` of the passes using it, and resources whose
+/// lifetimes don't overlap are placed in the same memory. The content of an aliased
+/// resource is lost when another one is written, so it has to be transitioned from
+/// `ImageLayout::Undefined` at the beginning of its lifetime.
+pub struct TransientHeap<R: Resources> {
+    heap: R::Heap,
+    memory_type: MemoryType,
+    size: u64,
+    resources: Vec<Transient>,
+}
+
+impl<R: Resources> TransientHeap<R> {
+    /// Create a heap of `size` bytes of the `memory_type`. Memory types with the
+    /// `LAZILY_ALLOCATED` property suit attachments created with `image::TRANSIENT_ATTACHMENT`.
+    pub fn new<F>(factory: &mut F, memory_type: &MemoryType, size: u64) -> Result<TransientHeap<R>, OutOfMemory>
+        where F: Factory<R>
+    {
+        Ok(TransientHeap {
+            heap: try!(factory.allocate_memory(memory_type, size)),
+            memory_type: *memory_type,
+            size: size,
+            resources: Vec::new(),
+        })
+    }
+
+    /// Return the heap shared by the resources.
+    pub fn heap(&self) -> &R::Heap {
+        &self.heap
+    }
+
+    /// Place a resource used by the passes of `lifetime`, returning its id and offset in the heap.
+    /// Its memory only overlaps the memory of resources with disjoint lifetimes.
+    pub fn allocate(&mut self, requirements: &Requirements, lifetime: Range<usize>)
+                    -> Result<(TransientId, u64), OutOfMemory>
+    {
+        if !requirements.supports(&self.memory_type) {
+            return Err(OutOfMemory);
+        }
+
+        let mut used = self.resources.iter()
+                                     .filter(|resource| overlaps(&resource.lifetime, &lifetime))
+                                     .map(|resource| resource.range.clone())
+                                     .collect::<Vec<_>>();
+        used.sort_by_key(|range| range.start);
+
+        // First gap between the memory of the live resources which is large enough
+        let mut start = 0;
+        for range in &used {
+            if align(start, requirements.alignment) + requirements.size <= range.start {
+                break;
+            }
+            if range.end > start {
+                start = range.end;
+            }
+        }
+        let start = align(start, requirements.alignment);
+        if start + requirements.size > self.size {
+            return Err(OutOfMemory);
+        }
+
+        self.resources.push(Transient {
+            range: start .. start + requirements.size,
+            lifetime: lifetime,
+        });
+        Ok((self.resources.len() - 1, start))
+    }
+
+    /// Create an image used by the passes of `lifetime`, and bind it to the heap.
+    pub fn create_image<F>(&mut self, factory: &mut F, kind: image::Kind, levels: image::Level,
+                           format: format::Format, usage: image::Usage, lifetime: Range<usize>)
+                           -> Result<(R::Image, TransientId), OutOfMemory>
+        where F: Factory<R>
+    {
+        let image = factory.create_image(kind, levels, format, usage);
+        let requirements = factory.get_image_requirements(&image);
+        let (id, offset) = match self.allocate(&requirements, lifetime) {
+            Ok(placement) => placement,
+            Err(e) => {
+                factory.destroy_unbound_image(image);
+                return Err(e);
+            }
+        };
+        Ok((factory.bind_image_memory(&self.heap, offset, image), id))
+    }
+
+    /// Check that the resource `id` is used by the `pass` of its lifetime, and that no resource
+    /// sharing its memory is alive at the same time. Misuses are logged and return `false`.
+    pub fn validate_use(&self, id: TransientId, pass: usize) -> bool {
+        let resource = &self.resources[id];
+        if pass < resource.lifetime.start || pass >= resource.lifetime.end {
+            error!("Transient resource {} used by pass {}, outside of its lifetime {:?}",
+                   id, pass, resource.lifetime);
+            return false;
+        }
+        let aliased = self.resources.iter().enumerate().find(|&(other_id, other)| {
+            other_id != id && overlaps(&other.range, &resource.range) &&
+            pass >= other.lifetime.start && pass < other.lifetime.end
+        });
+        match aliased {
+            Some((other_id, _)) => {
+                error!("Transient resources {} and {} share memory and are both alive in pass {}",
+                       id, other_id, pass);
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Forget all the resources placed in the heap, which must not be used anymore.
+    pub fn reset(&mut self) {
+        self.resources.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FreeList, Transient, TransientHeap};
+    use memory::{MemoryProperties, MemoryType, OutOfMemory, Requirements};
+    use TestResources;
+
+    fn transient_heap(size: u64) -> TransientHeap<TestResources> {
+        TransientHeap {
+            heap: 0,
+            memory_type: MemoryType { id: 1, properties: MemoryProperties::empty(), heap_index: 0 },
+            size: size,
+            resources: Vec::new(),
+        }
+    }
+
+    fn requirements(size: u64) -> Requirements {
+        Requirements { size: size, alignment: 16, type_mask: 0b10 }
+    }
+
+    #[test]
+    fn test_free_list_allocate() {
+        let mut list = FreeList::new(100);
+        assert_eq!(list.allocate(10, 1), Some(0 .. 10));
+        assert_eq!(list.allocate(10, 16), Some(16 .. 26));
+        assert_eq!(list.free, vec![10 .. 16, 26 .. 100]);
+        assert_eq!(list.allocate(6, 1), Some(10 .. 16));
+        assert_eq!(list.allocate(80, 1), None);
+        assert_eq!(list.allocate(74, 1), Some(26 .. 100));
+        assert!(list.free.is_empty());
+        assert_eq!(FreeList::new(0).allocate(1, 1), None);
+    }
+
+    #[test]
+    fn test_free_list_merge() {
+        let mut list = FreeList::new(30);
+        let a = list.allocate(10, 1).unwrap();
+        let b = list.allocate(10, 1).unwrap();
+        let c = list.allocate(10, 1).unwrap();
+        list.free(a);
+        list.free(c);
+        assert_eq!(list.free, vec![0 .. 10, 20 .. 30]);
+        list.free(b);
+        assert_eq!(list.free, vec![0 .. 30]);
+        assert!(list.is_empty(30));
+
+        let mut list = FreeList::new(30);
+        let a = list.allocate(10, 1).unwrap();
+        let b = list.allocate(10, 1).unwrap();
+        list.free(b);
+        assert_eq!(list.free, vec![10 .. 30]);
+        list.free(a);
+        assert!(list.is_empty(30));
+    }
+
+    #[test]
+    fn test_free_list_empty_range() {
+        let mut list = FreeList::new(30);
+        let a = list.allocate(10, 1).unwrap();
+        list.free(5 .. 5);
+        list.free(10 .. 10);
+        assert_eq!(list.free, vec![10 .. 30]);
+        list.free(a);
+        assert!(list.is_empty(30));
+    }
+
+    #[test]
+    fn test_transient_allocate() {
+        let mut heap = transient_heap(256);
+        assert_eq!(heap.allocate(&requirements(64), 0 .. 2), Ok((0, 0)));
+        // alive at the same time as the first one
+        assert_eq!(heap.allocate(&requirements(60), 1 .. 3), Ok((1, 64)));
+        // only alive after the first one, sharing its memory
+        assert_eq!(heap.allocate(&requirements(64), 2 .. 4), Ok((2, 0)));
+        assert_eq!(heap.allocate(&requirements(160), 0 .. 4), Err(OutOfMemory));
+        assert_eq!(heap.allocate(&requirements(128), 0 .. 4), Ok((3, 128)));
+        let other_type = Requirements { type_mask: 0b01, .. requirements(16) };
+        assert_eq!(heap.allocate(&other_type, 4 .. 5), Err(OutOfMemory));
+
+        heap.reset();
+        assert_eq!(heap.allocate(&requirements(256), 0 .. 4), Ok((0, 0)));
+    }
+
+    #[test]
+    fn test_transient_validate_use() {
+        let mut heap = transient_heap(256);
+        let (a, _) = heap.allocate(&requirements(64), 0 .. 2).unwrap();
+        let (b, _) = heap.allocate(&requirements(64), 2 .. 4).unwrap();
+        assert!(heap.validate_use(a, 1));
+        assert!(heap.validate_use(b, 2));
+        assert!(!heap.validate_use(a, 2));
+        assert!(!heap.validate_use(b, 1));
+
+        // placed by hand over the memory of `b` while it is alive
+        heap.resources.push(Transient { range: 32 .. 96, lifetime: 3 .. 5 });
+        assert!(heap.validate_use(b, 2));
+        assert!(!heap.validate_use(b, 3));
+        assert!(!heap.validate_use(2, 3));
+        assert!(heap.validate_use(2, 4));
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Buffer related structures.
+
+bitflags!(
+    /// Ways a buffer can be used by the GPU.
+    pub flags Usage: u16 {
+        const TRANSFER_SRC = 0x1,
+        const TRANSFER_DST = 0x2,
+        const CONSTANT     = 0x4,
+        const STORAGE      = 0x8,
+        const INDEX        = 0x10,
+        const VERTEX       = 0x20,
+        const INDIRECT     = 0x40,
+        /// Read by shaders through buffer views, as formatted texels.
+        const UNIFORM_TEXEL = 0x80,
+        /// Read and written by shaders through buffer views, as formatted texels.
+        const STORAGE_TEXEL = 0x100,
+    }
+);
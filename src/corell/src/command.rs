@@ -14,6 +14,314 @@
 
 //! Command Buffer device interface
 
-pub trait CommandBuffer {
+use std::ops::Range;
+use {target, InstanceCount, VertexCount, Resources};
+use memory::{Barrier, Pod};
+use pso::{BindPoint, PipelineStage, VertexBufferSet};
+use query::{QueryControl, QueryResultFlags};
+use shade::StageFlags;
+use image::{Extent, Filter, ImageLayout, Offset, SubresourceLayers, SubresourceRange};
 
+/// Arguments of a single draw of `CommandBuffer::draw_indirect`,
+/// laid out as expected in the argument buffer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(C)]
+pub struct DrawIndirectCommand {
+    pub vertex_count: VertexCount,
+    pub instance_count: InstanceCount,
+    pub first_vertex: VertexCount,
+    pub first_instance: InstanceCount,
+}
+
+/// Arguments of a single draw of `CommandBuffer::draw_indexed_indirect`,
+/// laid out as expected in the argument buffer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(C)]
+pub struct DrawIndexedIndirectCommand {
+    pub index_count: VertexCount,
+    pub instance_count: InstanceCount,
+    pub first_index: VertexCount,
+    pub vertex_offset: i32,
+    pub first_instance: InstanceCount,
+}
+
+/// Arguments of `CommandBuffer::dispatch_indirect`, laid out as expected in the argument buffer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(C)]
+pub struct DispatchIndirectCommand {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+// the arguments are written to buffers with `Factory::write_memory`
+unsafe impl Pod for DrawIndirectCommand {}
+unsafe impl Pod for DrawIndexedIndirectCommand {}
+unsafe impl Pod for DispatchIndirectCommand {}
+
+/// Region of a buffer to buffer copy, in bytes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BufferCopy {
+    pub src: u64,
+    pub dst: u64,
+    pub size: u64,
+}
+
+/// Region of an image to image copy.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ImageCopy {
+    pub src_subresource: SubresourceLayers,
+    pub src_offset: Offset,
+    pub dst_subresource: SubresourceLayers,
+    pub dst_offset: Offset,
+    pub extent: Extent,
+}
+
+/// Region of a multisampled image resolved to a single sampled one.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ImageResolve {
+    pub src_subresource: SubresourceLayers,
+    pub src_offset: Offset,
+    pub dst_subresource: SubresourceLayers,
+    pub dst_offset: Offset,
+    pub extent: Extent,
+}
+
+/// Region of a copy between a buffer and an image.
+/// The depth slices of 3D images are selected by the offset and extent, and the subresource
+/// covers their single array layer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BufferImageCopy {
+    /// Offset of the first texel in the buffer, in bytes.
+    pub buffer_offset: u64,
+    /// Length of the rows in the buffer, in texels. Zero means tightly packed rows.
+    pub buffer_row_length: u32,
+    /// Height of the slices in the buffer, in rows. Zero means tightly packed slices.
+    pub buffer_image_height: u32,
+    pub image_subresource: SubresourceLayers,
+    pub image_offset: Offset,
+    pub image_extent: Extent,
+}
+
+/// Region of an image blit. The bounds are the opposite corners of the source and
+/// destination regions, which don't need to have the same size.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ImageBlit {
+    pub src_subresource: SubresourceLayers,
+    pub src_bounds: [Offset; 2],
+    pub dst_subresource: SubresourceLayers,
+    pub dst_bounds: [Offset; 2],
+}
+
+/// Value of a cleared color target, matching its channel type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClearColor {
+    Float([f32; 4]),
+    Int([i32; 4]),
+    Uint([u32; 4]),
+}
+
+/// Value of a cleared attachment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClearValue {
+    Color(ClearColor),
+    DepthStencil(f32, u32),
+}
+
+/// Attachment of the current subpass, cleared by `CommandBuffer::clear_attachments`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttachmentClear {
+    /// Clear the color attachment at the given index of the subpass.
+    Color(usize, ClearColor),
+    /// Clear the depth and/or stencil of the depth stencil attachment.
+    DepthStencil(Option<f32>, Option<u32>),
+}
+
+/// Where the commands of a subpass are recorded.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SubpassContents {
+    /// In the command buffer of the render pass.
+    Inline,
+    /// In secondary command buffers, executed by the one of the render pass.
+    SecondaryBuffers,
+}
+
+/// Records commands for the GPU, to be submitted to a `CommandQueue`.
+pub trait CommandBuffer<R: Resources> {
+    /// Draw `vertex_count` vertices starting at `first_vertex`.
+    /// The instances are given as `(instance_count, first_instance)`, defaulting to a single one.
+    fn draw(&mut self, first_vertex: VertexCount, vertex_count: VertexCount,
+            instances: Option<(InstanceCount, InstanceCount)>);
+
+    /// Draw `index_count` indices starting at `first_index`, each offset by `vertex_offset`.
+    fn draw_indexed(&mut self, first_index: VertexCount, index_count: VertexCount, vertex_offset: i32,
+                    instances: Option<(InstanceCount, InstanceCount)>);
+
+    /// Issue `draw_count` draws, whose arguments are `DrawIndirectCommand`s read from `buffer`,
+    /// starting at `offset` bytes and `stride` bytes apart.
+    fn draw_indirect(&mut self, buffer: R::Buffer, offset: u64, draw_count: u32, stride: u32);
+
+    /// Same as `draw_indirect`, with `DrawIndexedIndirectCommand` arguments.
+    fn draw_indexed_indirect(&mut self, buffer: R::Buffer, offset: u64, draw_count: u32, stride: u32);
+
+    /// Same as `draw_indirect`, with the number of draws read as a 32 bit value from `count_buffer`
+    /// at `count_offset` bytes, and clamped to `max_draw_count`.
+    /// Requires `Capabilities::draw_indirect_count_supported`.
+    fn draw_indirect_count(&mut self, buffer: R::Buffer, offset: u64, count_buffer: R::Buffer,
+                           count_offset: u64, max_draw_count: u32, stride: u32);
+
+    /// Same as `draw_indirect_count`, with `DrawIndexedIndirectCommand` arguments.
+    fn draw_indexed_indirect_count(&mut self, buffer: R::Buffer, offset: u64, count_buffer: R::Buffer,
+                                   count_offset: u64, max_draw_count: u32, stride: u32);
+
+    /// Run the bound compute pipeline on `x * y * z` work groups, outside of a render pass.
+    /// The storage buffers and images it writes are read by later commands after a barrier
+    /// from the `COMPUTE_SHADER` stage with `memory::SHADER_WRITE` accesses.
+    fn dispatch(&mut self, x: u32, y: u32, z: u32);
+
+    /// Same as `dispatch`, with a `DispatchIndirectCommand` read from `buffer` at `offset` bytes.
+    fn dispatch_indirect(&mut self, buffer: R::Buffer, offset: u64);
+
+    /// Copy the `regions` of `src` to `dst`.
+    /// Regions that are not within both buffers are ignored.
+    fn copy_buffer(&mut self, src: R::Buffer, dst: R::Buffer, regions: &[BufferCopy]);
+
+    /// Copy the `regions` of `src` to `dst`. The images are in the given layouts
+    /// when the command executes, and are left in them once it is done.
+    fn copy_image(&mut self, src: R::Image, src_layout: ImageLayout,
+                  dst: R::Image, dst_layout: ImageLayout, regions: &[ImageCopy]);
+
+    /// Copy the `regions` of the buffer `src` to the image `dst`, which is in `dst_layout`.
+    fn copy_buffer_to_image(&mut self, src: R::Buffer, dst: R::Image, dst_layout: ImageLayout,
+                            regions: &[BufferImageCopy]);
+
+    /// Copy the `regions` of the image `src`, which is in `src_layout`, to the buffer `dst`.
+    fn copy_image_to_buffer(&mut self, src: R::Image, src_layout: ImageLayout, dst: R::Buffer,
+                            regions: &[BufferImageCopy]);
+
+    /// Copy the `regions` of `src` to `dst`, scaling them with the given filter and converting
+    /// between the formats of the images. This is typically used to generate the mipmaps.
+    fn blit_image(&mut self, src: R::Image, src_layout: ImageLayout,
+                  dst: R::Image, dst_layout: ImageLayout,
+                  filter: Filter, regions: &[ImageBlit]);
+
+    /// Resolve the `regions` of the multisampled `src` to the single sampled `dst`, averaging
+    /// the samples of each pixel. The images need to have the same format.
+    fn resolve_image(&mut self, src: R::Image, src_layout: ImageLayout,
+                     dst: R::Image, dst_layout: ImageLayout, regions: &[ImageResolve]);
+
+    /// Insert a barrier, making the commands of the `src_stages` recorded before it complete,
+    /// before the commands of the `dst_stages` recorded after it start.
+    /// The `barriers` additionally describe the memory dependencies and layout transitions.
+    fn pipeline_barrier(&mut self, src_stages: PipelineStage, dst_stages: PipelineStage,
+                        barriers: &[Barrier<R>]);
+
+    /// Transition the `range` of an image from the layout `old` to `new`, waiting for all the
+    /// previous commands to complete. Backends keep track of the layouts the images were
+    /// transitioned to while recording, and transition the subresources last left in another
+    /// layout than the one given to a command using them.
+    fn transition_image(&mut self, image: R::Image, range: SubresourceRange,
+                        old: ImageLayout, new: ImageLayout);
+
+    /// Set the `event` once the commands before it have completed the `stages`.
+    fn set_event(&mut self, event: &R::Event, stages: PipelineStage);
+
+    /// Unset the `event` once the commands before it have completed the `stages`.
+    fn reset_event(&mut self, event: &R::Event, stages: PipelineStage);
+
+    /// Wait for the `events` to be set, before executing the `dst_stages` of the following commands.
+    /// The `src_stages` are the ones given when setting the events, and the `barriers`
+    /// apply between the commands setting the events and the following ones.
+    fn wait_events(&mut self, events: &[&R::Event], src_stages: PipelineStage, dst_stages: PipelineStage,
+                   barriers: &[Barrier<R>]);
+
+    /// Begin the first subpass of `renderpass`, rendering into the attachments of `framebuffer`
+    /// within `render_area`. The attachments loaded with `AttachmentLoadOp::Clear` are cleared
+    /// to the `clear_values` of the same index.
+    fn begin_renderpass(&mut self, renderpass: &R::RenderPass, framebuffer: &R::FrameBuffer,
+                        render_area: target::Rect, clear_values: &[ClearValue], contents: SubpassContents);
+
+    /// Move on to the next subpass of the current render pass.
+    fn next_subpass(&mut self, contents: SubpassContents);
+
+    /// End the current render pass.
+    fn end_renderpass(&mut self);
+
+    /// Clear the `rects` of attachments of the current subpass, inside of a render pass.
+    fn clear_attachments(&mut self, clears: &[AttachmentClear], rects: &[target::Rect]);
+
+    /// Clear the `range` of a color image, which is in `layout`, outside of a render pass.
+    fn clear_color(&mut self, image: R::Image, layout: ImageLayout,
+                   range: SubresourceRange, value: ClearColor);
+
+    /// Clear the depth and/or stencil of the `range` of an image, which is in `layout`,
+    /// outside of a render pass.
+    fn clear_depth_stencil(&mut self, image: R::Image, layout: ImageLayout,
+                           range: SubresourceRange, depth: Option<f32>, stencil: Option<u32>);
+
+    /// Fill `size` bytes of the buffer, starting at `offset`, with copies of `value`.
+    /// Both `offset` and `size` must be multiples of 4, outside of a render pass.
+    fn fill_buffer(&mut self, buffer: R::Buffer, offset: u64, size: u64, value: u32);
+
+    /// Set the depth bias of the following draws, for pipelines with a dynamic `DEPTH_BIAS`.
+    /// The bias is `constant_factor` times the minimal depth difference, plus `slope_factor`
+    /// times the depth slope of the primitive, clamped to `clamp` unless it is zero.
+    fn set_depth_bias(&mut self, constant_factor: f32, clamp: f32, slope_factor: f32);
+
+    /// Set the depth bounds of the following draws, for pipelines with dynamic `DEPTH_BOUNDS`.
+    /// Fragments are discarded if the depth of the attachment is outside of `bounds`.
+    fn set_depth_bounds(&mut self, bounds: Range<f32>);
+
+    /// Set the width of the lines of the following draws, for pipelines with a dynamic `LINE_WIDTH`.
+    fn set_line_width(&mut self, width: f32);
+
+    /// Set the stencil reference values of the front and back faces for the following draws,
+    /// for pipelines with a dynamic `STENCIL_REFERENCE`. Both values must be equal without
+    /// `Capabilities::separate_stencil_masks_supported`.
+    fn set_stencil_reference(&mut self, front: u8, back: u8);
+
+    /// Bind a graphics or compute `pipeline`, used by the following draw or dispatch commands.
+    fn bind_pipeline(&mut self, pipeline: &R::PipelineStateObject);
+
+    /// Bind the vertex buffers `vbs`, starting at the binding `first_binding`.
+    fn bind_vertex_buffers(&mut self, first_binding: u32, vbs: VertexBufferSet<R>);
+
+    /// Bind the descriptor `sets` to the pipelines of `bind_point`, starting at the set `first_set`
+    /// of the `layout`. The `dynamic_offsets` are applied, in order, to the dynamic buffers of the sets.
+    fn bind_descriptor_sets(&mut self, bind_point: BindPoint, layout: &R::PipelineSignature,
+                            first_set: usize, sets: &[&R::DescriptorSet], dynamic_offsets: &[u32]);
+
+    /// Update the push constants of the `stages`, starting at `offset` bytes, with `data`.
+    /// The updated bytes must be within the push constant ranges of the `layout` for these stages.
+    fn push_constants(&mut self, layout: &R::PipelineSignature, stages: StageFlags, offset: u32, data: &[u8]);
+
+    /// Reset the `queries` of the `pool`, before they are used again.
+    fn reset_query_pool(&mut self, pool: &R::QueryPool, queries: Range<u32>);
+
+    /// Begin the `query` of the `pool`, gathering information about the following commands.
+    fn begin_query(&mut self, pool: &R::QueryPool, query: u32, control: QueryControl);
+
+    /// End the `query` of the `pool`.
+    fn end_query(&mut self, pool: &R::QueryPool, query: u32);
+
+    /// Write the time at which all the previous commands completed to the `query` of the `pool`,
+    /// which is a timestamp query pool.
+    fn write_timestamp(&mut self, pool: &R::QueryPool, query: u32);
+
+    /// Copy the results of the `queries` of the `pool` to the `buffer`, starting at `offset` bytes,
+    /// each query `stride` bytes after the previous one.
+    fn copy_query_results(&mut self, pool: &R::QueryPool, queries: Range<u32>, buffer: R::Buffer,
+                          offset: u64, stride: u64, flags: QueryResultFlags);
+
+    /// Skip the following draws, dispatches and attachment clears if the 32 bit value at `offset`
+    /// bytes of `buffer` is zero, or non zero if `inverted` is set, until `end_conditional_rendering`.
+    /// Requires `Capabilities::conditional_rendering_supported`.
+    fn begin_conditional_rendering(&mut self, buffer: R::Buffer, offset: u64, inverted: bool);
+
+    /// End the conditional rendering begun with `begin_conditional_rendering`.
+    fn end_conditional_rendering(&mut self);
+
+    /// Execute the commands recorded in secondary command `buffers`. Within a render pass,
+    /// the current subpass must have been started with `SubpassContents::SecondaryBuffers`.
+    fn execute_commands(&mut self, buffers: &[&Self]) where Self: Sized;
 }
@@ -0,0 +1,115 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Descriptor sets, binding resources to the shaders of a pipeline.
+
+use std::ops::Range;
+use image::ImageLayout;
+use shade::StageFlags;
+use Resources;
+
+/// Type of the descriptors of a binding.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DescriptorType {
+    Sampler,
+    /// Shader resource view of an image, combined with a sampler.
+    CombinedImageSampler,
+    /// Shader resource view of an image, read through a separate sampler.
+    SampledImage,
+    /// Unordered access view of an image, read and written by shaders. The image needs to be
+    /// in the `General` layout, and its format to support `image::STORAGE`.
+    StorageImage,
+    ConstantBuffer,
+    /// Buffer read and written by shaders. Its offset needs to be aligned
+    /// to `Limits::min_storage_buffer_offset_alignment`.
+    StorageBuffer,
+    /// Attachment of the current subpass, read by pixel shaders.
+    InputAttachment,
+    /// Buffer view, read by shaders as formatted texels.
+    UniformTexelBuffer,
+    /// Buffer view, read and written by shaders as formatted texels.
+    StorageTexelBuffer,
+}
+
+/// Binding of a descriptor set layout, holding an array of `count` descriptors
+/// visible to the `stages`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DescriptorSetLayoutBinding {
+    pub binding: usize,
+    pub ty: DescriptorType,
+    pub count: usize,
+    pub stages: StageFlags,
+}
+
+/// Number of descriptors of a type which can be allocated from a descriptor pool.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DescriptorRangeDesc {
+    pub ty: DescriptorType,
+    pub count: usize,
+}
+
+/// Descriptors written to a binding of a set, starting at the `array_offset` element.
+pub struct DescriptorSetWrite<'a, R: Resources> {
+    pub set: &'a R::DescriptorSet,
+    pub binding: usize,
+    pub array_offset: usize,
+    pub write: DescriptorWrite<'a, R>,
+}
+
+/// Descriptors of a `DescriptorSetWrite`, matching the type of the binding.
+pub enum DescriptorWrite<'a, R: Resources> {
+    Sampler(&'a [&'a R::Sampler]),
+    CombinedImageSampler(&'a [(&'a R::Sampler, &'a R::ShaderResourceView, ImageLayout)]),
+    SampledImage(&'a [(&'a R::ShaderResourceView, ImageLayout)]),
+    StorageImage(&'a [(&'a R::UnorderedAccessView, ImageLayout)]),
+    /// Byte ranges of buffers.
+    ConstantBuffer(&'a [(&'a R::Buffer, Range<u64>)]),
+    /// Byte ranges of buffers.
+    StorageBuffer(&'a [(&'a R::Buffer, Range<u64>)]),
+    InputAttachment(&'a [(&'a R::ShaderResourceView, ImageLayout)]),
+    UniformTexelBuffer(&'a [&'a R::BufferView]),
+    StorageTexelBuffer(&'a [&'a R::BufferView]),
+}
+
+impl<'a, R: Resources> DescriptorWrite<'a, R> {
+    /// Return the type of the written descriptors.
+    pub fn ty(&self) -> DescriptorType {
+        match *self {
+            DescriptorWrite::Sampler(_) => DescriptorType::Sampler,
+            DescriptorWrite::CombinedImageSampler(_) => DescriptorType::CombinedImageSampler,
+            DescriptorWrite::SampledImage(_) => DescriptorType::SampledImage,
+            DescriptorWrite::StorageImage(_) => DescriptorType::StorageImage,
+            DescriptorWrite::ConstantBuffer(_) => DescriptorType::ConstantBuffer,
+            DescriptorWrite::StorageBuffer(_) => DescriptorType::StorageBuffer,
+            DescriptorWrite::InputAttachment(_) => DescriptorType::InputAttachment,
+            DescriptorWrite::UniformTexelBuffer(_) => DescriptorType::UniformTexelBuffer,
+            DescriptorWrite::StorageTexelBuffer(_) => DescriptorType::StorageTexelBuffer,
+        }
+    }
+
+    /// Return the number of written descriptors.
+    pub fn count(&self) -> usize {
+        match *self {
+            DescriptorWrite::Sampler(samplers) => samplers.len(),
+            DescriptorWrite::CombinedImageSampler(images) => images.len(),
+            DescriptorWrite::SampledImage(images) |
+            DescriptorWrite::InputAttachment(images) => images.len(),
+            DescriptorWrite::StorageImage(images) => images.len(),
+            DescriptorWrite::ConstantBuffer(buffers) |
+            DescriptorWrite::StorageBuffer(buffers) => buffers.len(),
+            DescriptorWrite::UniformTexelBuffer(views) |
+            DescriptorWrite::StorageTexelBuffer(views) => views.len(),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deferred destruction of resources which may still be used by the GPU.
+//!
+//! Destroying a resource read or written by a pending command buffer is undefined behavior,
+//! and waiting for the queue to be idle stalls the frame. Instead, released resources are
+//! pushed to a `DestructionQueue`, which destroys them once the fence of the frame in which
+//! they were released is signaled.
+
+use std::collections::VecDeque;
+use {Factory, Resources};
+
+/// A resource released by the application, see `DestructionQueue::push`.
+#[derive(Clone, Debug)]
+pub enum Resource<R: Resources> {
+    Heap(R::Heap),
+    Buffer(R::Buffer),
+    Image(R::Image),
+    BufferView(R::BufferView),
+    ShaderResourceView(R::ShaderResourceView),
+    UnorderedAccessView(R::UnorderedAccessView),
+    RenderTargetView(R::RenderTargetView),
+    DepthStencilView(R::DepthStencilView),
+    FrameBuffer(R::FrameBuffer),
+    Sampler(R::Sampler),
+}
+
+impl<R: Resources> Resource<R> {
+    /// Destroy the resource right away.
+    pub fn destroy<F: Factory<R>>(self, factory: &mut F) {
+        match self {
+            Resource::Heap(heap) => factory.destroy_heap(heap),
+            Resource::Buffer(buffer) => factory.destroy_buffer(buffer),
+            Resource::Image(image) => factory.destroy_image(image),
+            Resource::BufferView(view) => factory.destroy_buffer_view(view),
+            Resource::ShaderResourceView(view) => factory.destroy_shader_resource_view(view),
+            Resource::UnorderedAccessView(view) => factory.destroy_unordered_access_view(view),
+            Resource::RenderTargetView(view) => factory.destroy_render_target_view(view),
+            Resource::DepthStencilView(view) => factory.destroy_depth_stencil_view(view),
+            Resource::FrameBuffer(framebuffer) => factory.destroy_framebuffer(framebuffer),
+            Resource::Sampler(sampler) => factory.destroy_sampler(sampler),
+        }
+    }
+}
+
+/// Resources released during the frames in flight, destroyed once these frames complete.
+///
+/// Every frame ends with `end_frame`, given the fence signaled by its last submission.
+/// `cleanup` must be called after waiting for the fence of a frame, and before resetting
+/// it for reuse, otherwise the resources of that frame are only destroyed by `flush`.
+#[derive(Debug)]
+pub struct DestructionQueue<R: Resources> {
+    // Fences of the ended frames, in submission order, with their released resources
+    frames: VecDeque<(R::Fence, Vec<Resource<R>>)>,
+    current: Vec<Resource<R>>,
+}
+
+impl<R: Resources> DestructionQueue<R> {
+    /// Create an empty queue.
+    pub fn new() -> DestructionQueue<R> {
+        DestructionQueue {
+            frames: VecDeque::new(),
+            current: Vec::new(),
+        }
+    }
+
+    /// Release a `resource` which may be used by the commands of the current frame,
+    /// or of the frames still in flight.
+    pub fn push(&mut self, resource: Resource<R>) {
+        self.current.push(resource);
+    }
+
+    /// End the current frame, whose last submission signals the `fence`.
+    pub fn end_frame(&mut self, fence: R::Fence) {
+        let resources = self.current.drain(..).collect();
+        self.frames.push_back((fence, resources));
+    }
+
+    /// Destroy the resources of the frames which have completed, without blocking.
+    pub fn cleanup<F: Factory<R>>(&mut self, factory: &mut F) {
+        for resource in self.pop_completed(|fence| factory.get_fence_status(fence)) {
+            resource.destroy(factory);
+        }
+    }
+
+    // Remove the resources of the oldest frames whose fence is `signaled`, in submission order
+    fn pop_completed<S>(&mut self, mut signaled: S) -> Vec<Resource<R>>
+        where S: FnMut(&R::Fence) -> bool
+    {
+        let mut resources = Vec::new();
+        while self.frames.front().map_or(false, |&(ref fence, _)| signaled(fence)) {
+            resources.extend(self.frames.pop_front().unwrap().1);
+        }
+        resources
+    }
+
+    /// Wait for all the ended frames to complete, and destroy all the released resources,
+    /// including those of the current frame. Used before destroying the device.
+    pub fn flush<F: Factory<R>>(&mut self, factory: &mut F) {
+        if !self.frames.is_empty() {
+            let fences = self.frames.iter().map(|&(ref fence, _)| fence).collect::<Vec<_>>();
+            factory.wait_for_fences(&fences, true, !0);
+        }
+        for (_, resources) in self.frames.drain(..) {
+            for resource in resources {
+                resource.destroy(factory);
+            }
+        }
+        for resource in self.current.drain(..) {
+            resource.destroy(factory);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DestructionQueue, Resource};
+    use TestResources;
+
+    fn buffers(resources: Vec<Resource<TestResources>>) -> Vec<u32> {
+        resources.into_iter().map(|resource| match resource {
+            Resource::Buffer(buffer) => buffer,
+            other => panic!("Unexpected resource {:?}", other),
+        }).collect()
+    }
+
+    #[test]
+    fn test_pop_completed() {
+        let mut queue = DestructionQueue::<TestResources>::new();
+        queue.push(Resource::Buffer(1));
+        queue.push(Resource::Buffer(2));
+        queue.end_frame(10);
+        queue.end_frame(11);
+        queue.push(Resource::Buffer(3));
+        queue.end_frame(12);
+        queue.push(Resource::Buffer(4));
+
+        assert!(queue.pop_completed(|_| false).is_empty());
+        // frames complete in submission order
+        assert!(queue.pop_completed(|&fence| fence == 12).is_empty());
+        assert_eq!(buffers(queue.pop_completed(|&fence| fence <= 11)), vec![1, 2]);
+        assert_eq!(queue.frames.len(), 1);
+        assert_eq!(buffers(queue.pop_completed(|_| true)), vec![3]);
+        assert!(queue.frames.is_empty());
+        // the current frame hasn't ended
+        assert_eq!(buffers(queue.current.drain(..).collect()), vec![4]);
+    }
+}
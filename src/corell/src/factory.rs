@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use {pso, shade};
+use std::{mem, ptr};
+use std::ops::Range;
+use memory::Pod;
+use {buffer, descriptor, format, image, memory, pass, pso, query, shade};
 use {Resources, SubPass};
 
 /// A `Factory` is responsible for creating and managing resources for the backend it was created
@@ -22,19 +25,215 @@ use {Resources, SubPass};
 /// pipelines and textures. See the individual methods for more information.
 #[allow(missing_docs)]
 pub trait Factory<R: Resources> {
-    /// 
-    // fn allocate_memory(&mut self);
+    /// Allocate a heap of `size` bytes of memory of type `memory_type`,
+    /// see `Device::get_memory_types`.
+    fn allocate_memory(&mut self, memory_type: &memory::MemoryType, size: u64)
+        -> Result<R::Heap, memory::OutOfMemory>;
 
-    ///
-    fn create_renderpass(&mut self) -> R::RenderPass;
+    /// Map `range` bytes of the `heap`, which must be of a `CPU_VISIBLE` type, to CPU memory.
+    /// Without `COHERENT` memory, writes are only visible to the GPU once submitted.
+    fn map_memory(&mut self, heap: &R::Heap, range: Range<u64>) -> *mut u8;
 
-    ///
-    fn create_pipeline_signature(&mut self) -> R::PipelineSignature;
+    /// Unmap the mapped range of the `heap`.
+    fn unmap_memory(&mut self, heap: &R::Heap);
 
-    ///
-    fn create_graphics_pipelines<'a>(&mut self, &[(&R::ShaderLib, &R::PipelineSignature, SubPass<'a, R>, &pso::GraphicsPipelineDesc)])
+    /// Create a buffer of `size` bytes, to be bound to memory with `bind_buffer_memory`.
+    fn create_buffer(&mut self, size: u64, usage: buffer::Usage) -> R::UnboundBuffer;
+
+    /// Return the memory requirements of the `buffer`.
+    fn get_buffer_requirements(&mut self, buffer: &R::UnboundBuffer) -> memory::Requirements;
+
+    /// Bind the `buffer` to the memory of the `heap`, starting at `offset` bytes,
+    /// which satisfies the requirements of the buffer.
+    fn bind_buffer_memory(&mut self, heap: &R::Heap, offset: u64, buffer: R::UnboundBuffer) -> R::Buffer;
+
+    /// Create an image with `levels` mipmap levels, to be bound to memory with `bind_image_memory`.
+    fn create_image(&mut self, kind: image::Kind, levels: image::Level, format: format::Format,
+                    usage: image::Usage) -> R::UnboundImage;
+
+    /// Return the memory requirements of the `image`.
+    fn get_image_requirements(&mut self, image: &R::UnboundImage) -> memory::Requirements;
+
+    /// Bind the `image` to the memory of the `heap`, starting at `offset` bytes,
+    /// which satisfies the requirements of the image.
+    fn bind_image_memory(&mut self, heap: &R::Heap, offset: u64, image: R::UnboundImage) -> R::Image;
+
+    /// Create a render pass made of the `subpasses`, which render to the `attachments`.
+    /// Create a view of the mipmap levels and array layers of the `range` of an image, read by
+    /// shaders as an image of the `kind`. Images created with `image::MUTABLE_FORMAT` can be
+    /// viewed with another `format` of the same size, reinterpreting their texels.
+    fn create_shader_resource_view(&mut self, image: &R::Image, kind: image::ViewKind, format: format::Format,
+                                   range: image::SubresourceRange) -> R::ShaderResourceView;
+
+    /// Create a view of the byte `range` of a buffer, accessed by shaders as an array of texels
+    /// of the `format`, for texel buffer descriptors. The buffer needs the `buffer::UNIFORM_TEXEL`
+    /// or `buffer::STORAGE_TEXEL` usage.
+    fn create_buffer_view(&mut self, buffer: &R::Buffer, format: format::Format, range: Range<u64>) -> R::BufferView;
+
+    /// Create a view of the `range` of an image, read and written by shaders,
+    /// see `create_shader_resource_view`.
+    fn create_unordered_access_view(&mut self, image: &R::Image, kind: image::ViewKind, format: format::Format,
+                                    range: image::SubresourceRange) -> R::UnorderedAccessView;
+
+    /// Create a view of the `layers` of an image, used as a color attachment. Views of a single
+    /// layer render to that layer, while views of several layers need layered rendering, where the
+    /// geometry shaders select the layer of each primitive, see `Capabilities::layered_rendering_supported`.
+    fn create_render_target_view(&mut self, image: &R::Image, format: format::Format,
+                                 layers: image::SubresourceLayers) -> R::RenderTargetView;
+
+    /// Create a view of the `layers` of an image, used as a depth stencil attachment,
+    /// see `create_render_target_view`.
+    fn create_depth_stencil_view(&mut self, image: &R::Image, format: format::Format,
+                                 layers: image::SubresourceLayers) -> R::DepthStencilView;
+
+    fn create_renderpass(&mut self, attachments: &[pass::Attachment], subpasses: &[pass::SubpassDesc],
+                         dependencies: &[pass::SubpassDependency]) -> R::RenderPass;
+
+    /// Create a frame buffer, providing the attachments of `renderpass`. The attachments
+    /// are indexed in order, the color attachments first, then the depth stencil ones.
+    fn create_framebuffer(&mut self, renderpass: &R::RenderPass,
+                          color_attachments: &[R::RenderTargetView], depth_stencil_attachments: &[R::DepthStencilView],
+                          width: u32, height: u32, layers: u32) -> R::FrameBuffer;
+
+    /// Create a sampler, reading shader resource views through the filtering and
+    /// addressing described by `info`.
+    fn create_sampler(&mut self, info: image::SamplerInfo) -> R::Sampler;
+
+    /// Create a pool of `count` queries of type `ty`.
+    fn create_query_pool(&mut self, ty: query::QueryType, count: u32) -> R::QueryPool;
+
+    /// Read the results of the `queries` of the `pool`, or `None` if they are not all
+    /// available yet. Timestamps are returned in nanoseconds, pipeline statistics queries
+    /// return one result per statistic, see `query::PipelineStatistics::from_results`.
+    fn get_query_results(&mut self, pool: &R::QueryPool, queries: Range<u32>) -> Option<Vec<u64>>;
+
+    /// Create an event, initially unset, which is set and waited upon by command buffers.
+    fn create_event(&mut self) -> R::Event;
+
+    /// Create a semaphore, ordering the work of queues and swapchains on the device.
+    fn create_semaphore(&mut self) -> R::Semaphore;
+
+    /// Create a fence, signaled by a queue submission and waited upon by the host.
+    fn create_fence(&mut self, signaled: bool) -> R::Fence;
+
+    /// Set the `fences` back to the unsignaled state. They must not be used by a pending submission.
+    fn reset_fences(&mut self, fences: &[&R::Fence]);
+
+    /// Block until all the `fences` are signaled, or only one of them if `wait_all` is not set,
+    /// for at most `timeout_ns` nanoseconds. Return `false` on timeout.
+    fn wait_for_fences(&mut self, fences: &[&R::Fence], wait_all: bool, timeout_ns: u64) -> bool;
+
+    /// Return whether the `fence` is signaled, without blocking.
+    fn get_fence_status(&mut self, fence: &R::Fence) -> bool;
+
+    /// Block until all the submissions to the queues of the device have completed.
+    fn wait_idle(&mut self);
+
+    /// Create a layout of descriptor sets, made of the `bindings`.
+    fn create_descriptor_set_layout(&mut self, bindings: &[descriptor::DescriptorSetLayoutBinding])
+        -> R::DescriptorSetLayout;
+
+    /// Create a pool allocating up to `max_sets` descriptor sets, holding in total
+    /// the number of descriptors of each type given by the `ranges`.
+    fn create_descriptor_pool(&mut self, max_sets: usize, ranges: &[descriptor::DescriptorRangeDesc])
+        -> R::DescriptorPool;
+
+    /// Allocate a descriptor set from the `pool` for each of the `layouts`,
+    /// or `None` if the pool is exhausted.
+    fn allocate_descriptor_sets(&mut self, pool: &mut R::DescriptorPool, layouts: &[&R::DescriptorSetLayout])
+        -> Option<Vec<R::DescriptorSet>>;
+
+    /// Give all the descriptor sets allocated from the `pool` back to it.
+    /// They must not be used by pending command buffers anymore.
+    fn reset_descriptor_pool(&mut self, pool: &mut R::DescriptorPool);
+
+    /// Write descriptors to descriptor sets.
+    /// The sets must not be used by pending command buffers.
+    fn update_descriptor_sets(&mut self, writes: &[descriptor::DescriptorSetWrite<R>]);
+
+    /// Create a pipeline signature, made of the layouts of the descriptor sets bound to the
+    /// pipelines, and of the byte ranges of push constants visible to each set of stages.
+    fn create_pipeline_signature(&mut self, sets: &[&R::DescriptorSetLayout],
+                                 push_constant_ranges: &[(shade::StageFlags, Range<u32>)])
+        -> R::PipelineSignature;
+
+    /// Create a pipeline cache, initialized with `data` previously returned by
+    /// `get_pipeline_cache_data`, or empty. Data from another device or driver is ignored.
+    fn create_pipeline_cache(&mut self, data: &[u8]) -> R::PipelineCache;
+
+    /// Return the content of the `cache`, to be stored on disk and restored on the next run.
+    fn get_pipeline_cache_data(&mut self, cache: &R::PipelineCache) -> Vec<u8>;
+
+    /// Create graphics pipelines, reusing and filling the `cache` if any.
+    fn create_graphics_pipelines<'a>(&mut self, &[(&R::ShaderLib, &R::PipelineSignature, SubPass<'a, R>, &pso::GraphicsPipelineDesc)],
+                                     cache: Option<&R::PipelineCache>)
+            -> Vec<Result<R::PipelineStateObject, pso::CreationError>>;
+
+    /// Create compute pipelines, each running the given entry point of a shader library,
+    /// reusing and filling the `cache` if any.
+    fn create_compute_pipelines(&mut self, &[(&R::ShaderLib, pso::EntryPoint, &R::PipelineSignature)],
+                                cache: Option<&R::PipelineCache>)
             -> Vec<Result<R::PipelineStateObject, pso::CreationError>>;
 
-    ///
-    fn create_compute_pipelines(&mut self) -> Vec<Result<R::PipelineStateObject, pso::CreationError>>;
+    /// Destroy a `heap`, which must not have resources bound to it anymore.
+    /// Resources used by pending command buffers are released through a
+    /// `destroy::DestructionQueue` instead.
+    fn destroy_heap(&mut self, heap: R::Heap);
+
+    /// Destroy a `buffer`, which must not be used by pending command buffers.
+    fn destroy_buffer(&mut self, buffer: R::Buffer);
+
+    /// Destroy an `image`, which must not be used by pending command buffers.
+    fn destroy_image(&mut self, image: R::Image);
+
+    /// Destroy a `buffer` which was never bound to memory.
+    fn destroy_unbound_buffer(&mut self, buffer: R::UnboundBuffer);
+
+    /// Destroy an `image` which was never bound to memory.
+    fn destroy_unbound_image(&mut self, image: R::UnboundImage);
+
+    /// Destroy a buffer `view`, which must not be used by pending command buffers.
+    fn destroy_buffer_view(&mut self, view: R::BufferView);
+
+    /// Destroy a shader resource `view`, which must not be used by pending command buffers.
+    fn destroy_shader_resource_view(&mut self, view: R::ShaderResourceView);
+
+    /// Destroy an unordered access `view`, which must not be used by pending command buffers.
+    fn destroy_unordered_access_view(&mut self, view: R::UnorderedAccessView);
+
+    /// Destroy a render target `view`, which must not be used by pending command buffers.
+    fn destroy_render_target_view(&mut self, view: R::RenderTargetView);
+
+    /// Destroy a depth stencil `view`, which must not be used by pending command buffers.
+    fn destroy_depth_stencil_view(&mut self, view: R::DepthStencilView);
+
+    /// Destroy a `framebuffer`, which must not be used by pending command buffers.
+    fn destroy_framebuffer(&mut self, framebuffer: R::FrameBuffer);
+
+    /// Destroy a `sampler`, which must not be used by pending command buffers.
+    fn destroy_sampler(&mut self, sampler: R::Sampler);
+
+    /// Destroy a `fence`, which must not be used by a pending submission.
+    fn destroy_fence(&mut self, fence: R::Fence);
+
+    /// Create a single compute pipeline, see `create_compute_pipelines`.
+    fn create_compute_pipeline(&mut self, shader_lib: &R::ShaderLib, entry: pso::EntryPoint, signature: &R::PipelineSignature)
+            -> Result<R::PipelineStateObject, pso::CreationError>
+    {
+        self.create_compute_pipelines(&[(shader_lib, entry, signature)], None)
+            .pop()
+            .unwrap_or(Err(pso::CreationError))
+    }
+
+    /// Write `data` to the `heap` starting at `offset` bytes, mapping it during the copy.
+    /// Used to fill constant buffers or the argument buffers of indirect commands, like
+    /// `command::DrawIndirectCommand`, without computing their byte layout.
+    fn write_memory<T: Pod>(&mut self, heap: &R::Heap, offset: u64, data: &[T]) where Self: Sized {
+        let size = data.len() * mem::size_of::<T>();
+        let mapped = self.map_memory(heap, offset .. offset + size as u64);
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr() as *const u8, mapped, size);
+        }
+        self.unmap_memory(heap);
+    }
 }
@@ -21,6 +21,7 @@
 //  ETC2_SRGB, // Use the EXT2 algorithm on 4 components (RGBA) in the sRGB color space.
 //  ETC2_EAC_RGBA8, // Use the EXT2 EAC algorithm on 4 components.
 use memory::Pod;
+use image;
 
 macro_rules! impl_channel_type {
     { $($name:ident = $shader_type:ident [ $($imp_trait:ident),* ] ,)* } => {
@@ -182,6 +183,21 @@ impl Swizzle {
 #[derive(Eq, Ord, PartialEq, PartialOrd, Hash, Copy, Clone, Debug)]
 pub struct Format(pub SurfaceType, pub ChannelType);
 
+/// Operations supported by images of a format on a device, used to pick a depth or HDR format
+/// among several candidates. See `Device::get_format_properties`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FormatProperties {
+    /// Usages supported by optimally tiled images, such as `image::COLOR_ATTACHMENT`
+    /// or `image::STORAGE`. Empty if the format is not supported at all.
+    pub usage: image::Usage,
+    /// Color attachments of the format can be blended.
+    pub blendable: bool,
+    /// Sampled images of the format can be read with linear filtering.
+    pub linear_filtering: bool,
+    /// Numbers of samples supported by 2D attachments of the format, in increasing order.
+    pub samples: Vec<image::NumSamples>,
+}
+
 
 /// Compile-time surface type trait.
 pub trait SurfaceTyped {
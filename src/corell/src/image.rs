@@ -0,0 +1,271 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Image related structures, describing the parts of an image accessed by commands.
+
+use state::Comparison;
+
+/// Mipmap level of an image.
+pub type Level = u8;
+/// Array layer of an image.
+pub type Layer = u16;
+/// Number of samples per pixel of an image, a power of two.
+pub type NumSamples = u8;
+
+/// Dimensions of an image, with the number of array layers of 1D and 2D images.
+/// 2D images can be multisampled, to be rendered with anti-aliasing.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Kind {
+    D1(u32, Layer),
+    D2(u32, u32, Layer, NumSamples),
+    /// Volume of the given width, height and depth. The depth slices are not array layers,
+    /// except for the render target views, see `Capabilities::volume_slice_rendering_supported`.
+    D3(u32, u32, u32),
+    /// Square 2D images of the given size, one for each face of a cube,
+    /// stored as 6 array layers in the order of `CUBE_FACES`.
+    Cube(u32),
+    /// Array of cubes, each taking 6 consecutive array layers.
+    CubeArray(u32, Layer),
+}
+
+impl Kind {
+    /// Return the number of samples per pixel.
+    pub fn num_samples(&self) -> NumSamples {
+        match *self {
+            Kind::D2(_, _, _, samples) => samples,
+            _ => 1,
+        }
+    }
+
+    /// Return whether the image is made of cube faces, which can be viewed as cubes.
+    pub fn is_cube(&self) -> bool {
+        match *self {
+            Kind::Cube(_) | Kind::CubeArray(_, _) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Face of a cube image.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CubeFace {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+/// Faces of a cube, in the order of their array layers.
+pub const CUBE_FACES: [CubeFace; 6] = [
+    CubeFace::PosX, CubeFace::NegX,
+    CubeFace::PosY, CubeFace::NegY,
+    CubeFace::PosZ, CubeFace::NegZ,
+];
+
+impl CubeFace {
+    /// Return the array layer of this face of the cube at index `cube` of an image.
+    /// Views of a single layer are used to sample or render to a single face.
+    pub fn layer(&self, cube: Layer) -> Layer {
+        cube * 6 + *self as Layer
+    }
+}
+
+/// Dimensions of an image as seen by shaders through a shader resource view.
+/// Array views can cover any number of consecutive layers of an image.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ViewKind {
+    D1,
+    D1Array,
+    D2,
+    D2Array,
+    D3,
+    /// 6 consecutive layers of a cube image, sampled with a direction.
+    Cube,
+    /// A multiple of 6 consecutive layers of a cube image.
+    CubeArray,
+}
+
+bitflags!(
+    /// Ways an image can be used by the GPU.
+    pub flags Usage: u16 {
+        const TRANSFER_SRC             = 0x1,
+        const TRANSFER_DST             = 0x2,
+        const COLOR_ATTACHMENT         = 0x4,
+        const DEPTH_STENCIL_ATTACHMENT = 0x8,
+        /// Read by shaders through shader resource views.
+        const SAMPLED                  = 0x10,
+        /// Accessed by shaders through unordered access views.
+        const STORAGE                  = 0x20,
+        /// Attachment whose content only lives during a render pass,
+        /// which may never be backed by memory.
+        const TRANSIENT_ATTACHMENT     = 0x40,
+        /// Viewed with other formats than the one of the image, of the same texel size,
+        /// like the sRGB and UNORM variants of a format.
+        const MUTABLE_FORMAT           = 0x80,
+    }
+);
+
+/// Aspects of the image format accessed by a command.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Aspect {
+    Color,
+    Depth,
+    Stencil,
+    DepthStencil,
+}
+
+/// Layout of the image memory. The layout of an image has to be compatible with
+/// the way it is used by the GPU, and is changed with layout transitions.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ImageLayout {
+    /// Supports all kinds of accesses, but may be less efficient.
+    General,
+    ColorAttachmentOptimal,
+    DepthStencilAttachmentOptimal,
+    DepthStencilReadOnlyOptimal,
+    ShaderReadOnlyOptimal,
+    TransferSrcOptimal,
+    TransferDstOptimal,
+    /// Content of the image is discarded by transitions from this layout.
+    Undefined,
+    Preinitialized,
+    Present,
+}
+
+/// Consecutive array layers of a single mipmap level.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SubresourceLayers {
+    pub aspect: Aspect,
+    pub level: Level,
+    pub base_layer: Layer,
+    pub layer_count: Layer,
+}
+
+/// Consecutive array layers of consecutive mipmap levels.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SubresourceRange {
+    pub aspect: Aspect,
+    pub base_level: Level,
+    pub level_count: Level,
+    pub base_layer: Layer,
+    pub layer_count: Layer,
+}
+
+/// Position of a texel in an image.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Offset {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// Size of a region of an image, in texels.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Extent {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+}
+
+/// Filtering applied when an image is scaled.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Filter {
+    /// Pick the nearest texel.
+    Nearest,
+    /// Linearly interpolate the nearest texels.
+    Linear,
+}
+
+/// Specifies how texture coordinates outside the range `[0, 1]` are handled.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum WrapMode {
+    /// Tile the texture. That is, sample the coordinate modulo `1.0`.
+    Tile,
+    /// Mirror the texture. Like tile, but uses abs(coord) before the modulo.
+    Mirror,
+    /// Clamp the texture to the value at `0.0` or `1.0` respectively.
+    Clamp,
+    /// Use the border color.
+    Border,
+}
+
+/// A wrapper for the LOD level of a texture, in steps of 1/8 of a level.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd)]
+pub struct Lod(i16);
+
+impl From<f32> for Lod {
+    fn from(v: f32) -> Lod {
+        Lod((v * 8.0) as i16)
+    }
+}
+
+impl Into<f32> for Lod {
+    fn into(self) -> f32 {
+        self.0 as f32 / 8.0
+    }
+}
+
+/// Color sampled outside of the image, with the `Border` wrap mode.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BorderColor {
+    TransparentBlack,
+    OpaqueBlack,
+    OpaqueWhite,
+}
+
+/// Specifies how to sample from an image, see `Factory::create_sampler`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SamplerInfo {
+    /// Filter used when the image is minified.
+    pub min_filter: Filter,
+    /// Filter used when the image is magnified.
+    pub mag_filter: Filter,
+    /// Filter used between mipmap levels.
+    pub mip_filter: Filter,
+    /// Wrapping mode for each of the U, V, and W axis.
+    pub wrap_mode: (WrapMode, WrapMode, WrapMode),
+    /// This bias is added to every computed mipmap level (N + lod_bias). For
+    /// example, if it would select mipmap level 2 and lod_bias is 1, it will
+    /// use mipmap level 3.
+    pub lod_bias: Lod,
+    /// This range is used to clamp LOD level used for sampling.
+    pub lod_range: (Lod, Lod),
+    /// Comparison of the sampled depth with a reference value, used by shadow samplers.
+    pub comparison: Option<Comparison>,
+    /// Border color is used when one of the wrap modes is set to border.
+    pub border: BorderColor,
+    /// Maximum anisotropy of the filtering, up to `Limits::max_sampler_anisotropy`,
+    /// or `None` to disable anisotropic filtering. Requires `Capabilities::anisotropy_supported`.
+    pub anisotropy: Option<u8>,
+}
+
+impl SamplerInfo {
+    /// Create a new sampler description with a given filter method and wrapping mode, using no LOD
+    /// modifications.
+    pub fn new(filter: Filter, wrap: WrapMode) -> SamplerInfo {
+        SamplerInfo {
+            min_filter: filter,
+            mag_filter: filter,
+            mip_filter: filter,
+            wrap_mode: (wrap, wrap, wrap),
+            lod_bias: Lod(0),
+            lod_range: (Lod(-8000), Lod(8000)),
+            comparison: None,
+            border: BorderColor::TransparentBlack,
+            anisotropy: None,
+        }
+    }
+}
@@ -12,24 +12,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[macro_use]
+extern crate bitflags;
 #[macro_use]
 extern crate log;
 extern crate draw_state;
 
-use std::fmt::Debug;
+use std::error::Error;
+use std::fmt::{self, Debug};
 use std::hash::Hash;
 use std::any::Any;
 use std::slice::Iter;
 
-pub use draw_state::state;
+pub use draw_state::{state, target};
 pub use self::factory::Factory;
 
+pub mod allocator;
+pub mod buffer;
 pub mod command;
+pub mod descriptor;
+pub mod destroy;
 pub mod factory;
 pub mod format;
+pub mod image;
 pub mod memory;
+pub mod pass;
 pub mod pso;
+pub mod query;
 pub mod shade;
+pub mod validate;
 
 /// Compile-time maximum number of color targets.
 pub const MAX_COLOR_TARGETS: usize = 8; // Limited by D3D12
@@ -101,7 +112,8 @@ pub trait Adapter {
     type Device: Device;
     type QueueFamily: QueueFamily;
 
-    /// Create a new device and command queues.
+    /// Create a new device and command queues, `count` queues for each `(family, count)`
+    /// of `queue_descs`. The queues are returned in the same order.
     fn open<'a, I>(&self, queue_descs: I) -> (Self::Device, Vec<Self::CommandQueue>)
         where I: Iterator<Item=(&'a Self::QueueFamily, u32)>;
 
@@ -125,11 +137,24 @@ pub struct AdapterInfo {
     pub software_rendering: bool,
 }
 
+bitflags!(
+    /// Operations supported by the queues of a family.
+    pub flags QueueType: u8 {
+        const GRAPHICS = 0x1,
+        const COMPUTE  = 0x2,
+        const TRANSFER = 0x4,
+    }
+);
+
 /// `QueueFamily` denotes a group of command queues provided by the backend
 /// with the same properties/type.
 pub trait QueueFamily: 'static {
     type Surface: Surface;
 
+    /// Return the operations supported by the queues of this family.
+    /// Queues supporting graphics or compute operations also support transfers.
+    fn queue_type(&self) -> QueueType;
+
     /// Check if the queue family supports presentation to a surface
     fn supports_present(&self, surface: &Self::Surface) -> bool;
 
@@ -138,15 +163,102 @@ pub trait QueueFamily: 'static {
     fn num_queues(&self) -> u32;
 }
 
+/// Optional features supported by a device.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Capabilities {
+    /// Draws can be skipped depending on a value in a buffer,
+    /// see `CommandBuffer::begin_conditional_rendering`.
+    pub conditional_rendering_supported: bool,
+    /// The number of indirect draws can be read from a buffer,
+    /// see `CommandBuffer::draw_indirect_count`.
+    pub draw_indirect_count_supported: bool,
+    /// Graphics pipelines can have a geometry shader.
+    pub geometry_shader_supported: bool,
+    /// Geometry shaders can select the layer of the attachments each primitive is rendered to,
+    /// in frame buffers with several layers.
+    pub layered_rendering_supported: bool,
+    /// Render target views of 3D images can select depth slices, through their array layers.
+    pub volume_slice_rendering_supported: bool,
+    /// Vertex buffers can be fetched once every several instances,
+    /// with an `InstanceRate` above 1.
+    pub instance_rate_supported: bool,
+    /// Graphics pipelines can have hull and domain shaders, tessellating `Primitive::PatchList`
+    /// primitives of up to `Limits::max_patch_size` vertices.
+    pub tessellation_supported: bool,
+    /// Samplers can filter anisotropically, see `image::SamplerInfo::anisotropy`.
+    pub anisotropy_supported: bool,
+    /// Color targets of a pipeline can have different blend states and write masks.
+    pub independent_blend_supported: bool,
+    /// The first color target can be blended with the second color output of the fragment shader,
+    /// see `pso::BlendFactor`.
+    pub dual_source_blending_supported: bool,
+    /// Logic operations can replace the blending of the color targets, see `pso::LogicOp`.
+    pub logic_op_supported: bool,
+    /// The front and back faces can use different stencil masks and reference values.
+    pub separate_stencil_masks_supported: bool,
+}
+
+/// Limits of a device, which resources and commands have to respect.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Limits {
+    /// Alignment of the offsets of constant buffer descriptors, in bytes.
+    pub min_constant_buffer_offset_alignment: u64,
+    /// Alignment of the offsets of storage buffer descriptors, in bytes.
+    pub min_storage_buffer_offset_alignment: u64,
+    /// Maximum number of vertices in a patch, for tessellation.
+    pub max_patch_size: PatchSize,
+    /// Maximum width and height of 2D images, in pixels.
+    pub max_texture_size: usize,
+    /// Maximum number of color attachments of a subpass.
+    pub max_color_attachments: usize,
+    /// Maximum number of work groups of a dispatch, in each dimension.
+    pub max_compute_group_count: [u32; 3],
+    /// Maximum size of a work group, in each dimension.
+    pub max_compute_group_size: [u32; 3],
+    /// Maximum anisotropy of samplers.
+    pub max_sampler_anisotropy: u8,
+    /// Granularity in bytes of the pages of a heap which buffers and optimally tiled
+    /// images placed next to each other must not share.
+    pub buffer_image_granularity: u64,
+}
+
 pub trait Device {
+    /// Return the optional features supported by the device.
+    fn get_capabilities(&self) -> &Capabilities;
 
+    /// Return the limits of the device.
+    fn get_limits(&self) -> Limits;
+
+    /// Return the types of memory which resources can be bound to.
+    fn get_memory_types(&self) -> &[memory::MemoryType];
+
+    /// Return the size in bytes of each memory heap, indexed by `MemoryType::heap_index`.
+    fn get_memory_heaps(&self) -> &[u64];
+
+    /// Return the usages supported by images of the `format`. Storage images, written by shaders,
+    /// are only supported by some formats.
+    fn get_format_usage(&self, format: format::Format) -> image::Usage;
+
+    /// Return the numbers of samples supported by 2D images of the `format` with the `usage`,
+    /// in increasing order. Single sampled images are always supported.
+    fn get_supported_samples(&self, format: format::Format, usage: image::Usage) -> Vec<image::NumSamples>;
+
+    /// Return the operations supported by images of the `format`, gathering `get_format_usage`
+    /// and the sample counts of attachments with the filtering and blending support.
+    fn get_format_properties(&self, format: format::Format) -> format::FormatProperties;
 }
 
 pub trait CommandQueue {
     type CommandBuffer;
-
-    /// Submits a `CommandBuffer` to the GPU queue for execution.
-    fn submit(&mut self, cmd_buffer: &Self::CommandBuffer);
+    type Semaphore;
+    type Fence;
+
+    /// Submits `CommandBuffer`s to the GPU queue for execution. Their commands reaching the
+    /// given pipeline stages wait for the `wait` semaphores to be signaled. Once they complete,
+    /// the `signal` semaphores and the `fence` are signaled.
+    fn submit(&mut self, cmd_buffers: &[&Self::CommandBuffer],
+              wait: &[(&Self::Semaphore, pso::PipelineStage)], signal: &[&Self::Semaphore],
+              fence: Option<&Self::Fence>);
 }
 
 /// A `Surface` abstracts the surface of a native window, which will be presented
@@ -168,26 +280,118 @@ impl Frame {
     }
 }
 
+/// Status of a successful swapchain operation.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SwapChainStatus {
+    /// The swapchain matches the surface.
+    Optimal,
+    /// The swapchain can still be presented to, but doesn't match the surface anymore,
+    /// after a resize for example, and should be rebuilt.
+    Suboptimal,
+}
+
+/// Error of a swapchain operation.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SwapChainError {
+    /// The surface changed, the swapchain needs to be rebuilt before presenting again.
+    OutOfDate,
+    /// The surface is not available anymore.
+    SurfaceLost,
+    /// The device has been lost.
+    DeviceLost,
+}
+
+impl fmt::Display for SwapChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl Error for SwapChainError {
+    fn description(&self) -> &str {
+        match *self {
+            SwapChainError::OutOfDate => "The swapchain is out of date with its surface",
+            SwapChainError::SurfaceLost => "The surface of the swapchain has been lost",
+            SwapChainError::DeviceLost => "The device has been lost",
+        }
+    }
+}
+
 /// The `SwapChain` is the backend representation of the surface.
 /// It consists of multiple buffers, which will be presented on the surface.
 pub trait SwapChain {
-    fn acquire_frame(&mut self) -> Frame;
-    fn present(&mut self);
+    /// Semaphore of the backend, see `Factory::create_semaphore`.
+    type Semaphore;
+
+    /// Acquire a new frame for rendering. This needs to be called before presenting.
+    /// The frame may still be read by the presentation engine, `signal` is signaled
+    /// once it can be written to.
+    fn acquire_frame(&mut self, signal: Option<&Self::Semaphore>)
+        -> Result<(Frame, SwapChainStatus), SwapChainError>;
+
+    /// Present one acquired frame in FIFO order, once all the `wait` semaphores are signaled.
+    fn present(&mut self, wait: &[&Self::Semaphore]) -> Result<SwapChainStatus, SwapChainError>;
 }
 
 /// Different resource types of a specific API. 
 pub trait Resources:          Clone + Hash + Debug + Any {
+    type Heap:                Clone + Hash + Debug + Any + Send + Sync;
+    type UnboundBuffer:       Clone + Hash + Debug + Any + Send + Sync;
     type Buffer:              Clone + Hash + Debug + Any + Send + Sync + Copy;
+    type UnboundImage:        Clone + Hash + Debug + Any + Send + Sync;
     type ShaderLib:           Clone + Hash + Debug + Any + Send + Sync;
     type RenderPass:          Clone + Hash + Debug + Any + Send + Sync;
+    type FrameBuffer:         Clone + Hash + Debug + Any + Send + Sync;
     type PipelineSignature:   Clone + Hash + Debug + Any + Send + Sync;
     type PipelineStateObject: Clone + Hash + Debug + Any + Send + Sync;
+    type PipelineCache:       Clone + Hash + Debug + Any + Send + Sync;
+    type DescriptorSetLayout: Clone + Hash + Debug + Any + Send + Sync;
+    type DescriptorPool:      Clone + Hash + Debug + Any + Send + Sync;
+    type DescriptorSet:       Clone + Hash + Debug + Any + Send + Sync;
+    type QueryPool:           Clone + Hash + Debug + Any + Send + Sync;
+    type Event:               Clone + Hash + Debug + Any + Send + Sync;
+    type Semaphore:           Clone + Hash + Debug + Any + Send + Sync;
+    type Fence:               Clone + Hash + Debug + Any + Send + Sync;
     type Image:               Clone + Hash + Debug + Any + Send + Sync;
     type ShaderResourceView:  Clone + Hash + Debug + Any + Send + Sync + Copy;
     type UnorderedAccessView: Clone + Hash + Debug + Any + Send + Sync + Copy;
     type RenderTargetView:    Clone + Hash + Debug + Any + Send + Sync + Copy;
     type DepthStencilView:    Clone + Hash + Debug + Any + Send + Sync;
     type Sampler:             Clone + Hash + Debug + Any + Send + Sync + Copy;
+    type BufferView:          Clone + Hash + Debug + Any + Send + Sync + Copy;
+}
+
+/// Resources of the unit tests, identified by integers.
+#[cfg(test)]
+#[derive(Clone, Debug, Hash)]
+pub enum TestResources {}
+
+#[cfg(test)]
+impl Resources for TestResources {
+    type Heap = u32;
+    type UnboundBuffer = u32;
+    type Buffer = u32;
+    type UnboundImage = u32;
+    type ShaderLib = u32;
+    type RenderPass = u32;
+    type FrameBuffer = u32;
+    type PipelineSignature = u32;
+    type PipelineStateObject = u32;
+    type PipelineCache = u32;
+    type DescriptorSetLayout = u32;
+    type DescriptorPool = u32;
+    type DescriptorSet = u32;
+    type QueryPool = u32;
+    type Event = u32;
+    type Semaphore = u32;
+    type Fence = u32;
+    type Image = u32;
+    type ShaderResourceView = u32;
+    type UnorderedAccessView = u32;
+    type RenderTargetView = u32;
+    type DepthStencilView = u32;
+    type Sampler = u32;
+    type BufferView = u32;
 }
 
 /// Different types of a specific API.
@@ -198,11 +402,13 @@ pub trait Backend {
     // D3D12 has a 3D queue which supports all 3 types, Compute queue with compute and transfer support and a Copy queue
     // Older APIs don't have the concept of queues anyway
     // Metal ?
-    type CommandQueue: CommandQueue;
+    type CommandQueue: CommandQueue<CommandBuffer = Self::CommandBuffer,
+                                    Semaphore = <Self::Resources as Resources>::Semaphore,
+                                    Fence = <Self::Resources as Resources>::Fence>;
     type Device: Device;
     type Instance: Instance;
     type Adapter: Adapter;
     type Resources: Resources;
     type Surface: Surface;
-    type SwapChain: SwapChain;
+    type SwapChain: SwapChain<Semaphore = <Self::Resources as Resources>::Semaphore>;
 }
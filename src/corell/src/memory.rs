@@ -14,6 +14,12 @@
 
 //! Memory stuff
 
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use image::{ImageLayout, SubresourceRange};
+use Resources;
+
 /// A trait for plain-old-data types.
 ///
 /// A POD type does not have invalid bit patterns and can be safely
@@ -31,3 +37,114 @@ impl_pod! { ar =
 }
 
 unsafe impl<T: Pod, U: Pod> Pod for (T, U) {}
+
+bitflags!(
+    /// Kinds of memory accesses, made visible to each other by barriers.
+    pub flags Access: u32 {
+        const INDIRECT_COMMAND_READ          = 0x1,
+        const INDEX_BUFFER_READ              = 0x2,
+        const VERTEX_ATTRIBUTE_READ          = 0x4,
+        const CONSTANT_BUFFER_READ           = 0x8,
+        const INPUT_ATTACHMENT_READ          = 0x10,
+        const SHADER_READ                    = 0x20,
+        const SHADER_WRITE                   = 0x40,
+        const COLOR_ATTACHMENT_READ          = 0x80,
+        const COLOR_ATTACHMENT_WRITE         = 0x100,
+        const DEPTH_STENCIL_ATTACHMENT_READ  = 0x200,
+        const DEPTH_STENCIL_ATTACHMENT_WRITE = 0x400,
+        const TRANSFER_READ                  = 0x800,
+        const TRANSFER_WRITE                 = 0x1000,
+        const HOST_READ                      = 0x2000,
+        const HOST_WRITE                     = 0x4000,
+        const MEMORY_READ                    = 0x8000,
+        const MEMORY_WRITE                   = 0x10000,
+    }
+);
+
+/// Memory dependency of a pipeline barrier: the `src` accesses made before the
+/// barrier are made visible to the `dst` accesses made after it.
+#[derive(Clone, Debug)]
+pub enum Barrier<'a, R: Resources> {
+    /// Dependency between all the memory accesses.
+    AllMemory {
+        src: Access,
+        dst: Access,
+    },
+    /// Dependency between the accesses to a range of bytes of a buffer.
+    Buffer {
+        buffer: &'a R::Buffer,
+        range: Range<u64>,
+        src: Access,
+        dst: Access,
+    },
+    /// Dependency between the accesses to an image, which is also transitioned
+    /// from the layout `old` to `new`.
+    Image {
+        image: &'a R::Image,
+        subresource: SubresourceRange,
+        src: Access,
+        dst: Access,
+        old: ImageLayout,
+        new: ImageLayout,
+    },
+}
+
+bitflags!(
+    /// Properties of a memory type.
+    pub flags MemoryProperties: u16 {
+        /// Fastest memory for the GPU to access.
+        const DEVICE_LOCAL     = 0x1,
+        /// Memory which can be mapped and accessed by the CPU.
+        const CPU_VISIBLE      = 0x2,
+        /// CPU writes are visible to the GPU and GPU writes to the CPU without explicit flushes.
+        const COHERENT         = 0x4,
+        /// CPU accesses are cached, making reads faster.
+        const CPU_CACHED       = 0x8,
+        /// Memory only backed on demand, for transient attachments.
+        const LAZILY_ALLOCATED = 0x10,
+    }
+);
+
+/// Type of memory of a device, from which heaps are allocated.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MemoryType {
+    /// Index of the type, as used by `Requirements::type_mask`.
+    pub id: usize,
+    pub properties: MemoryProperties,
+    /// Index of the memory heap of the device providing the memory.
+    pub heap_index: usize,
+}
+
+/// Memory requirements of a resource.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Requirements {
+    /// Size in bytes of the memory bound to the resource.
+    pub size: u64,
+    /// Alignment in bytes of the offset of the memory bound to the resource.
+    pub alignment: u64,
+    /// Bit mask of the ids of the memory types the resource can be bound to.
+    pub type_mask: u64,
+}
+
+impl Requirements {
+    /// Return whether the resource can be bound to memory of type `ty`.
+    pub fn supports(&self, ty: &MemoryType) -> bool {
+        self.type_mask & (1 << ty.id) != 0
+    }
+}
+
+/// Error of a memory allocation.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct OutOfMemory;
+
+impl fmt::Display for OutOfMemory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl Error for OutOfMemory {
+    fn description(&self) -> &str {
+        "Out of memory, or too many allocations"
+    }
+}
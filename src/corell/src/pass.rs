@@ -0,0 +1,88 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Render pass descriptions, used to create `RenderPass` objects.
+
+use std::ops::Range;
+use format::Format;
+use image::{ImageLayout, NumSamples};
+use memory::Access;
+use pso::PipelineStage;
+
+/// Operation applied to the content of an attachment at the start of a render pass.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AttachmentLoadOp {
+    /// Keep the previous content.
+    Load,
+    /// Clear to the value given when beginning the render pass.
+    Clear,
+    /// The previous content is not needed.
+    DontCare,
+}
+
+/// Operation applied to the content of an attachment at the end of a render pass.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AttachmentStoreOp {
+    /// Keep the rendered content.
+    Store,
+    /// The rendered content is not needed after the pass.
+    DontCare,
+}
+
+/// Description of an attachment of a render pass.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Attachment {
+    pub format: Format,
+    /// Number of samples of the attachment, matching the images of the frame buffers.
+    pub samples: NumSamples,
+    pub load_op: AttachmentLoadOp,
+    pub store_op: AttachmentStoreOp,
+    pub stencil_load_op: AttachmentLoadOp,
+    pub stencil_store_op: AttachmentStoreOp,
+    /// Layouts of the image at the start and at the end of the render pass.
+    pub layouts: Range<ImageLayout>,
+}
+
+/// Index of an attachment, and the layout it is in during a subpass.
+pub type AttachmentRef = (usize, ImageLayout);
+
+/// Description of the attachments used by a subpass.
+#[derive(Clone, Copy, Debug)]
+pub struct SubpassDesc<'a> {
+    pub color_attachments: &'a [AttachmentRef],
+    pub depth_stencil_attachment: Option<AttachmentRef>,
+    pub input_attachments: &'a [AttachmentRef],
+    /// Attachments not used by the subpass, whose content has to be kept for later subpasses.
+    pub preserve_attachments: &'a [usize],
+}
+
+/// Subpass of a dependency.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SubpassRef {
+    /// The commands outside of the render pass.
+    External,
+    /// The subpass at the given index.
+    Pass(usize),
+}
+
+/// Execution and memory dependency between two subpasses.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SubpassDependency {
+    pub src_pass: SubpassRef,
+    pub dst_pass: SubpassRef,
+    pub src_stage: PipelineStage,
+    pub dst_stage: PipelineStage,
+    pub src_access: Access,
+    pub dst_access: Access,
+}
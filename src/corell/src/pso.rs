@@ -16,7 +16,7 @@ use std::error::Error;
 use std::fmt;
 
 use {format, state};
-use Primitive;
+use {Primitive, Resources};
 use MAX_COLOR_TARGETS;
 
 /// Error types happening upon PSO creation on the device side.
@@ -35,16 +35,146 @@ impl Error for CreationError {
     }
 }
 
+bitflags!(
+    /// Stages of the pipeline, synchronized by barriers.
+    pub flags PipelineStage: u32 {
+        const TOP_OF_PIPE              = 0x1,
+        const DRAW_INDIRECT            = 0x2,
+        const VERTEX_INPUT             = 0x4,
+        const VERTEX_SHADER            = 0x8,
+        const HULL_SHADER              = 0x10,
+        const DOMAIN_SHADER            = 0x20,
+        const GEOMETRY_SHADER          = 0x40,
+        const FRAGMENT_SHADER          = 0x80,
+        const EARLY_FRAGMENT_TESTS     = 0x100,
+        const LATE_FRAGMENT_TESTS      = 0x200,
+        const COLOR_ATTACHMENT_OUTPUT  = 0x400,
+        const COMPUTE_SHADER           = 0x800,
+        const TRANSFER                 = 0x1000,
+        const BOTTOM_OF_PIPE           = 0x2000,
+        const HOST                     = 0x4000,
+    }
+);
+
+/// Kind of pipeline that resources are bound to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BindPoint {
+    Graphics,
+    Compute,
+}
+
+/// Blend factor, extending `state::Factor` with the second color output of the fragment
+/// shader for dual-source blending, which requires `Capabilities::dual_source_blending_supported`
+/// and is limited to the first color target.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BlendFactor {
+    /// A factor using at most a single source.
+    Factor(state::Factor),
+    Src1Color,
+    OneMinusSrc1Color,
+    Src1Alpha,
+    OneMinusSrc1Alpha,
+}
+
+impl BlendFactor {
+    /// Return whether the factor uses the second color output of the fragment shader.
+    pub fn is_dual_source(&self) -> bool {
+        match *self {
+            BlendFactor::Factor(_) => false,
+            _ => true,
+        }
+    }
+}
+
+impl From<state::Factor> for BlendFactor {
+    fn from(factor: state::Factor) -> BlendFactor {
+        BlendFactor::Factor(factor)
+    }
+}
+
+/// Blending of a color or alpha channel, see `state::BlendChannel`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BlendChannel {
+    /// Blend equation.
+    pub equation: state::Equation,
+    /// Source factor.
+    pub source: BlendFactor,
+    /// Destination factor.
+    pub destination: BlendFactor,
+}
+
+impl BlendChannel {
+    /// Return whether one of the factors uses the second color output of the fragment shader.
+    pub fn is_dual_source(&self) -> bool {
+        self.source.is_dual_source() || self.destination.is_dual_source()
+    }
+}
+
+impl From<state::BlendChannel> for BlendChannel {
+    fn from(channel: state::BlendChannel) -> BlendChannel {
+        BlendChannel {
+            equation: channel.equation,
+            source: channel.source.into(),
+            destination: channel.destination.into(),
+        }
+    }
+}
+
+/// Bitwise operation combining the fragment shader output `s` with the stored value `d`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LogicOp {
+    /// 0
+    Clear,
+    /// s & d
+    And,
+    /// s & !d
+    AndReverse,
+    /// s
+    Copy,
+    /// !s & d
+    AndInverted,
+    /// d
+    NoOp,
+    /// s ^ d
+    Xor,
+    /// s | d
+    Or,
+    /// !(s | d)
+    Nor,
+    /// !(s ^ d)
+    Equivalent,
+    /// !d
+    Invert,
+    /// s | !d
+    OrReverse,
+    /// !s
+    CopyInverted,
+    /// !s | d
+    OrInverted,
+    /// !(s & d)
+    Nand,
+    /// !0
+    Set,
+}
+
 /// Color output configuration of the PSO.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct ColorInfo {
     /// Color channel mask
     pub mask: state::ColorMask,
     /// Optional color blending
-    pub color: Option<state::BlendChannel>,
+    pub color: Option<BlendChannel>,
     /// Optional alpha blending
-    pub alpha: Option<state::BlendChannel>,
+    pub alpha: Option<BlendChannel>,
+}
+
+impl ColorInfo {
+    /// Return whether the blending uses the second color output of the fragment shader.
+    pub fn is_dual_source(&self) -> bool {
+        self.color.map_or(false, |b| b.is_dual_source()) || self.alpha.map_or(false, |b| b.is_dual_source())
+    }
 }
+
 impl From<state::ColorMask> for ColorInfo {
     fn from(mask: state::ColorMask) -> ColorInfo {
         ColorInfo {
@@ -58,8 +188,8 @@ impl From<state::Blend> for ColorInfo {
     fn from(blend: state::Blend) -> ColorInfo {
         ColorInfo {
             mask: state::MASK_ALL,
-            color: Some(blend.color),
-            alpha: Some(blend.alpha),
+            color: Some(blend.color.into()),
+            alpha: Some(blend.alpha.into()),
         }
     }
 }
@@ -71,7 +201,8 @@ pub struct DepthStencilInfo {
     pub depth: Option<state::Depth>,
     /// Optional stencil test on the front faces
     pub front: Option<state::StencilSide>,
-    /// Optional stencil test on the back faces
+    /// Optional stencil test on the back faces. Its masks can only differ from the front ones
+    /// with `Capabilities::separate_stencil_masks_supported`.
     pub back: Option<state::StencilSide>,
 }
 impl From<state::Depth> for DepthStencilInfo {
@@ -128,14 +259,49 @@ pub struct VertexBufferDesc {
     pub rate: InstanceRate,
 }
 
+impl VertexBufferDesc {
+    /// Describe a buffer with one element per vertex.
+    pub fn per_vertex(stride: ElemStride) -> VertexBufferDesc {
+        VertexBufferDesc { stride: stride, rate: 0 }
+    }
+
+    /// Describe a buffer with one element for every `rate` instances.
+    /// Rates above 1 require `Capabilities::instance_rate_supported`.
+    pub fn per_instance(stride: ElemStride, rate: InstanceRate) -> VertexBufferDesc {
+        debug_assert!(rate > 0);
+        VertexBufferDesc { stride: stride, rate: rate }
+    }
+
+    /// Whether the elements of the buffer are fetched per instance, rather than per vertex.
+    pub fn is_per_instance(&self) -> bool {
+        self.rate != 0
+    }
+}
+
 /// Index of a vertex buffer.
 pub type BufferIndex = u8;
 /// Offset of an attribute from the start of the buffer, in bytes
 pub type ElemOffset = u32;
 /// Offset between attribute values, in bytes
 pub type ElemStride = u8;
-/// The number of instances between each subsequent attribute value
+/// The number of instances between each subsequent attribute value,
+/// or 0 if the attribute advances with every vertex.
 pub type InstanceRate = u8;
+/// Offset of the first element in a vertex buffer, in bytes
+pub type BufferOffset = u64;
+
+/// Vertex buffers bound to consecutive bindings, with the offsets of their first element.
+/// The buffers are fetched per vertex or per instance depending on the `VertexBufferDesc`
+/// of the same binding in the bound pipeline.
+#[derive(Clone, Debug)]
+pub struct VertexBufferSet<R: Resources>(pub Vec<(R::Buffer, BufferOffset)>);
+
+impl<R: Resources> VertexBufferSet<R> {
+    /// Create an empty set.
+    pub fn new() -> VertexBufferSet<R> {
+        VertexBufferSet(Vec::new())
+    }
+}
 
 /// A struct element descriptor.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -149,6 +315,20 @@ pub struct Element<F> {
 /// PSO vertex attribute descriptor
 pub type AttributeDesc = (BufferIndex, Element<format::Format>);
 
+bitflags!(
+    /// Pipeline states set by command buffer commands, instead of the pipeline description.
+    pub flags DynamicStates: u8 {
+        /// Depth bias, set with `set_depth_bias`. Enables the depth bias.
+        const DEPTH_BIAS   = 0x1,
+        /// Depth bounds, set with `set_depth_bounds`. Enables the depth bounds test.
+        const DEPTH_BOUNDS = 0x2,
+        /// Line width, set with `set_line_width`.
+        const LINE_WIDTH   = 0x4,
+        /// Stencil reference values, set with `set_stencil_reference`. Otherwise they are 0.
+        const STENCIL_REFERENCE = 0x8,
+    }
+);
+
 pub struct GraphicsPipelineDesc {
     /// Type of the primitive
     pub primitive: Primitive,
@@ -159,15 +339,31 @@ pub struct GraphicsPipelineDesc {
     /// Shader entry points
     pub shader_entries: GraphicsShaderSet,
     /// Render target views (RTV)
-    /// The entries are supposed to be contiguous, starting from 0
+    /// The entries are supposed to be contiguous, starting from 0. Their `ColorInfo` can only
+    /// differ with `Capabilities::independent_blend_supported`.
     pub color_targets: [Option<ColorTargetDesc>; MAX_COLOR_TARGETS],
+    /// Optional logic operation, applied to all the color targets instead of their blending.
+    /// Only affects the targets of integer or normalized formats, and requires
+    /// `Capabilities::logic_op_supported`.
+    pub logic_op: Option<LogicOp>,
     /// Vertex buffers
     pub vertex_buffers: Vec<VertexBufferDesc>,
     /// Vertex attributes
     pub attributes: Vec<AttributeDesc>,
+    /// States set by commands
+    pub dynamic_states: DynamicStates,
 }
 
 impl GraphicsPipelineDesc {
+    /// Return whether the color targets have different blend states or write masks.
+    pub fn has_independent_blend(&self) -> bool {
+        let mut infos = self.color_targets.iter().filter_map(|desc| desc.map(|(_, info)| info));
+        match infos.next() {
+            Some(first) => infos.any(|info| info != first),
+            None => false,
+        }
+    }
+
     /// Create a new empty PSO descriptor.
     pub fn new(primitive: Primitive, rasterizer: state::Rasterizer, shader_entries: GraphicsShaderSet) -> GraphicsPipelineDesc {
         GraphicsPipelineDesc {
@@ -176,8 +372,10 @@ impl GraphicsPipelineDesc {
             depth_stencil: None,
             shader_entries: shader_entries,
             color_targets: [None; MAX_COLOR_TARGETS],
+            logic_op: None,
             vertex_buffers: Vec::new(),
             attributes: Vec::new(),
+            dynamic_states: DynamicStates::empty(),
         }
     }
 }
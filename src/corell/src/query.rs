@@ -0,0 +1,133 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Queries, gathering information about the execution of commands on the GPU.
+
+/// Type of the queries of a query pool.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum QueryType {
+    /// Number of samples passing the depth and stencil tests,
+    /// between the beginning and the end of the query.
+    Occlusion,
+    /// Time at which the previous commands completed, written by `write_timestamp`.
+    Timestamp,
+    /// Counters of the graphics and compute pipelines, between the beginning and the end
+    /// of the query. Each query has one result per statistic, see `PipelineStatistics`.
+    PipelineStatistics(PipelineStatistic),
+}
+
+bitflags!(
+    /// Statistics gathered by pipeline statistics queries.
+    pub flags PipelineStatistic: u32 {
+        const INPUT_ASSEMBLY_VERTICES     = 0x1,
+        const INPUT_ASSEMBLY_PRIMITIVES   = 0x2,
+        const VERTEX_SHADER_INVOCATIONS   = 0x4,
+        const GEOMETRY_SHADER_INVOCATIONS = 0x8,
+        const GEOMETRY_SHADER_PRIMITIVES  = 0x10,
+        const CLIPPING_INVOCATIONS        = 0x20,
+        const CLIPPING_PRIMITIVES         = 0x40,
+        const PIXEL_SHADER_INVOCATIONS    = 0x80,
+        const HULL_SHADER_PATCHES         = 0x100,
+        const DOMAIN_SHADER_INVOCATIONS   = 0x200,
+        const COMPUTE_SHADER_INVOCATIONS  = 0x400,
+    }
+);
+
+impl PipelineStatistic {
+    /// Number of results of each query gathering these statistics.
+    pub fn count(&self) -> usize {
+        self.bits().count_ones() as usize
+    }
+}
+
+/// Results of a pipeline statistics query.
+/// The statistics which were not gathered are zero.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PipelineStatistics {
+    /// Vertices submitted to the input assembly.
+    pub input_assembly_vertices: u64,
+    /// Primitives assembled.
+    pub input_assembly_primitives: u64,
+    /// Vertex shader invocations.
+    pub vertex_shader_invocations: u64,
+    /// Geometry shader invocations.
+    pub geometry_shader_invocations: u64,
+    /// Primitives generated by the geometry shader.
+    pub geometry_shader_primitives: u64,
+    /// Primitives reaching the clipping stage.
+    pub clipping_invocations: u64,
+    /// Primitives output by the clipping stage.
+    pub clipping_primitives: u64,
+    /// Pixel shader invocations.
+    pub pixel_shader_invocations: u64,
+    /// Patches processed by the hull shader.
+    pub hull_shader_patches: u64,
+    /// Domain shader invocations.
+    pub domain_shader_invocations: u64,
+    /// Compute shader invocations.
+    pub compute_shader_invocations: u64,
+}
+
+impl PipelineStatistics {
+    /// Unpack the results of pipeline statistics queries, as returned by
+    /// `Factory::get_query_results`, gathering the `statistics`.
+    pub fn from_results(statistics: PipelineStatistic, results: &[u64]) -> Vec<PipelineStatistics> {
+        let count = statistics.count();
+        if count == 0 {
+            return Vec::new();
+        }
+        results.chunks(count).map(|values| {
+            // the results are ordered by statistic bit
+            let mut values = values.iter().cloned();
+            let mut next = |stat: PipelineStatistic| {
+                if statistics.contains(stat) { values.next().unwrap_or(0) } else { 0 }
+            };
+            PipelineStatistics {
+                input_assembly_vertices: next(INPUT_ASSEMBLY_VERTICES),
+                input_assembly_primitives: next(INPUT_ASSEMBLY_PRIMITIVES),
+                vertex_shader_invocations: next(VERTEX_SHADER_INVOCATIONS),
+                geometry_shader_invocations: next(GEOMETRY_SHADER_INVOCATIONS),
+                geometry_shader_primitives: next(GEOMETRY_SHADER_PRIMITIVES),
+                clipping_invocations: next(CLIPPING_INVOCATIONS),
+                clipping_primitives: next(CLIPPING_PRIMITIVES),
+                pixel_shader_invocations: next(PIXEL_SHADER_INVOCATIONS),
+                hull_shader_patches: next(HULL_SHADER_PATCHES),
+                domain_shader_invocations: next(DOMAIN_SHADER_INVOCATIONS),
+                compute_shader_invocations: next(COMPUTE_SHADER_INVOCATIONS),
+            }
+        }).collect()
+    }
+}
+
+bitflags!(
+    /// Behavior of a query, set when beginning it.
+    pub flags QueryControl: u8 {
+        /// Count the exact number of samples passing, instead of only
+        /// whether any did.
+        const PRECISE = 0x1,
+    }
+);
+
+bitflags!(
+    /// Synchronization and contents of the query results copied.
+    /// Results are always written as 64 bit unsigned integers.
+    pub flags QueryResultFlags: u8 {
+        /// Wait for the results of all queries to be available.
+        const WAIT              = 0x1,
+        /// Follow each result with its availability, zero if it is unavailable.
+        const WITH_AVAILABILITY = 0x2,
+        /// Write partial results of the unavailable queries.
+        const PARTIAL           = 0x4,
+    }
+);
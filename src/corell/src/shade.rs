@@ -14,6 +14,19 @@
 
 //! Shader handling.
 
+bitflags!(
+    /// Set of shader stages.
+    pub flags StageFlags: u8 {
+        const VERTEX       = 0x1,
+        const HULL         = 0x2,
+        const DOMAIN       = 0x4,
+        const GEOMETRY     = 0x8,
+        const PIXEL        = 0x10,
+        const COMPUTE      = 0x20,
+        const ALL_GRAPHICS = 0x1F,
+    }
+);
+
 /// Shader pipeline stage
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 pub enum Stage {
@@ -0,0 +1,306 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of the command recording state, shared by the backends.
+//!
+//! Backends drive a `Validator` from their command buffers, and skip the commands it rejects
+//! instead of recording them. The checks only run in debug builds.
+
+use std::error::Error;
+use std::fmt;
+use format::Format;
+
+/// Misuse of a command buffer, detected while recording.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    /// A draw was recorded outside of a render pass.
+    DrawOutsideRenderPass,
+    /// The command can only be recorded outside of a render pass.
+    InsideRenderPass(&'static str),
+    /// The command can only be recorded inside of a render pass.
+    OutsideRenderPass(&'static str),
+    /// A render pass was begun before ending the current one.
+    RenderPassAlreadyBegun,
+    /// The recording ended inside of a render pass.
+    RenderPassNotEnded,
+    /// `next_subpass` was called on the last subpass of the render pass.
+    NoNextSubpass { subpass_count: usize },
+    /// A draw was recorded without a bound graphics pipeline.
+    NoPipelineBound,
+    /// The bound pipeline was created for another subpass.
+    IncompatibleSubpass { pipeline: usize, current: usize },
+    /// The bound pipeline was created for a render pass with a different number of attachments.
+    AttachmentCountMismatch { pipeline: usize, current: usize },
+    /// The bound pipeline was created for a render pass with a different attachment format.
+    AttachmentFormatMismatch { attachment: usize, pipeline: Format, current: Format },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::InsideRenderPass(command) |
+            ValidationError::OutsideRenderPass(command) =>
+                write!(f, "{}: {}", self.description(), command),
+            ValidationError::NoNextSubpass { subpass_count } =>
+                write!(f, "{}, the render pass has {} subpasses", self.description(), subpass_count),
+            ValidationError::IncompatibleSubpass { pipeline, current } =>
+                write!(f, "{}: subpass {}, current subpass {}", self.description(), pipeline, current),
+            ValidationError::AttachmentCountMismatch { pipeline, current } =>
+                write!(f, "{}: {} attachments, current render pass has {}", self.description(), pipeline, current),
+            ValidationError::AttachmentFormatMismatch { attachment, pipeline, current } =>
+                write!(f, "{} {}: {:?}, current render pass uses {:?}", self.description(), attachment, pipeline, current),
+            _ => write!(f, "{}", self.description()),
+        }
+    }
+}
+
+impl Error for ValidationError {
+    fn description(&self) -> &str {
+        match *self {
+            ValidationError::DrawOutsideRenderPass => "Draw recorded outside of a render pass",
+            ValidationError::InsideRenderPass(_) => "Command recorded inside of a render pass",
+            ValidationError::OutsideRenderPass(_) => "Command recorded outside of a render pass",
+            ValidationError::RenderPassAlreadyBegun => "Render pass begun inside of another one",
+            ValidationError::RenderPassNotEnded => "Recording ended inside of a render pass",
+            ValidationError::NoNextSubpass { .. } => "No subpass after the current one",
+            ValidationError::NoPipelineBound => "Draw recorded without a bound graphics pipeline",
+            ValidationError::IncompatibleSubpass { .. } => "Bound pipeline was created for another subpass",
+            ValidationError::AttachmentCountMismatch { .. } =>
+                "Bound pipeline was created for a render pass with different attachments",
+            ValidationError::AttachmentFormatMismatch { .. } =>
+                "Bound pipeline was created for a different format of attachment",
+        }
+    }
+}
+
+/// Render pass a graphics pipeline was created for, given by the formats of its attachments
+/// and the index of the subpass.
+pub type PipelinePass<'a> = Option<(&'a [Format], usize)>;
+
+#[derive(Clone, Debug)]
+struct RenderPassState {
+    attachments: Vec<Format>,
+    subpass: usize,
+    subpass_count: usize,
+}
+
+/// Tracks the state of a command buffer while recording, reporting misuses through `log`.
+///
+/// Every method returns the error it detected, in which case the command must not be recorded.
+/// In release builds, all the commands are accepted.
+#[derive(Clone, Debug, Default)]
+pub struct Validator {
+    renderpass: Option<RenderPassState>,
+    // Render pass of the bound graphics pipeline, `None` if no pipeline is bound
+    pipeline: Option<Option<(Vec<Format>, usize)>>,
+}
+
+fn check(result: Result<(), ValidationError>) -> Result<(), ValidationError> {
+    if let Err(ref err) = result {
+        error!("{}", err);
+    }
+    result
+}
+
+impl Validator {
+    /// Create a validator for a command buffer which isn't recording.
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    /// Start a recording, outside of any render pass unless the command buffer continues
+    /// the subpass `subpass` of a render pass with the given `attachments` and `subpass_count`.
+    pub fn begin(&mut self, renderpass: Option<(&[Format], usize, usize)>) {
+        self.renderpass = renderpass.map(|(attachments, subpass, subpass_count)| RenderPassState {
+            attachments: attachments.to_vec(),
+            subpass: subpass,
+            subpass_count: subpass_count,
+        });
+        self.pipeline = None;
+    }
+
+    /// Finish the recording.
+    pub fn end(&mut self, secondary: bool) -> Result<(), ValidationError> {
+        if !cfg!(debug_assertions) || secondary {
+            return Ok(());
+        }
+        check(match self.renderpass {
+            Some(_) => Err(ValidationError::RenderPassNotEnded),
+            None => Ok(()),
+        })
+    }
+
+    /// Begin a render pass with the formats of its `attachments`.
+    pub fn begin_renderpass(&mut self, attachments: &[Format], subpass_count: usize) -> Result<(), ValidationError> {
+        if cfg!(debug_assertions) && self.renderpass.is_some() {
+            return check(Err(ValidationError::RenderPassAlreadyBegun));
+        }
+        self.renderpass = Some(RenderPassState {
+            attachments: attachments.to_vec(),
+            subpass: 0,
+            subpass_count: subpass_count,
+        });
+        Ok(())
+    }
+
+    /// Move on to the next subpass.
+    pub fn next_subpass(&mut self) -> Result<(), ValidationError> {
+        try!(self.inside_renderpass("next_subpass"));
+        if let Some(ref mut pass) = self.renderpass {
+            if cfg!(debug_assertions) && pass.subpass + 1 >= pass.subpass_count {
+                return check(Err(ValidationError::NoNextSubpass { subpass_count: pass.subpass_count }));
+            }
+            pass.subpass += 1;
+        }
+        Ok(())
+    }
+
+    /// End the current render pass.
+    pub fn end_renderpass(&mut self) -> Result<(), ValidationError> {
+        try!(self.inside_renderpass("end_renderpass"));
+        self.renderpass = None;
+        Ok(())
+    }
+
+    /// Bind a graphics pipeline, created for the render pass `pass` if any.
+    pub fn bind_graphics_pipeline(&mut self, pass: PipelinePass) {
+        self.pipeline = Some(pass.map(|(attachments, subpass)| (attachments.to_vec(), subpass)));
+    }
+
+    /// Check that a draw can be recorded, with a pipeline compatible with the current subpass.
+    pub fn draw(&self) -> Result<(), ValidationError> {
+        if !cfg!(debug_assertions) {
+            return Ok(());
+        }
+        check(self.draw_state())
+    }
+
+    fn draw_state(&self) -> Result<(), ValidationError> {
+        let pass = match self.renderpass {
+            Some(ref pass) => pass,
+            None => return Err(ValidationError::DrawOutsideRenderPass),
+        };
+        let &(ref attachments, subpass) = match self.pipeline {
+            Some(Some(ref pipeline_pass)) => pipeline_pass,
+            // the render pass of the pipeline is unknown to the backend
+            Some(None) => return Ok(()),
+            None => return Err(ValidationError::NoPipelineBound),
+        };
+        if subpass != pass.subpass {
+            return Err(ValidationError::IncompatibleSubpass { pipeline: subpass, current: pass.subpass });
+        }
+        if attachments.len() != pass.attachments.len() {
+            return Err(ValidationError::AttachmentCountMismatch {
+                pipeline: attachments.len(),
+                current: pass.attachments.len(),
+            });
+        }
+        match attachments.iter().zip(pass.attachments.iter()).position(|(a, b)| a != b) {
+            Some(i) => Err(ValidationError::AttachmentFormatMismatch {
+                attachment: i,
+                pipeline: attachments[i],
+                current: pass.attachments[i],
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that the `command` is recorded outside of a render pass.
+    pub fn outside_renderpass(&self, command: &'static str) -> Result<(), ValidationError> {
+        if cfg!(debug_assertions) && self.renderpass.is_some() {
+            return check(Err(ValidationError::InsideRenderPass(command)));
+        }
+        Ok(())
+    }
+
+    /// Check that the `command` is recorded inside of a render pass.
+    pub fn inside_renderpass(&self, command: &'static str) -> Result<(), ValidationError> {
+        if cfg!(debug_assertions) && self.renderpass.is_none() {
+            return check(Err(ValidationError::OutsideRenderPass(command)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ValidationError, Validator};
+    use format::{ChannelType, Format, SurfaceType};
+
+    const COLOR: Format = Format(SurfaceType::R8_G8_B8_A8, ChannelType::Unorm);
+    const DEPTH: Format = Format(SurfaceType::D24, ChannelType::Unorm);
+
+    #[test]
+    fn test_draw_outside_renderpass() {
+        let mut validator = Validator::new();
+        validator.begin(None);
+        validator.bind_graphics_pipeline(Some((&[COLOR], 0)));
+        assert_eq!(validator.draw(), Err(ValidationError::DrawOutsideRenderPass));
+        assert_eq!(validator.next_subpass(), Err(ValidationError::OutsideRenderPass("next_subpass")));
+        assert_eq!(validator.end_renderpass(), Err(ValidationError::OutsideRenderPass("end_renderpass")));
+        assert_eq!(validator.outside_renderpass("copy"), Ok(()));
+        assert_eq!(validator.end(false), Ok(()));
+    }
+
+    #[test]
+    fn test_draw_pipeline() {
+        let mut validator = Validator::new();
+        validator.begin(None);
+        validator.begin_renderpass(&[COLOR, DEPTH], 1).unwrap();
+        assert_eq!(validator.outside_renderpass("copy"), Err(ValidationError::InsideRenderPass("copy")));
+        assert_eq!(validator.draw(), Err(ValidationError::NoPipelineBound));
+        validator.bind_graphics_pipeline(Some((&[COLOR, DEPTH], 0)));
+        assert_eq!(validator.draw(), Ok(()));
+        validator.bind_graphics_pipeline(None);
+        assert_eq!(validator.draw(), Ok(()));
+        validator.bind_graphics_pipeline(Some((&[COLOR], 0)));
+        assert_eq!(validator.draw(), Err(ValidationError::AttachmentCountMismatch { pipeline: 1, current: 2 }));
+        validator.bind_graphics_pipeline(Some((&[COLOR, COLOR], 0)));
+        assert_eq!(validator.draw(), Err(ValidationError::AttachmentFormatMismatch {
+            attachment: 1,
+            pipeline: COLOR,
+            current: DEPTH,
+        }));
+        validator.end_renderpass().unwrap();
+        assert_eq!(validator.end(false), Ok(()));
+    }
+
+    #[test]
+    fn test_subpasses() {
+        let mut validator = Validator::new();
+        validator.begin(None);
+        validator.begin_renderpass(&[COLOR], 2).unwrap();
+        assert_eq!(validator.begin_renderpass(&[COLOR], 1), Err(ValidationError::RenderPassAlreadyBegun));
+        validator.bind_graphics_pipeline(Some((&[COLOR], 1)));
+        assert_eq!(validator.draw(), Err(ValidationError::IncompatibleSubpass { pipeline: 1, current: 0 }));
+        validator.next_subpass().unwrap();
+        assert_eq!(validator.draw(), Ok(()));
+        assert_eq!(validator.next_subpass(), Err(ValidationError::NoNextSubpass { subpass_count: 2 }));
+        assert_eq!(validator.draw(), Ok(()));
+    }
+
+    #[test]
+    fn test_end_inside_renderpass() {
+        let mut validator = Validator::new();
+        validator.begin(None);
+        validator.begin_renderpass(&[COLOR], 1).unwrap();
+        assert_eq!(validator.end(false), Err(ValidationError::RenderPassNotEnded));
+
+        // secondary command buffers continue the render pass of their primary one
+        validator.begin(Some((&[COLOR], 0, 1)));
+        validator.bind_graphics_pipeline(Some((&[COLOR], 0)));
+        assert_eq!(validator.draw(), Ok(()));
+        assert_eq!(validator.end(true), Ok(()));
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Translation of SPIR-V modules to the shading languages of the other backends,
+//! with `spirv_cross`, so that shaders only need to be written once.
+//!
+//! Typically the shaders are authored in GLSL, compiled to SPIR-V offline with
+//! `glslangValidator`, and stored in the `vulkan` member of a `shade::Source`
+//! (or as `.spv` files next to the other sources of a `shade::Loader`).
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use spirv_cross::{glsl, spirv, ErrorCode};
+#[cfg(target_os = "windows")]
+use spirv_cross::hlsl;
+#[cfg(feature = "metal")]
+use spirv_cross::msl;
+
+use shade::{self, Backend, Source};
+
+/// Error translating a SPIR-V module.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CrossError {
+    /// The code to translate is not a SPIR-V module.
+    NotSpirv,
+    /// `spirv_cross` failed to parse or translate the module.
+    Translation(String),
+}
+
+impl fmt::Display for CrossError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CrossError::NotSpirv => write!(f, "{}", self.description()),
+            CrossError::Translation(ref e) => write!(f, "Unable to translate the SPIR-V module: {}", e),
+        }
+    }
+}
+
+impl Error for CrossError {
+    fn description(&self) -> &str {
+        match *self {
+            CrossError::NotSpirv => "The shader code is not a SPIR-V module",
+            CrossError::Translation(_) => "Unable to translate the SPIR-V module",
+        }
+    }
+}
+
+fn words(code: &[u8]) -> Vec<u32> {
+    let le = code.chunks(4)
+        .map(|w| w[0] as u32 | (w[1] as u32) << 8 | (w[2] as u32) << 16 | (w[3] as u32) << 24)
+        .collect::<Vec<_>>();
+    if le[0] == shade::SPIRV_MAGIC {
+        le
+    } else {
+        le.into_iter().map(u32::swap_bytes).collect()
+    }
+}
+
+fn glsl_version(version: u32, embedded: bool) -> glsl::Version {
+    use spirv_cross::glsl::Version::*;
+    match (embedded, version) {
+        (true, v) if v >= 300 => V3_00Es,
+        (true, _) => V1_00Es,
+        (false, v) if v >= 450 => V4_50,
+        (false, v) if v >= 440 => V4_40,
+        (false, v) if v >= 430 => V4_30,
+        (false, v) if v >= 420 => V4_20,
+        (false, v) if v >= 410 => V4_10,
+        (false, v) if v >= 400 => V4_00,
+        (false, v) if v >= 330 => V3_30,
+        (false, v) if v >= 150 => V1_50,
+        (false, v) if v >= 140 => V1_40,
+        (false, v) if v >= 130 => V1_30,
+        (false, _) => V1_20,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn hlsl_model(model: shade::DxShaderModel) -> hlsl::ShaderModel {
+    match model {
+        m if m >= 50 => hlsl::ShaderModel::V5_0,
+        m if m >= 41 => hlsl::ShaderModel::V4_1,
+        m if m >= 40 => hlsl::ShaderModel::V4_0,
+        _ => hlsl::ShaderModel::V3_0,
+    }
+}
+
+/// Translate a SPIR-V module to the language of the backend.
+/// For Vulkan, the module is returned as is.
+pub fn translate(spirv: &[u8], backend: Backend) -> Result<Vec<u8>, CrossError> {
+    if !shade::is_spirv(spirv) {
+        return Err(CrossError::NotSpirv);
+    }
+    let module = spirv::Module::from_words(&words(spirv));
+    let fail = |e: ErrorCode| CrossError::Translation(format!("{:?}", e));
+    let code = match backend {
+        Backend::Glsl(version) | Backend::GlslEs(version) => {
+            let mut ast = try!(spirv::Ast::<glsl::Target>::parse(&module).map_err(&fail));
+            try!(ast.set_compiler_options(&glsl::CompilerOptions {
+                version: glsl_version(version.major * 100 + version.minor, version.is_embedded),
+                .. glsl::CompilerOptions::default()
+            }).map_err(&fail));
+            try!(ast.compile().map_err(&fail))
+        }
+        #[cfg(target_os = "windows")]
+        Backend::Hlsl(model) => {
+            let mut ast = try!(spirv::Ast::<hlsl::Target>::parse(&module).map_err(&fail));
+            try!(ast.set_compiler_options(&hlsl::CompilerOptions {
+                shader_model: hlsl_model(model),
+                .. hlsl::CompilerOptions::default()
+            }).map_err(&fail));
+            try!(ast.compile().map_err(&fail))
+        }
+        #[cfg(feature = "metal")]
+        Backend::Msl(_) => {
+            let mut ast = try!(spirv::Ast::<msl::Target>::parse(&module).map_err(&fail));
+            try!(ast.compile().map_err(&fail))
+        }
+        #[cfg(feature = "vulkan")]
+        Backend::Vulkan => return Ok(spirv.to_vec()),
+    };
+    Ok(code.into_bytes())
+}
+
+/// Translates SPIR-V modules, keeping the results around so that every
+/// module is only translated once per backend.
+pub struct CrossCompiler {
+    cache: HashMap<u64, Vec<(Backend, Vec<u8>)>>,
+}
+
+impl CrossCompiler {
+    /// Create a compiler with an empty cache.
+    pub fn new() -> CrossCompiler {
+        CrossCompiler {
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Translate a SPIR-V module to the language of the backend, see `translate`.
+    pub fn translate(&mut self, spirv: &[u8], backend: Backend) -> Result<&[u8], CrossError> {
+        let mut hasher = DefaultHasher::new();
+        spirv.hash(&mut hasher);
+        let entries = self.cache.entry(hasher.finish()).or_insert_with(Vec::new);
+        let index = match entries.iter().position(|&(b, _)| b == backend) {
+            Some(index) => index,
+            None => {
+                let code = try!(translate(spirv, backend));
+                entries.push((backend, code));
+                entries.len() - 1
+            }
+        };
+        Ok(&entries[index].1)
+    }
+
+    /// Pick the version of the source written for the backend if there is one,
+    /// and otherwise translate its `vulkan` member.
+    pub fn select<'a>(&'a mut self, source: &Source<'a>, backend: Backend) -> Result<&'a [u8], CrossError> {
+        match source.select(backend) {
+            Ok(code) => Ok(code),
+            Err(_) => self.translate(source.vulkan, backend),
+        }
+    }
+
+    /// Forget all the translated modules.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+}
@@ -14,12 +14,18 @@
 
 #[macro_use]
 extern crate log;
+#[cfg(feature = "env_logger")]
 extern crate env_logger;
 extern crate winit;
 extern crate glutin;
 extern crate gfx;
+extern crate gfx_corell;
 extern crate gfx_device_gl;
 extern crate gfx_window_glutin;
+#[cfg(feature = "serde")]
+extern crate toml;
+#[cfg(feature = "spirv-cross")]
+extern crate spirv_cross;
 // extern crate gfx_window_glfw;
 
 #[cfg(target_os = "windows")]
@@ -35,9 +41,22 @@ extern crate gfx_window_metal;
 #[cfg(feature = "vulkan")]
 extern crate gfx_device_vulkan;
 #[cfg(feature = "vulkan")]
+extern crate gfx_device_vulkanll;
+#[cfg(feature = "vulkan")]
 extern crate gfx_window_vulkan;
 
+#[cfg(feature = "vulkan")]
+pub mod compute;
+pub mod config;
+#[cfg(feature = "spirv-cross")]
+pub mod cross;
 pub mod shade;
+pub mod threaded;
+
+#[cfg(feature = "vulkan")]
+pub use compute::ComputeApplication;
+pub use config::ConfigError;
+pub use threaded::{ThreadedApplication, Update};
 
 #[cfg(not(feature = "vulkan"))]
 pub type ColorFormat = gfx::format::Rgba8;
@@ -45,7 +64,7 @@ pub type ColorFormat = gfx::format::Rgba8;
 pub type ColorFormat = gfx::format::Bgra8;
 
 #[cfg(feature = "metal")]
-pub type DepthFormat = gfx::format::Depth32F;
+pub type DepthFormat = gfx::format::Depth32FStencil8;
 #[cfg(not(feature = "metal"))]
 pub type DepthFormat = gfx::format::DepthStencil;
 
@@ -55,72 +74,275 @@ pub struct WindowTargets<R: gfx::Resources> {
     pub aspect_ratio: f32,
 }
 
+/// Graphics API to run on.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Backend {
     OpenGL2,
     Direct3D11 { pix_mode: bool },
     Metal,
+    Vulkan,
+}
+
+/// Coarse classification of adapters, used by `DeviceSelection`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdapterKind {
+    Discrete,
+    Integrated,
+    Software,
+    Other,
+}
+
+/// Policy for picking the adapter (physical device) to run on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeviceSelection {
+    /// Whatever the backend picks by default.
+    Any,
+    /// The first discrete GPU.
+    Discrete,
+    /// The first integrated GPU.
+    Integrated,
+    /// The first software rasterizer, such as WARP. Useful on machines without a GPU.
+    Software,
+    /// The first adapter whose name contains the given string.
+    ByName(String),
+    /// The adapter at the given position in the backend enumeration order.
+    Index(u32),
+}
+
+impl DeviceSelection {
+    /// Return the position of the chosen adapter, given the `(name, kind)`
+    /// descriptions of all the available ones.
+    pub fn select<'a, I>(&self, adapters: I) -> Option<usize> where
+        I: IntoIterator<Item = (&'a str, AdapterKind)>
+    {
+        let mut adapters = adapters.into_iter();
+        match *self {
+            DeviceSelection::Any => adapters.next().map(|_| 0),
+            DeviceSelection::Discrete => adapters.position(|(_, kind)| kind == AdapterKind::Discrete),
+            DeviceSelection::Integrated => adapters.position(|(_, kind)| kind == AdapterKind::Integrated),
+            DeviceSelection::Software => adapters.position(|(_, kind)| kind == AdapterKind::Software),
+            DeviceSelection::ByName(ref name) => adapters.position(|(n, _)| n.contains(name.as_str())),
+            DeviceSelection::Index(index) => adapters.nth(index as usize).map(|_| index as usize),
+        }
+    }
+}
+
+/// Harness configuration, passed to the `launch_*` functions.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Create the device with the API debug layer, and record commands in the way
+    /// graphics debuggers (PIX, RenderDoc) handle best. On Direct3D 11 this also
+    /// switches from deferred contexts to command lists executed on the immediate one.
+    pub debug_device: bool,
+    /// Enable the API validation layers, forwarding their messages to the `log` crate.
+    /// Currently affects Vulkan. On by default in debug builds.
+    pub validation: bool,
+    /// Which adapter to create the device on. Currently affects Vulkan and Direct3D 11.
+    /// DXGI doesn't report whether a hardware adapter is discrete or integrated.
+    pub device: DeviceSelection,
+    /// Install `env_logger` as the `log` backend on launch. Turn this off to plug in
+    /// a logger of your own; an already installed logger is never replaced either way.
+    pub init_logger: bool,
+    /// Inner size of the window, or the default size picked by the windowing system.
+    pub size: Option<(u32, u32)>,
+    /// Requested backend. Only the one `Application::launch_default` was built
+    /// for is available, a different request is reported and ignored.
+    pub backend: Option<Backend>,
+    /// Synchronize presentation with the vertical blank.
+    pub vsync: bool,
+    /// Number of samples of the main color target. Currently affects OpenGL.
+    pub msaa: u8,
+    /// Go fullscreen on the primary monitor.
+    pub fullscreen: bool,
+    /// Keep the window hidden, for benchmarks and automated runs.
+    pub headless: bool,
+    /// Exit after rendering this many frames, handy for benchmarks and tests.
+    pub max_frames: Option<u64>,
+    /// Poll the files returned by `Application::shader_files` and call
+    /// `Application::on_shaders_reloaded` when they change.
+    pub watch_shaders: bool,
+    /// Number of frames the CPU may record ahead of the GPU. Each frame gets an
+    /// encoder of its own, and waits on its fence before being recorded again.
+    /// A single frame is flushed without fences, as Metal doesn't support them yet.
+    pub frames_in_flight: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            debug_device: false,
+            validation: cfg!(debug_assertions),
+            device: DeviceSelection::Any,
+            init_logger: true,
+            size: None,
+            backend: None,
+            vsync: true,
+            msaa: 1,
+            fullscreen: false,
+            headless: false,
+            max_frames: None,
+            watch_shaders: false,
+            frames_in_flight: 2,
+        }
+    }
+}
+
+impl Config {
+    fn apply_window(&self, mut wb: winit::WindowBuilder) -> winit::WindowBuilder {
+        if let Some((width, height)) = self.size {
+            wb = wb.with_dimensions(width, height);
+        }
+        if self.fullscreen {
+            wb = wb.with_fullscreen(winit::get_primary_monitor());
+        }
+        if self.headless {
+            wb = wb.with_visibility(false);
+        }
+        wb
+    }
+
+    fn check_backend(&self, available: Backend) {
+        let matches = match (self.backend, available) {
+            (None, _) |
+            (Some(Backend::OpenGL2), Backend::OpenGL2) |
+            (Some(Backend::Direct3D11 { .. }), Backend::Direct3D11 { .. }) |
+            (Some(Backend::Metal), Backend::Metal) |
+            (Some(Backend::Vulkan), Backend::Vulkan) => true,
+            _ => false,
+        };
+        if !matches {
+            warn!("Backend {:?} is not available in this build, running on {:?}", self.backend.unwrap(), available);
+        }
+    }
+
+    fn warn_unsupported(&self, backend: &str, vsync_control: bool) {
+        if self.msaa > 1 {
+            warn!("MSAA is not supported by the {} launcher, ignoring", backend);
+        }
+        if !self.vsync && !vsync_control {
+            warn!("Disabling vsync is not supported by the {} launcher, ignoring", backend);
+        }
+    }
+}
+
+fn init_logger(config: &Config) {
+    if config.init_logger {
+        install_env_logger();
+    }
+}
+
+#[cfg(feature = "env_logger")]
+fn install_env_logger() {
+    if env_logger::init().is_err() {
+        debug!("A logger is already installed, keeping it");
+    }
+}
+
+#[cfg(not(feature = "env_logger"))]
+fn install_env_logger() {}
+
+/// Frame timing gathered by the launch loop, handed back together with the
+/// application once the loop exits.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameStats {
+    pub num_frames: u64,
+    pub elapsed: std::time::Duration,
+}
+
+impl FrameStats {
+    /// Average frame time in milliseconds, zero if no frame was rendered.
+    pub fn average_frame_time(&self) -> f64 {
+        if self.num_frames == 0 {
+            return 0.0;
+        }
+        let elapsed_ms = (self.elapsed.as_secs() * 1000) as f64 +
+                         self.elapsed.subsec_nanos() as f64 / 1000_000.0;
+        elapsed_ms / self.num_frames as f64
+    }
 }
 
 struct Harness {
     start: std::time::Instant,
-    num_frames: f64,
+    num_frames: u64,
 }
 
 impl Harness {
     fn new() -> Harness {
         Harness {
             start: std::time::Instant::now(),
-            num_frames: 0.0,
+            num_frames: 0,
         }
     }
     fn bump(&mut self) {
-        self.num_frames += 1.0;
+        self.num_frames += 1;
     }
-}
-
-impl Drop for Harness {
-    fn drop(&mut self) {
-        let time_end = self.start.elapsed();
-        println!("Avg frame time: {} ms",
-                 ((time_end.as_secs() * 1000) as f64 +
-                  (time_end.subsec_nanos() / 1000_000) as f64) / self.num_frames);
+    fn finish(self) -> FrameStats {
+        let stats = FrameStats {
+            num_frames: self.num_frames,
+            elapsed: self.start.elapsed(),
+        };
+        println!("Avg frame time: {} ms", stats.average_frame_time());
+        stats
     }
 }
 
-pub trait Factory<R: gfx::Resources>: gfx::Factory<R> {
-    type CommandBuffer: gfx::CommandBuffer<R>;
-    fn create_encoder(&mut self) -> gfx::Encoder<R, Self::CommandBuffer>;
+pub trait Factory<R: gfx::Resources, C: gfx::CommandBuffer<R>>: gfx::Factory<R> {
+    fn create_encoder(&mut self) -> gfx::Encoder<R, C>;
 }
 
 pub trait ApplicationBase<R: gfx::Resources, C: gfx::CommandBuffer<R>> {
-    fn new<F>(&mut F, shade::Backend, WindowTargets<R>) -> Self where F: Factory<R, CommandBuffer = C>;
+    fn new<F>(&mut F, shade::Backend, WindowTargets<R>, &Config) -> Self where F: Factory<R, C>;
     fn render<D>(&mut self, &mut D) where D: gfx::Device<Resources = R, CommandBuffer = C>;
     fn get_exit_key() -> Option<winit::VirtualKeyCode>;
     fn on(&mut self, winit::Event);
-    fn on_resize<F>(&mut self, &mut F, WindowTargets<R>) where F: Factory<R, CommandBuffer = C>;
+    fn on_resize<F>(&mut self, &mut F, WindowTargets<R>) where F: Factory<R, C>;
+    fn shader_files(&self) -> Vec<std::path::PathBuf> {
+        Vec::new()
+    }
+    fn on_shaders_reloaded<F>(&mut self, &mut F, &[std::path::PathBuf]) where F: Factory<R, C> {}
+}
+
+fn check_shaders<R, C, A, F>(watcher: &mut Option<shade::Watcher>, app: &mut A, factory: &mut F) where
+    R: gfx::Resources,
+    C: gfx::CommandBuffer<R>,
+    A: ApplicationBase<R, C>,
+    F: Factory<R, C>,
+{
+    if let Some(ref mut watcher) = *watcher {
+        let changed = watcher.poll(&app.shader_files());
+        if !changed.is_empty() {
+            info!("Reloading shaders: {:?}", changed);
+            app.on_shaders_reloaded(factory, &changed);
+        }
+    }
 }
 
 
-impl Factory<gfx_device_gl::Resources> for gfx_device_gl::Factory {
-    type CommandBuffer = gfx_device_gl::CommandBuffer;
-    fn create_encoder(&mut self) -> gfx::Encoder<gfx_device_gl::Resources, Self::CommandBuffer> {
+impl Factory<gfx_device_gl::Resources, gfx_device_gl::CommandBuffer> for gfx_device_gl::Factory {
+    fn create_encoder(&mut self) -> gfx::Encoder<gfx_device_gl::Resources, gfx_device_gl::CommandBuffer> {
         self.create_command_buffer().into()
     }
 }
 
-pub fn launch_gl3<A>(wb: winit::WindowBuilder) where
+pub fn launch_gl3<A>(wb: winit::WindowBuilder, config: &Config) -> Option<(A, FrameStats)> where
 A: Sized + ApplicationBase<gfx_device_gl::Resources, gfx_device_gl::CommandBuffer>
 {
     use gfx::traits::Device;
 
-    env_logger::init().unwrap();
+    init_logger(config);
+    config.check_backend(Backend::OpenGL2);
     let gl_version = glutin::GlRequest::GlThenGles {
         opengl_version: (3, 2), // TODO: try more versions
         opengles_version: (2, 0),
     };
-    let builder = glutin::WindowBuilder::from_winit_builder(wb)
-                                        .with_gl(gl_version)
-                                        .with_vsync();
+    let mut builder = glutin::WindowBuilder::from_winit_builder(config.apply_window(wb))
+                                            .with_gl(gl_version);
+    if config.vsync {
+        builder = builder.with_vsync();
+    }
+    if config.msaa > 1 {
+        builder = builder.with_multisampling(config.msaa as u16);
+    }
     let (window, mut device, mut factory, main_color, main_depth) =
         gfx_window_glutin::init::<ColorFormat, DepthFormat>(builder);
     let (mut cur_width, mut cur_height) = window.get_inner_size_points().unwrap();
@@ -135,14 +357,15 @@ A: Sized + ApplicationBase<gfx_device_gl::Resources, gfx_device_gl::CommandBuffe
         color: main_color,
         depth: main_depth,
         aspect_ratio: cur_width as f32 / cur_height as f32,
-    });
+    }, config);
 
+    let mut watcher = if config.watch_shaders { Some(shade::Watcher::new()) } else { None };
     let mut harness = Harness::new();
-    loop {
+    'main: loop {
         for event in window.poll_events() {
             match event {
-                winit::Event::Closed => return,
-                winit::Event::KeyboardInput(winit::ElementState::Pressed, _, key) if key == A::get_exit_key() => return,
+                winit::Event::Closed => break 'main,
+                winit::Event::KeyboardInput(winit::ElementState::Pressed, _, key) if key == A::get_exit_key() => break 'main,
                 winit::Event::Resized(width, height) => if width != cur_width || height != cur_height {
                     cur_width = width;
                     cur_height = height;
@@ -157,11 +380,16 @@ A: Sized + ApplicationBase<gfx_device_gl::Resources, gfx_device_gl::CommandBuffe
             }
         }
         // draw a frame
+        check_shaders(&mut watcher, &mut app, &mut factory);
         app.render(&mut device);
         window.swap_buffers().unwrap();
         device.cleanup();
         harness.bump();
+        if config.max_frames == Some(harness.num_frames) {
+            break;
+        }
     }
+    Some((app, harness.finish()))
 }
 
 
@@ -171,22 +399,65 @@ pub type D3D11CommandBuffer = gfx_device_dx11::CommandBuffer<gfx_device_dx11::De
 pub type D3D11CommandBufferFake = gfx_device_dx11::CommandBuffer<gfx_device_dx11::CommandList>;
 
 #[cfg(target_os = "windows")]
-impl Factory<gfx_device_dx11::Resources> for gfx_device_dx11::Factory {
-    type CommandBuffer = D3D11CommandBuffer;
-    fn create_encoder(&mut self) -> gfx::Encoder<gfx_device_dx11::Resources, Self::CommandBuffer> {
+impl Factory<gfx_device_dx11::Resources, D3D11CommandBuffer> for gfx_device_dx11::Factory {
+    fn create_encoder(&mut self) -> gfx::Encoder<gfx_device_dx11::Resources, D3D11CommandBuffer> {
         self.create_command_buffer_native().into()
     }
 }
 
 #[cfg(target_os = "windows")]
-pub fn launch_d3d11<A>(wb: winit::WindowBuilder) where
+impl Factory<gfx_device_dx11::Resources, D3D11CommandBufferFake> for gfx_device_dx11::Factory {
+    fn create_encoder(&mut self) -> gfx::Encoder<gfx_device_dx11::Resources, D3D11CommandBufferFake> {
+        self.create_command_buffer().into()
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn launch_d3d11<A>(wb: winit::WindowBuilder, config: &Config) -> Option<(A, FrameStats)> where
 A: Sized + ApplicationBase<gfx_device_dx11::Resources, D3D11CommandBuffer>
+{
+    run_d3d11::<A, _, _, _>(wb, config, gfx_device_dx11::Deferred::from)
+}
+
+/// Same as `launch_d3d11`, but records into command lists that are executed
+/// on the immediate context, which is easier to follow in graphics debuggers.
+#[cfg(target_os = "windows")]
+pub fn launch_d3d11_immediate<A>(wb: winit::WindowBuilder, config: &Config) -> Option<(A, FrameStats)> where
+A: Sized + ApplicationBase<gfx_device_dx11::Resources, D3D11CommandBufferFake>
+{
+    run_d3d11::<A, _, _, _>(wb, config, |device| device)
+}
+
+#[cfg(target_os = "windows")]
+fn run_d3d11<A, C, D, W>(wb: winit::WindowBuilder, config: &Config, wrap_device: W) -> Option<(A, FrameStats)> where
+A: Sized + ApplicationBase<gfx_device_dx11::Resources, C>,
+C: gfx::CommandBuffer<gfx_device_dx11::Resources>,
+D: gfx::Device<Resources = gfx_device_dx11::Resources, CommandBuffer = C> + gfx_window_dxgi::DeviceExt,
+W: FnOnce(gfx_device_dx11::Device) -> D,
+gfx_device_dx11::Factory: Factory<gfx_device_dx11::Resources, C>,
 {
     use gfx::traits::{Device, Factory};
 
-    env_logger::init().unwrap();
+    init_logger(config);
+    config.check_backend(Backend::Direct3D11 { pix_mode: config.debug_device });
+    config.warn_unsupported("Direct3D 11", true);
+    let adapter = match config.device {
+        DeviceSelection::Any => None,
+        ref selection => {
+            let adapters = gfx_window_dxgi::enumerate_adapters();
+            let descriptions = adapters.iter().map(|a| (a.name.as_str(),
+                if a.software { AdapterKind::Software } else { AdapterKind::Other }));
+            match selection.select(descriptions) {
+                Some(index) => Some(index),
+                None => {
+                    error!("No DXGI adapter matches {:?}, available: {:?}", selection, adapters);
+                    return None;
+                }
+            }
+        }
+    };
     let (mut window, device, mut factory, main_color) =
-        gfx_window_dxgi::init::<ColorFormat>(wb).unwrap();
+        gfx_window_dxgi::init::<ColorFormat>(config.apply_window(wb), config.debug_device, adapter).unwrap();
     let main_depth = factory.create_depth_stencil_view_only(window.size.0, window.size.1)
                             .unwrap();
 
@@ -195,19 +466,21 @@ A: Sized + ApplicationBase<gfx_device_dx11::Resources, D3D11CommandBuffer>
         color: main_color,
         depth: main_depth,
         aspect_ratio: window.size.0 as f32 / window.size.1 as f32,
-    });
-    let mut device = gfx_device_dx11::Deferred::from(device);
+    }, config);
+    let mut device = wrap_device(device);
 
+    let mut watcher = if config.watch_shaders { Some(shade::Watcher::new()) } else { None };
     let mut harness = Harness::new();
-    loop {
+    'main: loop {
         let mut new_size = None;
         for event in window.poll_events() {
             match event {
-                winit::Event::Closed => return,
-                winit::Event::KeyboardInput(winit::ElementState::Pressed, _, key) if key == A::get_exit_key() => return,
+                winit::Event::Closed => break 'main,
+                winit::Event::KeyboardInput(winit::ElementState::Pressed, _, key) if key == A::get_exit_key() => break 'main,
                 winit::Event::Resized(width, height) => {
                     let size = (width as gfx::texture::Size, height as gfx::texture::Size);
-                    if size != window.size {
+                    // minimized windows report a zero size, which DXGI can't handle
+                    if size != window.size && width != 0 && height != 0 {
                         // working around the borrow checker: window is already borrowed here
                         new_size = Some(size);
                         break;
@@ -217,7 +490,7 @@ A: Sized + ApplicationBase<gfx_device_dx11::Resources, D3D11CommandBuffer>
             }
         }
         if let Some((width, height)) = new_size {
-            use gfx_window_dxgi::update_views;
+            use gfx_window_dxgi::{update_views, SwapChainError};
             match update_views(&mut window, &mut factory, &mut device, width, height) {
                 Ok(new_color) => {
                     let new_depth = factory.create_depth_stencil_view_only(width, height).unwrap();
@@ -227,35 +500,52 @@ A: Sized + ApplicationBase<gfx_device_dx11::Resources, D3D11CommandBuffer>
                         aspect_ratio: width as f32 / height as f32,
                     });
                 },
+                Err(e @ SwapChainError::DeviceLost(_)) => {
+                    error!("Resize failed: {}", e);
+                    break 'main;
+                },
                 Err(e) => error!("Resize failed: {}", e),
             }
             continue;
         }
+        check_shaders(&mut watcher, &mut app, &mut factory);
         app.render(&mut device);
-        window.swap_buffers(1);
+        if let Err(e) = window.swap_buffers(if config.vsync { 1 } else { 0 }) {
+            error!("Present failed: {}", e);
+            break 'main;
+        }
         device.cleanup();
         harness.bump();
+        if config.max_frames == Some(harness.num_frames) {
+            break;
+        }
     }
+    Some((app, harness.finish()))
 }
 
 
 #[cfg(feature = "metal")]
-impl Factory<gfx_device_metal::Resources> for gfx_device_metal::Factory {
-    type CommandBuffer = gfx_device_metal::CommandBuffer;
-    fn create_encoder(&mut self) -> gfx::Encoder<gfx_device_metal::Resources, Self::CommandBuffer> {
+impl Factory<gfx_device_metal::Resources, gfx_device_metal::CommandBuffer> for gfx_device_metal::Factory {
+    fn create_encoder(&mut self) -> gfx::Encoder<gfx_device_metal::Resources, gfx_device_metal::CommandBuffer> {
         self.create_command_buffer().into()
     }
 }
 
 #[cfg(feature = "metal")]
-pub fn launch_metal<A>(wb: winit::WindowBuilder) where
+pub fn launch_metal<A>(wb: winit::WindowBuilder, config: &Config) -> Option<(A, FrameStats)> where
 A: Sized + ApplicationBase<gfx_device_metal::Resources, gfx_device_metal::CommandBuffer>
 {
     use gfx::traits::{Device, Factory};
     use gfx::texture::Size;
 
-    env_logger::init().unwrap();
-    let (window, mut device, mut factory, main_color) = gfx_window_metal::init::<ColorFormat>(wb)
+    init_logger(config);
+    config.check_backend(Backend::Metal);
+    config.warn_unsupported("Metal", false);
+    let config = &Config {
+        frames_in_flight: 1,
+        .. config.clone()
+    };
+    let (window, mut device, mut factory, main_color) = gfx_window_metal::init::<ColorFormat>(config.apply_window(wb))
                                                                                 .unwrap();
     let (width, height) = window.get_inner_size_points().unwrap();
     let main_depth = factory.create_depth_stencil_view_only(width as Size, height as Size).unwrap();
@@ -265,72 +555,146 @@ A: Sized + ApplicationBase<gfx_device_metal::Resources, gfx_device_metal::Comman
         color: main_color,
         depth: main_depth,
         aspect_ratio: width as f32 / height as f32
-    });
+    }, config);
 
+    let mut cur_size = (width, height);
+    let mut watcher = if config.watch_shaders { Some(shade::Watcher::new()) } else { None };
     let mut harness = Harness::new();
-    loop {
+    'main: loop {
         for event in window.poll_events() {
             match event {
-                winit::Event::Closed => return,
-                winit::Event::KeyboardInput(winit::ElementState::Pressed, _, key) if key == A::get_exit_key() => return,
-                winit::Event::Resized(_width, _height) => {
-                    warn!("TODO: resize on Metal");
+                winit::Event::Closed => break 'main,
+                winit::Event::KeyboardInput(winit::ElementState::Pressed, _, key) if key == A::get_exit_key() => break 'main,
+                winit::Event::Resized(width, height) => if (width, height) != cur_size {
+                    cur_size = (width, height);
+                    window.resize(width, height);
+                    let new_color = gfx_window_metal::update_views(&window, &mut factory, width, height);
+                    let new_depth = factory.create_depth_stencil_view_only(width as Size, height as Size).unwrap();
+                    app.on_resize(&mut factory, WindowTargets {
+                        color: new_color,
+                        depth: new_depth,
+                        aspect_ratio: width as f32 / height as f32,
+                    });
                 },
                 _ => app.on(event),
             }
         }
+        check_shaders(&mut watcher, &mut app, &mut factory);
         app.render(&mut device);
         window.swap_buffers().unwrap();
         device.cleanup();
         harness.bump();
+        if config.max_frames == Some(harness.num_frames) {
+            break;
+        }
     }
+    Some((app, harness.finish()))
 }
 
 
 #[cfg(feature = "vulkan")]
-impl Factory<gfx_device_vulkan::Resources> for gfx_device_vulkan::Factory {
-    type CommandBuffer = gfx_device_vulkan::CommandBuffer;
-    fn create_encoder(&mut self) -> gfx::Encoder<gfx_device_vulkan::Resources, Self::CommandBuffer> {
+impl Factory<gfx_device_vulkan::Resources, gfx_device_vulkan::CommandBuffer> for gfx_device_vulkan::Factory {
+    fn create_encoder(&mut self) -> gfx::Encoder<gfx_device_vulkan::Resources, gfx_device_vulkan::CommandBuffer> {
         self.create_command_buffer().into()
     }
 }
 
 #[cfg(feature = "vulkan")]
-pub fn launch_vulkan<A>(wb: winit::WindowBuilder) where
+pub fn launch_vulkan<A>(wb: winit::WindowBuilder, config: &Config) -> Option<(A, FrameStats)> where
 A: Sized + ApplicationBase<gfx_device_vulkan::Resources, gfx_device_vulkan::CommandBuffer>
 {
     use gfx::traits::{Device, Factory};
     use gfx::texture::Size;
 
-    env_logger::init().unwrap();
-    let (mut win, mut factory) = gfx_window_vulkan::init::<ColorFormat>(wb);
-    let (width, height) = win.get_size();
+    init_logger(config);
+    config.check_backend(Backend::Vulkan);
+    config.warn_unsupported("Vulkan", false);
+    let adapter = match config.device {
+        DeviceSelection::Any => None,
+        ref selection => {
+            use gfx_device_vulkan::DeviceType;
+            let adapters = gfx_device_vulkan::enumerate_adapters();
+            let descriptions = adapters.iter().map(|a| (a.name.as_str(), match a.device_type {
+                DeviceType::Discrete => AdapterKind::Discrete,
+                DeviceType::Integrated => AdapterKind::Integrated,
+                DeviceType::Cpu => AdapterKind::Software,
+                DeviceType::Virtual | DeviceType::Other => AdapterKind::Other,
+            }));
+            match selection.select(descriptions) {
+                Some(index) => Some(index),
+                None => {
+                    error!("No Vulkan adapter matches {:?}, available: {:?}", selection, adapters);
+                    return None;
+                }
+            }
+        }
+    };
+    let (mut win, mut factory) = gfx_window_vulkan::init::<ColorFormat>(config.apply_window(wb), config.validation, adapter);
+    let (width, height) = win.get_extent();
     let main_depth = factory.create_depth_stencil::<DepthFormat>(width as Size, height as Size).unwrap();
 
     let backend = shade::Backend::Vulkan;
     let mut app = A::new(&mut factory, backend, WindowTargets {
         color: win.get_any_target(),
         depth: main_depth.2,
-        aspect_ratio: width as f32 / height as f32, //TODO
-    });
+        aspect_ratio: width as f32 / height as f32,
+    }, config);
 
+    let mut cur_size = win.get_size();
+    let mut out_of_date = false;
+    let mut watcher = if config.watch_shaders { Some(shade::Watcher::new()) } else { None };
     let mut harness = Harness::new();
-    loop {
+    'main: loop {
+        let mut new_size = None;
         for event in win.get_window().poll_events() {
             match event {
-                winit::Event::Closed => return,
-                winit::Event::KeyboardInput(winit::ElementState::Pressed, _, key) if key == A::get_exit_key() => return,
-                winit::Event::Resized(_width, _height) => {
-                    warn!("TODO: resize on Vulkan");
+                winit::Event::Closed => break 'main,
+                winit::Event::KeyboardInput(winit::ElementState::Pressed, _, key) if key == A::get_exit_key() => break 'main,
+                // working around the borrow checker: window is already borrowed here,
+                // so only the last size is applied, after the events
+                winit::Event::Resized(width, height) => if (width, height) != cur_size {
+                    new_size = Some((width, height));
                 },
                 _ => app.on(event),
             }
         }
-        let mut frame = win.start_frame();
+        if out_of_date && new_size.is_none() {
+            new_size = Some(win.get_size());
+        }
+        // minimized windows report a 0x0 size, which a swapchain can't have
+        match new_size {
+            Some((0, _)) | Some((_, 0)) => new_size = None,
+            _ => (),
+        }
+        if let Some((width, height)) = new_size {
+            cur_size = (width, height);
+            out_of_date = false;
+            let (width, height) = win.resize(&mut factory, width, height);
+            let new_depth = factory.create_depth_stencil::<DepthFormat>(width as Size, height as Size).unwrap();
+            app.on_resize(&mut factory, WindowTargets {
+                color: win.get_any_target(),
+                depth: new_depth.2,
+                aspect_ratio: width as f32 / height as f32,
+            });
+            continue;
+        }
+        check_shaders(&mut watcher, &mut app, &mut factory);
+        let mut frame = match win.start_frame() {
+            Some(frame) => frame,
+            None => {
+                out_of_date = true;
+                continue;
+            }
+        };
         app.render(frame.get_queue());
         frame.get_queue().cleanup();
+        out_of_date = !frame.present();
         harness.bump();
+        if config.max_frames == Some(harness.num_frames) {
+            break;
+        }
     }
+    Some((app, harness.finish()))
 }
 
 
@@ -355,44 +719,78 @@ pub trait Application<R: gfx::Resources>: Sized {
         self.on_resize(targets);
     }
     fn on(&mut self, _event: winit::Event) {}
-
-    fn launch_simple(name: &str) where Self: Application<DefaultResources> {
+    /// Shader files to watch when `Config::watch_shaders` is set, see `shade::Loader::files`.
+    fn shader_files(&self) -> Vec<std::path::PathBuf> {
+        Vec::new()
+    }
+    /// Called when some of the `shader_files` have changed on disk. This is the place
+    /// to reload them, recreate the programs and rebuild the pipelines using them.
+    fn on_shaders_reloaded<F: gfx::Factory<R>>(&mut self, _factory: &mut F,
+                                               _changed: &[std::path::PathBuf]) {}
+
+    fn launch_simple(name: &str) -> Option<(Self, FrameStats)> where Self: Application<DefaultResources> {
+        let config = match Config::from_args_or_usage() {
+            Some(config) => config,
+            None => return None,
+        };
         let wb = winit::WindowBuilder::new().with_title(name);
-        <Self as Application<DefaultResources>>::launch_default(wb)
+        <Self as Application<DefaultResources>>::launch_default(wb, &config)
     }
     #[cfg(all(not(target_os = "windows"), not(feature = "vulkan"), not(feature = "metal")))]
-    fn launch_default(wb: winit::WindowBuilder) where Self: Application<DefaultResources> {
-        launch_gl3::<Wrap<_, _, Self>>(wb);
+    fn launch_default(wb: winit::WindowBuilder, config: &Config) -> Option<(Self, FrameStats)> where Self: Application<DefaultResources> {
+        launch_gl3::<Wrap<_, _, Self>>(wb, config).map(Wrap::unwrap)
     }
     #[cfg(all(target_os = "windows", not(feature = "vulkan")))]
-    fn launch_default(wb: winit::WindowBuilder) where Self: Application<DefaultResources> {
-        launch_d3d11::<Wrap<_, _, Self>>(wb);
+    fn launch_default(wb: winit::WindowBuilder, config: &Config) -> Option<(Self, FrameStats)> where Self: Application<DefaultResources> {
+        if config.debug_device || config.backend == Some(Backend::Direct3D11 { pix_mode: true }) {
+            launch_d3d11_immediate::<Wrap<_, _, Self>>(wb, config).map(Wrap::unwrap)
+        } else {
+            launch_d3d11::<Wrap<_, _, Self>>(wb, config).map(Wrap::unwrap)
+        }
     }
     #[cfg(feature = "metal")]
-    fn launch_default(wb: winit::WindowBuilder) where Self: Application<DefaultResources> {
-        launch_metal::<Wrap<_, _, Self>>(wb);
+    fn launch_default(wb: winit::WindowBuilder, config: &Config) -> Option<(Self, FrameStats)> where Self: Application<DefaultResources> {
+        launch_metal::<Wrap<_, _, Self>>(wb, config).map(Wrap::unwrap)
     }
     #[cfg(feature = "vulkan")]
-    fn launch_default(wb: winit::WindowBuilder) where Self: Application<DefaultResources> {
-        launch_vulkan::<Wrap<_, _, Self>>(wb);
+    fn launch_default(wb: winit::WindowBuilder, config: &Config) -> Option<(Self, FrameStats)> where Self: Application<DefaultResources> {
+        launch_vulkan::<Wrap<_, _, Self>>(wb, config).map(Wrap::unwrap)
     }
 }
 
-pub struct Wrap<R: gfx::Resources, C, A> {
+struct Frame<R: gfx::Resources, C> {
     encoder: gfx::Encoder<R, C>,
+    fence: Option<gfx::handle::Fence<R>>,
+}
+
+pub struct Wrap<R: gfx::Resources, C, A> {
+    frames: Vec<Frame<R, C>>,
+    current: usize,
     app: A,
 }
 
+impl<R: gfx::Resources, C, A> Wrap<R, C, A> {
+    fn unwrap((wrap, stats): (Self, FrameStats)) -> (A, FrameStats) {
+        (wrap.app, stats)
+    }
+}
+
 impl<R, C, A> ApplicationBase<R, C> for Wrap<R, C, A>
     where R: gfx::Resources,
           C: gfx::CommandBuffer<R>,
           A: Application<R>
 {
-    fn new<F>(factory: &mut F, backend: shade::Backend, window_targets: WindowTargets<R>) -> Self
-        where F: Factory<R, CommandBuffer = C>
+    fn new<F>(factory: &mut F, backend: shade::Backend, window_targets: WindowTargets<R>,
+              config: &Config) -> Self
+        where F: Factory<R, C>
     {
-        Wrap {
+        let frames = (0 .. std::cmp::max(config.frames_in_flight, 1)).map(|_| Frame {
             encoder: factory.create_encoder(),
+            fence: None,
+        }).collect();
+        Wrap {
+            frames: frames,
+            current: 0,
             app: A::new(factory, backend, window_targets),
         }
     }
@@ -400,8 +798,20 @@ impl<R, C, A> ApplicationBase<R, C> for Wrap<R, C, A>
     fn render<D>(&mut self, device: &mut D)
         where D: gfx::Device<Resources = R, CommandBuffer = C>
     {
-        self.app.render(&mut self.encoder);
-        self.encoder.flush(device);
+        let num_frames = self.frames.len();
+        let frame = &mut self.frames[self.current];
+        if num_frames == 1 {
+            self.app.render(&mut frame.encoder);
+            frame.encoder.flush(device);
+            return;
+        }
+        // wait for the GPU to be done with the last submission of this encoder
+        if let Some(fence) = frame.fence.take() {
+            device.wait_fence(&fence);
+        }
+        self.app.render(&mut frame.encoder);
+        frame.fence = Some(frame.encoder.fenced_flush(device).unwrap());
+        self.current = (self.current + 1) % num_frames;
     }
 
     fn get_exit_key() -> Option<winit::VirtualKeyCode> {
@@ -413,8 +823,42 @@ impl<R, C, A> ApplicationBase<R, C> for Wrap<R, C, A>
     }
 
     fn on_resize<F>(&mut self, factory: &mut F, window_targets: WindowTargets<R>)
-        where F: Factory<R, CommandBuffer = C>
+        where F: Factory<R, C>
     {
         self.app.on_resize_ext(factory, window_targets);
     }
+
+    fn shader_files(&self) -> Vec<std::path::PathBuf> {
+        self.app.shader_files()
+    }
+
+    fn on_shaders_reloaded<F>(&mut self, factory: &mut F, changed: &[std::path::PathBuf])
+        where F: Factory<R, C>
+    {
+        self.app.on_shaders_reloaded(factory, changed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdapterKind, DeviceSelection};
+
+    #[test]
+    fn test_device_selection() {
+        let adapters = [
+            ("Intel HD Graphics 530", AdapterKind::Integrated),
+            ("NVIDIA GeForce GTX 1080", AdapterKind::Discrete),
+            ("llvmpipe", AdapterKind::Software),
+        ];
+        let select = |sel: DeviceSelection| sel.select(adapters.iter().cloned());
+        assert_eq!(select(DeviceSelection::Any), Some(0));
+        assert_eq!(select(DeviceSelection::Discrete), Some(1));
+        assert_eq!(select(DeviceSelection::Integrated), Some(0));
+        assert_eq!(select(DeviceSelection::Software), Some(2));
+        assert_eq!(select(DeviceSelection::ByName("GeForce".to_string())), Some(1));
+        assert_eq!(select(DeviceSelection::ByName("Radeon".to_string())), None);
+        assert_eq!(select(DeviceSelection::Index(2)), Some(2));
+        assert_eq!(select(DeviceSelection::Index(3)), None);
+        assert_eq!(DeviceSelection::Any.select(Vec::new()), None);
+    }
 }
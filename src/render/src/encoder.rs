@@ -21,8 +21,9 @@ use std::error::Error;
 use std::any::Any;
 use std::{fmt, mem};
 
-use core::{Device, SubmissionResult, IndexType, Resources, VertexCount};
-use core::{command, format, handle, texture};
+use core::{Device, SubmissionError, SubmissionResult, IndexType, Resources, VertexCount};
+use core::{buffer, command, format, handle, texture};
+use core::factory::Factory;
 use core::memory::{self, cast_slice, Typed, Pod, Usage};
 use slice;
 use pso;
@@ -44,6 +45,10 @@ pub enum CopyError {
         dst_offset: usize,
         size: usize,
     },
+    OutOfSrcTextureBounds {
+        size: [texture::Size; 3],
+        copy_end: [texture::Size; 3],
+    },
     NoSrcBindFlag,
     NoDstBindFlag,
 }
@@ -58,6 +63,8 @@ impl fmt::Display for CopyError {
                 write!(f, "{}: {} / {}", self.description(), copy_end, size),
             OutOfDstBounds { ref size, ref copy_end } =>
                 write!(f, "{}: {} / {}", self.description(), copy_end, size),
+            OutOfSrcTextureBounds { ref size, ref copy_end } =>
+                write!(f, "{}: {:?} / {:?}", self.description(), copy_end, size),
             Overlap { ref src_offset, ref dst_offset, ref size } =>
                 write!(f, "{}: [{} - {}] to [{} - {}]",
                        self.description(),
@@ -75,12 +82,52 @@ impl Error for CopyError {
             OutOfSrcBounds {..} => "Copy source is out of bounds",
             OutOfDstBounds {..} => "Copy destination is out of bounds",
             Overlap {..} => "Copy source and destination are overlapping",
+            OutOfSrcTextureBounds {..} => "Copy source is out of the texture bounds",
             NoSrcBindFlag => "Copy source is missing `TRANSFER_SRC`",
             NoDstBindFlag => "Copy destination is missing `TRANSFER_DST`",
         }
     }
 }
 
+/// An error reading a texture back, see `Encoder::read_texture`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReadError {
+    /// The download buffer could not be created.
+    Buffer(buffer::CreationError),
+    /// The texture could not be copied to the download buffer.
+    Copy(CopyError),
+    /// The copy could not be submitted.
+    Submission(SubmissionError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadError::Buffer(ref e) => write!(f, "{}: {}", self.description(), e),
+            ReadError::Copy(ref e) => write!(f, "{}: {}", self.description(), e),
+            ReadError::Submission(ref e) => write!(f, "{}: {}", self.description(), e),
+        }
+    }
+}
+
+impl Error for ReadError {
+    fn description(&self) -> &str {
+        match *self {
+            ReadError::Buffer(_) => "Unable to create the download buffer",
+            ReadError::Copy(_) => "Unable to copy the texture",
+            ReadError::Submission(_) => "Unable to submit the copy",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            ReadError::Buffer(ref e) => Some(e),
+            ReadError::Copy(ref e) => Some(e),
+            ReadError::Submission(ref e) => Some(e),
+        }
+    }
+}
+
 /// An error occuring in buffer/texture updates.
 #[allow(missing_docs)]
 #[derive(Clone, Debug, PartialEq)]
@@ -182,6 +229,17 @@ impl<R: Resources, C: command::Buffer<R>> Encoder<R, C> {
         device.submit(&mut self.command_buffer, &self.access_info)
     }
 
+    /// Like `flush`, but returns a fence that is signaled once the GPU has executed
+    /// the commands, so that the caller can keep recording other encoders meanwhile.
+    pub fn fenced_flush<D>(&mut self, device: &mut D) -> SubmissionResult<handle::Fence<R>>
+        where D: Device<Resources=R, CommandBuffer=C>
+    {
+        device.pin_submitted_resources(&self.handles);
+        let fence = try!(device.fenced_submit(&mut self.command_buffer, &self.access_info, None));
+        self.reset();
+        Ok(fence)
+    }
+
     /// Resets the encoded commands.
     pub fn reset(&mut self) {
         self.command_buffer.reset();
@@ -236,6 +294,91 @@ impl<R: Resources, C: command::Buffer<R>> Encoder<R, C> {
         Ok(())
     }
 
+    /// Copy a region of a texture to a buffer, with the texels tightly packed
+    /// row after row, e.g. to read them back on the CPU with a download buffer.
+    pub fn copy_texture_to_buffer_raw(&mut self, src: &handle::RawTexture<R>,
+                                      face: Option<texture::CubeFace>, img: texture::RawImageInfo,
+                                      dst: &handle::RawBuffer<R>, dst_offset_bytes: usize)
+                                      -> CopyResult {
+        if !src.get_info().bind.contains(memory::TRANSFER_SRC) {
+            return Err(CopyError::NoSrcBindFlag);
+        }
+        if !dst.get_info().bind.contains(memory::TRANSFER_DST) {
+            return Err(CopyError::NoDstBindFlag);
+        }
+
+        let dim = src.get_info().kind.get_dimensions();
+        if !img.is_inside(dim) {
+            let (w, h, d, _) = dim;
+            return Err(CopyError::OutOfSrcTextureBounds {
+                size: [w, h, d],
+                copy_end: [
+                    img.xoffset + img.width,
+                    img.yoffset + img.height,
+                    img.zoffset + img.depth,
+                ],
+            });
+        }
+        let size_bytes = img.get_texel_count() * (img.format.0.get_total_bits() as usize / 8);
+        let dst_copy_end = dst_offset_bytes + size_bytes;
+        if dst_copy_end > dst.get_info().size {
+            return Err(CopyError::OutOfDstBounds {
+                size: dst.get_info().size,
+                copy_end: dst_copy_end,
+            });
+        }
+        self.access_info.buffer_write(dst);
+
+        self.command_buffer.copy_texture_to_buffer(
+            self.handles.ref_texture(src).clone(),
+            src.get_info().kind, face, img,
+            self.handles.ref_buffer(dst).clone(),
+            dst_offset_bytes);
+        Ok(())
+    }
+
+    /// Copy a region of a texture to a buffer, see `copy_texture_to_buffer_raw`.
+    /// The offset is in elements of the buffer.
+    pub fn copy_texture_to_buffer<S, T>(&mut self, src: &handle::Texture<R, T::Surface>,
+                                        face: Option<texture::CubeFace>, img: texture::NewImageInfo,
+                                        dst: &handle::Buffer<R, S::DataType>, dst_offset: usize)
+                                        -> CopyResult
+    where
+        S: format::SurfaceTyped,
+        T: format::Formatted<Surface = S>,
+    {
+        self.copy_texture_to_buffer_raw(src.raw(), face, img.convert(T::get_format()),
+                                        dst.raw(), dst_offset * mem::size_of::<S::DataType>())
+    }
+
+    /// Read a region of a texture back on the CPU, e.g. to take a screenshot.
+    ///
+    /// The region is copied to a new download buffer, then the commands recorded so far
+    /// are flushed and waited for, so that the returned buffer can be mapped right away
+    /// with `Factory::read_mapping`. Since this stalls the CPU, it is not meant to be
+    /// used every frame.
+    pub fn read_texture<S, T, F, D>(&mut self, factory: &mut F, device: &mut D,
+                                    src: &handle::Texture<R, T::Surface>,
+                                    face: Option<texture::CubeFace>, img: texture::NewImageInfo)
+                                    -> Result<handle::Buffer<R, S::DataType>, ReadError>
+    where
+        S: format::SurfaceTyped,
+        T: format::Formatted<Surface = S>,
+        F: Factory<R>,
+        D: Device<Resources=R, CommandBuffer=C>,
+    {
+        let buffer = try!(factory.create_buffer(img.get_texel_count(),
+                                                buffer::Role::Staging,
+                                                Usage::Download,
+                                                memory::TRANSFER_DST)
+                                 .map_err(ReadError::Buffer));
+        try!(self.copy_texture_to_buffer::<S, T>(src, face, img, &buffer, 0)
+                 .map_err(ReadError::Copy));
+        let fence = try!(self.fenced_flush(device).map_err(ReadError::Submission));
+        device.wait_fence(&fence);
+        Ok(buffer)
+    }
+
     /// Update a buffer with a slice of data.
     pub fn update_buffer<T: Pod>(&mut self, buf: &handle::Buffer<R, T>,
                          data: &[T], offset_elements: usize)
@@ -333,6 +476,20 @@ impl<R: Resources, C: command::Buffer<R>> Encoder<R, C> {
         }
     }
 
+    /// Begins a group of commands labeled `name`, which graphics debuggers like RenderDoc show.
+    /// The group is closed by `end_debug_group`, and groups may be nested.
+    pub fn begin_debug_group(&mut self, name: &str) {
+        self.command_buffer.begin_debug_group(name)
+    }
+    /// Ends the last group of commands begun with `begin_debug_group`.
+    pub fn end_debug_group(&mut self) {
+        self.command_buffer.end_debug_group()
+    }
+    /// Inserts a marker labeled `name` between the commands, shown by graphics debuggers.
+    pub fn insert_debug_marker(&mut self, name: &str) {
+        self.command_buffer.insert_debug_marker(name)
+    }
+
     /// Clears the supplied `RenderTargetView` to the supplied `ClearColor`.
     pub fn clear<T: format::RenderFormat>(&mut self,
                  view: &handle::RenderTargetView<R, T>, value: T::View)
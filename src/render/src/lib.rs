@@ -44,7 +44,7 @@ pub use core::memory::{self, Bind, TRANSFER_SRC, TRANSFER_DST, RENDER_TARGET,
 pub use core::command::{Buffer as CommandBuffer, InstanceParams};
 pub use core::shade::{ProgramInfo, UniformValue};
 
-pub use encoder::{Encoder, UpdateError};
+pub use encoder::{Encoder, CopyError, ReadError, UpdateError};
 pub use factory::PipelineStateError;
 pub use slice::{Slice, IntoIndexBuffer, IndexBuffer};
 pub use pso::{PipelineState};
@@ -62,6 +62,8 @@ mod encoder;
 mod factory;
 /// Slices
 mod slice;
+/// Streaming buffers
+pub mod streaming;
 // Pipeline states
 pub mod pso;
 /// Shaders
@@ -0,0 +1,99 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming of per-frame data through persistently mapped buffers.
+
+use core::{buffer, handle, mapping, memory, Device, Resources};
+use core::factory::Factory;
+use core::memory::{Bind, Pod};
+
+/// Upload buffer split into one region per frame in flight, so that the data of
+/// a frame can be written while the GPU is still reading the previous ones.
+///
+/// Every frame, the data is written with `write_frame`, and once the commands using
+/// it are submitted, the fence of the submission is given back with `end_frame`.
+/// Writing to a region only waits for the GPU if it is still using the data that
+/// was written there `frames` frames ago.
+pub struct RingBuffer<R: Resources, T> {
+    buffer: handle::Buffer<R, T>,
+    capacity: usize,
+    fences: Vec<Option<handle::Fence<R>>>,
+    current: usize,
+}
+
+impl<R: Resources, T: Pod> RingBuffer<R, T> {
+    /// Create a ring of `frames` regions of `capacity` elements each.
+    /// The buffer has `Upload` usage, the role and bind flags tell how the GPU uses it.
+    pub fn new<F: Factory<R>>(factory: &mut F, capacity: usize, frames: usize,
+                              role: buffer::Role, bind: Bind)
+                              -> Result<RingBuffer<R, T>, buffer::CreationError> {
+        assert!(frames > 0, "A ring buffer needs at least one frame");
+        let buffer = try!(factory.create_buffer(capacity * frames, role,
+                                                memory::Usage::Upload, bind));
+        Ok(RingBuffer {
+            buffer: buffer,
+            capacity: capacity,
+            fences: (0 .. frames).map(|_| None).collect(),
+            current: frames - 1,
+        })
+    }
+
+    /// Write the data of the next frame to its region, after waiting for the GPU
+    /// to be done with the previous contents of the region.
+    ///
+    /// Returns the buffer, and the offset of the region in elements.
+    pub fn write_frame<F, D>(&mut self, factory: &mut F, device: &mut D, data: &[T])
+                             -> Result<(handle::Buffer<R, T>, usize), mapping::Error>
+        where F: Factory<R>, D: Device<Resources=R>
+    {
+        if data.len() > self.capacity {
+            return Err(mapping::Error::OutOfBounds {
+                start: 0,
+                end: data.len(),
+                len: self.capacity,
+            });
+        }
+        self.current = (self.current + 1) % self.fences.len();
+        if let Some(fence) = self.fences[self.current].take() {
+            device.wait_fence(&fence);
+        }
+
+        let offset = self.current * self.capacity;
+        // the fences of `end_frame` keep the GPU away from the region
+        let mut writer = try!(unsafe { factory.write_mapping_unsynchronized(&self.buffer) });
+        try!(writer.copy_from_slice(offset, data));
+        try!(writer.flush_range(offset, offset + data.len()));
+        Ok((self.buffer.clone(), offset))
+    }
+
+    /// Give the fence of the submission that uses the data of the current frame.
+    pub fn end_frame(&mut self, fence: handle::Fence<R>) {
+        self.fences[self.current] = Some(fence);
+    }
+
+    /// Get the underlying buffer.
+    pub fn buffer(&self) -> &handle::Buffer<R, T> {
+        &self.buffer
+    }
+
+    /// Get the number of elements of each region.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of regions, i.e. of frames that can be in flight.
+    pub fn frames(&self) -> usize {
+        self.fences.len()
+    }
+}
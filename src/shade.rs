@@ -13,8 +13,20 @@
 // limitations under the License.
 
 
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fmt;
+use std::{fmt, fs, io};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use gfx;
+use gfx::shade::core::{self as core_shade, CreateProgramError, CreateShaderError};
+use gfx_device_gl;
+#[cfg(feature = "spirv-cross")]
+use cross::CrossCompiler;
+pub use gfx::shade::core::Stage;
 
 pub use gfx_device_gl::Version as GlslVersion;
 #[cfg(target_os = "windows")]
@@ -36,6 +48,36 @@ pub enum Backend {
 
 pub const EMPTY: &'static [u8] = &[];
 
+/// First word of every SPIR-V module.
+pub const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+/// Check that `code` looks like a SPIR-V module: a whole number of words,
+/// starting with the magic number in either byte order.
+pub fn is_spirv(code: &[u8]) -> bool {
+    if code.len() < 4 || code.len() % 4 != 0 {
+        return false;
+    }
+    let le = code[0] as u32 | (code[1] as u32) << 8 | (code[2] as u32) << 16 | (code[3] as u32) << 24;
+    let be = code[3] as u32 | (code[2] as u32) << 8 | (code[1] as u32) << 16 | (code[0] as u32) << 24;
+    le == SPIRV_MAGIC || be == SPIRV_MAGIC
+}
+
+/// Read a precompiled SPIR-V module, typically a `.spv` file produced by `glslangValidator`,
+/// to be referenced by the `vulkan` member of a `Source`.
+pub fn load_spirv<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    use std::fs::File;
+    use std::io::Read;
+
+    let mut code = Vec::new();
+    try!(try!(File::open(path.as_ref())).read_to_end(&mut code));
+    if is_spirv(&code) {
+        Ok(code)
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData,
+                           format!("{} is not a SPIR-V module", path.as_ref().display())))
+    }
+}
+
 /// A type storing shader source for different graphics APIs and versions.
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Source<'a> {
@@ -54,6 +96,7 @@ pub struct Source<'a> {
     pub hlsl_50: &'a [u8],
     pub msl_10: &'a [u8],
     pub msl_11: &'a [u8],
+    /// Precompiled SPIR-V module, see `load_spirv`.
     pub vulkan: &'a [u8],
 }
 
@@ -142,10 +185,1497 @@ impl<'a> Source<'a> {
             #[cfg(feature = "vulkan")]
             Backend::Vulkan => {
                 match *self {
-                    Source { vulkan: s, .. } if s != EMPTY => s,
+                    Source { vulkan: s, .. } if is_spirv(s) => s,
+                    Source { vulkan: s, .. } if s != EMPTY => {
+                        error!("The Vulkan shader source is not a SPIR-V module");
+                        return Err(SelectError(backend))
+                    }
                     _ => return Err(SelectError(backend)),
                 }
             }
         })
     }
+
+    /// Pick a version of a compute shader for the backend, only considering
+    /// the versions that support the compute stage: GLSL 4.30, HLSL model 5.0,
+    /// every MSL revision and SPIR-V. GLSL ES has no compute stage before 3.10.
+    pub fn select_compute(&self, backend: Backend) -> Result<&'a [u8], SelectError> {
+        Source {
+            glsl_430: self.glsl_430,
+            hlsl_50: self.hlsl_50,
+            msl_10: self.msl_10,
+            msl_11: self.msl_11,
+            vulkan: self.vulkan,
+            .. Source::empty()
+        }.select(backend)
+    }
+
+    /// Copy the sources, injecting a `#define name value` for each of the `defines`,
+    /// after the `#version` directive for GLSL and at the top for HLSL and Metal.
+    /// Compiled HLSL bytecode and SPIR-V are left untouched, as there is nothing to
+    /// inject into; use specialization or separate binaries for those.
+    pub fn with_defines(&self, defines: &[(&str, &str)]) -> SourceBuf {
+        self.map(|field, code| {
+            if code.is_empty() {
+                Vec::new()
+            } else if field.starts_with("glsl") {
+                inject_defines(code, defines, true)
+            } else if field.starts_with("msl") || field.starts_with("hlsl") && !code.starts_with(b"DXBC") {
+                inject_defines(code, defines, false)
+            } else {
+                if !defines.is_empty() {
+                    warn!("Unable to inject defines into the compiled {} shader", field);
+                }
+                code.to_vec()
+            }
+        })
+    }
+
+    /// Same as `select`, but when there is no suitable version for a GLSL backend,
+    /// the highest GLSL version available is translated with `translate_glsl`.
+    pub fn select_translated(&self, backend: Backend, stage: Stage) -> Result<Cow<'a, [u8]>, SelectError> {
+        let error = match self.select(backend) {
+            Ok(code) => return Ok(Cow::Borrowed(code)),
+            Err(e) => e,
+        };
+        let (version, embedded) = match backend {
+            Backend::Glsl(v) => (v.major * 100 + v.minor, false),
+            Backend::GlslEs(v) => (v.major * 100 + v.minor, true),
+            #[allow(unreachable_patterns)]
+            _ => return Err(error),
+        };
+        let candidates = [self.glsl_430, self.glsl_400, self.glsl_150, self.glsl_140, self.glsl_130, self.glsl_120,
+                          self.glsl_es_300, self.glsl_es_200, self.glsl_es_100];
+        match candidates.iter().find(|code| !code.is_empty()) {
+            Some(code) => {
+                let code = String::from_utf8_lossy(code);
+                Ok(Cow::Owned(translate_glsl(&code, stage, version, embedded).into_bytes()))
+            }
+            None => Err(error),
+        }
+    }
+}
+
+/// Check that the backend, at its version, has the given shader stage:
+/// geometry shaders need GLSL 1.50 or HLSL model 4.0, tessellation needs GLSL 4.00
+/// or HLSL model 5.0. Neither is available with GLSL ES or Metal.
+pub fn supports_stage(backend: Backend, stage: Stage) -> bool {
+    match (backend, stage) {
+        (_, Stage::Vertex) | (_, Stage::Pixel) => true,
+        (Backend::Glsl(version), Stage::Geometry) => version.major * 100 + version.minor >= 150,
+        (Backend::Glsl(version), _) => version.major * 100 + version.minor >= 400,
+        (Backend::GlslEs(_), _) => false,
+        #[cfg(target_os = "windows")]
+        (Backend::Hlsl(model), Stage::Geometry) => model >= 40,
+        #[cfg(target_os = "windows")]
+        (Backend::Hlsl(model), _) => model >= 50,
+        #[cfg(feature = "metal")]
+        (Backend::Msl(_), _) => false,
+        #[cfg(feature = "vulkan")]
+        (Backend::Vulkan, _) => true,
+    }
+}
+
+/// Error selecting the sources of a `Program`, or creating its shaders.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgramError {
+    /// The backend, at its version, doesn't have this shader stage.
+    UnsupportedStage(Stage, Backend),
+    /// None of the sources of the stage can be used with the backend.
+    Select(Stage, SelectError),
+    /// Hull and domain shaders need to be given together, and can't be
+    /// combined with a geometry shader.
+    InvalidStages,
+    /// The shader failed to compile.
+    Compile(CompileError),
+    /// The shader of the stage failed to be created.
+    Create(Stage, CreateShaderError),
+    /// The shaders failed to be linked into a program.
+    Link(CreateProgramError),
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProgramError::UnsupportedStage(stage, backend) =>
+                write!(f, "The {:?} backend doesn't support {:?} shaders", backend, stage),
+            ProgramError::Select(stage, ref e) => write!(f, "{:?} shader: {}", stage, e),
+            ProgramError::InvalidStages => write!(f, "{}", self.description()),
+            ProgramError::Compile(ref e) => write!(f, "{}", e),
+            ProgramError::Create(stage, ref e) => write!(f, "{:?} shader: {}", stage, e),
+            ProgramError::Link(ref e) => write!(f, "{}: {}", self.description(), e),
+        }
+    }
+}
+
+impl Error for ProgramError {
+    fn description(&self) -> &str {
+        match *self {
+            ProgramError::UnsupportedStage(..) => "The backend doesn't support the shader stage",
+            ProgramError::Select(..) => "No shader source for the backend",
+            ProgramError::InvalidStages => "Unsupported combination of shader stages",
+            ProgramError::Compile(_) => "The shader failed to compile",
+            ProgramError::Create(..) => "The shader failed to be created",
+            ProgramError::Link(_) => "The program failed to be linked",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            ProgramError::Select(_, ref e) => Some(e),
+            ProgramError::Compile(ref e) => Some(e),
+            ProgramError::Create(_, ref e) => Some(e),
+            ProgramError::Link(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of a compiler diagnostic.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A message of the shader compiler, located in the source when the log says where.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Line in the source, starting from 1.
+    pub line: Option<usize>,
+    /// Column in the line, starting from 1.
+    pub column: Option<usize>,
+    /// File and line the source line comes from, for sources expanded by `preprocess`.
+    /// Filled by `Loader::locate`.
+    pub origin: Option<(PathBuf, usize)>,
+    pub message: String,
+}
+
+fn split_location(line: &str) -> Option<(Severity, &str, &str)> {
+    let lower = line.to_lowercase();
+    // AMD and ANGLE put the severity first, e.g. `ERROR: 0:12: 'x' : undeclared identifier`
+    for &(prefix, severity) in &[("error:", Severity::Error), ("warning:", Severity::Warning)] {
+        if lower.starts_with(prefix) {
+            let rest = line[prefix.len()..].trim_left();
+            return Some(match rest.find(": ") {
+                Some(pos) => (severity, &rest[..pos], rest[pos + 1..].trim()),
+                None => (severity, "", rest),
+            });
+        }
+    }
+    // everyone else puts the location first, e.g. `0(12) : error C0000: ...`
+    // or `program_source:12:5: error: ...`
+    let (pos, keyword, severity) = match find_severity(line) {
+        Some(found) => found,
+        None => return None,
+    };
+    let location = line[..pos].trim().trim_right_matches(':').trim();
+    let message = line[pos + keyword.len()..].trim_left_matches(|c: char| c != ':');
+    let message = message.trim_left_matches(':').trim();
+    Some((severity, location, message))
+}
+
+// Find the first severity keyword written as a whole word right after the location,
+// so that `error` in `0(3) : warning C7050: "error_count" ...` is part of the message.
+fn find_severity(line: &str) -> Option<(usize, &'static str, Severity)> {
+    let mut found: Option<(usize, &'static str, Severity)> = None;
+    for &(keyword, severity) in &[("error", Severity::Error), ("warning", Severity::Warning)] {
+        let mut start = 0;
+        while let Some(offset) = line[start..].find(keyword) {
+            let pos = start + offset;
+            let follows_location = line[..pos].trim_right().ends_with(':');
+            let whole_word = line[pos + keyword.len()..].starts_with(|c: char| c == ':' || c.is_whitespace());
+            if follows_location && whole_word {
+                if found.map_or(true, |(first, _, _)| pos < first) {
+                    found = Some((pos, keyword, severity));
+                }
+                break;
+            }
+            start = pos + keyword.len();
+        }
+    }
+    found
+}
+
+fn numbers(text: &str) -> Vec<usize> {
+    text.split(|c: char| !c.is_digit(10))
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| s.parse().ok())
+        .collect()
+}
+
+fn parse_diagnostic(backend: Backend, line: &str) -> Option<Diagnostic> {
+    let (severity, location, message) = match split_location(line) {
+        Some(parts) => parts,
+        None => return None,
+    };
+    let position = match backend {
+        // `0(12)` from NVIDIA, `0:12(5)` from Mesa, `0:12` from AMD,
+        // the first number being the index of the source string
+        Backend::Glsl(_) | Backend::GlslEs(_) => {
+            let n = numbers(location);
+            (n.get(1).cloned(), n.get(2).cloned())
+        }
+        // `path\to\shader.hlsl(12,5)` or `(12,5-9)`, the path may contain digits
+        #[cfg(target_os = "windows")]
+        Backend::Hlsl(_) => {
+            let n = numbers(location.rfind('(').map_or("", |pos| &location[pos..]));
+            (n.get(0).cloned(), n.get(1).cloned())
+        }
+        // `program_source:12:5`
+        #[cfg(feature = "metal")]
+        Backend::Msl(_) => {
+            let n = numbers(location);
+            match n.len() {
+                0 => (None, None),
+                1 => (Some(n[0]), None),
+                len => (Some(n[len - 2]), Some(n[len - 1])),
+            }
+        }
+        #[allow(unreachable_patterns)]
+        _ => (None, None),
+    };
+    Some(Diagnostic {
+        severity: severity,
+        line: position.0,
+        column: position.1,
+        origin: None,
+        message: message.to_string(),
+    })
+}
+
+/// Shader compilation failure, with the diagnostics parsed from the compiler log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompileError {
+    pub stage: Stage,
+    pub backend: Backend,
+    /// Log of the compiler, as is.
+    pub log: String,
+    /// Messages of the log that could be parsed.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Source the shader was compiled from, to show the lines the diagnostics point at.
+    pub source: String,
+}
+
+impl CompileError {
+    /// Parse the compiler log of a shader.
+    pub fn new(stage: Stage, backend: Backend, log: &str, source: &[u8]) -> CompileError {
+        CompileError {
+            stage: stage,
+            backend: backend,
+            log: log.to_string(),
+            diagnostics: log.lines().filter_map(|line| parse_diagnostic(backend, line)).collect(),
+            source: String::from_utf8_lossy(source).into_owned(),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(writeln!(f, "Failed to compile the {:?} shader for {:?}", self.stage, self.backend));
+        if self.diagnostics.is_empty() {
+            return write!(f, "{}", self.log.trim_right());
+        }
+        for d in &self.diagnostics {
+            let severity = match d.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            match (&d.origin, d.line, d.column) {
+                (&Some((ref path, line)), _, Some(column)) =>
+                    try!(writeln!(f, "{}:{}:{}: {}: {}", path.display(), line, column, severity, d.message)),
+                (&Some((ref path, line)), _, None) =>
+                    try!(writeln!(f, "{}:{}: {}: {}", path.display(), line, severity, d.message)),
+                (_, Some(line), Some(column)) => try!(writeln!(f, "{}:{}: {}: {}", line, column, severity, d.message)),
+                (_, Some(line), None) => try!(writeln!(f, "{}: {}: {}", line, severity, d.message)),
+                _ => try!(writeln!(f, "{}: {}", severity, d.message)),
+            }
+            if let Some(text) = d.line.and_then(|line| self.source.lines().nth(line.wrapping_sub(1))) {
+                try!(writeln!(f, "    | {}", text));
+                if let Some(column) = d.column {
+                    try!(writeln!(f, "    | {:>1$}", "^", column));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for CompileError {
+    fn description(&self) -> &str {
+        "The shader failed to compile"
+    }
+}
+
+/// Sources of all the stages of a shader program.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Program<'a> {
+    pub vertex: Source<'a>,
+    pub hull: Option<Source<'a>>,
+    pub domain: Option<Source<'a>>,
+    pub geometry: Option<Source<'a>>,
+    pub pixel: Source<'a>,
+}
+
+/// Shader code of the stages of a `Program`, as selected for a backend.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ProgramCode<'a> {
+    pub vertex: &'a [u8],
+    pub hull: Option<&'a [u8]>,
+    pub domain: Option<&'a [u8]>,
+    pub geometry: Option<&'a [u8]>,
+    pub pixel: &'a [u8],
+}
+
+impl<'a> Program<'a> {
+    /// Create a program with only the vertex and pixel stages.
+    pub fn new(vertex: Source<'a>, pixel: Source<'a>) -> Program<'a> {
+        Program {
+            vertex: vertex,
+            hull: None,
+            domain: None,
+            geometry: None,
+            pixel: pixel,
+        }
+    }
+
+    /// Pick the code of every stage for the backend, checking first that
+    /// the backend supports all the stages of the program.
+    pub fn select(&self, backend: Backend) -> Result<ProgramCode<'a>, ProgramError> {
+        match (self.hull.is_some(), self.domain.is_some(), self.geometry.is_some()) {
+            (true, false, _) | (false, true, _) | (true, true, true) =>
+                return Err(ProgramError::InvalidStages),
+            _ => (),
+        }
+        let select = |stage, source: Option<Source<'a>>| match source {
+            Some(_) if !supports_stage(backend, stage) =>
+                Err(ProgramError::UnsupportedStage(stage, backend)),
+            Some(ref source) => source.select(backend)
+                                      .map(Some)
+                                      .map_err(|e| ProgramError::Select(stage, e)),
+            None => Ok(None),
+        };
+        Ok(ProgramCode {
+            vertex: try!(self.vertex.select(backend).map_err(|e| ProgramError::Select(Stage::Vertex, e))),
+            hull: try!(select(Stage::Hull, self.hull)),
+            domain: try!(select(Stage::Domain, self.domain)),
+            geometry: try!(select(Stage::Geometry, self.geometry)),
+            pixel: try!(self.pixel.select(backend).map_err(|e| ProgramError::Select(Stage::Pixel, e))),
+        })
+    }
+
+    /// Pick the first of the `programs` that the backend can run, in order of preference,
+    /// returning its index along with its code. For instance, a tessellated program using
+    /// HLSL model 5.0 can be followed by a simpler one, so that the application still runs
+    /// on feature level 10 hardware.
+    ///
+    /// If none of them can be used, the error is the one of the preferred program.
+    pub fn select_first(programs: &[Program<'a>], backend: Backend)
+                        -> Result<(usize, ProgramCode<'a>), ProgramError> {
+        let mut first_error = None;
+        for (i, program) in programs.iter().enumerate() {
+            match program.select(backend) {
+                Ok(code) => {
+                    if i != 0 {
+                        info!("Falling back to program {} for {:?}: {}", i, backend, first_error.as_ref().unwrap());
+                    }
+                    return Ok((i, code));
+                }
+                Err(e) => if first_error.is_none() {
+                    first_error = Some(e);
+                },
+            }
+        }
+        Err(first_error.unwrap_or(ProgramError::InvalidStages))
+    }
+
+    /// Select the code for the backend and create the matching `ShaderSet`.
+    pub fn create_shader_set<R, F>(&self, factory: &mut F, backend: Backend)
+                                   -> Result<gfx::ShaderSet<R>, ProgramError> where
+        R: gfx::Resources,
+        F: gfx::Factory<R>,
+    {
+        let code = try!(self.select(backend));
+        code.create_shader_set(factory, backend)
+    }
+
+    /// Same as `create_shader_set`, but with the first of the `programs` that the
+    /// backend can run, see `select_first`. The index of the program is returned
+    /// along with the shaders.
+    pub fn create_first_shader_set<R, F>(programs: &[Program<'a>], factory: &mut F, backend: Backend)
+                                         -> Result<(usize, gfx::ShaderSet<R>), ProgramError> where
+        R: gfx::Resources,
+        F: gfx::Factory<R>,
+    {
+        let (index, code) = try!(Program::select_first(programs, backend));
+        code.create_shader_set(factory, backend).map(|set| (index, set))
+    }
+
+    /// Same as `create_shader_set`, but on failure the error is printed, with the
+    /// source lines the compiler complains about, and the process exits.
+    pub fn expect_shader_set<R, F>(&self, factory: &mut F, backend: Backend) -> gfx::ShaderSet<R> where
+        R: gfx::Resources,
+        F: gfx::Factory<R>,
+    {
+        use std::io::Write;
+        use std::process;
+
+        match self.create_shader_set(factory, backend) {
+            Ok(set) => set,
+            Err(e) => {
+                let _ = writeln!(io::stderr(), "{}", e);
+                process::exit(1)
+            }
+        }
+    }
+
+    /// Create the program and return its interface, as reflected by the backend:
+    /// vertex attributes, constant buffer layouts, textures and samplers.
+    pub fn reflect<R, F>(&self, factory: &mut F, backend: Backend) -> Result<gfx::ProgramInfo, ProgramError> where
+        R: gfx::Resources,
+        F: gfx::Factory<R>,
+    {
+        let set = try!(self.create_shader_set(factory, backend));
+        let program = try!(factory.create_program(&set).map_err(ProgramError::Link));
+        Ok(program.get_info().clone())
+    }
+
+    /// Create the GL program, from the binary stored in the `cache` by a previous
+    /// run if there is one the driver accepts, and otherwise from the shaders, in
+    /// which case the binary of the new program is stored in the `cache`.
+    pub fn create_gl_program(&self, factory: &mut gfx_device_gl::Factory, backend: Backend, cache: &Cache)
+                             -> Result<gfx::handle::Program<gfx_device_gl::Resources>, ProgramError> {
+        use gfx::Factory;
+
+        let code = try!(self.select(backend));
+        let key = Cache::key(backend, &[Some(code.vertex), code.hull, code.domain, code.geometry, Some(code.pixel)]);
+        let usage = code.usage();
+        if let Some(data) = cache.load(key) {
+            if data.len() > 4 {
+                let binary = gfx_device_gl::ProgramBinary {
+                    format: data[0] as u32 | (data[1] as u32) << 8 | (data[2] as u32) << 16 | (data[3] as u32) << 24,
+                    data: data[4..].to_vec(),
+                };
+                match factory.create_program_from_binary(&binary, usage) {
+                    Ok(program) => return Ok(program),
+                    Err(e) => info!("Cached program binary rejected, linking again: {}", e),
+                }
+            }
+        }
+
+        let set = try!(code.create_shader_set(factory, backend));
+        let program = try!(factory.create_program(&set).map_err(ProgramError::Link));
+        if let Some(binary) = factory.get_program_binary(&program) {
+            let f = binary.format;
+            let mut data = vec![f as u8, (f >> 8) as u8, (f >> 16) as u8, (f >> 24) as u8];
+            data.extend_from_slice(&binary.data);
+            if let Err(e) = cache.store(key, &data) {
+                warn!("Unable to store the program binary: {}", e);
+            }
+        }
+        Ok(program)
+    }
+}
+
+impl<'a> ProgramCode<'a> {
+    /// Create the shaders of the stages and return them as a `ShaderSet`.
+    pub fn create_shader_set<R, F>(&self, factory: &mut F, backend: Backend)
+                                   -> Result<gfx::ShaderSet<R>, ProgramError> where
+        R: gfx::Resources,
+        F: gfx::Factory<R>,
+    {
+        let code = *self;
+        let fail = |stage, code: &[u8], e| match e {
+            CreateShaderError::CompilationFailed(ref log) =>
+                ProgramError::Compile(CompileError::new(stage, backend, log, code)),
+            e => ProgramError::Create(stage, e),
+        };
+        let vs = try!(factory.create_shader_vertex(code.vertex)
+                             .map_err(|e| fail(Stage::Vertex, code.vertex, e)));
+        let ps = try!(factory.create_shader_pixel(code.pixel)
+                             .map_err(|e| fail(Stage::Pixel, code.pixel, e)));
+        Ok(match code {
+            ProgramCode { hull: Some(hs), domain: Some(ds), .. } => {
+                let hs = try!(factory.create_shader_hull(hs)
+                                     .map_err(|e| fail(Stage::Hull, hs, e)));
+                let ds = try!(factory.create_shader_domain(ds)
+                                     .map_err(|e| fail(Stage::Domain, ds, e)));
+                gfx::ShaderSet::Tessellated(vs, hs, ds, ps)
+            }
+            ProgramCode { geometry: Some(gs), .. } => {
+                let gs = try!(factory.create_shader_geometry(gs)
+                                     .map_err(|e| fail(Stage::Geometry, gs, e)));
+                gfx::ShaderSet::Geometry(vs, gs, ps)
+            }
+            _ => gfx::ShaderSet::Simple(vs, ps),
+        })
+    }
+
+    /// Return the stages using the parameters of the program.
+    pub fn usage(&self) -> core_shade::Usage {
+        let mut usage = core_shade::VERTEX | core_shade::PIXEL;
+        if self.geometry.is_some() {
+            usage = usage | core_shade::GEOMETRY;
+        }
+        if self.hull.is_some() {
+            usage = usage | core_shade::HULL | core_shade::DOMAIN;
+        }
+        usage
+    }
+}
+
+/// Part of the interface of a program that a pipeline `Init` struct leaves unbound.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Unbound {
+    VertexAttribute(String),
+    ConstantBuffer(String),
+    ResourceView(String),
+    UnorderedView(String),
+    Sampler(String),
+    Output(String),
+}
+
+/// Check a pipeline `Init` struct against the interface of a linked program,
+/// without creating the PSO.
+///
+/// Components of the `Init` struct that don't match the program make an error,
+/// the same as `create_pipeline_from_program` would return. Otherwise, the inputs
+/// and outputs of the program that the `Init` struct doesn't provide are returned:
+/// they are not an error for the backends, but are likely to be a mistake.
+pub fn check_init<R, I>(program: &gfx::handle::Program<R>, init: &I)
+                        -> Result<Vec<Unbound>, gfx::pso::InitError<String>> where
+    R: gfx::Resources,
+    I: gfx::pso::PipelineInit,
+{
+    let info = program.get_info();
+    let mut desc = gfx::pso::Descriptor::new(gfx::Primitive::TriangleList, gfx::state::Rasterizer::new_fill());
+    try!(init.link_to(&mut desc, info));
+
+    let mut unbound = Vec::new();
+    unbound.extend(info.vertex_attributes.iter()
+        .filter(|var| desc.attributes[var.slot as usize].is_none())
+        .map(|var| Unbound::VertexAttribute(var.name.clone())));
+    unbound.extend(info.constant_buffers.iter()
+        .filter(|var| desc.constant_buffers[var.slot as usize].is_none())
+        .map(|var| Unbound::ConstantBuffer(var.name.clone())));
+    unbound.extend(info.textures.iter()
+        .filter(|var| desc.resource_views[var.slot as usize].is_none())
+        .map(|var| Unbound::ResourceView(var.name.clone())));
+    unbound.extend(info.unordereds.iter()
+        .filter(|var| desc.unordered_views[var.slot as usize].is_none())
+        .map(|var| Unbound::UnorderedView(var.name.clone())));
+    unbound.extend(info.samplers.iter()
+        .filter(|var| desc.samplers[var.slot as usize].is_none())
+        .map(|var| Unbound::Sampler(var.name.clone())));
+    if info.knows_outputs {
+        unbound.extend(info.outputs.iter()
+            .filter(|var| desc.color_targets[var.slot as usize].is_none())
+            .map(|var| Unbound::Output(var.name.clone())));
+    }
+    Ok(unbound)
+}
+
+/// On-disk cache of compiled programs, to cut the startup time of applications
+/// with many shaders. Entries are keyed by a hash of the shader code and of the
+/// backend, including the driver version, see `Cache::key`.
+///
+/// Only GL needs it: D3D11 shaders are given as compiled blobs already, and
+/// Vulkan and Metal pipelines have caches of their own.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Create a cache storing its entries in `dir`, creating the directory if needed.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> io::Result<Cache> {
+        let dir = dir.into();
+        try!(fs::create_dir_all(&dir));
+        Ok(Cache { dir: dir })
+    }
+
+    /// Compute the key of a program from its backend and the code of its stages.
+    pub fn key(backend: Backend, code: &[Option<&[u8]>]) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", backend).hash(&mut hasher);
+        code.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.bin", key))
+    }
+
+    /// Read the entry stored for the key, if any.
+    pub fn load(&self, key: u64) -> Option<Vec<u8>> {
+        use std::io::Read;
+
+        let mut data = Vec::new();
+        match fs::File::open(self.path(key)).and_then(|mut file| file.read_to_end(&mut data)) {
+            Ok(_) => Some(data),
+            Err(_) => None,
+        }
+    }
+
+    /// Store an entry for the key, replacing the previous one.
+    pub fn store(&self, key: u64, data: &[u8]) -> io::Result<()> {
+        use std::io::Write;
+
+        // write to a temporary file first, so that a crash doesn't leave a truncated entry
+        let path = self.path(key);
+        let temp = path.with_extension("tmp");
+        try!(try!(fs::File::create(&temp)).write_all(data));
+        fs::rename(temp, path)
+    }
+
+    /// Remove all the entries.
+    pub fn clear(&self) -> io::Result<()> {
+        for entry in try!(fs::read_dir(&self.dir)) {
+            let path = try!(entry).path();
+            if path.extension().map_or(false, |ext| ext == "bin") {
+                try!(fs::remove_file(path));
+            }
+        }
+        Ok(())
+    }
+}
+
+macro_rules! source_buf {
+    ($( $field:ident ),*) => {
+        /// Owned counterpart of `Source`, as produced by `Source::with_defines`.
+        #[derive(Clone, PartialEq, Debug)]
+        pub struct SourceBuf {
+            $( pub $field: Vec<u8>, )*
+        }
+
+        impl SourceBuf {
+            /// Borrow as a `Source`, ready for `select`.
+            pub fn as_source(&self) -> Source {
+                Source {
+                    $( $field: &self.$field, )*
+                }
+            }
+        }
+
+        impl<'a> Source<'a> {
+            fn map<F: Fn(&str, &[u8]) -> Vec<u8>>(&self, fun: F) -> SourceBuf {
+                SourceBuf {
+                    $( $field: fun(stringify!($field), self.$field), )*
+                }
+            }
+        }
+    }
+}
+
+source_buf!(glsl_120, glsl_130, glsl_140, glsl_150, glsl_400, glsl_430,
+            glsl_es_100, glsl_es_200, glsl_es_300, hlsl_30, hlsl_40, hlsl_41, hlsl_50,
+            msl_10, msl_11, vulkan);
+
+fn inject_defines(code: &[u8], defines: &[(&str, &str)], after_version: bool) -> Vec<u8> {
+    let mut header = Vec::new();
+    for &(name, value) in defines {
+        header.extend_from_slice(format!("#define {} {}\n", name, value).as_bytes());
+    }
+    // the `#version` directive has to come first in GLSL
+    let split = if after_version {
+        let mut offset = 0;
+        let mut split = 0;
+        for line in code.split(|&b| b == b'\n') {
+            offset += line.len() + 1;
+            let start = line.iter().position(|b| !b" \t\r".contains(b)).unwrap_or(line.len());
+            if line[start ..].starts_with(b"#version") {
+                split = cmp::min(offset, code.len());
+                break;
+            }
+        }
+        split
+    } else {
+        0
+    };
+    let mut result = Vec::with_capacity(code.len() + header.len() + 1);
+    result.extend_from_slice(&code[.. split]);
+    if split > 0 && code[split - 1] != b'\n' {
+        result.push(b'\n');
+    }
+    result.extend_from_slice(&header);
+    result.extend_from_slice(&code[split ..]);
+    result
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Replace the whole-word occurrences of `from` in `line`.
+fn replace_word(line: &str, from: &str, to: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(pos) = rest.find(from) {
+        let before = rest[..pos].chars().next_back();
+        let after = rest[pos + from.len()..].chars().next();
+        out.push_str(&rest[..pos]);
+        if before.map_or(true, |c| !is_ident_char(c)) && after.map_or(true, |c| !is_ident_char(c)) {
+            out.push_str(to);
+        } else {
+            out.push_str(from);
+        }
+        rest = &rest[pos + from.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Rename the calls to `function` depending on the sampler they are given,
+/// e.g. `texture(tex, uv)` to `texture2D(tex, uv)` when `tex` is a `sampler2D`.
+fn rename_sampling(line: &str, function: &str, samplers: &[(String, String)], suffix: &str) -> String {
+    let call = format!("{}(", function);
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(pos) = rest.find(&call) {
+        out.push_str(&rest[..pos]);
+        let args = &rest[pos + call.len()..];
+        if rest[..pos].chars().next_back().map_or(false, is_ident_char) {
+            out.push_str(&call);
+        } else {
+            let sampler = args.trim_left().split(|c: char| !is_ident_char(c)).next().unwrap_or("");
+            let legacy = match samplers.iter().find(|&&(ref name, _)| name == sampler) {
+                Some(&(_, ref ty)) if ty == "sampler1D" => "texture1D",
+                Some(&(_, ref ty)) if ty == "sampler3D" => "texture3D",
+                Some(&(_, ref ty)) if ty == "samplerCube" => "textureCube",
+                Some(&(_, ref ty)) if ty == "sampler2DShadow" => "shadow2D",
+                _ => "texture2D",
+            };
+            out.push_str(legacy);
+            out.push_str(suffix);
+            out.push('(');
+        }
+        rest = args;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Remove a leading `layout(...)` qualifier and the interpolation qualifiers.
+fn strip_qualifiers(line: &str) -> &str {
+    let mut line = line.trim_left();
+    if line.starts_with("layout") {
+        if let Some(end) = line.find(')') {
+            line = line[end + 1..].trim_left();
+        }
+    }
+    for qualifier in &["smooth ", "flat ", "noperspective "] {
+        if line.starts_with(qualifier) {
+            line = line[qualifier.len()..].trim_left();
+        }
+    }
+    line
+}
+
+/// Parse the `#version` directive of a GLSL source, returning the version
+/// number and whether it is GLSL ES.
+pub fn glsl_version(code: &str) -> Option<(u32, bool)> {
+    code.lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("#version"))
+        .and_then(|line| {
+            let mut words = line["#version".len()..].split_whitespace();
+            let version = words.next().and_then(|v| v.parse().ok());
+            // GLSL ES 1.00 has no `es` suffix
+            version.map(|v| (v, v == 100 || words.next() == Some("es")))
+        })
+}
+
+/// Translate a GLSL source to another version of the language, taking care of the
+/// differences between the legacy (GLSL 1.20 and GLSL ES 1.00) and the modern
+/// (GLSL 1.30+ and GLSL ES 3.00) syntax:
+///
+/// - `attribute` and `varying` against `in` and `out`
+/// - `gl_FragColor` and `gl_FragData[i]` against declared outputs, which are
+///   named `Target0`, `Target1`, etc when upgrading
+/// - `texture2D`, `textureCube`, etc against `texture`
+/// - `layout` and interpolation qualifiers, which are removed when downgrading
+///
+/// This is a textual pass, expecting one declaration per line.
+pub fn translate_glsl(code: &str, stage: Stage, version: u32, embedded: bool) -> String {
+    let (source_version, source_embedded) = glsl_version(code).unwrap_or((110, false));
+    let is_modern = |v: u32, es: bool| if es { v >= 300 } else { v >= 130 };
+    let from_modern = is_modern(source_version, source_embedded);
+    let to_modern = is_modern(version, embedded);
+    let upgrade = !from_modern && to_modern;
+    let downgrade = from_modern && !to_modern;
+
+    // collect the samplers, to pick the legacy texture functions, and the pixel outputs
+    let mut samplers = Vec::new();
+    let mut outputs = Vec::new();
+    for line in code.lines() {
+        let mut words = strip_qualifiers(line).split(|c: char| c.is_whitespace() || c == ';')
+                                              .filter(|w| !w.is_empty());
+        match (words.next(), words.next(), words.next()) {
+            (Some("uniform"), Some(ty), Some(name)) if ty.starts_with("sampler") =>
+                samplers.push((name.to_string(), ty.to_string())),
+            (Some("out"), Some(_), Some(name)) if stage == Stage::Pixel => outputs.push(name.to_string()),
+            _ => (),
+        }
+    }
+
+    // declarations to add after the preprocessor directives at the top
+    let mut extra = Vec::new();
+    if embedded && stage == Stage::Pixel && !code.contains("precision ") {
+        extra.push("precision mediump float;".to_string());
+    }
+    if upgrade && stage == Stage::Pixel {
+        let precision = if embedded { "mediump " } else { "" };
+        if code.contains("gl_FragColor") {
+            extra.push(format!("out {}vec4 Target0;", precision));
+        }
+        for i in 0..8 {
+            if code.contains(&format!("gl_FragData[{}]", i)) {
+                extra.push(format!("out {}vec4 Target{};", precision, i));
+            }
+        }
+    }
+
+    let mut out = match (version, embedded) {
+        (100, true) => "#version 100\n".to_string(),
+        (v, true) => format!("#version {} es\n", v),
+        (v, false) => format!("#version {}\n", v),
+    };
+    let mut in_header = true;
+    for line in code.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("#version") {
+            continue;
+        }
+        if in_header && !trimmed.is_empty() && !trimmed.starts_with('#') && !trimmed.starts_with("//") {
+            in_header = false;
+            for decl in &extra {
+                out.push_str(decl);
+                out.push('\n');
+            }
+        }
+
+        let mut line = line.to_string();
+        if downgrade {
+            let decl = strip_qualifiers(&line).to_string();
+            line = match (stage, decl.starts_with("in "), decl.starts_with("out ")) {
+                (Stage::Vertex, true, _) => format!("attribute {}", &decl[3..]),
+                (Stage::Vertex, _, true) => format!("varying {}", &decl[4..]),
+                (Stage::Pixel, true, _) => format!("varying {}", &decl[3..]),
+                (Stage::Pixel, _, true) => continue,
+                _ if decl.starts_with("uniform ") => decl,
+                _ => line,
+            };
+            for (i, name) in outputs.iter().enumerate() {
+                let target = if outputs.len() == 1 {
+                    "gl_FragColor".to_string()
+                } else {
+                    format!("gl_FragData[{}]", i)
+                };
+                line = replace_word(&line, name, &target);
+            }
+            line = rename_sampling(&line, "texture", &samplers, "");
+            line = rename_sampling(&line, "textureLod", &samplers, "Lod");
+        } else if upgrade {
+            let trimmed = line.trim_left().to_string();
+            if trimmed.starts_with("attribute ") {
+                line = format!("in {}", &trimmed["attribute ".len()..]);
+            } else if trimmed.starts_with("varying ") {
+                let qualifier = if stage == Stage::Vertex { "out" } else { "in" };
+                line = format!("{} {}", qualifier, &trimmed["varying ".len()..]);
+            }
+            for legacy in &["texture1D", "texture2D", "texture3D", "textureCube", "shadow2D"] {
+                line = replace_word(&line, legacy, "texture");
+                line = replace_word(&line, &format!("{}Lod", legacy), "textureLod");
+            }
+            line = replace_word(&line, "gl_FragColor", "Target0");
+            for i in 0..8 {
+                line = line.replace(&format!("gl_FragData[{}]", i), &format!("Target{}", i));
+            }
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Loads shaders from a directory, picking the file that suits the backend.
+///
+/// A shader `name` for the `stage` (`vs`, `ps`, etc) is looked up as
+/// `name.stage.TAG.EXT`, trying the supported versions from the highest down,
+/// and then as `name.stage.EXT`, where `EXT` and `TAG` are:
+///
+/// - GLSL: `glsl`, with tags `430` down to `120`
+/// - GLSL ES: `glsl`, with tags `300es` down to `100es`, and `es.glsl` as the fallback
+/// - HLSL: `hlsl`, with shader models `50` down to `30`
+/// - Metal: `metal`, with revisions `11` and `10`
+/// - Vulkan: `spv`, unversioned
+///
+/// Text sources (GLSL and Metal) go through `preprocess`, with the directories
+/// given to `add_include_path` searched after the one of the including file.
+///
+/// With the `spirv-cross` feature, `name.stage.spv` is translated to the language
+/// of the backend when none of the other files exist, see `cross::translate`.
+pub struct Loader {
+    root: PathBuf,
+    backend: Backend,
+    include_paths: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    // Latest preprocessed version of each main file, to locate compile errors
+    sources: Vec<Preprocessed>,
+    #[cfg(feature = "spirv-cross")]
+    cross: CrossCompiler,
+}
+
+impl Loader {
+    /// Create a loader looking into the `root` directory.
+    pub fn new<P: Into<PathBuf>>(root: P, backend: Backend) -> Loader {
+        Loader {
+            root: root.into(),
+            backend: backend,
+            include_paths: Vec::new(),
+            files: Vec::new(),
+            sources: Vec::new(),
+            #[cfg(feature = "spirv-cross")]
+            cross: CrossCompiler::new(),
+        }
+    }
+
+    /// Return the backend the shaders are loaded for.
+    pub fn get_backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Add a directory to search for `#include`d files.
+    pub fn add_include_path<P: Into<PathBuf>>(&mut self, path: P) {
+        self.include_paths.push(path.into());
+    }
+
+    fn is_text(&self) -> bool {
+        match self.backend {
+            Backend::Glsl(_) | Backend::GlslEs(_) => true,
+            #[cfg(feature = "metal")]
+            Backend::Msl(_) => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Return the files that have been loaded so far, including the `#include`d ones.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    // Track a loaded file, once even if it is loaded again by a reload
+    fn add_file(&mut self, path: PathBuf) {
+        if !self.files.contains(&path) {
+            self.files.push(path);
+        }
+    }
+
+    /// Point the diagnostics of `error` at the files and lines they originate from,
+    /// if its source is a text shader loaded as is by this loader.
+    pub fn locate(&self, error: &mut CompileError) {
+        if let Some(source) = self.sources.iter().find(|s| s.code == error.source) {
+            source.locate(error);
+        }
+    }
+
+    fn candidates(&self, name: &str, stage: &str) -> Vec<String> {
+        fn versioned(name: &str, stage: &str, tags: &[(u32, &str)], version: u32,
+                     ext: &str, fallback: &str) -> Vec<String> {
+            tags.iter()
+                .filter(|&&(v, _)| version >= v)
+                .map(|&(_, tag)| format!("{}.{}.{}.{}", name, stage, tag, ext))
+                .chain(Some(format!("{}.{}.{}", name, stage, fallback)))
+                .collect()
+        }
+        match self.backend {
+            Backend::Glsl(version) => versioned(name, stage,
+                &[(430, "430"), (400, "400"), (150, "150"), (140, "140"), (130, "130"), (120, "120")],
+                version.major * 100 + version.minor, "glsl", "glsl"),
+            Backend::GlslEs(version) => versioned(name, stage,
+                &[(300, "300es"), (200, "200es"), (100, "100es")],
+                version.major * 100 + version.minor, "glsl", "es.glsl"),
+            #[cfg(target_os = "windows")]
+            Backend::Hlsl(model) => versioned(name, stage,
+                &[(50, "50"), (41, "41"), (40, "40"), (30, "30")],
+                model as u32, "hlsl", "hlsl"),
+            #[cfg(feature = "metal")]
+            Backend::Msl(revision) => versioned(name, stage,
+                &[(11, "11"), (10, "10")],
+                revision as u32, "metal", "metal"),
+            #[cfg(feature = "vulkan")]
+            Backend::Vulkan => vec![format!("{}.{}.spv", name, stage)],
+        }
+    }
+
+    /// Load the shader `name` for the given `stage`.
+    pub fn load(&mut self, name: &str, stage: &str) -> io::Result<Vec<u8>> {
+        use std::fs::File;
+        use std::io::Read;
+
+        let candidates = self.candidates(name, stage);
+        for file_name in &candidates {
+            let path = self.root.join(file_name);
+            if self.is_text() && path.is_file() {
+                let source = try!(preprocess(&path, &self.include_paths).map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+                }));
+                for path in &source.files {
+                    self.add_file(path.clone());
+                }
+                let code = source.code.clone().into_bytes();
+                self.sources.retain(|s| s.files[0] != source.files[0]);
+                self.sources.push(source);
+                return Ok(code);
+            }
+            let mut file = match File::open(&path) {
+                Ok(file) => file,
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            let mut code = Vec::new();
+            try!(file.read_to_end(&mut code));
+            #[cfg(feature = "vulkan")]
+            {
+                if self.backend == Backend::Vulkan && !is_spirv(&code) {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData,
+                        format!("{} is not a SPIR-V module", path.display())));
+                }
+            }
+            self.add_file(path);
+            return Ok(code);
+        }
+        #[cfg(feature = "spirv-cross")]
+        {
+            let path = self.root.join(format!("{}.{}.spv", name, stage));
+            if !candidates.iter().any(|c| self.root.join(c) == path) && path.is_file() {
+                let spirv = try!(load_spirv(&path));
+                let code = try!(self.cross.translate(&spirv, self.backend).map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+                }));
+                self.add_file(path);
+                return Ok(code.to_vec());
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::NotFound,
+            format!("No shader found in {}, tried {:?}", self.root.display(), candidates)))
+    }
+}
+
+/// Error preprocessing a shader source.
+#[derive(Debug)]
+pub enum PreprocessError {
+    /// A file could not be read.
+    Io(PathBuf, io::Error),
+    /// An `#include` directive is malformed.
+    Syntax { file: PathBuf, line: usize },
+    /// An included file was found neither next to the including file nor in the include paths.
+    NotFound { name: String, file: PathBuf, line: usize },
+    /// A file includes itself, directly or not, and has no `#pragma once`.
+    Recursive { file: PathBuf, line: usize },
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PreprocessError::Io(ref path, ref e) => write!(f, "{}: {}", path.display(), e),
+            PreprocessError::Syntax { ref file, line } =>
+                write!(f, "{}:{}: malformed #include", file.display(), line),
+            PreprocessError::NotFound { ref name, ref file, line } =>
+                write!(f, "{}:{}: included file \"{}\" not found", file.display(), line, name),
+            PreprocessError::Recursive { ref file, line } =>
+                write!(f, "{}:{}: recursive #include", file.display(), line),
+        }
+    }
+}
+
+impl Error for PreprocessError {
+    fn description(&self) -> &str {
+        match *self {
+            PreprocessError::Io(..) => "Unable to read a shader file",
+            PreprocessError::Syntax { .. } => "Malformed #include",
+            PreprocessError::NotFound { .. } => "Included file not found",
+            PreprocessError::Recursive { .. } => "Recursive #include",
+        }
+    }
+}
+
+/// Shader source with the `#include` directives expanded.
+#[derive(Clone, Debug)]
+pub struct Preprocessed {
+    pub code: String,
+    /// All the files read, starting with the main one.
+    pub files: Vec<PathBuf>,
+    /// File and line each line of `code` comes from.
+    lines: Vec<(usize, usize)>,
+}
+
+impl Preprocessed {
+    /// Map a 1-based line of `code`, as reported by the shader compiler,
+    /// to the file and line it originates from.
+    pub fn origin(&self, line: usize) -> Option<(&Path, usize)> {
+        line.checked_sub(1)
+            .and_then(|i| self.lines.get(i))
+            .map(|&(file, line)| (self.files[file].as_path(), line))
+    }
+
+    /// Point the diagnostics of a `CompileError` of this source at their original files and lines.
+    pub fn locate(&self, error: &mut CompileError) {
+        for d in &mut error.diagnostics {
+            d.origin = d.line.and_then(|line| self.origin(line)).map(|(path, line)| (path.to_path_buf(), line));
+        }
+    }
+}
+
+struct Preprocessor<'a> {
+    include_paths: &'a [PathBuf],
+    once: HashSet<PathBuf>,
+    stack: Vec<PathBuf>,
+    out: Preprocessed,
+}
+
+impl<'a> Preprocessor<'a> {
+    fn resolve(&self, name: &str, from: &Path) -> Option<PathBuf> {
+        from.parent()
+            .into_iter()
+            .chain(self.include_paths.iter().map(|p| p.as_path()))
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    fn process(&mut self, path: &Path) -> Result<(), PreprocessError> {
+        use std::fs::File;
+        use std::io::Read;
+
+        let key = path.canonicalize().unwrap_or(path.to_path_buf());
+        if self.once.contains(&key) {
+            return Ok(());
+        }
+        let mut text = String::new();
+        try!(File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|e| PreprocessError::Io(path.to_path_buf(), e)));
+        let file_index = self.out.files.len();
+        self.out.files.push(path.to_path_buf());
+        self.stack.push(key.clone());
+
+        for (i, line) in text.lines().enumerate() {
+            let directive = line.trim_left();
+            let directive = if directive.starts_with('#') {
+                directive[1..].trim_left()
+            } else {
+                ""
+            };
+            if directive.starts_with("include") {
+                let syntax = PreprocessError::Syntax { file: path.to_path_buf(), line: i + 1 };
+                let arg = directive["include".len()..].trim();
+                if arg.len() < 2 || !(arg.starts_with('"') && arg.ends_with('"') ||
+                                      arg.starts_with('<') && arg.ends_with('>')) {
+                    return Err(syntax);
+                }
+                let name = &arg[1 .. arg.len() - 1];
+                let included = match self.resolve(name, path) {
+                    Some(included) => included,
+                    None => return Err(PreprocessError::NotFound {
+                        name: name.to_string(),
+                        file: path.to_path_buf(),
+                        line: i + 1,
+                    }),
+                };
+                let included_key = included.canonicalize().unwrap_or(included.clone());
+                if self.stack.contains(&included_key) && !self.once.contains(&included_key) {
+                    return Err(PreprocessError::Recursive { file: path.to_path_buf(), line: i + 1 });
+                }
+                try!(self.process(&included));
+            } else if directive.split_whitespace().eq(["pragma", "once"].iter().cloned()) {
+                self.once.insert(key.clone());
+            } else {
+                self.out.code.push_str(line);
+                self.out.code.push('\n');
+                self.out.lines.push((file_index, i + 1));
+            }
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+/// Expand the `#include "file"` directives of the shader at `path`, recursively.
+/// Included files are searched next to the including file, then in `include_paths`.
+/// Files containing `#pragma once` are only included once, classic `#ifndef`
+/// guards are left for the shader compiler to handle.
+pub fn preprocess<P: AsRef<Path>>(path: P, include_paths: &[PathBuf])
+                                  -> Result<Preprocessed, PreprocessError> {
+    let mut preprocessor = Preprocessor {
+        include_paths: include_paths,
+        once: HashSet::new(),
+        stack: Vec::new(),
+        out: Preprocessed {
+            code: String::new(),
+            files: Vec::new(),
+            lines: Vec::new(),
+        },
+    };
+    try!(preprocessor.process(path.as_ref()));
+    Ok(preprocessor.out)
+}
+
+const WATCH_PERIOD_MS: u64 = 250;
+
+/// Polls shader files for modifications, throttled to a few checks per second.
+pub struct Watcher {
+    times: HashMap<PathBuf, SystemTime>,
+    last_poll: Option<Instant>,
+}
+
+impl Watcher {
+    pub fn new() -> Watcher {
+        Watcher {
+            times: HashMap::new(),
+            last_poll: None,
+        }
+    }
+
+    /// Return the files that have been modified since the previous poll.
+    /// Files seen for the first time are only recorded.
+    pub fn poll<'a, I>(&mut self, files: I) -> Vec<PathBuf> where
+        I: IntoIterator<Item = &'a PathBuf>
+    {
+        let now = Instant::now();
+        match self.last_poll {
+            Some(last) if now - last < Duration::from_millis(WATCH_PERIOD_MS) => return Vec::new(),
+            _ => self.last_poll = Some(now),
+        }
+        let mut changed = Vec::new();
+        for path in files {
+            let time = match path.metadata().and_then(|m| m.modified()) {
+                Ok(time) => time,
+                // the file may be in the middle of being saved
+                Err(_) => continue,
+            };
+            if let Some(old) = self.times.insert(path.clone(), time) {
+                if old != time {
+                    changed.push(path.clone());
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use std::io::Write;
+    use super::{glsl_version, inject_defines, is_spirv, preprocess, split_location, translate_glsl, Backend, Cache,
+                CompileError, Diagnostic, GlslVersion, Loader, Program, ProgramError, SelectError, Severity, Source,
+                Stage};
+
+    #[test]
+    fn test_preprocess() {
+        let dir = env::temp_dir().join("gfx_app_test_preprocess");
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::File::create(dir.join("main.glsl")).and_then(|mut f| f.write_all(
+            b"#version 150\n#include \"common.glsl\"\n#include <common.glsl>\nvoid main() {}\n")).unwrap();
+        fs::File::create(dir.join("lib").join("common.glsl")).and_then(|mut f| f.write_all(
+            b"#pragma once\nfloat common;\n")).unwrap();
+
+        let result = preprocess(dir.join("main.glsl"), &[dir.join("lib")]).unwrap();
+        assert_eq!(result.code, "#version 150\nfloat common;\nvoid main() {}\n");
+        assert_eq!(result.files.len(), 2);
+        assert_eq!(result.origin(2), Some((dir.join("lib").join("common.glsl").as_path(), 2)));
+        assert_eq!(result.origin(3), Some((dir.join("main.glsl").as_path(), 4)));
+
+        let backend = Backend::Glsl(GlslVersion::new(1, 50, None, ""));
+        let mut error = CompileError::new(Stage::Pixel, backend, "0:2(1): error: oops\n", result.code.as_bytes());
+        result.locate(&mut error);
+        assert_eq!(error.diagnostics[0].origin, Some((dir.join("lib").join("common.glsl"), 2)));
+        assert!(preprocess(dir.join("main.glsl"), &[]).is_err());
+    }
+
+    #[test]
+    fn test_with_defines() {
+        let source = Source {
+            glsl_150: b"#version 150 core\nvoid main() {}\n",
+            msl_11: b"kernel void main() {}",
+            vulkan: &[0x03, 0x02, 0x23, 0x07],
+            .. Source::empty()
+        };
+        let defined = source.with_defines(&[("USE_NORMAL_MAP", "1"), ("LIGHTS", "4")]);
+        assert_eq!(&defined.glsl_150[..],
+                   &b"#version 150 core\n#define USE_NORMAL_MAP 1\n#define LIGHTS 4\nvoid main() {}\n"[..]);
+        assert_eq!(&defined.msl_11[..],
+                   &b"#define USE_NORMAL_MAP 1\n#define LIGHTS 4\nkernel void main() {}"[..]);
+        assert_eq!(&defined.vulkan[..], source.vulkan);
+        assert!(defined.glsl_120.is_empty());
+    }
+
+    #[test]
+    fn test_inject_defines_invalid_utf8() {
+        let code = b"// \xff\xfe\n  #version 150 core\nvoid main() {}\n";
+        assert_eq!(&inject_defines(code, &[("LIGHTS", "4")], true)[..],
+                   &b"// \xff\xfe\n  #version 150 core\n#define LIGHTS 4\nvoid main() {}\n"[..]);
+    }
+
+    #[test]
+    fn test_select_compute() {
+        let source = Source {
+            glsl_150: b"#version 150 core\nvoid main() {}\n",
+            glsl_430: b"#version 430 core\nlayout(local_size_x = 64) in;\nvoid main() {}\n",
+            glsl_es_300: b"#version 300 es\nvoid main() {}\n",
+            .. Source::empty()
+        };
+        assert_eq!(source.select_compute(Backend::Glsl(GlslVersion::new(4, 50, None, ""))),
+                   Ok(source.glsl_430));
+        assert!(source.select_compute(Backend::Glsl(GlslVersion::new(4, 10, None, ""))).is_err());
+        assert!(source.select_compute(Backend::GlslEs(GlslVersion::new_embedded(3, 0, ""))).is_err());
+    }
+
+    #[test]
+    fn test_program_select() {
+        let vertex = Source { glsl_150: b"#version 150 core\nvoid main() {}\n", .. Source::empty() };
+        let tess = Source { glsl_400: b"#version 400 core\nvoid main() {}\n", .. Source::empty() };
+        let program = Program {
+            hull: Some(tess),
+            domain: Some(tess),
+            .. Program::new(vertex, vertex)
+        };
+        let code = program.select(Backend::Glsl(GlslVersion::new(4, 10, None, ""))).unwrap();
+        assert_eq!(code.hull, Some(tess.glsl_400));
+        assert_eq!(code.geometry, None);
+
+        let gl33 = Backend::Glsl(GlslVersion::new(3, 30, None, ""));
+        assert_eq!(program.select(gl33), Err(ProgramError::UnsupportedStage(Stage::Hull, gl33)));
+        assert_eq!(Program { domain: None, .. program }.select(gl33), Err(ProgramError::InvalidStages));
+    }
+
+    #[test]
+    fn test_select_first() {
+        let simple = Source { glsl_150: b"#version 150 core\nvoid main() {}\n", .. Source::empty() };
+        let tess = Source { glsl_400: b"#version 400 core\nvoid main() {}\n", .. Source::empty() };
+        let programs = [
+            Program { hull: Some(tess), domain: Some(tess), .. Program::new(tess, tess) },
+            Program::new(simple, simple),
+        ];
+        let gl45 = Backend::Glsl(GlslVersion::new(4, 50, None, ""));
+        assert_eq!(Program::select_first(&programs, gl45).unwrap().0, 0);
+        let gl33 = Backend::Glsl(GlslVersion::new(3, 30, None, ""));
+        assert_eq!(Program::select_first(&programs, gl33).unwrap().0, 1);
+        let gl21 = Backend::Glsl(GlslVersion::new(1, 20, None, ""));
+        assert_eq!(Program::select_first(&programs, gl21).unwrap_err(),
+                   ProgramError::Select(Stage::Vertex, SelectError(gl21)));
+    }
+
+    #[test]
+    fn test_translate_glsl() {
+        let ps = "#version 150 core\nuniform samplerCube t_Env;\nin vec3 v_Dir;\nout vec4 Target0;\n\
+                  void main() {\n    Target0 = texture(t_Env, v_Dir);\n}\n";
+        let legacy = translate_glsl(ps, Stage::Pixel, 100, true);
+        assert_eq!(legacy, "#version 100\nprecision mediump float;\nuniform samplerCube t_Env;\nvarying vec3 v_Dir;\n\
+                            void main() {\n    gl_FragColor = textureCube(t_Env, v_Dir);\n}\n");
+        assert_eq!(glsl_version(&legacy), Some((100, true)));
+
+        let modern = translate_glsl(&legacy, Stage::Pixel, 300, true);
+        assert!(modern.starts_with("#version 300 es\nout mediump vec4 Target0;\n"));
+        assert!(modern.contains("in vec3 v_Dir;\n"));
+        assert!(modern.contains("Target0 = texture(t_Env, v_Dir);"));
+
+        let vs = "#version 330 core\nlayout(location = 0) in vec3 a_Pos;\nout vec3 v_Dir;\nvoid main() {}\n";
+        assert_eq!(translate_glsl(vs, Stage::Vertex, 120, false),
+                   "#version 120\nattribute vec3 a_Pos;\nvarying vec3 v_Dir;\nvoid main() {}\n");
+    }
+
+    #[test]
+    fn test_compile_error() {
+        let source = b"#version 150 core\nvoid main() {\n    gl_Position = pos;\n}\n";
+        let backend = Backend::Glsl(GlslVersion::new(1, 50, None, ""));
+        let error = CompileError::new(Stage::Vertex, backend, "0:3(19): error: `pos' undeclared\n", source);
+        assert_eq!(error.diagnostics, vec![Diagnostic {
+            severity: Severity::Error,
+            line: Some(3),
+            column: Some(19),
+            origin: None,
+            message: "`pos' undeclared".to_string(),
+        }]);
+        assert!(error.to_string().ends_with("3:19: error: `pos' undeclared\n    |     gl_Position = pos;\n    |                   ^\n"));
+
+        let error = CompileError::new(Stage::Pixel, backend, "ERROR: 0:12: 'x' : undeclared identifier", source);
+        assert_eq!(error.diagnostics[0].line, Some(12));
+        assert_eq!(error.diagnostics[0].message, "'x' : undeclared identifier");
+    }
+
+    #[test]
+    fn test_split_location() {
+        // AMD and ANGLE
+        assert_eq!(split_location("ERROR: 0:12: 'x' : undeclared identifier"),
+                   Some((Severity::Error, "0:12", "'x' : undeclared identifier")));
+        assert_eq!(split_location("WARNING: 0:4: 'error' : extension not supported"),
+                   Some((Severity::Warning, "0:4", "'error' : extension not supported")));
+        // NVIDIA
+        assert_eq!(split_location("0(12) : error C0000: syntax error, unexpected '}'"),
+                   Some((Severity::Error, "0(12)", "syntax error, unexpected '}'")));
+        assert_eq!(split_location("0(3) : warning C7050: \"error_count\" might be used before being initialized"),
+                   Some((Severity::Warning, "0(3)", "\"error_count\" might be used before being initialized")));
+        // Mesa
+        assert_eq!(split_location("0:3(19): error: `pos' undeclared"),
+                   Some((Severity::Error, "0:3(19)", "`pos' undeclared")));
+        // Metal
+        assert_eq!(split_location("program_source:12:5: error: use of undeclared identifier 'x'"),
+                   Some((Severity::Error, "program_source:12:5", "use of undeclared identifier 'x'")));
+        assert_eq!(split_location("program_source:3:9: warning: unused variable 'error'"),
+                   Some((Severity::Warning, "program_source:3:9", "unused variable 'error'")));
+        assert_eq!(split_location("Compilation failed, see error_log"), None);
+    }
+
+    #[test]
+    fn test_cache() {
+        let cache = Cache::new(env::temp_dir().join("gfx_app_test_cache")).unwrap();
+        cache.clear().unwrap();
+        let backend = Backend::Glsl(GlslVersion::new(1, 50, None, ""));
+        let key = Cache::key(backend, &[Some(&b"vertex"[..]), None, None, None, Some(&b"pixel"[..])]);
+        assert!(key != Cache::key(backend, &[Some(&b"vertex"[..]), None, None, None, Some(&b"pixel2"[..])]));
+        assert_eq!(cache.load(key), None);
+        cache.store(key, b"binary").unwrap();
+        assert_eq!(cache.load(key), Some(b"binary".to_vec()));
+    }
+
+    #[test]
+    fn test_loader_candidates() {
+        let loader = Loader::new("shaders", Backend::Glsl(GlslVersion::new(1, 40, None, "")));
+        assert_eq!(loader.candidates("cube", "vs"),
+                   vec!["cube.vs.140.glsl", "cube.vs.130.glsl", "cube.vs.120.glsl", "cube.vs.glsl"]);
+        let loader = Loader::new("shaders", Backend::GlslEs(GlslVersion::new_embedded(2, 0, "")));
+        assert_eq!(loader.candidates("cube", "ps"),
+                   vec!["cube.ps.200es.glsl", "cube.ps.100es.glsl", "cube.ps.es.glsl"]);
+    }
+
+    #[test]
+    fn test_loader_reload() {
+        let dir = env::temp_dir().join("gfx_app_test_loader_reload");
+        fs::create_dir_all(&dir).unwrap();
+        fs::File::create(dir.join("quad.vs.glsl")).and_then(|mut f| f.write_all(
+            b"#version 150\n#include \"common.glsl\"\nvoid main() {}\n")).unwrap();
+        fs::File::create(dir.join("common.glsl")).and_then(|mut f| f.write_all(
+            b"float common;\n")).unwrap();
+
+        let mut loader = Loader::new(dir.clone(), Backend::Glsl(GlslVersion::new(1, 50, None, "")));
+        let code = loader.load("quad", "vs").unwrap();
+        assert_eq!(loader.load("quad", "vs").unwrap(), code);
+        assert_eq!(loader.files(), &[dir.join("quad.vs.glsl"), dir.join("common.glsl")]);
+    }
+
+    #[test]
+    fn test_is_spirv() {
+        assert!(is_spirv(&[0x03, 0x02, 0x23, 0x07, 0, 0, 1, 0]));
+        assert!(is_spirv(&[0x07, 0x23, 0x02, 0x03]));
+        assert!(!is_spirv(&[0x03, 0x02, 0x23, 0x07, 0]));
+        assert!(!is_spirv(b"#version 150 core\n"));
+        assert!(!is_spirv(&[]));
+    }
 }
@@ -0,0 +1,169 @@
+// Copyright 2017 The Gfx-rs Developers.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Threaded launch mode: the simulation runs on a thread of its own, while the
+//! main thread keeps handling the window and recording/submitting commands.
+//!
+//! The two sides only share the `State` snapshots produced by `Update::update`.
+//! The simulation publishes each snapshot into a mailbox, the renderer picks up
+//! the latest one at the start of a frame and keeps drawing it until a newer
+//! one arrives, so neither side waits for the other.
+
+use std::marker::PhantomData;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gfx;
+use winit;
+
+use {shade, Application, Config, DefaultResources, FrameStats, WindowTargets};
+
+/// Simulation half of a `ThreadedApplication`, living on the update thread.
+pub trait Update: Send + 'static {
+    /// Snapshot of everything the renderer needs to draw a frame.
+    type State: Send + 'static;
+
+    /// Advance the simulation by `dt` seconds and return the new state to render.
+    fn update(&mut self, dt: f32) -> Self::State;
+    /// Handle a window event, forwarded from the main thread.
+    fn on(&mut self, _event: winit::Event) {}
+    /// Target duration of a simulation step.
+    fn period() -> Duration {
+        Duration::from_millis(16)
+    }
+}
+
+/// Application split into a renderer, staying on the main thread with the device,
+/// and an `Update` simulation running on a separate thread.
+pub trait ThreadedApplication<R: gfx::Resources>: Sized {
+    type Update: Update;
+
+    fn new<F: gfx::Factory<R>>(&mut F, shade::Backend, WindowTargets<R>) -> (Self, Self::Update);
+    fn render<C: gfx::CommandBuffer<R>>(&mut self, &<Self::Update as Update>::State,
+                                        &mut gfx::Encoder<R, C>);
+
+    fn get_exit_key() -> Option<winit::VirtualKeyCode> {
+        Some(winit::VirtualKeyCode::Escape)
+    }
+    fn on_resize(&mut self, WindowTargets<R>) {}
+
+    /// Launch on the default backend. Once the window is closed, the update thread
+    /// is stopped and both halves are handed back, together with the frame statistics.
+    fn launch_threaded(wb: winit::WindowBuilder, config: &Config)
+                       -> Option<(Self, Option<Self::Update>, FrameStats)>
+                       where Self: ThreadedApplication<DefaultResources>
+    {
+        <Threaded<DefaultResources, Self> as Application<DefaultResources>>::launch_default(wb, config)
+            .map(|(threaded, stats)| {
+                let (app, update) = threaded.finish();
+                (app, update, stats)
+            })
+    }
+}
+
+type Mailbox<T> = Arc<Mutex<Option<T>>>;
+
+/// Adapter running a `ThreadedApplication` through the `Application` machinery.
+pub struct Threaded<R: gfx::Resources, A: ThreadedApplication<R>> {
+    app: A,
+    state: Option<<A::Update as Update>::State>,
+    mailbox: Mailbox<<A::Update as Update>::State>,
+    events: Option<mpsc::Sender<winit::Event>>,
+    thread: Option<thread::JoinHandle<A::Update>>,
+    _marker: PhantomData<R>,
+}
+
+impl<R: gfx::Resources, A: ThreadedApplication<R>> Threaded<R, A> {
+    /// Stop the update thread and return both halves of the application.
+    /// The update half is `None` if its thread panicked.
+    pub fn finish(mut self) -> (A, Option<A::Update>) {
+        // disconnecting the channel is the signal for the update thread to return
+        self.events = None;
+        let update = self.thread.take().and_then(|thread| thread.join().ok());
+        (self.app, update)
+    }
+}
+
+fn run_update<U: Update>(mut update: U, events: mpsc::Receiver<winit::Event>,
+                         mailbox: Mailbox<U::State>) -> U {
+    let period = U::period();
+    let mut last = Instant::now();
+    loop {
+        loop {
+            match events.try_recv() {
+                Ok(event) => update.on(event),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => return update,
+            }
+        }
+        let now = Instant::now();
+        let dt = now - last;
+        last = now;
+        let state = update.update(dt.as_secs() as f32 + dt.subsec_nanos() as f32 * 1e-9);
+        *mailbox.lock().unwrap() = Some(state);
+
+        let spent = now.elapsed();
+        if spent < period {
+            thread::sleep(period - spent);
+        }
+    }
+}
+
+impl<R: gfx::Resources, A: ThreadedApplication<R>> Application<R> for Threaded<R, A> {
+    fn new<F: gfx::Factory<R>>(factory: &mut F, backend: shade::Backend,
+                               window_targets: WindowTargets<R>) -> Self {
+        let (app, update) = A::new(factory, backend, window_targets);
+        let mailbox = Arc::new(Mutex::new(None));
+        let (sender, receiver) = mpsc::channel();
+        let thread_mailbox = mailbox.clone();
+        let thread = thread::Builder::new()
+            .name("update".to_string())
+            .spawn(move || run_update(update, receiver, thread_mailbox))
+            .unwrap();
+        Threaded {
+            app: app,
+            state: None,
+            mailbox: mailbox,
+            events: Some(sender),
+            thread: Some(thread),
+            _marker: PhantomData,
+        }
+    }
+
+    fn render<C: gfx::CommandBuffer<R>>(&mut self, encoder: &mut gfx::Encoder<R, C>) {
+        if let Some(state) = self.mailbox.lock().unwrap().take() {
+            self.state = Some(state);
+        }
+        // nothing to draw until the first simulation step is done
+        if let Some(ref state) = self.state {
+            self.app.render(state, encoder);
+        }
+    }
+
+    fn get_exit_key() -> Option<winit::VirtualKeyCode> {
+        A::get_exit_key()
+    }
+
+    fn on_resize(&mut self, window_targets: WindowTargets<R>) {
+        self.app.on_resize(window_targets);
+    }
+
+    fn on(&mut self, event: winit::Event) {
+        if let Some(ref events) = self.events {
+            // the update thread is gone only if it panicked, which `finish` reports
+            let _ = events.send(event);
+        }
+    }
+}
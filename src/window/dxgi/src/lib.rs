@@ -17,14 +17,15 @@
 #[macro_use]
 extern crate log;
 extern crate dxguid;
+extern crate dxgi;
 extern crate winapi;
 extern crate winit;
 extern crate gfx_core as core;
 extern crate gfx_device_dx11 as device_dx11;
 
-use std::ptr;
+use std::{fmt, ptr};
 use winit::os::windows::WindowExt;
-use core::{format, handle as h, factory as f, memory, texture as tex};
+use core::{format, handle as h, memory, texture as tex};
 use core::texture::Size;
 use device_dx11::{Device, Factory, Resources};
 
@@ -32,20 +33,20 @@ use device_dx11::{Device, Factory, Resources};
 pub struct Window {
     inner: winit::Window,
     swap_chain: *mut winapi::IDXGISwapChain,
-    driver_type: winapi::D3D_DRIVER_TYPE,
+    accelerated: bool,
     color_format: format::Format,
     pub size: (Size, Size),
 }
 
 impl Window {
     pub fn is_accelerated(&self) -> bool {
-        self.driver_type == winapi::D3D_DRIVER_TYPE_HARDWARE
+        self.accelerated
     }
 
-    pub fn swap_buffers(&self, wait: u8) {
+    pub fn swap_buffers(&self, wait: u8) -> Result<(), SwapChainError> {
         match unsafe {(*self.swap_chain).Present(wait as winapi::UINT, 0)} {
-            winapi::S_OK | winapi::DXGI_STATUS_OCCLUDED => {}
-            hr => panic!("Present Error: {:X}", hr)
+            winapi::S_OK | winapi::DXGI_STATUS_OCCLUDED => Ok(()),
+            hr => Err(SwapChainError::from_hresult(hr)),
         }
     }
 
@@ -76,7 +77,7 @@ impl Window {
     }
 
     pub fn resize_swap_chain<Cf>(&mut self, factory: &mut Factory, width: Size, height: Size)
-                             -> Result<h::RenderTargetView<Resources, Cf>, winapi::HRESULT>
+                             -> Result<h::RenderTargetView<Resources, Cf>, SwapChainError>
     where Cf: format::RenderFormat
     {
         let result = unsafe {
@@ -89,7 +90,37 @@ impl Window {
             let raw = self.make_back_buffer(factory);
             Ok(memory::Typed::new(raw))
         } else {
-            Err(result)
+            Err(SwapChainError::from_hresult(result))
+        }
+    }
+}
+
+/// Error presenting or resizing the swap chain.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SwapChainError {
+    /// The device has been removed, reset or hung. It can't be used
+    /// any more and has to be re-created from scratch.
+    DeviceLost(winapi::HRESULT),
+    /// Any other failure, with the raw result code.
+    Other(winapi::HRESULT),
+}
+
+impl SwapChainError {
+    fn from_hresult(hr: winapi::HRESULT) -> SwapChainError {
+        match hr {
+            winapi::DXGI_ERROR_DEVICE_REMOVED |
+            winapi::DXGI_ERROR_DEVICE_RESET |
+            winapi::DXGI_ERROR_DEVICE_HUNG => SwapChainError::DeviceLost(hr),
+            _ => SwapChainError::Other(hr),
+        }
+    }
+}
+
+impl fmt::Display for SwapChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SwapChainError::DeviceLost(hr) => write!(f, "device lost (code {:X})", hr),
+            SwapChainError::Other(hr) => write!(f, "swap chain error (code {:X})", hr),
         }
     }
 }
@@ -102,19 +133,88 @@ pub enum InitError {
     Format(format::Format),
     /// Unable to find a supported driver type.
     DriverType,
+    /// The requested adapter doesn't exist.
+    Adapter(usize),
+}
+
+/// Information about a DXGI adapter.
+#[derive(Clone, Debug)]
+pub struct AdapterInfo {
+    /// Adapter description, as reported by the driver.
+    pub name: String,
+    /// Dedicated video memory, in bytes.
+    pub dedicated_video_memory: usize,
+    /// Locally unique identifier of the adapter, valid until the system restarts.
+    pub luid: u64,
+    /// The adapter is a software rasterizer, such as WARP.
+    pub software: bool,
+}
+
+/// Enumerate the adapters, passing each one to `fun` until it returns `false`.
+/// The adapter pointer is only valid for the duration of the call.
+fn for_each_adapter<F>(mut fun: F) where
+    F: FnMut(*mut winapi::IDXGIAdapter1, AdapterInfo) -> bool
+{
+    let mut factory: *mut winapi::IDXGIFactory1 = ptr::null_mut();
+    let hr = unsafe {
+        dxgi::CreateDXGIFactory1(&dxguid::IID_IDXGIFactory1,
+            &mut factory as *mut *mut winapi::IDXGIFactory1 as *mut *mut _)
+    };
+    if !winapi::SUCCEEDED(hr) {
+        error!("Failed to create a DXGI factory: {:X}", hr);
+        return;
+    }
+
+    let mut index = 0;
+    loop {
+        let mut adapter = ptr::null_mut();
+        if unsafe { (*factory).EnumAdapters1(index, &mut adapter) } != winapi::S_OK {
+            break;
+        }
+        let mut desc: winapi::DXGI_ADAPTER_DESC1 = unsafe { std::mem::zeroed() };
+        unsafe { (*adapter).GetDesc1(&mut desc) };
+        let name_len = desc.Description.iter().position(|&c| c == 0)
+                                              .unwrap_or(desc.Description.len());
+        let info = AdapterInfo {
+            name: String::from_utf16_lossy(&desc.Description[.. name_len]),
+            dedicated_video_memory: desc.DedicatedVideoMemory as usize,
+            luid: ((desc.AdapterLuid.HighPart as u64) << 32) | desc.AdapterLuid.LowPart as u64,
+            software: desc.Flags & winapi::DXGI_ADAPTER_FLAG_SOFTWARE.0 != 0,
+        };
+        let proceed = fun(adapter, info);
+        unsafe { (*adapter).Release() };
+        if !proceed {
+            break;
+        }
+        index += 1;
+    }
+    unsafe { (*factory).Release() };
+}
+
+/// List the adapters available to DXGI, in the order expected by `init`.
+pub fn enumerate_adapters() -> Vec<AdapterInfo> {
+    let mut adapters = Vec::new();
+    for_each_adapter(|_, info| {
+        adapters.push(info);
+        true
+    });
+    adapters
 }
 
 /// Initialize with a given size. Typed format version.
-pub fn init<Cf>(wb: winit::WindowBuilder)
+/// With `debug` set, the device is created with the D3D11 debug layer.
+/// `adapter` is an index into `enumerate_adapters()`, if none is given the
+/// hardware adapter is tried first, then WARP and the reference rasterizer.
+pub fn init<Cf>(wb: winit::WindowBuilder, debug: bool, adapter: Option<usize>)
            -> Result<(Window, Device, Factory, h::RenderTargetView<Resources, Cf>), InitError>
 where Cf: format::RenderFormat
 {
-    init_raw(wb, Cf::get_format())
+    init_raw(wb, Cf::get_format(), debug, adapter)
         .map(|(window, device, factory, color)| (window, device, factory, memory::Typed::new(color)))
 }
 
 /// Initialize with a given size. Raw format version.
-pub fn init_raw(wb: winit::WindowBuilder, color_format: format::Format)
+pub fn init_raw(wb: winit::WindowBuilder, color_format: format::Format, debug: bool, adapter: Option<usize>)
                 -> Result<(Window, Device, Factory, h::RawRenderTargetView<Resources>), InitError> {
     let inner = match wb.build() {
         Ok(w) => w,
@@ -156,26 +256,60 @@ pub fn init_raw(wb: winit::WindowBuilder, color_format: format::Format)
     };
 
     info!("Creating swap chain of size {}x{}", width, height);
-    for dt in driver_types.iter() {
-        match device_dx11::create(*dt, &swap_desc) {
-            Ok((device, mut factory, chain)) => {
-                info!("Success with driver {:?}, shader model {}", *dt, device.get_shader_model());
-                let win = Window {
-                    inner: inner,
-                    swap_chain: chain,
-                    driver_type: *dt,
-                    color_format: color_format,
-                    size: (width as Size, height as Size),
+    let (accelerated, device, mut factory, chain) = match adapter {
+        Some(index) => {
+            let mut result = Err(InitError::Adapter(index));
+            let mut current = 0;
+            for_each_adapter(|raw, info| {
+                if current < index {
+                    current += 1;
+                    return true;
+                }
+                info!("Using adapter {:?}", info);
+                // an explicit adapter requires the unknown driver type
+                result = match device_dx11::create(winapi::D3D_DRIVER_TYPE_UNKNOWN, &swap_desc, debug,
+                                                   raw as *mut winapi::IDXGIAdapter) {
+                    Ok((device, factory, chain)) => Ok((!info.software, device, factory, chain)),
+                    Err(hres) => {
+                        info!("Failure with adapter {}: code {:x}", index, hres);
+                        Err(InitError::DriverType)
+                    },
                 };
-                let color = win.make_back_buffer(&mut factory);
-                return Ok((win, device, factory, color))
-            },
-            Err(hres) => {
-                info!("Failure with driver {:?}: code {:x}", *dt, hres);
-            },
-        }
-    }
-    Err(InitError::DriverType)
+                false
+            });
+            try!(result)
+        },
+        None => {
+            let mut created = None;
+            for dt in driver_types.iter() {
+                match device_dx11::create(*dt, &swap_desc, debug, ptr::null_mut()) {
+                    Ok((device, factory, chain)) => {
+                        info!("Success with driver {:?}", *dt);
+                        created = Some((*dt == winapi::D3D_DRIVER_TYPE_HARDWARE, device, factory, chain));
+                        break;
+                    },
+                    Err(hres) => {
+                        info!("Failure with driver {:?}: code {:x}", *dt, hres);
+                    },
+                }
+            }
+            match created {
+                Some(created) => created,
+                None => return Err(InitError::DriverType),
+            }
+        },
+    };
+
+    info!("Created device with shader model {}", device.get_shader_model());
+    let win = Window {
+        inner: inner,
+        swap_chain: chain,
+        accelerated: accelerated,
+        color_format: color_format,
+        size: (width as Size, height as Size),
+    };
+    let color = win.make_back_buffer(&mut factory);
+    Ok((win, device, factory, color))
 }
 
 pub trait DeviceExt: core::Device {
@@ -196,18 +330,12 @@ impl DeviceExt for Device {
 
 /// Update the internal dimensions of the main framebuffer targets. Generic version over the format.
 pub fn update_views<Cf, D>(window: &mut Window, factory: &mut Factory, device: &mut D, width: u16, height: u16)
-            -> Result<h::RenderTargetView<Resources, Cf>, f::TargetViewError>
+            -> Result<h::RenderTargetView<Resources, Cf>, SwapChainError>
 where Cf: format::RenderFormat, D: DeviceExt
 {
-    
     factory.cleanup();
     device.clear_state();
     device.cleanup();
 
     window.resize_swap_chain::<Cf>(factory, width, height)
-        .map_err(|hr| {
-            error!("Resize failed with code {:X}", hr);
-            f::TargetViewError::NotDetached
-        }
-    )    
 }
@@ -35,7 +35,8 @@ use cocoa::appkit::{NSWindow, NSView};
 
 use core::format::{RenderFormat, Format};
 use core::handle::{RawRenderTargetView, RenderTargetView};
-use core::memory::Typed;
+use core::memory::{self, Typed};
+use core::texture as tex;
 
 use device_metal::{Device, Factory, Resources};
 
@@ -52,6 +53,7 @@ pub struct MetalWindow {
     layer: CAMetalLayer,
     drawable: *mut CAMetalDrawable,
     backbuffer: *mut MTLTexture,
+    color_format: Format,
     pool: Cell<NSAutoreleasePool>
 }
 
@@ -82,6 +84,31 @@ impl MetalWindow {
 
         Ok(())
     }
+
+    /// Resize the drawables of the layer. The main color target has to be
+    /// re-created afterwards with `update_views`.
+    pub fn resize(&self, width: u32, height: u32) {
+        self.layer.set_drawable_size(NSSize::new(width as f64, height as f64));
+    }
+}
+
+/// Create a new main color target after the window has been resized.
+pub fn update_views<C: RenderFormat>(window: &MetalWindow, factory: &mut Factory, width: u32, height: u32)
+                                     -> RenderTargetView<Resources, C>
+{
+    let info = tex::Info {
+        kind: tex::Kind::D2(width as tex::Size, height as tex::Size, tex::AaMode::Single),
+        levels: 1,
+        format: window.color_format.0,
+        bind: memory::RENDER_TARGET,
+        usage: memory::Usage::Data,
+    };
+    let desc = tex::RenderDesc {
+        channel: window.color_format.1,
+        level: 0,
+        layer: None,
+    };
+    Typed::new(factory.wrap_back_buffer(window.backbuffer, info, desc))
 }
 
 
@@ -141,6 +168,7 @@ pub fn init_raw(wb: winit::WindowBuilder, color_format: Format)
             layer: layer,
             drawable: daddr,
             backbuffer: addr,
+            color_format: color_format,
             pool: Cell::new(NSAutoreleasePool::alloc().init())
         };
 
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[macro_use]
+extern crate log;
 extern crate winit;
 extern crate vk_sys as vk;
 extern crate gfx_core as core;
@@ -20,6 +22,7 @@ extern crate gfx_device_vulkan as device_vulkan;
 #[cfg(target_os = "windows")]
 extern crate kernel32;
 
+use std::cmp;
 use std::ffi::CStr;
 use std::ptr;
 use std::os::raw;
@@ -36,20 +39,25 @@ pub type TargetHandle<T> = core::handle::RenderTargetView<device_vulkan::Resourc
 pub struct SwapTarget<T> {
     _image: vk::Image,
     target: TargetHandle<T>,
-    _fence: vk::Fence,
+    fence: vk::Fence,
 }
 
 pub struct Window<T> {
     window: winit::Window,
     _debug_callback: Option<vk::DebugReportCallbackEXT>,
+    surface: vk::SurfaceKHR,
     swapchain: vk::SwapchainKHR,
+    extent: (u32, u32),
     targets: Vec<SwapTarget<T>>,
     queue: device_vulkan::GraphicsQueue,
 }
 
+/// Image of the swapchain being rendered to, presented with `present`,
+/// or when dropped.
 pub struct Frame<'a, T: 'a> {
     window: &'a mut Window<T>,
     target_id: u32,
+    presented: bool,
 }
 
 impl<'a, T: Clone> Frame<'a, T> {
@@ -59,10 +67,17 @@ impl<'a, T: Clone> Frame<'a, T> {
     pub fn get_queue(&mut self) -> &mut device_vulkan::GraphicsQueue {
         &mut self.window.queue
     }
+
+    /// Present the image. Returns `false` if the swapchain is out of date or suboptimal,
+    /// e.g. after the window was resized, and has to be recreated with `Window::resize`.
+    pub fn present(mut self) -> bool {
+        self.presented = true;
+        is_up_to_date(self.queue_present())
+    }
 }
 
-impl<'a, T> Drop for Frame<'a, T> {
-    fn drop(&mut self) {
+impl<'a, T> Frame<'a, T> {
+    fn queue_present(&mut self) -> vk::Result {
         let mut result = vk::SUCCESS;
         let info = vk::PresentInfoKHR {
             sType: vk::STRUCTURE_TYPE_PRESENT_INFO_KHR,
@@ -78,23 +93,49 @@ impl<'a, T> Drop for Frame<'a, T> {
         unsafe {
             vk.QueuePresentKHR(self.window.queue.get_queue(), &info);
         }
-        assert_eq!(vk::SUCCESS, result);
+        result
+    }
+}
+
+impl<'a, T> Drop for Frame<'a, T> {
+    fn drop(&mut self) {
+        if !self.presented {
+            // the status is lost, the next `start_frame` reports it again
+            is_up_to_date(self.queue_present());
+        }
+    }
+}
+
+// Check the status of a presentation, returning whether the swapchain
+// still matches the surface.
+fn is_up_to_date(result: vk::Result) -> bool {
+    match result {
+        vk::SUCCESS => true,
+        vk::SUBOPTIMAL_KHR | vk::ERROR_OUT_OF_DATE_KHR => false,
+        err => panic!("Error on swapchain presentation {:?}", err),
     }
 }
 
 impl<T: Clone> Window<T> {
-    pub fn start_frame(&mut self) -> Frame<T> {
-        //TODO: handle window resize (requires swapchain recreation)
-        let index = unsafe {
+    /// Acquire the next image of the swapchain. Returns `None` if the swapchain is
+    /// out of date, e.g. after the window was resized, and has to be recreated with `resize`.
+    pub fn start_frame(&mut self) -> Option<Frame<T>> {
+        let mut index = 0;
+        let result = unsafe {
             let (dev, vk) = self.queue.get_share().get_device();
-            let mut i = 0;
-            assert_eq!(vk::SUCCESS, vk.AcquireNextImageKHR(dev, self.swapchain, 60, 0, 0, &mut i));
-            i
+            vk.AcquireNextImageKHR(dev, self.swapchain, 60, 0, 0, &mut index)
         };
-        Frame {
+        match result {
+            // a suboptimal swapchain can still be presented to
+            vk::SUCCESS | vk::SUBOPTIMAL_KHR => (),
+            vk::ERROR_OUT_OF_DATE_KHR => return None,
+            err => panic!("Error on swapchain image acquisition {:?}", err),
+        }
+        Some(Frame {
             window: self,
             target_id: index,
-        }
+            presented: false,
+        })
     }
 
     pub fn get_any_target(&self) -> TargetHandle<T> {
@@ -108,6 +149,38 @@ impl<T: Clone> Window<T> {
     pub fn get_size(&self) -> (u32, u32) {
         self.window.get_inner_size_points().unwrap()
     }
+
+    /// Return the size of the swapchain images, which may differ from the window size
+    /// to fit the extents supported by the surface.
+    pub fn get_extent(&self) -> (u32, u32) {
+        self.extent
+    }
+}
+
+impl<T: core::format::RenderFormat> Window<T> {
+    /// Re-create the swapchain and its render targets for the new window size.
+    /// The old targets are released, so any views obtained from them have to be
+    /// replaced by the caller afterwards. Returns the new size of the swapchain images,
+    /// see `get_extent`.
+    pub fn resize(&mut self, factory: &mut device_vulkan::Factory, width: u32, height: u32) -> (u32, u32) {
+        {
+            let (dev, vk) = self.queue.get_share().get_device();
+            assert_eq!(vk::SUCCESS, unsafe { vk.DeviceWaitIdle(dev) });
+        }
+        let old_swapchain = self.swapchain;
+        let (swapchain, extent, targets) = create_swapchain(&mut self.queue, factory, self.surface,
+                                                            (width, height), old_swapchain);
+        let old_targets = std::mem::replace(&mut self.targets, targets);
+        self.swapchain = swapchain;
+        self.extent = extent;
+
+        let (dev, vk) = self.queue.get_share().get_device();
+        for target in old_targets {
+            unsafe { vk.DestroyFence(dev, target.fence, ptr::null()) };
+        }
+        unsafe { vk.DestroySwapchainKHR(dev, old_swapchain, ptr::null()) };
+        extent
+    }
 }
 
 const LAYERS: &'static [&'static str] = &[
@@ -131,22 +204,42 @@ extern "system" fn callback(flags: vk::DebugReportFlagsEXT,
                             _msg_code: i32, layer_prefix_c: *const raw::c_char,
                             description_c: *const raw::c_char, _user_data: *mut raw::c_void) -> u32
 {
-    let layer_prefix = unsafe { CStr::from_ptr(layer_prefix_c) }.to_str().unwrap();
-    let description  = unsafe { CStr::from_ptr(description_c)  }.to_str().unwrap();
-    println!("Vk flags {:x} in layer {}: {}", flags, layer_prefix, description);
+    let layer_prefix = unsafe { CStr::from_ptr(layer_prefix_c) }.to_string_lossy();
+    let description  = unsafe { CStr::from_ptr(description_c)  }.to_string_lossy();
+    if flags & vk::DEBUG_REPORT_ERROR_BIT_EXT != 0 {
+        error!("[{}] {}", layer_prefix, description);
+    } else if flags & (vk::DEBUG_REPORT_WARNING_BIT_EXT | vk::DEBUG_REPORT_PERFORMANCE_WARNING_BIT_EXT) != 0 {
+        warn!("[{}] {}", layer_prefix, description);
+    } else if flags & vk::DEBUG_REPORT_INFORMATION_BIT_EXT != 0 {
+        info!("[{}] {}", layer_prefix, description);
+    } else {
+        debug!("[{}] {}", layer_prefix, description);
+    }
     vk::FALSE
 }
 
-pub fn init<T: core::format::RenderFormat>(wb: winit::WindowBuilder)
+/// Create a window with a Vulkan swapchain. With `validation` set, the standard
+/// validation layers are enabled if installed, and their reports are forwarded to the `log` crate.
+/// `adapter` is an index into `device_vulkan::enumerate_adapters()`.
+pub fn init<T: core::format::RenderFormat>(wb: winit::WindowBuilder, validation: bool,
+                                           adapter: Option<usize>)
                 -> (Window<T>, device_vulkan::Factory) {
     let title = wb.window.title.clone();
     let window = wb.build().unwrap();
 
-    let debug = false;
+    // Only enable validation if the layers are actually installed
+    let debug = validation && {
+        let installed = device_vulkan::enumerate_layers();
+        let available = LAYERS_DEBUG.iter().all(|layer| installed.iter().any(|name| name == layer));
+        if !available {
+            warn!("Validation layers requested but not available");
+        }
+        available
+    };
     let (mut device, mut factory, backend) = device_vulkan::create(&title, 1,
         if debug {LAYERS_DEBUG} else {LAYERS},
         if debug {EXTENSIONS_DEBUG} else {EXTENSIONS},
-        DEV_EXTENSIONS);
+        DEV_EXTENSIONS, adapter);
 
     let debug_callback = if debug {
         let info = vk::DebugReportCallbackCreateInfoEXT {
@@ -170,11 +263,6 @@ pub fn init<T: core::format::RenderFormat>(wb: winit::WindowBuilder)
 
     let surface = create_surface(backend.clone(), &window);
 
-    let (dev, vk) = backend.get_device();
-    let mut images: [vk::Image; 2] = [0; 2];
-    let mut num = images.len() as u32;
-    let format = <T as format::Formatted>::get_format();
-
     let surface_capabilities = {
         let (_, vk) = backend.get_instance();
         let dev = backend.get_physical_device();
@@ -227,6 +315,44 @@ pub fn init<T: core::format::RenderFormat>(wb: winit::WindowBuilder)
     };
 
     let (width, height) = window.get_inner_size_points().unwrap();
+    let (swapchain, extent, targets) = create_swapchain(&mut device, &mut factory, surface, (width, height), 0);
+
+    let win = Window {
+        window: window,
+        _debug_callback: debug_callback,
+        surface: surface,
+        swapchain: swapchain,
+        extent: extent,
+        targets: targets,
+        queue: device,
+    };
+    (win, factory)
+}
+
+fn create_swapchain<T: core::format::RenderFormat>(queue: &mut device_vulkan::GraphicsQueue,
+                    factory: &mut device_vulkan::Factory, surface: vk::SurfaceKHR,
+                    (width, height): (u32, u32), old_swapchain: vk::SwapchainKHR)
+                    -> (vk::SwapchainKHR, (u32, u32), Vec<SwapTarget<T>>) {
+    let mut images: [vk::Image; 2] = [0; 2];
+    let mut num = images.len() as u32;
+    let format = <T as format::Formatted>::get_format();
+
+    // the surface may only support its current extent, or a range of them
+    let capabilities = {
+        let share = queue.get_share();
+        let (_, vk) = share.get_instance();
+        let mut capabilities: vk::SurfaceCapabilitiesKHR = unsafe { std::mem::uninitialized() };
+        assert_eq!(vk::SUCCESS, unsafe {
+            vk.GetPhysicalDeviceSurfaceCapabilitiesKHR(share.get_physical_device(), surface, &mut capabilities)
+        });
+        capabilities
+    };
+    let (width, height) = if capabilities.currentExtent.width != !0 {
+        (capabilities.currentExtent.width, capabilities.currentExtent.height)
+    } else {
+        (cmp::max(capabilities.minImageExtent.width, cmp::min(capabilities.maxImageExtent.width, width)),
+         cmp::max(capabilities.minImageExtent.height, cmp::min(capabilities.maxImageExtent.height, height)))
+    };
 
     // TODO: Use the queried information to check if our values are supported before creating the swapchain
     let swapchain_info = vk::SwapchainCreateInfoKHR {
@@ -247,17 +373,19 @@ pub fn init<T: core::format::RenderFormat>(wb: winit::WindowBuilder)
         compositeAlpha: vk::COMPOSITE_ALPHA_OPAQUE_BIT_KHR,
         presentMode: vk::PRESENT_MODE_FIFO_KHR, // required to be supported
         clipped: vk::TRUE,
-        oldSwapchain: 0,
+        oldSwapchain: old_swapchain,
     };
 
     let mut swapchain = 0;
-    assert_eq!(vk::SUCCESS, unsafe {
-        vk.CreateSwapchainKHR(dev, &swapchain_info, ptr::null(), &mut swapchain)
-    });
-
-    assert_eq!(vk::SUCCESS, unsafe {
-        vk.GetSwapchainImagesKHR(dev, swapchain, &mut num, images.as_mut_ptr())
-    });
+    {
+        let (dev, vk) = queue.get_share().get_device();
+        assert_eq!(vk::SUCCESS, unsafe {
+            vk.CreateSwapchainKHR(dev, &swapchain_info, ptr::null(), &mut swapchain)
+        });
+        assert_eq!(vk::SUCCESS, unsafe {
+            vk.GetSwapchainImagesKHR(dev, swapchain, &mut num, images.as_mut_ptr())
+        });
+    }
 
     let mut cbuf = factory.create_command_buffer();
 
@@ -267,23 +395,16 @@ pub fn init<T: core::format::RenderFormat>(wb: winit::WindowBuilder)
         SwapTarget {
             _image: *image,
             target: Typed::new(raw_view),
-            _fence: factory.create_fence(true),
+            fence: factory.create_fence(true),
         }
     }).collect();
 
     {
         use core::Device;
-        device.submit(&mut cbuf, &core::command::AccessInfo::new()).unwrap();
+        queue.submit(&mut cbuf, &core::command::AccessInfo::new()).unwrap();
     }
 
-    let win = Window {
-        window: window,
-        _debug_callback: debug_callback,
-        swapchain: swapchain,
-        targets: targets,
-        queue: device,
-    };
-    (win, factory)
+    (swapchain, (width, height), targets)
 }
 
 #[cfg(target_os = "windows")]